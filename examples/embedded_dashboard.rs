@@ -0,0 +1,61 @@
+// This example shows another tool's probe results in the windows-mtr dashboard.
+// Run it with `cargo run --example embedded_dashboard` and press `Q` to quit.
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use windows_mtr::crossterm::event::KeyCode;
+use windows_mtr::dashboard_ui::{
+    DashboardAction, DashboardOptions, HopSnapshot, Keymap, Theme, run_snapshot_ui,
+};
+use windows_mtr::ratatui::style::Color;
+use windows_mtr::report_render::{SavedHop, SavedHost};
+use windows_mtr::report_schema::ReportBackend;
+
+fn main() -> anyhow::Result<()> {
+    let (snapshot_tx, snapshot_rx) = mpsc::channel();
+
+    // Stand-in for a real probe: three hops whose last hop slowly gets worse.
+    thread::spawn(move || {
+        for round in 0u64.. {
+            let hops = (1..=3)
+                .map(|ttl| {
+                    let rtt = f64::from(ttl) * 4.0 + (round % 40) as f64 * f64::from(ttl - 1);
+                    SavedHop {
+                        ttl,
+                        hosts: vec![SavedHost {
+                            ip: format!("192.0.2.{ttl}"),
+                            hostname: None,
+                        }],
+                        loss_pct: Some(0.0),
+                        last_ms: Some(rtt),
+                        avg_ms: Some(rtt),
+                        ..SavedHop::default()
+                    }
+                })
+                .collect();
+            let snapshot = HopSnapshot {
+                target: 0,
+                hops: Ok(hops),
+            };
+            if snapshot_tx.send(snapshot).is_err() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(500));
+        }
+    });
+
+    let mut keymap = Keymap::default();
+    keymap.unbind(KeyCode::Char('q'));
+    keymap.bind(KeyCode::Char('Q'), DashboardAction::Quit);
+
+    let mut options = DashboardOptions::new(vec!["192.0.2.3".to_string()], ReportBackend::Trippy);
+    options.theme = Theme {
+        accent: Color::LightBlue,
+        ..Theme::default()
+    };
+    options.keymap = keymap;
+
+    run_snapshot_ui(options, snapshot_rx)?;
+    Ok(())
+}
@@ -43,7 +43,8 @@ try {
   $json = & $mtr --json -n -c 1 127.0.0.1
   if ($LASTEXITCODE -ne 0) { throw "Packaged JSON report failed with exit code $LASTEXITCODE" }
   $jsonReport = $json | ConvertFrom-Json
-  if ($jsonReport.schema_version -ne "1.0") { throw "Packaged JSON report did not emit schema_version 1.0" }
+  if ($jsonReport.schema_version -ne "2.1") { throw "Packaged JSON report did not emit schema_version 2.1" }
+  if ($jsonReport.report.backend -ne "windows-icmp-helper") { throw "Packaged JSON report did not come from the Windows ICMP Helper backend" }
 
   & $mtr --csv $csvPath -n -c 1 127.0.0.1 | Out-Null
   if ($LASTEXITCODE -ne 0) { throw "Packaged CSV report failed with exit code $LASTEXITCODE" }
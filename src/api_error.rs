@@ -56,6 +56,20 @@ impl ApiError {
     fn problem_type(code: &'static str) -> &'static str {
         match code {
             "invalid_target" => "https://windows-mtr.dev/problems/invalid-target",
+            "target_forbidden" => "https://windows-mtr.dev/problems/target-forbidden",
+            "option_forbidden" => "https://windows-mtr.dev/problems/option-forbidden",
+            "queue_full" => "https://windows-mtr.dev/problems/queue-full",
+            "shutting_down" => "https://windows-mtr.dev/problems/shutting-down",
+            "probe_not_comparable" => "https://windows-mtr.dev/problems/probe-not-comparable",
+            "admin_scope_required" => "https://windows-mtr.dev/problems/admin-scope-required",
+            "invalid_config" => "https://windows-mtr.dev/problems/invalid-config",
+            "reload_unavailable" => "https://windows-mtr.dev/problems/reload-unavailable",
+            "idempotency_key_mismatch" => {
+                "https://windows-mtr.dev/problems/idempotency-key-mismatch"
+            }
+            "idempotency_key_conflict" => {
+                "https://windows-mtr.dev/problems/idempotency-key-conflict"
+            }
             "invalid_ip_address" => "https://windows-mtr.dev/problems/invalid-ip-address",
             "invalid_option" => "https://windows-mtr.dev/problems/invalid-option",
             "missing_port" => "https://windows-mtr.dev/problems/missing-port",
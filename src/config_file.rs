@@ -0,0 +1,790 @@
+//! TOML configuration shared by probe CLI mode and `--api` mode.
+//!
+//! Settings are layered: built-in defaults, then the config file (base `[probe]`/`[api]`
+//! sections, then the selected `[profiles.<name>]` overlay), then `WINDOWS_MTR__SECTION__KEY`
+//! environment overrides. The binary applies CLI flags last.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use reqwest::Url;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::service::rest_api::{AuthStrategy, RestApiConfig};
+use crate::service::target_policy::{IpCidr, TargetPolicy, TargetRule};
+use crate::service::webhooks::parse_webhook_url;
+
+/// Explicit config file path, used when `--config` is not given.
+pub const CONFIG_PATH_ENV: &str = "WINDOWS_MTR_CONFIG";
+/// Profile name, used when `--profile` is not given.
+pub const PROFILE_ENV: &str = "WINDOWS_MTR_PROFILE";
+/// Prefix for per-key overrides, for example `WINDOWS_MTR__API__MAX_QUEUED_PROBES=16`.
+pub const ENV_OVERRIDE_PREFIX: &str = "WINDOWS_MTR__";
+
+const APP_DIR_NAME: &str = "windows-mtr";
+const CONFIG_FILE_NAME: &str = "config.toml";
+const SECTIONS: &[&str] = &["probe", "api"];
+
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigFileError {
+    #[error("config file {} does not exist", .path.display())]
+    NotFound { path: PathBuf },
+
+    #[error("failed to read config file {}: {source}", .path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("invalid config file {}: {message}", .path.display())]
+    Parse { path: PathBuf, message: String },
+
+    #[error("invalid environment override {name}: {message}")]
+    InvalidEnvOverride { name: String, message: String },
+
+    #[error("profile `{name}` is not defined in {}", .path.display())]
+    UnknownProfile { name: String, path: PathBuf },
+
+    #[error("profile `{name}` was requested, but no config file was found")]
+    ProfileWithoutConfig { name: String },
+
+    #[error("invalid merged configuration: {0}")]
+    Invalid(String),
+}
+
+/// Effective `[probe]` and `[api]` settings after the file, profile, and environment layers.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub probe: ProbeSettings,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub api: ApiSettings,
+}
+
+/// On-disk layout: [`Settings`] plus named profiles that overlay it. Only used to validate the
+/// file with line/column context; merging happens on the raw table.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[allow(dead_code)]
+struct ConfigDocument {
+    #[serde(default)]
+    probe: ProbeSettings,
+    #[serde(default)]
+    api: ApiSettings,
+    #[serde(default)]
+    profiles: BTreeMap<String, Settings>,
+}
+
+/// Defaults for CLI probe runs; every field is overridden by the matching flag.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProbeSettings {
+    pub protocol: Option<ProtocolSetting>,
+    pub port: Option<u16>,
+    pub count: Option<usize>,
+    pub interval_seconds: Option<f64>,
+    pub timeout_seconds: Option<f64>,
+    pub max_hops: Option<u8>,
+    pub no_dns: Option<bool>,
+    pub show_asn: Option<bool>,
+    pub output: Option<OutputSetting>,
+    pub latency_warn_ms: Option<f64>,
+    pub latency_bad_ms: Option<f64>,
+    pub loss_warn_pct: Option<f64>,
+    pub loss_bad_pct: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProtocolSetting {
+    Icmp,
+    Tcp,
+    Udp,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputSetting {
+    Interactive,
+    Report,
+    ReportWide,
+    Json,
+    JsonPretty,
+}
+
+/// Mirrors [`RestApiConfig`]. Secrets are never stored in the file: `api_key_env`,
+/// `named_key_envs` (principal name to variable), and `webhooks.secret_env` name environment
+/// variables, and `api_key_file` names a file holding the key, all resolved by the binary at
+/// startup and on reload.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiSettings {
+    pub bind: Option<SocketAddr>,
+    pub auth: Option<AuthSetting>,
+    pub api_key_env: Option<String>,
+    pub api_key_file: Option<PathBuf>,
+    pub named_key_envs: Option<BTreeMap<String, String>>,
+    pub admin_principals: Option<Vec<String>>,
+    pub request_timeout_seconds: Option<u64>,
+    pub max_concurrent_probes: Option<usize>,
+    pub max_queued_probes: Option<usize>,
+    pub fair_queue: Option<bool>,
+    pub max_requests_per_window: Option<usize>,
+    pub rate_limit_window_seconds: Option<u64>,
+    pub max_targets_per_request: Option<usize>,
+    pub max_payload_bytes: Option<usize>,
+    pub max_completed_jobs: Option<usize>,
+    pub completed_job_ttl_seconds: Option<u64>,
+    pub idempotency_ttl_seconds: Option<u64>,
+    pub probe_timeout_seconds: Option<u64>,
+    pub shutdown_grace_seconds: Option<u64>,
+    pub mtls_trusted_ingress: Option<Vec<IpAddr>>,
+    pub allow_source_addresses: Option<Vec<IpCidr>>,
+    pub allow_interfaces: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub target_policy: TargetPolicySettings,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub webhooks: WebhookSettings,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuthSetting {
+    NoneLocalOnly,
+    ApiKey,
+    Mtls,
+}
+
+impl From<AuthSetting> for AuthStrategy {
+    fn from(value: AuthSetting) -> Self {
+        match value {
+            AuthSetting::NoneLocalOnly => Self::NoneLocalOnly,
+            AuthSetting::ApiKey => Self::ApiKey,
+            AuthSetting::Mtls => Self::Mtls,
+        }
+    }
+}
+
+impl From<AuthStrategy> for AuthSetting {
+    fn from(value: AuthStrategy) -> Self {
+        match value {
+            AuthStrategy::NoneLocalOnly => Self::NoneLocalOnly,
+            AuthStrategy::ApiKey => Self::ApiKey,
+            AuthStrategy::Mtls => Self::Mtls,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TargetPolicySettings {
+    pub allow: Option<Vec<TargetRule>>,
+    pub deny: Option<Vec<TargetRule>>,
+    pub block_private: Option<bool>,
+    pub principals: Option<BTreeMap<String, PrincipalPolicySettings>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PrincipalPolicySettings {
+    #[serde(default)]
+    pub allow: Vec<TargetRule>,
+    #[serde(default)]
+    pub deny: Vec<TargetRule>,
+    #[serde(default)]
+    pub block_private: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookSettings {
+    #[serde(
+        default,
+        deserialize_with = "deserialize_webhook_urls",
+        serialize_with = "serialize_webhook_urls"
+    )]
+    pub urls: Option<Vec<Url>>,
+    pub secret_env: Option<String>,
+    pub allow_callback_urls: Option<bool>,
+    pub loss_threshold_pct: Option<f64>,
+    pub latency_threshold_ms: Option<f64>,
+    pub max_attempts: Option<u32>,
+    pub dead_letter: Option<PathBuf>,
+}
+
+impl ApiSettings {
+    /// Copy every configured field onto `config`; the secret env names are left to the caller.
+    pub fn apply_to(&self, config: &mut RestApiConfig) {
+        let seconds = Duration::from_secs;
+
+        if let Some(bind) = self.bind {
+            config.bind_addr = bind;
+        }
+        if let Some(auth) = self.auth {
+            config.auth_strategy = auth.into();
+        }
+        if let Some(principals) = &self.admin_principals {
+            config.admin_principals = principals.clone();
+        }
+        if let Some(timeout) = self.request_timeout_seconds {
+            config.request_timeout = seconds(timeout);
+        }
+        if let Some(limit) = self.max_concurrent_probes {
+            config.max_concurrent_probes = limit;
+        }
+        if let Some(depth) = self.max_queued_probes {
+            config.max_queued_probes = depth;
+        }
+        if let Some(fair) = self.fair_queue {
+            config.fair_queueing = fair;
+        }
+        if let Some(max_requests) = self.max_requests_per_window {
+            config.max_requests_per_window = max_requests;
+        }
+        if let Some(window) = self.rate_limit_window_seconds {
+            config.rate_limit_window = seconds(window);
+        }
+        if let Some(limit) = self.max_targets_per_request {
+            config.max_targets_per_request = limit;
+        }
+        if let Some(limit) = self.max_payload_bytes {
+            config.max_payload_bytes = limit;
+        }
+        if let Some(limit) = self.max_completed_jobs {
+            config.max_completed_jobs = limit;
+        }
+        if let Some(ttl) = self.completed_job_ttl_seconds {
+            config.completed_job_ttl = seconds(ttl);
+        }
+        if let Some(ttl) = self.idempotency_ttl_seconds {
+            config.idempotency_key_ttl = seconds(ttl);
+        }
+        if let Some(timeout) = self.probe_timeout_seconds {
+            config.probe_execution_timeout = seconds(timeout);
+        }
+        if let Some(grace) = self.shutdown_grace_seconds {
+            config.shutdown_grace_period = seconds(grace);
+        }
+        if let Some(ingress) = &self.mtls_trusted_ingress {
+            config.trusted_mtls_ingress_ips = ingress.clone();
+        }
+        if let Some(ranges) = &self.allow_source_addresses {
+            config.allowed_source_addresses = ranges.clone();
+        }
+        if let Some(interfaces) = &self.allow_interfaces {
+            config.allowed_interfaces = interfaces.clone();
+        }
+
+        let policy = &self.target_policy;
+        if let Some(allow) = &policy.allow {
+            config.target_policy.default.allow = allow.clone();
+        }
+        if let Some(deny) = &policy.deny {
+            config.target_policy.default.deny = deny.clone();
+        }
+        if let Some(block_private) = policy.block_private {
+            config.target_policy.default.block_private_ranges = block_private;
+        }
+        for (principal, overlay) in policy.principals.iter().flatten() {
+            config.target_policy.principals.insert(
+                principal.clone(),
+                TargetPolicy {
+                    allow: overlay.allow.clone(),
+                    deny: overlay.deny.clone(),
+                    block_private_ranges: overlay.block_private,
+                },
+            );
+        }
+
+        let webhooks = &self.webhooks;
+        if let Some(urls) = &webhooks.urls {
+            config.webhooks.sinks = urls.clone();
+        }
+        if let Some(allow) = webhooks.allow_callback_urls {
+            config.webhooks.allow_callback_urls = allow;
+        }
+        if let Some(threshold) = webhooks.loss_threshold_pct {
+            config.webhooks.loss_threshold_pct = Some(threshold);
+        }
+        if let Some(threshold) = webhooks.latency_threshold_ms {
+            config.webhooks.latency_threshold_ms = Some(threshold);
+        }
+        if let Some(max_attempts) = webhooks.max_attempts {
+            config.webhooks.max_attempts = max_attempts;
+        }
+        if let Some(path) = &webhooks.dead_letter {
+            config.webhooks.dead_letter_path = Some(path.clone());
+        }
+    }
+
+    /// Describe an effective [`RestApiConfig`], for `--print-config`. Secrets are omitted.
+    pub fn from_config(config: &RestApiConfig) -> Self {
+        let default_policy = &config.target_policy.default;
+        let principals = config
+            .target_policy
+            .principals
+            .iter()
+            .map(|(principal, policy)| {
+                (
+                    principal.clone(),
+                    PrincipalPolicySettings {
+                        allow: policy.allow.clone(),
+                        deny: policy.deny.clone(),
+                        block_private: policy.block_private_ranges,
+                    },
+                )
+            })
+            .collect::<BTreeMap<_, _>>();
+        let webhooks = &config.webhooks;
+
+        Self {
+            bind: Some(config.bind_addr),
+            auth: Some(config.auth_strategy.into()),
+            api_key_env: None,
+            api_key_file: None,
+            named_key_envs: None,
+            admin_principals: Some(config.admin_principals.clone()),
+            request_timeout_seconds: Some(config.request_timeout.as_secs()),
+            max_concurrent_probes: Some(config.max_concurrent_probes),
+            max_queued_probes: Some(config.max_queued_probes),
+            fair_queue: Some(config.fair_queueing),
+            max_requests_per_window: Some(config.max_requests_per_window),
+            rate_limit_window_seconds: Some(config.rate_limit_window.as_secs()),
+            max_targets_per_request: Some(config.max_targets_per_request),
+            max_payload_bytes: Some(config.max_payload_bytes),
+            max_completed_jobs: Some(config.max_completed_jobs),
+            completed_job_ttl_seconds: Some(config.completed_job_ttl.as_secs()),
+            idempotency_ttl_seconds: Some(config.idempotency_key_ttl.as_secs()),
+            probe_timeout_seconds: Some(config.probe_execution_timeout.as_secs()),
+            shutdown_grace_seconds: Some(config.shutdown_grace_period.as_secs()),
+            mtls_trusted_ingress: Some(config.trusted_mtls_ingress_ips.clone()),
+            allow_source_addresses: Some(config.allowed_source_addresses.clone()),
+            allow_interfaces: Some(config.allowed_interfaces.clone()),
+            target_policy: TargetPolicySettings {
+                allow: Some(default_policy.allow.clone()),
+                deny: Some(default_policy.deny.clone()),
+                block_private: Some(default_policy.block_private_ranges),
+                principals: (!principals.is_empty()).then_some(principals),
+            },
+            webhooks: WebhookSettings {
+                urls: Some(webhooks.sinks.clone()),
+                secret_env: None,
+                allow_callback_urls: Some(webhooks.allow_callback_urls),
+                loss_threshold_pct: webhooks.loss_threshold_pct,
+                latency_threshold_ms: webhooks.latency_threshold_ms,
+                max_attempts: Some(webhooks.max_attempts),
+                dead_letter: webhooks.dead_letter_path.clone(),
+            },
+        }
+    }
+}
+
+/// Settings plus where they came from.
+#[derive(Debug, Clone, Default)]
+pub struct LoadedConfig {
+    pub path: Option<PathBuf>,
+    pub profile: Option<String>,
+    pub settings: Settings,
+}
+
+/// Inputs for resolving [`Settings`]; [`ConfigLoader::from_process_env`] is what the binary uses.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigLoader {
+    /// Must exist when set; otherwise the first existing `search_paths` entry is used.
+    pub explicit_path: Option<PathBuf>,
+    pub profile: Option<String>,
+    pub search_paths: Vec<PathBuf>,
+    pub env_overrides: Vec<(String, String)>,
+}
+
+impl ConfigLoader {
+    /// `explicit_path` and `profile` come from `--config`/`--profile` and fall back to
+    /// [`CONFIG_PATH_ENV`]/[`PROFILE_ENV`].
+    pub fn from_process_env(explicit_path: Option<PathBuf>, profile: Option<String>) -> Self {
+        let mut env_overrides = env::vars()
+            .filter(|(name, _)| name.starts_with(ENV_OVERRIDE_PREFIX))
+            .collect::<Vec<_>>();
+        env_overrides.sort();
+
+        Self {
+            explicit_path: explicit_path
+                .or_else(|| env::var_os(CONFIG_PATH_ENV).map(PathBuf::from))
+                .filter(|path| !path.as_os_str().is_empty()),
+            profile: profile
+                .or_else(|| env::var(PROFILE_ENV).ok())
+                .filter(|name| !name.trim().is_empty()),
+            search_paths: standard_config_paths(|name| env::var_os(name)),
+            env_overrides,
+        }
+    }
+
+    pub fn load(&self) -> Result<LoadedConfig, ConfigFileError> {
+        let path = match &self.explicit_path {
+            Some(path) if !path.is_file() => {
+                return Err(ConfigFileError::NotFound { path: path.clone() });
+            }
+            Some(path) => Some(path.clone()),
+            None => self
+                .search_paths
+                .iter()
+                .find(|path| path.is_file())
+                .cloned(),
+        };
+
+        let mut merged = toml::Table::new();
+        match (&path, &self.profile) {
+            (Some(path), profile) => {
+                merged = read_config_table(path)?;
+                let profiles = merged.remove("profiles");
+                if let Some(name) = profile {
+                    let overlay = match profiles {
+                        Some(toml::Value::Table(mut profiles)) => profiles.remove(name),
+                        _ => None,
+                    };
+                    let Some(toml::Value::Table(overlay)) = overlay else {
+                        return Err(ConfigFileError::UnknownProfile {
+                            name: name.clone(),
+                            path: path.clone(),
+                        });
+                    };
+                    merge_tables(&mut merged, overlay);
+                }
+            }
+            (None, Some(name)) => {
+                return Err(ConfigFileError::ProfileWithoutConfig { name: name.clone() });
+            }
+            (None, None) => {}
+        }
+
+        for (name, raw) in &self.env_overrides {
+            merge_tables(&mut merged, env_override_table(name, raw)?);
+        }
+
+        let settings = toml::Value::Table(merged)
+            .try_into::<Settings>()
+            .map_err(|error| ConfigFileError::Invalid(error.to_string()))?;
+
+        Ok(LoadedConfig {
+            path,
+            profile: self.profile.clone(),
+            settings,
+        })
+    }
+}
+
+/// Per-user location first, then the system-wide one.
+///
+/// Windows: `%APPDATA%\windows-mtr\config.toml`, then `%PROGRAMDATA%\windows-mtr\config.toml`.
+/// Elsewhere: `$XDG_CONFIG_HOME/windows-mtr/config.toml` (or `~/.config/...`), then
+/// `/etc/windows-mtr/config.toml`.
+pub fn standard_config_paths(var: impl Fn(&str) -> Option<OsString>) -> Vec<PathBuf> {
+    let in_app_dir = |base: PathBuf| base.join(APP_DIR_NAME).join(CONFIG_FILE_NAME);
+    let non_empty = |name: &str| var(name).filter(|value| !value.is_empty());
+
+    if cfg!(windows) {
+        ["APPDATA", "PROGRAMDATA"]
+            .into_iter()
+            .filter_map(non_empty)
+            .map(|base| in_app_dir(PathBuf::from(base)))
+            .collect()
+    } else {
+        let user = non_empty("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| non_empty("HOME").map(|home| Path::new(&home).join(".config")));
+        user.into_iter()
+            .chain([PathBuf::from("/etc")])
+            .map(in_app_dir)
+            .collect()
+    }
+}
+
+/// Parse and validate a config file; errors carry the file path and TOML line/column.
+fn read_config_table(path: &Path) -> Result<toml::Table, ConfigFileError> {
+    let text = fs::read_to_string(path).map_err(|source| ConfigFileError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let parse_error = |error: toml::de::Error| ConfigFileError::Parse {
+        path: path.to_path_buf(),
+        message: error.to_string(),
+    };
+
+    toml::from_str::<ConfigDocument>(&text).map_err(parse_error)?;
+    text.parse::<toml::Table>().map_err(parse_error)
+}
+
+/// Turn `WINDOWS_MTR__API__WEBHOOKS__MAX_ATTEMPTS=3` into `{ api = { webhooks = { max_attempts = 3 } } }`.
+fn env_override_table(name: &str, raw: &str) -> Result<toml::Table, ConfigFileError> {
+    let invalid = |message: String| ConfigFileError::InvalidEnvOverride {
+        name: name.to_string(),
+        message,
+    };
+
+    let keys = name
+        .trim_start_matches(ENV_OVERRIDE_PREFIX)
+        .split("__")
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>();
+    if keys.len() < 2 || !SECTIONS.contains(&keys[0].as_str()) || keys.iter().any(String::is_empty)
+    {
+        return Err(invalid(format!(
+            "expected {ENV_OVERRIDE_PREFIX}PROBE__<KEY> or {ENV_OVERRIDE_PREFIX}API__<KEY>"
+        )));
+    }
+
+    let mut value = parse_env_value(raw);
+    for key in keys.iter().rev() {
+        let mut table = toml::Table::new();
+        table.insert(key.clone(), value);
+        value = toml::Value::Table(table);
+    }
+    let toml::Value::Table(table) = value else {
+        unreachable!("keys is non-empty, so the value is wrapped in at least one table");
+    };
+
+    toml::Value::Table(table.clone())
+        .try_into::<Settings>()
+        .map_err(|error| invalid(error.to_string()))?;
+    Ok(table)
+}
+
+/// Values are read as TOML literals (`16`, `true`, `["a", "b"]`), falling back to a bare string.
+fn parse_env_value(raw: &str) -> toml::Value {
+    format!("value = {raw}")
+        .parse::<toml::Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+/// Later layers replace scalars and arrays, and merge into nested tables key by key.
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(overlay)) => {
+                merge_tables(existing, overlay);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn deserialize_webhook_urls<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<Url>>, D::Error> {
+    Option::<Vec<String>>::deserialize(deserializer)?
+        .map(|urls| {
+            urls.iter()
+                .map(|raw| parse_webhook_url(raw).map_err(serde::de::Error::custom))
+                .collect()
+        })
+        .transpose()
+}
+
+fn serialize_webhook_urls<S: Serializer>(
+    urls: &Option<Vec<Url>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    urls.as_ref()
+        .map(|urls| urls.iter().map(Url::as_str).collect::<Vec<_>>())
+        .serialize(serializer)
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+[probe]
+protocol = "tcp"
+port = 443
+count = 5
+output = "report"
+
+[api]
+bind = "127.0.0.1:4000"
+max_queued_probes = 32
+
+[api.target_policy]
+block_private = true
+deny = ["*.corp.example"]
+
+[api.target_policy.principals."CN=lab-runner"]
+allow = ["10.20.0.0/16"]
+
+[profiles.wan-check.probe]
+count = 50
+interval_seconds = 0.5
+
+[profiles.wan-check.api.webhooks]
+urls = ["https://hooks.example/mtr"]
+secret_env = "WEBHOOK_SECRET"
+"#;
+
+    fn write_config(contents: &str) -> (tempfile::TempDir, PathBuf) {
+        let directory = tempfile::tempdir().expect("tempdir should exist");
+        let path = directory.path().join(CONFIG_FILE_NAME);
+        fs::write(&path, contents).expect("config should write");
+        (directory, path)
+    }
+
+    fn loader(path: &Path) -> ConfigLoader {
+        ConfigLoader {
+            explicit_path: Some(path.to_path_buf()),
+            ..ConfigLoader::default()
+        }
+    }
+
+    #[test]
+    fn profile_and_env_overrides_layer_on_top_of_the_file() {
+        let (_directory, path) = write_config(SAMPLE);
+        let mut loader = loader(&path);
+        loader.profile = Some("wan-check".to_string());
+        loader.env_overrides = vec![
+            ("WINDOWS_MTR__PROBE__COUNT".to_string(), "99".to_string()),
+            (
+                "WINDOWS_MTR__API__ALLOW_INTERFACES".to_string(),
+                r#"["eth0", "eth1"]"#.to_string(),
+            ),
+            (
+                "WINDOWS_MTR__API__API_KEY_ENV".to_string(),
+                "MTR_KEY".to_string(),
+            ),
+        ];
+
+        let loaded = loader.load().expect("config should load");
+        let probe = &loaded.settings.probe;
+        assert_eq!(loaded.path.as_deref(), Some(path.as_path()));
+        assert_eq!(probe.protocol, Some(ProtocolSetting::Tcp));
+        assert_eq!(probe.port, Some(443));
+        assert_eq!(probe.count, Some(99));
+        assert_eq!(probe.interval_seconds, Some(0.5));
+        assert_eq!(probe.output, Some(OutputSetting::Report));
+
+        let api = &loaded.settings.api;
+        assert_eq!(api.api_key_env.as_deref(), Some("MTR_KEY"));
+        assert_eq!(
+            api.allow_interfaces,
+            Some(vec!["eth0".to_string(), "eth1".to_string()])
+        );
+        assert_eq!(api.webhooks.secret_env.as_deref(), Some("WEBHOOK_SECRET"));
+
+        let mut config = RestApiConfig::default();
+        api.apply_to(&mut config);
+        assert_eq!(config.bind_addr, "127.0.0.1:4000".parse().unwrap());
+        assert_eq!(config.max_queued_probes, 32);
+        assert!(config.target_policy.default.block_private_ranges);
+        assert_eq!(
+            config.target_policy.for_principal("CN=lab-runner").allow,
+            vec!["10.20.0.0/16".parse().unwrap()]
+        );
+        assert_eq!(config.webhooks.sinks.len(), 1);
+
+        let described = ApiSettings::from_config(&config);
+        let mut round_trip = RestApiConfig::default();
+        described.apply_to(&mut round_trip);
+        assert_eq!(ApiSettings::from_config(&round_trip), described);
+    }
+
+    #[test]
+    fn base_sections_apply_without_a_profile() {
+        let (_directory, path) = write_config(SAMPLE);
+        let loaded = loader(&path).load().expect("config should load");
+
+        assert_eq!(loaded.settings.probe.count, Some(5));
+        assert_eq!(loaded.settings.api.webhooks, WebhookSettings::default());
+    }
+
+    #[test]
+    fn file_errors_report_path_and_line() {
+        let (_directory, path) = write_config("[probe]\ncount = 5\nprotocl = \"tcp\"\n");
+        let error = loader(&path).load().expect_err("unknown key should fail");
+        let message = error.to_string();
+        assert!(message.contains(&path.display().to_string()), "{message}");
+        assert!(message.contains("line 3"), "{message}");
+        assert!(message.contains("protocl"), "{message}");
+
+        let (_directory, path) = write_config("[api.target_policy]\ndeny = [\"10.0.0.0/33\"]\n");
+        let message = loader(&path).load().unwrap_err().to_string();
+        assert!(message.contains("line 2"), "{message}");
+        assert!(message.contains("prefix length"), "{message}");
+    }
+
+    #[test]
+    fn unknown_profiles_and_bad_env_overrides_are_rejected() {
+        let (_directory, path) = write_config(SAMPLE);
+        let mut missing_profile = loader(&path);
+        missing_profile.profile = Some("lan".to_string());
+        assert!(matches!(
+            missing_profile.load(),
+            Err(ConfigFileError::UnknownProfile { .. })
+        ));
+
+        let without_file = ConfigLoader {
+            profile: Some("wan-check".to_string()),
+            ..ConfigLoader::default()
+        };
+        assert!(matches!(
+            without_file.load(),
+            Err(ConfigFileError::ProfileWithoutConfig { .. })
+        ));
+
+        for (name, value) in [
+            ("WINDOWS_MTR__PROBE__COUNT", "many"),
+            ("WINDOWS_MTR__API__NOT_A_FIELD", "1"),
+            ("WINDOWS_MTR__COUNT", "1"),
+        ] {
+            let env_only = ConfigLoader {
+                env_overrides: vec![(name.to_string(), value.to_string())],
+                ..ConfigLoader::default()
+            };
+            let error = env_only.load().expect_err("override should be rejected");
+            assert!(error.to_string().contains(name), "{error}");
+        }
+
+        assert!(matches!(
+            loader(&path.with_extension("missing")).load(),
+            Err(ConfigFileError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn standard_paths_prefer_the_user_location() {
+        let paths = standard_config_paths(|name| match name {
+            "XDG_CONFIG_HOME" => Some(OsString::from("/home/me/.config")),
+            "APPDATA" => Some(OsString::from(r"C:\Users\me\AppData\Roaming")),
+            "PROGRAMDATA" => Some(OsString::from(r"C:\ProgramData")),
+            _ => None,
+        });
+
+        assert_eq!(paths.len(), 2);
+        assert!(
+            paths
+                .iter()
+                .all(|path| path.ends_with("windows-mtr/config.toml")
+                    || path.ends_with(r"windows-mtr\config.toml"))
+        );
+        if !cfg!(windows) {
+            assert_eq!(
+                paths,
+                [
+                    PathBuf::from("/home/me/.config/windows-mtr/config.toml"),
+                    PathBuf::from("/etc/windows-mtr/config.toml"),
+                ]
+            );
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! `--alert` rules on the destination hop and the actions that run when they fire.
+
+use super::app::DashboardApp;
+use anyhow::Context;
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Alert rules for live targets and where fired alerts go. The loss and RTT limits are the
+/// `--loss-bad-pct` and `--latency-bad-ms` thresholds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlertConfig {
+    /// Consecutive rounds the destination's loss or average RTT must stay above its limit.
+    pub rounds: u32,
+    /// Shell command started for every alert, with the alert as JSON on stdin.
+    pub on_alert: Option<String>,
+    /// JSON Lines file every alert is appended to.
+    pub log_path: Option<PathBuf>,
+}
+
+impl AlertConfig {
+    /// Append `alert` to the log and start the `--on-alert` command.
+    pub(super) fn dispatch(&self, alert: &Alert) -> anyhow::Result<()> {
+        let payload = serde_json::to_string(alert).context("failed to encode the alert")?;
+        if let Some(path) = &self.log_path {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut log| writeln!(log, "{payload}"))
+                .with_context(|| format!("failed to write alert log {}", path.display()))?;
+        }
+        if let Some(command) = &self.on_alert {
+            run_alert_command(command, payload)?;
+        }
+        Ok(())
+    }
+}
+
+/// Start `command` in the platform shell with `payload` on stdin. Its output is discarded so it
+/// cannot draw over the dashboard.
+fn run_alert_command(command: &str, payload: String) -> anyhow::Result<()> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut child = Command::new(shell)
+        .args([flag, command])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("failed to start --on-alert command `{command}`"))?;
+    let stdin = child.stdin.take();
+    // Feed and reap the command off the UI thread; a slow hook must not stall the dashboard.
+    thread::spawn(move || {
+        if let Some(mut stdin) = stdin {
+            let _ = stdin.write_all(payload.as_bytes());
+        }
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+/// What happened at the destination.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum AlertRule {
+    Loss,
+    Rtt,
+    ResponderChanged,
+}
+
+/// A fired alert, as written to the alert log and the `--on-alert` command.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(super) struct Alert {
+    pub(super) target: String,
+    pub(super) rule: AlertRule,
+    pub(super) message: String,
+    /// Dashboard round the rule fired in.
+    pub(super) round: u64,
+    /// Milliseconds since the Unix epoch.
+    pub(super) time_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) value: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) threshold: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) previous_responder: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) responder: Option<String>,
+}
+
+/// The banner above the dashboard: only the newest alert is kept, the rest are counted.
+#[derive(Debug)]
+pub(super) struct AlertBanner {
+    pub(super) latest: Alert,
+    /// Alerts fired before `latest` since the banner was last dismissed.
+    pub(super) earlier: usize,
+}
+
+impl AlertBanner {
+    /// Put `alert` on the banner, counting the one it replaces.
+    pub(super) fn push(banner: &mut Option<Self>, alert: Alert) {
+        let earlier = banner
+            .as_ref()
+            .map_or(0, |banner| banner.earlier.saturating_add(1));
+        *banner = Some(Self {
+            latest: alert,
+            earlier,
+        });
+    }
+}
+
+/// Per-target progress of the alert rules.
+#[derive(Debug, Default)]
+pub(super) struct AlertState {
+    /// Consecutive rounds the destination's loss has been above the limit.
+    loss_rounds: u32,
+    rtt_rounds: u32,
+    /// Destination address of the latest round that had a reply.
+    pub(super) responder: Option<String>,
+}
+
+impl DashboardApp {
+    /// Evaluate the alert rules against the latest round. Loss and RTT alerts fire once when
+    /// their limit has been exceeded for `rounds` rounds in a row, and again only after a
+    /// round back under it.
+    pub(super) fn check_alerts(&mut self, rounds: u32, now: SystemTime) -> Vec<Alert> {
+        let Some(destination) = self.hops.last() else {
+            return Vec::new();
+        };
+        let state = &mut self.alert_state;
+        let loss_limit = f64::from(self.enhanced_ui.loss_bad_pct);
+        let rtt_limit = f64::from(self.enhanced_ui.latency_bad_ms);
+        let alert = |rule, message, value, threshold| Alert {
+            target: self.target.clone(),
+            rule,
+            message,
+            round: self.rounds,
+            time_ms: now
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_millis() as u64),
+            value,
+            threshold,
+            previous_responder: None,
+            responder: None,
+        };
+        let mut fired = Vec::new();
+
+        let loss = destination.loss_pct.filter(|loss| *loss > loss_limit);
+        state.loss_rounds = loss.map_or(0, |_| state.loss_rounds.saturating_add(1));
+        if let Some(loss) = loss
+            && state.loss_rounds == rounds
+        {
+            fired.push(alert(
+                AlertRule::Loss,
+                format!("destination loss {loss:.1}% above {loss_limit}% for {rounds} rounds"),
+                Some(loss),
+                Some(loss_limit),
+            ));
+        }
+
+        let rtt = destination.avg_ms.filter(|rtt| *rtt > rtt_limit);
+        state.rtt_rounds = rtt.map_or(0, |_| state.rtt_rounds.saturating_add(1));
+        if let Some(rtt) = rtt
+            && state.rtt_rounds == rounds
+        {
+            fired.push(alert(
+                AlertRule::Rtt,
+                format!("destination avg RTT {rtt:.1} ms above {rtt_limit} ms for {rounds} rounds"),
+                Some(rtt),
+                Some(rtt_limit),
+            ));
+        }
+
+        // A round without a reply says nothing about who answers, so it keeps the last one.
+        if let Some(responder) = destination.addresses.first().map(|address| &address.ip) {
+            if let Some(previous) = state.responder.as_ref().filter(|ip| *ip != responder) {
+                fired.push(Alert {
+                    previous_responder: Some(previous.clone()),
+                    responder: Some(responder.clone()),
+                    ..alert(
+                        AlertRule::ResponderChanged,
+                        format!("destination responder changed from {previous} to {responder}"),
+                        None,
+                        None,
+                    )
+                });
+            }
+            state.responder = Some(responder.clone());
+        }
+        fired
+    }
+}
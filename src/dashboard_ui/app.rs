@@ -0,0 +1,1726 @@
+//! Per-target dashboard state: hop statistics and history, view settings, key bindings and
+//! how actions change them.
+
+use super::alerts::{AlertBanner, AlertConfig, AlertState};
+use super::export::ExportFormat;
+use super::view::{DashboardOptions, TargetSnapshot};
+use super::{
+    HEATMAP_LEN, HEATMAP_SCROLL_ROUNDS, HISTORY_LEN, HOP_SAMPLE_LEN, MAX_ADDRESSES_PER_HOP,
+    MAX_POLL_INTERVAL, MIN_POLL_INTERVAL, POLL_INTERVAL, REPLAY_LONG_SEEK_MS, REPLAY_MAX_SPEED,
+    REPLAY_MIN_SPEED, REPLAY_SHORT_SEEK_MS, TAB_TITLES, TOAST_DURATION,
+};
+use crate::recording::{RecordedHop, RecordedRound, Recording};
+use crate::report_diff::parse_asn;
+use crate::report_render::{SavedHop, SavedHost, SavedReport};
+use crate::report_schema::{HopTimeline, ReportBackend, ReportProtocol, TimelineRound};
+use crate::service::{EnhancedUiConfig, ProbeRequest};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use ratatui::style::Color;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt::Write as _;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Which actions a help row or hint is about.
+pub(super) type ActionFilter = fn(DashboardAction) -> bool;
+
+#[derive(Clone)]
+pub(super) struct HopStat {
+    pub(super) hop: usize,
+    pub(super) host: String,
+    /// Responders seen at this TTL in this round.
+    pub(super) addresses: Vec<HopAddress>,
+    pub(super) loss_pct: Option<f64>,
+    pub(super) best_ms: Option<f64>,
+    pub(super) avg_ms: Option<f64>,
+    pub(super) worst_ms: Option<f64>,
+    /// RTTs measured this round, oldest first; empty when the source only has aggregates.
+    pub(super) samples: Vec<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct HopAddress {
+    pub(super) ip: String,
+    /// Reverse-DNS name, without trippy's `AS<number>` prefix.
+    pub(super) hostname: Option<String>,
+    pub(super) asn: Option<u32>,
+}
+
+impl HopAddress {
+    /// Split trippy's `AS13335 one.one.one.one` hostnames into name and ASN.
+    pub(super) fn new(ip: &str, hostname: Option<&str>) -> Self {
+        let asn = hostname.and_then(parse_asn);
+        let hostname = hostname
+            .map(|name| match asn {
+                Some(_) => name.split_once(' ').map_or("", |(_, rest)| rest),
+                None => name,
+            })
+            .map(str::trim)
+            .filter(|name| !name.is_empty() && *name != ip)
+            .map(str::to_string);
+        Self {
+            ip: ip.to_string(),
+            hostname,
+            asn,
+        }
+    }
+
+    pub(super) fn from_saved(host: &SavedHost) -> Self {
+        Self::new(&host.ip, host.hostname.as_deref())
+    }
+
+    pub(super) fn to_saved(&self) -> SavedHost {
+        SavedHost {
+            ip: self.ip.clone(),
+            hostname: self.hostname.clone(),
+        }
+    }
+
+    pub(super) fn label(&self) -> String {
+        let mut label = match &self.hostname {
+            Some(hostname) => format!("{hostname} ({})", self.ip),
+            None => self.ip.clone(),
+        };
+        if let Some(asn) = self.asn {
+            label.push_str(&format!("  AS{asn}"));
+        }
+        label
+    }
+}
+
+impl HopStat {
+    /// A hop of a saved report or an embedding tool's snapshot. The last RTT counts as this
+    /// round's sample.
+    pub(super) fn from_saved(hop: &SavedHop) -> Self {
+        Self {
+            hop: hop.ttl as usize,
+            host: hop.wide_host_label(),
+            addresses: hop.hosts.iter().map(HopAddress::from_saved).collect(),
+            loss_pct: hop.loss_pct,
+            best_ms: hop.best_ms.or(hop.avg_ms),
+            avg_ms: hop.avg_ms,
+            worst_ms: hop.worst_ms.or(hop.avg_ms),
+            samples: hop.last_ms.into_iter().collect(),
+        }
+    }
+
+    fn from_recorded(hop: &RecordedHop) -> Self {
+        Self {
+            hop: hop.ttl as usize,
+            host: hop.host.clone(),
+            addresses: SavedHost::from_label(&hop.host)
+                .map(|host| HopAddress::from_saved(&host))
+                .into_iter()
+                .collect(),
+            loss_pct: hop.loss_pct,
+            best_ms: hop.best_ms,
+            avg_ms: hop.avg_ms,
+            worst_ms: hop.worst_ms,
+            samples: Vec::new(),
+        }
+    }
+
+    pub(super) fn to_saved(&self) -> SavedHop {
+        SavedHop {
+            ttl: u32::try_from(self.hop).unwrap_or(u32::MAX),
+            hosts: self.addresses.iter().map(HopAddress::to_saved).collect(),
+            loss_pct: self.loss_pct,
+            last_ms: self.samples.last().copied(),
+            avg_ms: self.avg_ms,
+            best_ms: self.best_ms,
+            worst_ms: self.worst_ms,
+            ..SavedHop::default()
+        }
+    }
+
+    pub(super) fn to_recorded(&self) -> RecordedHop {
+        RecordedHop {
+            ttl: u32::try_from(self.hop).unwrap_or(u32::MAX),
+            host: self.host.clone(),
+            loss_pct: self.loss_pct,
+            best_ms: self.best_ms,
+            avg_ms: self.avg_ms,
+            worst_ms: self.worst_ms,
+        }
+    }
+}
+
+/// What a key press does. [`Keymap`] decides which key runs which action.
+///
+/// While the `/` search or a port or max hops prompt is open, typed text becomes the
+/// `SearchInput`/`PromptInput` variants, and Enter, Esc and Backspace submit, cancel and edit,
+/// whatever the keymap says.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum DashboardAction {
+    Quit,
+    NextTab,
+    PreviousTab,
+    ToggleHelp,
+    SelectPreviousHop,
+    SelectNextHop,
+    ClearSelection,
+    CycleSort,
+    StartSearch,
+    SearchInput(char),
+    SearchBackspace,
+    SubmitSearch,
+    CancelSearch,
+    ToggleUnresponsive,
+    CycleHostDisplay,
+    ToggleHeatmapMetric,
+    /// Back to the multi-target summary grid.
+    ShowSummary,
+    /// Open the highlighted summary grid row.
+    OpenTarget,
+    /// Show this target, counted from 0.
+    FocusTarget(usize),
+    NextTarget,
+    PreviousTarget,
+    /// Scroll the heatmap by this many rounds; negative is older.
+    ScrollHeatmap(i64),
+    Export(ExportFormat),
+    /// Put the text report on the terminal's clipboard with OSC 52.
+    CopyReport,
+    /// Pause the replay or, live, the probing.
+    TogglePause,
+    /// Move the replay clock by this many milliseconds of recording time.
+    Seek(i64),
+    SeekStart,
+    SeekEnd,
+    /// Halve the replay speed or double the live poll interval.
+    Slower,
+    /// Double the replay speed or halve the live poll interval.
+    Faster,
+    /// Clear the live hop table, charts and history.
+    ResetStats,
+    /// Switch to the next of ICMP, TCP and UDP; TCP and UDP ask for a port first.
+    CycleProtocol,
+    EditMaxHops,
+    PromptInput(char),
+    PromptBackspace,
+    SubmitPrompt,
+    CancelPrompt,
+    /// Clear the alert banner.
+    DismissAlerts,
+}
+
+/// Colours of the dashboard. The default suits dark terminals.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Theme {
+    /// Tab titles.
+    pub text: Color,
+    /// Selected tab, key names in the help overlay and the RTT histogram.
+    pub accent: Color,
+    /// Table header rows.
+    pub header: Color,
+    /// Below the warning thresholds, and the `OK` badge.
+    pub ok: Color,
+    /// Between the warning and critical thresholds, and the `WARN` badge.
+    pub warn: Color,
+    /// Above the critical thresholds, the `BAD` and `DOWN` badges and the alert banner.
+    pub bad: Color,
+    /// `ERROR` badge of a target whose latest poll failed.
+    pub error: Color,
+    /// Unanswered heatmap rounds and the `WAIT` badge.
+    pub muted: Color,
+    /// RTT chart lines.
+    pub latency: Color,
+    /// Destination latency sparkline on the Charts view.
+    pub sparkline: Color,
+    /// Loss charts and the hop detail loss timeline.
+    pub loss: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            text: Color::White,
+            accent: Color::Cyan,
+            header: Color::Yellow,
+            ok: Color::Green,
+            warn: Color::Yellow,
+            bad: Color::Red,
+            error: Color::Magenta,
+            muted: Color::DarkGray,
+            latency: Color::Green,
+            sparkline: Color::LightGreen,
+            loss: Color::Red,
+        }
+    }
+}
+
+/// Keys of [`Keymap::default`], apart from `1`-`9`, which focus the first nine targets. The help
+/// overlay and Help bar list an action's keys in this order.
+const DEFAULT_KEYS: &[(KeyCode, DashboardAction)] = &[
+    (KeyCode::Char('q'), DashboardAction::Quit),
+    (KeyCode::Tab, DashboardAction::NextTab),
+    (KeyCode::Right, DashboardAction::NextTab),
+    (KeyCode::Char('d'), DashboardAction::NextTab),
+    (KeyCode::BackTab, DashboardAction::PreviousTab),
+    (KeyCode::Left, DashboardAction::PreviousTab),
+    (KeyCode::Char('h'), DashboardAction::ToggleHelp),
+    (KeyCode::Char('?'), DashboardAction::ToggleHelp),
+    (KeyCode::Up, DashboardAction::SelectPreviousHop),
+    (KeyCode::Down, DashboardAction::SelectNextHop),
+    (KeyCode::Char('k'), DashboardAction::SelectPreviousHop),
+    (KeyCode::Char('j'), DashboardAction::SelectNextHop),
+    (KeyCode::Esc, DashboardAction::ClearSelection),
+    (KeyCode::Char('s'), DashboardAction::CycleSort),
+    (KeyCode::Char('/'), DashboardAction::StartSearch),
+    (KeyCode::Char('u'), DashboardAction::ToggleUnresponsive),
+    (KeyCode::Char('n'), DashboardAction::CycleHostDisplay),
+    (KeyCode::Char('v'), DashboardAction::ToggleHeatmapMetric),
+    (
+        KeyCode::Char(','),
+        DashboardAction::ScrollHeatmap(-HEATMAP_SCROLL_ROUNDS),
+    ),
+    (
+        KeyCode::Char('<'),
+        DashboardAction::ScrollHeatmap(-HEATMAP_SCROLL_ROUNDS),
+    ),
+    (
+        KeyCode::Char('.'),
+        DashboardAction::ScrollHeatmap(HEATMAP_SCROLL_ROUNDS),
+    ),
+    (
+        KeyCode::Char('>'),
+        DashboardAction::ScrollHeatmap(HEATMAP_SCROLL_ROUNDS),
+    ),
+    (KeyCode::Char('g'), DashboardAction::ShowSummary),
+    (KeyCode::Enter, DashboardAction::OpenTarget),
+    (KeyCode::PageUp, DashboardAction::PreviousTarget),
+    (KeyCode::PageDown, DashboardAction::NextTarget),
+    (
+        KeyCode::Char('e'),
+        DashboardAction::Export(ExportFormat::Json),
+    ),
+    (
+        KeyCode::Char('c'),
+        DashboardAction::Export(ExportFormat::Csv),
+    ),
+    (
+        KeyCode::Char('m'),
+        DashboardAction::Export(ExportFormat::Markdown),
+    ),
+    (KeyCode::Char('y'), DashboardAction::CopyReport),
+    (KeyCode::Char('p'), DashboardAction::TogglePause),
+    (KeyCode::Char(' '), DashboardAction::TogglePause),
+    (KeyCode::Char('r'), DashboardAction::ResetStats),
+    (KeyCode::Char('t'), DashboardAction::CycleProtocol),
+    (KeyCode::Char('M'), DashboardAction::EditMaxHops),
+    (KeyCode::Char('x'), DashboardAction::DismissAlerts),
+    (
+        KeyCode::Char('['),
+        DashboardAction::Seek(-REPLAY_SHORT_SEEK_MS),
+    ),
+    (
+        KeyCode::Char(']'),
+        DashboardAction::Seek(REPLAY_SHORT_SEEK_MS),
+    ),
+    (
+        KeyCode::Char('{'),
+        DashboardAction::Seek(-REPLAY_LONG_SEEK_MS),
+    ),
+    (
+        KeyCode::Char('}'),
+        DashboardAction::Seek(REPLAY_LONG_SEEK_MS),
+    ),
+    (KeyCode::Home, DashboardAction::SeekStart),
+    (KeyCode::End, DashboardAction::SeekEnd),
+    (KeyCode::Char('-'), DashboardAction::Slower),
+    (KeyCode::Char('+'), DashboardAction::Faster),
+    (KeyCode::Char('='), DashboardAction::Faster),
+];
+
+/// Which key runs which [`DashboardAction`]. The help overlay and Help bar describe the keys
+/// bound here.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Keymap {
+    bindings: HashMap<KeyCode, DashboardAction>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let digits = ('1'..='9').map(|digit| {
+            (
+                KeyCode::Char(digit),
+                DashboardAction::FocusTarget(digit as usize - '1' as usize),
+            )
+        });
+        Self {
+            bindings: DEFAULT_KEYS.iter().copied().chain(digits).collect(),
+        }
+    }
+}
+
+impl Keymap {
+    /// No keys at all, to build a layout from scratch.
+    pub fn empty() -> Self {
+        Self {
+            bindings: HashMap::new(),
+        }
+    }
+
+    /// Run `action` on `key`, returning what `key` did before.
+    pub fn bind(&mut self, key: KeyCode, action: DashboardAction) -> Option<DashboardAction> {
+        self.bindings.insert(key, action)
+    }
+
+    /// Make `key` do nothing, returning what it did before.
+    pub fn unbind(&mut self, key: KeyCode) -> Option<DashboardAction> {
+        self.bindings.remove(&key)
+    }
+
+    pub fn action(&self, key: KeyCode) -> Option<DashboardAction> {
+        self.bindings.get(&key).copied()
+    }
+
+    pub(super) fn action_for_event(&self, key: KeyEvent) -> Option<DashboardAction> {
+        if key.kind == KeyEventKind::Press {
+            self.action(key.code)
+        } else {
+            None
+        }
+    }
+
+    /// Names of the keys running any action `wanted` accepts, joined by `separator`, or `None`
+    /// when no key does. Three or more consecutive digits are shortened to `1-9`.
+    pub(super) fn keys_for(&self, wanted: ActionFilter, separator: &str) -> Option<String> {
+        let mut keys = self
+            .bindings
+            .iter()
+            .filter(|&(_, &action)| wanted(action))
+            .map(|(&key, _)| {
+                let position = DEFAULT_KEYS.iter().position(|&(default, _)| default == key);
+                (position.unwrap_or(usize::MAX), key_label(key))
+            })
+            .collect::<Vec<_>>();
+        keys.sort();
+        let (digits, mut labels): (Vec<_>, Vec<_>) = keys
+            .into_iter()
+            .map(|(_, label)| label)
+            .partition(|label| label.len() == 1 && label.as_bytes()[0].is_ascii_digit());
+        let run = digits.len() >= 3
+            && digits
+                .windows(2)
+                .all(|pair| pair[1].as_bytes()[0] == pair[0].as_bytes()[0] + 1);
+        if run {
+            labels.push(format!("{}-{}", digits[0], digits[digits.len() - 1]));
+        } else {
+            labels.extend(digits);
+        }
+        (!labels.is_empty()).then(|| labels.join(separator))
+    }
+
+    /// `keys action; keys action; ...` for the Help bar, skipping actions without a key.
+    pub(super) fn hints(&self, hints: &[(ActionFilter, &str)]) -> String {
+        hints
+            .iter()
+            .filter_map(|&(wanted, label)| {
+                self.keys_for(wanted, "/")
+                    .map(|keys| format!("{keys} {label}"))
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+fn key_label(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(ch) => ch.to_string(),
+        KeyCode::BackTab => "Shift+Tab".to_string(),
+        KeyCode::PageUp => "PgUp".to_string(),
+        KeyCode::PageDown => "PgDn".to_string(),
+        KeyCode::F(number) => format!("F{number}"),
+        other => format!("{other:?}"),
+    }
+}
+
+/// Hop table order. Metric columns sort worst first.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(super) enum HopSort {
+    #[default]
+    Hop,
+    Loss,
+    Avg,
+    Worst,
+    Best,
+    Host,
+}
+
+impl HopSort {
+    pub(super) fn next(self) -> Self {
+        match self {
+            Self::Hop => Self::Loss,
+            Self::Loss => Self::Avg,
+            Self::Avg => Self::Worst,
+            Self::Worst => Self::Best,
+            Self::Best => Self::Host,
+            Self::Host => Self::Hop,
+        }
+    }
+
+    pub(super) fn label(self) -> &'static str {
+        match self {
+            Self::Hop => "Hop",
+            Self::Loss => "Loss%",
+            Self::Avg => "Avg",
+            Self::Worst => "Worst",
+            Self::Best => "Best",
+            Self::Host => "Host",
+        }
+    }
+
+    pub(super) fn arrow(self) -> &'static str {
+        match self {
+            Self::Hop | Self::Host => "▲",
+            _ => "▼",
+        }
+    }
+
+    fn compare(self, a: &HopStat, b: &HopStat) -> std::cmp::Ordering {
+        // Missing metrics sort last.
+        let descending = |a: Option<f64>, b: Option<f64>| match (a, b) {
+            (Some(a), Some(b)) => b.total_cmp(&a),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        };
+        match self {
+            Self::Hop => a.hop.cmp(&b.hop),
+            Self::Loss => descending(a.loss_pct, b.loss_pct),
+            Self::Avg => descending(a.avg_ms, b.avg_ms),
+            Self::Worst => descending(a.worst_ms, b.worst_ms),
+            Self::Best => descending(a.best_ms, b.best_ms),
+            Self::Host => a.host.to_lowercase().cmp(&b.host.to_lowercase()),
+        }
+        .then(a.hop.cmp(&b.hop))
+    }
+}
+
+/// What the Host column shows for a responding hop.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(super) enum HostDisplay {
+    #[default]
+    Both,
+    Ip,
+    Hostname,
+}
+
+impl HostDisplay {
+    pub(super) fn next(self) -> Self {
+        match self {
+            Self::Both => Self::Ip,
+            Self::Ip => Self::Hostname,
+            Self::Hostname => Self::Both,
+        }
+    }
+
+    pub(super) fn label(self) -> &'static str {
+        match self {
+            Self::Both => "name (IP)",
+            Self::Ip => "IP",
+            Self::Hostname => "name",
+        }
+    }
+
+    pub(super) fn host_label(self, hop: &HopStat) -> String {
+        if self == Self::Both || hop.addresses.is_empty() {
+            return hop.host.clone();
+        }
+        hop.addresses
+            .iter()
+            .map(|address| match (self, &address.hostname) {
+                (Self::Hostname, Some(hostname)) => hostname.clone(),
+                _ => address.ip.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// What colours the heatmap cells.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(super) enum HeatmapMetric {
+    #[default]
+    Rtt,
+    Loss,
+}
+
+impl HeatmapMetric {
+    pub(super) fn next(self) -> Self {
+        match self {
+            Self::Rtt => Self::Loss,
+            Self::Loss => Self::Rtt,
+        }
+    }
+
+    pub(super) fn label(self) -> &'static str {
+        match self {
+            Self::Rtt => "RTT",
+            Self::Loss => "Loss",
+        }
+    }
+
+    pub(super) fn value(self, round: &TimelineRound) -> Option<f64> {
+        match self {
+            Self::Rtt => round.rtt_ms,
+            Self::Loss => round.loss_pct,
+        }
+    }
+
+    /// `(warn, bad)` from the `--latency-*-ms` and `--loss-*-pct` options.
+    pub(super) fn thresholds(self, config: &EnhancedUiConfig) -> (f64, f64) {
+        match self {
+            Self::Rtt => (
+                f64::from(config.latency_warn_ms),
+                f64::from(config.latency_bad_ms),
+            ),
+            Self::Loss => (
+                f64::from(config.loss_warn_pct),
+                f64::from(config.loss_bad_pct),
+            ),
+        }
+    }
+
+    pub(super) fn unit(self) -> &'static str {
+        match self {
+            Self::Rtt => " ms",
+            Self::Loss => "%",
+        }
+    }
+}
+
+/// Where a value falls against a warn/bad pair of `EnhancedUiConfig` thresholds.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub(super) enum Band {
+    Ok,
+    Warn,
+    Bad,
+}
+
+impl Band {
+    pub(super) fn of(value: f64, (warn, bad): (f64, f64)) -> Self {
+        if value >= bad {
+            Self::Bad
+        } else if value >= warn {
+            Self::Warn
+        } else {
+            Self::Ok
+        }
+    }
+
+    /// The worse of a hop's loss and average RTT bands. Missing metrics count as fine.
+    pub(super) fn of_hop(hop: &HopStat, config: &EnhancedUiConfig) -> Self {
+        let loss = hop.loss_pct.map_or(Self::Ok, |loss| {
+            Self::of(loss, HeatmapMetric::Loss.thresholds(config))
+        });
+        let rtt = hop.avg_ms.map_or(Self::Ok, |avg| {
+            Self::of(avg, HeatmapMetric::Rtt.thresholds(config))
+        });
+        loss.max(rtt)
+    }
+
+    pub(super) fn color(self, theme: &Theme) -> Color {
+        match self {
+            Self::Ok => theme.ok,
+            Self::Warn => theme.warn,
+            Self::Bad => theme.bad,
+        }
+    }
+}
+
+/// Where the dashboard's hop data comes from.
+pub(super) enum DashboardMode {
+    Live,
+    /// [`HopSnapshot`](super::HopSnapshot)s from an embedding tool.
+    Embedded,
+    SavedReport,
+    Replay(Replay),
+}
+
+/// Playback state for `mtr replay`.
+pub(super) struct Replay {
+    pub(super) rounds: Vec<RecordedRound>,
+    pub(super) started_at_ms: u64,
+    /// Index of the round on screen.
+    pub(super) position: usize,
+    /// Recording time being shown, in milliseconds since the session started.
+    pub(super) clock_ms: f64,
+    pub(super) speed: f64,
+    pub(super) paused: bool,
+    pub(super) last_tick: Instant,
+}
+
+impl Replay {
+    pub(super) fn end_ms(&self) -> f64 {
+        self.rounds
+            .last()
+            .map(|round| round.t_ms as f64)
+            .unwrap_or_default()
+    }
+
+    /// Index of the last round at or before `clock_ms`.
+    fn position_at(&self, clock_ms: f64) -> usize {
+        self.rounds
+            .partition_point(|round| round.t_ms as f64 <= clock_ms)
+            .saturating_sub(1)
+    }
+}
+
+/// Probe settings a live target can change without restarting.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) struct ProbeSettings {
+    pub(super) protocol: ReportProtocol,
+    /// TCP and UDP target port; kept while probing with ICMP for the next switch back.
+    pub(super) port: Option<u16>,
+    /// `None` keeps the backend default.
+    pub(super) max_hops: Option<u8>,
+    /// Pause between polls.
+    pub(super) interval: Duration,
+    pub(super) paused: bool,
+    /// Bumped when the protocol or port changes so polls still using the old ones are dropped.
+    pub(super) epoch: u64,
+}
+
+impl ProbeSettings {
+    pub(super) fn new(protocol: ReportProtocol, request: &ProbeRequest) -> Self {
+        Self {
+            protocol,
+            port: request.port,
+            max_hops: request.max_hops,
+            interval: POLL_INTERVAL,
+            paused: false,
+            epoch: 0,
+        }
+    }
+
+    /// `request` with these settings' protocol, port and max hops.
+    pub(super) fn apply_to(&self, request: &ProbeRequest) -> ProbeRequest {
+        let icmp = self.protocol == ReportProtocol::Icmp;
+        ProbeRequest {
+            tcp: self.protocol == ReportProtocol::Tcp,
+            udp: self.protocol == ReportProtocol::Udp,
+            port: self.port.filter(|_| !icmp),
+            source_port: request.source_port.filter(|_| !icmp),
+            max_hops: self.max_hops,
+            ..request.clone()
+        }
+    }
+
+    pub(super) fn backend(&self, native_icmp: bool) -> ReportBackend {
+        if native_icmp && self.protocol == ReportProtocol::Icmp {
+            ReportBackend::WindowsIcmpHelper
+        } else {
+            ReportBackend::Trippy
+        }
+    }
+
+    /// Header summary, such as `TCP:443 | max hops 30 | every 900ms`.
+    pub(super) fn label(&self) -> String {
+        let mut label = protocol_label(self.protocol).to_string();
+        if let Some(port) = self.port.filter(|_| self.protocol != ReportProtocol::Icmp) {
+            let _ = write!(label, ":{port}");
+        }
+        match self.max_hops {
+            Some(max_hops) => {
+                let _ = write!(label, " | max hops {max_hops}");
+            }
+            None => label.push_str(" | default max hops"),
+        }
+        if self.interval < Duration::from_secs(1) {
+            let _ = write!(label, " | every {}ms", self.interval.as_millis());
+        } else {
+            let _ = write!(label, " | every {:.1}s", self.interval.as_secs_f64());
+        }
+        if self.paused {
+            label.push_str(" | PAUSED");
+        }
+        label
+    }
+}
+
+fn protocol_label(protocol: ReportProtocol) -> &'static str {
+    match protocol {
+        ReportProtocol::Icmp => "ICMP",
+        ReportProtocol::Tcp => "TCP",
+        ReportProtocol::Udp => "UDP",
+    }
+}
+
+fn next_protocol(protocol: ReportProtocol) -> ReportProtocol {
+    match protocol {
+        ReportProtocol::Icmp => ReportProtocol::Tcp,
+        ReportProtocol::Tcp => ReportProtocol::Udp,
+        ReportProtocol::Udp => ReportProtocol::Icmp,
+    }
+}
+
+/// Runtime controls of a live target and the channel its poller reads them from.
+pub(super) struct LiveProbe {
+    pub(super) settings: ProbeSettings,
+    /// The native ICMP probe replaces trippy while the protocol is ICMP.
+    pub(super) native_icmp: bool,
+    control_tx: mpsc::Sender<ProbeSettings>,
+}
+
+/// A probe setting being typed; only digits are accepted.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(super) enum SettingsPrompt {
+    /// Port to switch to this protocol with.
+    Port(ReportProtocol, String),
+    /// Empty restores the backend default.
+    MaxHops(String),
+}
+
+impl SettingsPrompt {
+    fn draft_mut(&mut self) -> &mut String {
+        match self {
+            Self::Port(_, draft) | Self::MaxHops(draft) => draft,
+        }
+    }
+
+    pub(super) fn status(&self) -> String {
+        match self {
+            Self::Port(protocol, draft) => format!(
+                "{} port: {draft}_ (Enter apply, Esc cancel)",
+                protocol_label(*protocol)
+            ),
+            Self::MaxHops(draft) => {
+                format!("Max hops: {draft}_ (empty for the default; Enter apply, Esc cancel)")
+            }
+        }
+    }
+
+    /// `settings` with the typed value, or why it was rejected.
+    pub(super) fn apply(&self, mut settings: ProbeSettings) -> Result<ProbeSettings, String> {
+        match self {
+            Self::Port(protocol, draft) => {
+                let port = draft
+                    .parse::<u16>()
+                    .ok()
+                    .filter(|port| *port > 0)
+                    .ok_or_else(|| {
+                        format!("{} needs a port from 1 to 65535", protocol_label(*protocol))
+                    })?;
+                settings.protocol = *protocol;
+                settings.port = Some(port);
+            }
+            Self::MaxHops(draft) if draft.is_empty() => settings.max_hops = None,
+            Self::MaxHops(draft) => {
+                settings.max_hops = Some(
+                    draft
+                        .parse::<u8>()
+                        .ok()
+                        .filter(|max_hops| *max_hops > 0)
+                        .ok_or("Max hops must be from 1 to 255")?,
+                );
+            }
+        }
+        Ok(settings)
+    }
+}
+
+/// Bounded history of one TTL for the hop detail pane.
+#[derive(Default)]
+pub(super) struct HopHistory {
+    /// `(round, RTT ms)`: the round's latest sample, or its average without samples.
+    pub(super) rtt: VecDeque<(f64, f64)>,
+    /// `(round, loss %)`.
+    pub(super) loss: VecDeque<(f64, f64)>,
+    pub(super) samples: VecDeque<f64>,
+    /// Every address seen at this TTL, in first-seen order.
+    pub(super) addresses: Vec<HopAddress>,
+    /// RTT and loss per round, oldest first, for the heatmap and exports.
+    pub(super) rounds: VecDeque<TimelineRound>,
+}
+
+impl HopHistory {
+    pub(super) fn record(&mut self, round: u64, hop: &HopStat) {
+        let rtt = hop.samples.last().copied().or(hop.avg_ms);
+        push_bounded(
+            &mut self.rounds,
+            TimelineRound {
+                round,
+                rtt_ms: rtt,
+                loss_pct: hop.loss_pct,
+            },
+            HEATMAP_LEN,
+        );
+
+        let round = round as f64;
+        if let Some(rtt) = rtt {
+            push_bounded(&mut self.rtt, (round, rtt), HISTORY_LEN);
+        }
+        if let Some(loss) = hop.loss_pct {
+            push_bounded(&mut self.loss, (round, loss), HISTORY_LEN);
+        }
+        let samples = if hop.samples.is_empty() {
+            hop.avg_ms.as_slice()
+        } else {
+            &hop.samples
+        };
+        for sample in samples {
+            push_bounded(&mut self.samples, *sample, HOP_SAMPLE_LEN);
+        }
+
+        for address in &hop.addresses {
+            if let Some(seen) = self.addresses.iter_mut().find(|seen| seen.ip == address.ip) {
+                if address.hostname.is_some() {
+                    seen.hostname.clone_from(&address.hostname);
+                }
+                seen.asn = address.asn.or(seen.asn);
+            } else if self.addresses.len() < MAX_ADDRESSES_PER_HOP {
+                self.addresses.push(address.clone());
+            }
+        }
+    }
+
+    pub(super) fn timeline(&self, ttl: usize) -> HopTimeline {
+        HopTimeline {
+            ttl: u32::try_from(ttl).unwrap_or(u32::MAX),
+            hosts: self.addresses.iter().map(HopAddress::to_saved).collect(),
+            rounds: self.rounds.iter().copied().collect(),
+        }
+    }
+
+    /// The entry for `round`, if this TTL was in that round's snapshot.
+    pub(super) fn round(&self, round: u64) -> Option<&TimelineRound> {
+        self.rounds
+            .binary_search_by_key(&round, |entry| entry.round)
+            .ok()
+            .map(|index| &self.rounds[index])
+    }
+}
+
+fn push_bounded<T>(buffer: &mut VecDeque<T>, value: T, capacity: usize) {
+    if buffer.len() == capacity {
+        buffer.pop_front();
+    }
+    buffer.push_back(value);
+}
+
+pub struct DashboardApp {
+    pub(super) target: String,
+    pub(super) tab_index: usize,
+    pub(super) hops: Vec<HopStat>,
+    pub(super) latency_history: Vec<(f64, f64)>,
+    pub(super) loss_history: Vec<(f64, f64)>,
+    pub(super) hop_history: BTreeMap<usize, HopHistory>,
+    /// Snapshots ingested so far; the x axis of the per-hop charts.
+    pub(super) rounds: u64,
+    /// TTL of the hop open in the detail pane.
+    pub(super) selected_ttl: Option<usize>,
+    pub(super) started_at: Instant,
+    pub(super) last_error: Option<String>,
+    pub(super) consecutive_poll_failures: u32,
+    pub(super) show_help: bool,
+    pub(super) sort: HopSort,
+    /// Committed `/` search; matches host names and addresses, case-insensitively.
+    pub(super) filter: String,
+    /// Search being typed; `Some` while the `/` prompt is open.
+    search_draft: Option<String>,
+    /// Hide hops with 100% loss.
+    hide_unresponsive: bool,
+    pub(super) host_display: HostDisplay,
+    /// Warn/bad thresholds and the row colouring, sparkline and summary toggles.
+    pub(super) enhanced_ui: EnhancedUiConfig,
+    pub(super) theme: Theme,
+    pub(super) heatmap_metric: HeatmapMetric,
+    /// Rounds the heatmap is scrolled back from the latest; 0 follows new rounds.
+    pub(super) heatmap_scroll: u64,
+    /// Protocol and backend stamped on exports; `None` when there is nothing new to export.
+    pub(super) export_source: Option<(Option<ReportProtocol>, ReportBackend)>,
+    /// Directory exports are written to.
+    pub(super) export_dir: PathBuf,
+    /// Help bar message and when it expires.
+    pub(super) toast: Option<(String, Instant)>,
+    /// OSC 52 sequence for the UI loop to write to the terminal.
+    pub(super) pending_clipboard: Option<String>,
+    /// Runtime probe controls; `None` for saved reports and replays.
+    pub(super) probe: Option<LiveProbe>,
+    /// Port or max hops being typed.
+    pub(super) settings_prompt: Option<SettingsPrompt>,
+    pub(super) alert_state: AlertState,
+    pub(super) mode: DashboardMode,
+}
+
+impl DashboardApp {
+    pub(super) fn new(target: &str) -> Self {
+        Self {
+            target: target.to_string(),
+            tab_index: 0,
+            hops: Vec::new(),
+            latency_history: Vec::new(),
+            loss_history: Vec::new(),
+            hop_history: BTreeMap::new(),
+            rounds: 0,
+            selected_ttl: None,
+            started_at: Instant::now(),
+            last_error: None,
+            consecutive_poll_failures: 0,
+            show_help: false,
+            sort: HopSort::default(),
+            filter: String::new(),
+            search_draft: None,
+            hide_unresponsive: false,
+            host_display: HostDisplay::default(),
+            enhanced_ui: EnhancedUiConfig::default(),
+            theme: Theme::default(),
+            heatmap_metric: HeatmapMetric::default(),
+            heatmap_scroll: 0,
+            export_source: None,
+            export_dir: PathBuf::from("."),
+            toast: None,
+            pending_clipboard: None,
+            probe: None,
+            settings_prompt: None,
+            alert_state: AlertState::default(),
+            mode: DashboardMode::Live,
+        }
+    }
+
+    /// A live target probing with `settings`. Every later change is sent to the returned
+    /// receiver, which the target's poller reads.
+    pub(super) fn live(
+        target: &str,
+        settings: ProbeSettings,
+        native_icmp: bool,
+        enhanced_ui: EnhancedUiConfig,
+    ) -> (Self, mpsc::Receiver<ProbeSettings>) {
+        let (control_tx, control_rx) = mpsc::channel();
+        let mut app = Self::new(target);
+        app.export_source = Some((Some(settings.protocol), settings.backend(native_icmp)));
+        app.enhanced_ui = enhanced_ui;
+        app.probe = Some(LiveProbe {
+            settings,
+            native_icmp,
+            control_tx,
+        });
+        (app, control_rx)
+    }
+
+    pub(super) fn saved(report: &SavedReport) -> Self {
+        let target = report.target.as_deref().unwrap_or("unknown target");
+        let mut app = Self::new(target);
+        app.mode = DashboardMode::SavedReport;
+        app.ingest_snapshot(
+            report
+                .hops
+                .iter()
+                .map(|hop| HopStat {
+                    samples: Vec::new(),
+                    ..HopStat::from_saved(hop)
+                })
+                .collect(),
+        );
+        app
+    }
+
+    /// A target whose snapshots come from an embedding tool. The probe controls do not apply.
+    pub(super) fn external(target: &str, options: &DashboardOptions) -> Self {
+        let mut app = Self::new(target);
+        app.mode = DashboardMode::Embedded;
+        app.export_source = Some((options.protocol, options.backend));
+        app.enhanced_ui = options.enhanced_ui;
+        app.theme = options.theme;
+        app
+    }
+
+    pub(super) fn replay(recording: Recording, speed: f64) -> Self {
+        let mut app = Self::new(&recording.header.target);
+        app.export_source = Some((None, ReportBackend::Recording));
+        let first = recording.rounds.first().map(|round| round.t_ms as f64);
+        app.mode = DashboardMode::Replay(Replay {
+            rounds: recording.rounds,
+            started_at_ms: recording.header.started_at_ms,
+            position: 0,
+            clock_ms: first.unwrap_or_default(),
+            speed: speed.clamp(REPLAY_MIN_SPEED, REPLAY_MAX_SPEED),
+            paused: false,
+            last_tick: Instant::now(),
+        });
+        app.seek_replay(first.unwrap_or_default());
+        app
+    }
+
+    pub(super) fn is_read_only(&self) -> bool {
+        !matches!(self.mode, DashboardMode::Live | DashboardMode::Embedded)
+    }
+
+    /// Advance the replay clock by the wall time since the last tick and show the rounds it
+    /// passed. Pauses at the end of the recording.
+    pub(super) fn tick(&mut self, now: Instant) {
+        let DashboardMode::Replay(replay) = &mut self.mode else {
+            return;
+        };
+        let elapsed = now.saturating_duration_since(replay.last_tick);
+        replay.last_tick = now;
+        if replay.paused {
+            return;
+        }
+
+        replay.clock_ms =
+            (replay.clock_ms + elapsed.as_secs_f64() * 1000.0 * replay.speed).min(replay.end_ms());
+        if replay.clock_ms >= replay.end_ms() {
+            replay.paused = true;
+        }
+        let position = replay.position_at(replay.clock_ms);
+        let reached = replay.rounds[replay.position + 1..=position.max(replay.position)]
+            .iter()
+            .map(|round| round.hops.iter().map(HopStat::from_recorded).collect())
+            .collect::<Vec<Vec<_>>>();
+        replay.position = position.max(replay.position);
+        for hops in reached {
+            self.ingest_snapshot(hops);
+        }
+    }
+
+    /// Jump the replay to `clock_ms` and rebuild the charts from the rounds before it.
+    fn seek_replay(&mut self, clock_ms: f64) {
+        let DashboardMode::Replay(replay) = &mut self.mode else {
+            return;
+        };
+        replay.clock_ms = clock_ms.clamp(0.0, replay.end_ms());
+        replay.position = replay.position_at(replay.clock_ms);
+        let window = replay.rounds
+            [replay.position.saturating_sub(HISTORY_LEN - 1)..=replay.position]
+            .iter()
+            .map(|round| round.hops.iter().map(HopStat::from_recorded).collect())
+            .collect::<Vec<Vec<_>>>();
+
+        self.hops.clear();
+        self.latency_history.clear();
+        self.loss_history.clear();
+        self.hop_history.clear();
+        self.rounds = 0;
+        for hops in window {
+            self.ingest_snapshot(hops);
+        }
+    }
+
+    fn apply_replay_action(&mut self, action: DashboardAction) {
+        let DashboardMode::Replay(replay) = &mut self.mode else {
+            return;
+        };
+        let clock_ms = replay.clock_ms;
+        match action {
+            DashboardAction::TogglePause => {
+                replay.paused = !replay.paused;
+                // Restarting from the end plays the recording again.
+                if !replay.paused && replay.clock_ms >= replay.end_ms() {
+                    self.seek_replay(0.0);
+                }
+            }
+            DashboardAction::Seek(offset_ms) => self.seek_replay(clock_ms + offset_ms as f64),
+            DashboardAction::SeekStart => self.seek_replay(0.0),
+            DashboardAction::SeekEnd => {
+                let end_ms = replay.end_ms();
+                self.seek_replay(end_ms);
+            }
+            DashboardAction::Slower => {
+                replay.speed = (replay.speed / 2.0).max(REPLAY_MIN_SPEED);
+            }
+            DashboardAction::Faster => {
+                replay.speed = (replay.speed * 2.0).min(REPLAY_MAX_SPEED);
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_probe_action(&mut self, action: DashboardAction) {
+        let Some(probe) = &self.probe else {
+            return;
+        };
+        let mut settings = probe.settings;
+        match action {
+            DashboardAction::TogglePause => settings.paused = !settings.paused,
+            DashboardAction::Slower => {
+                settings.interval = (settings.interval * 2).min(MAX_POLL_INTERVAL);
+            }
+            DashboardAction::Faster => {
+                settings.interval = (settings.interval / 2).max(MIN_POLL_INTERVAL);
+            }
+            DashboardAction::ResetStats => {
+                self.reset_stats();
+                self.show_toast("Statistics reset".to_string());
+                return;
+            }
+            DashboardAction::CycleProtocol => match next_protocol(settings.protocol) {
+                ReportProtocol::Icmp => settings.protocol = ReportProtocol::Icmp,
+                protocol => {
+                    let draft = settings.port.map(|port| port.to_string());
+                    self.settings_prompt =
+                        Some(SettingsPrompt::Port(protocol, draft.unwrap_or_default()));
+                    return;
+                }
+            },
+            DashboardAction::EditMaxHops => {
+                let draft = settings.max_hops.map(|max_hops| max_hops.to_string());
+                self.settings_prompt = Some(SettingsPrompt::MaxHops(draft.unwrap_or_default()));
+                return;
+            }
+            _ => return,
+        }
+        self.update_probe(settings);
+    }
+
+    fn apply_prompt_action(&mut self, action: DashboardAction) {
+        let Some(prompt) = &mut self.settings_prompt else {
+            return;
+        };
+        match action {
+            DashboardAction::PromptInput(c) if c.is_ascii_digit() => prompt.draft_mut().push(c),
+            DashboardAction::PromptBackspace => {
+                prompt.draft_mut().pop();
+            }
+            DashboardAction::SubmitPrompt => {
+                let Some(probe) = &self.probe else {
+                    return;
+                };
+                // A rejected value keeps the prompt open so it can be corrected.
+                match prompt.apply(probe.settings) {
+                    Ok(settings) => {
+                        self.settings_prompt = None;
+                        self.update_probe(settings);
+                    }
+                    Err(message) => self.show_toast(message),
+                }
+            }
+            DashboardAction::CancelPrompt => self.settings_prompt = None,
+            _ => {}
+        }
+    }
+
+    /// Hand new settings to the poller, which applies them before its next poll. A new protocol
+    /// or port starts the statistics over.
+    fn update_probe(&mut self, mut settings: ProbeSettings) {
+        let Some(probe) = &mut self.probe else {
+            return;
+        };
+        let restarted =
+            (settings.protocol, settings.port) != (probe.settings.protocol, probe.settings.port);
+        if restarted {
+            settings.epoch += 1;
+        }
+        probe.settings = settings;
+        // The poller outlives the dashboard, so a closed channel only means it is shutting down.
+        let _ = probe.control_tx.send(settings);
+        self.export_source = Some((Some(settings.protocol), settings.backend(probe.native_icmp)));
+        if restarted {
+            self.reset_stats();
+        }
+    }
+
+    /// Forget every round so far, like mtr's `r`.
+    pub(super) fn reset_stats(&mut self) {
+        self.hops.clear();
+        self.latency_history.clear();
+        self.loss_history.clear();
+        self.hop_history.clear();
+        self.rounds = 0;
+        self.selected_ttl = None;
+        self.heatmap_scroll = 0;
+        self.last_error = None;
+        self.consecutive_poll_failures = 0;
+        self.started_at = Instant::now();
+        self.alert_state = AlertState::default();
+    }
+
+    pub(super) fn ingest_snapshot(&mut self, hops: Vec<HopStat>) {
+        if hops.is_empty() {
+            self.last_error = Some("No hop data returned by the probe".to_string());
+            self.consecutive_poll_failures = self.consecutive_poll_failures.saturating_add(1);
+            return;
+        }
+
+        self.hops = hops;
+        self.last_error = None;
+        self.consecutive_poll_failures = 0;
+
+        let x = self.latency_history.len().max(self.loss_history.len()) as f64;
+        if let Some(latency) = self.hops.last().and_then(|hop| hop.avg_ms) {
+            self.latency_history.push((x, latency));
+        }
+        if let Some(loss) = self.hops.last().and_then(|hop| hop.loss_pct) {
+            self.loss_history.push((x, loss));
+        }
+
+        if self.latency_history.len() > HISTORY_LEN {
+            self.latency_history.remove(0);
+        }
+        if self.loss_history.len() > HISTORY_LEN {
+            self.loss_history.remove(0);
+        }
+
+        self.rounds += 1;
+        for hop in &self.hops {
+            self.hop_history
+                .entry(hop.hop)
+                .or_default()
+                .record(self.rounds, hop);
+        }
+    }
+
+    pub(super) fn selected_hop(&self) -> Option<&HopStat> {
+        let ttl = self.selected_ttl?;
+        self.hops.iter().find(|hop| hop.hop == ttl)
+    }
+
+    /// Hop table rows after the search, the unresponsive-hop toggle and the sort.
+    pub(super) fn visible_hops(&self) -> Vec<&HopStat> {
+        let needle = self
+            .search_draft
+            .as_deref()
+            .unwrap_or(&self.filter)
+            .trim()
+            .to_lowercase();
+        let mut hops = self
+            .hops
+            .iter()
+            .filter(|hop| {
+                !(self.hide_unresponsive && hop.loss_pct.is_some_and(|loss| loss >= 100.0))
+            })
+            .filter(|hop| needle.is_empty() || hop_matches(hop, &needle))
+            .collect::<Vec<_>>();
+        hops.sort_by(|a, b| self.sort.compare(a, b));
+        hops
+    }
+
+    /// Move the selection `offset` rows, starting from the first or last row when nothing is
+    /// selected yet. Selecting a hop shows the Hops tab.
+    fn move_selection(&mut self, offset: isize) {
+        let visible = self.visible_hops();
+        if visible.is_empty() {
+            return;
+        }
+        let last = visible.len() - 1;
+        let index = match self
+            .selected_ttl
+            .and_then(|ttl| visible.iter().position(|hop| hop.hop == ttl))
+        {
+            Some(index) => index.saturating_add_signed(offset).min(last),
+            None if offset < 0 => last,
+            None => 0,
+        };
+        self.selected_ttl = Some(visible[index].hop);
+        self.tab_index = 1;
+    }
+
+    /// While the `/` or a settings prompt is open, keys edit it instead of driving the
+    /// dashboard.
+    pub(super) fn action_for_event(
+        &self,
+        key: KeyEvent,
+        keymap: &Keymap,
+    ) -> Option<DashboardAction> {
+        if self.search_draft.is_none() && self.settings_prompt.is_none() {
+            return keymap.action_for_event(key);
+        }
+        if key.kind != KeyEventKind::Press {
+            return None;
+        }
+        if self.settings_prompt.is_some() {
+            return match key.code {
+                KeyCode::Char(c) => Some(DashboardAction::PromptInput(c)),
+                KeyCode::Backspace => Some(DashboardAction::PromptBackspace),
+                KeyCode::Enter => Some(DashboardAction::SubmitPrompt),
+                KeyCode::Esc => Some(DashboardAction::CancelPrompt),
+                _ => None,
+            };
+        }
+        match key.code {
+            KeyCode::Char(c) => Some(DashboardAction::SearchInput(c)),
+            KeyCode::Backspace => Some(DashboardAction::SearchBackspace),
+            KeyCode::Enter => Some(DashboardAction::SubmitSearch),
+            KeyCode::Esc => Some(DashboardAction::CancelSearch),
+            _ => None,
+        }
+    }
+
+    fn apply_view_action(&mut self, action: DashboardAction) {
+        match action {
+            DashboardAction::CycleSort => self.sort = self.sort.next(),
+            DashboardAction::StartSearch => {
+                self.search_draft = Some(self.filter.clone());
+                self.tab_index = 1;
+            }
+            DashboardAction::SearchInput(c) => {
+                if let Some(draft) = &mut self.search_draft {
+                    draft.push(c);
+                }
+            }
+            DashboardAction::SearchBackspace => {
+                if let Some(draft) = &mut self.search_draft {
+                    draft.pop();
+                }
+            }
+            DashboardAction::SubmitSearch => {
+                if let Some(draft) = self.search_draft.take() {
+                    self.filter = draft.trim().to_string();
+                }
+            }
+            DashboardAction::CancelSearch => self.search_draft = None,
+            DashboardAction::ToggleUnresponsive => {
+                self.hide_unresponsive = !self.hide_unresponsive;
+            }
+            DashboardAction::CycleHostDisplay => self.host_display = self.host_display.next(),
+            DashboardAction::ToggleHeatmapMetric => {
+                self.heatmap_metric = self.heatmap_metric.next();
+                self.tab_index = 3;
+            }
+            DashboardAction::ScrollHeatmap(rounds) => {
+                let retained = self.rounds.min(HEATMAP_LEN as u64);
+                self.heatmap_scroll = self
+                    .heatmap_scroll
+                    .saturating_add_signed(-rounds)
+                    .min(retained.saturating_sub(1));
+                self.tab_index = 3;
+            }
+            _ => {}
+        }
+    }
+
+    /// Non-default sort, filter and display settings, for the Help bar.
+    pub(super) fn view_status(&self) -> Vec<String> {
+        let mut status = Vec::new();
+        if let Some(prompt) = &self.settings_prompt {
+            status.push(prompt.status());
+        }
+        if let Some(draft) = &self.search_draft {
+            status.push(format!("Search: {draft}_ (Enter apply, Esc cancel)"));
+        } else if !self.filter.is_empty() {
+            status.push(format!("Filter: \"{}\" (/ to edit)", self.filter));
+        }
+        if self.sort != HopSort::Hop {
+            status.push(format!("Sort: {}", self.sort.label()));
+        }
+        if self.hide_unresponsive {
+            status.push("Hiding ??? hops".to_string());
+        }
+        if self.host_display != HostDisplay::Both {
+            status.push(format!("Hosts: {}", self.host_display.label()));
+        }
+        status
+    }
+
+    pub(super) fn show_toast(&mut self, message: String) {
+        self.toast = Some((message, Instant::now() + TOAST_DURATION));
+    }
+
+    pub(super) fn active_toast(&self) -> Option<&str> {
+        self.toast
+            .as_ref()
+            .filter(|(_, expires)| Instant::now() < *expires)
+            .map(|(message, _)| message.as_str())
+    }
+
+    /// Rounds shown in a heatmap `columns` cells wide. Stays full-width while scrolled back.
+    pub(super) fn heatmap_window(&self, columns: usize) -> RangeInclusive<u64> {
+        let columns = columns.max(1) as u64;
+        let oldest = self.rounds.saturating_sub(HEATMAP_LEN as u64 - 1).max(1);
+        let newest = self
+            .rounds
+            .saturating_sub(self.heatmap_scroll)
+            .max((oldest + columns - 1).min(self.rounds));
+        newest.saturating_sub(columns - 1).max(oldest)..=newest
+    }
+
+    pub(super) fn ingest_error(&mut self, err: anyhow::Error) {
+        self.last_error = Some(err.to_string());
+        self.consecutive_poll_failures = self.consecutive_poll_failures.saturating_add(1);
+    }
+
+    fn next_tab(&mut self) {
+        self.tab_index = (self.tab_index + 1) % TAB_TITLES.len();
+    }
+
+    fn prev_tab(&mut self) {
+        self.tab_index = (self.tab_index + TAB_TITLES.len() - 1) % TAB_TITLES.len();
+    }
+
+    pub(super) fn apply_action(&mut self, action: DashboardAction) -> bool {
+        match action {
+            DashboardAction::Quit => true,
+            DashboardAction::NextTab => {
+                self.next_tab();
+                false
+            }
+            DashboardAction::PreviousTab => {
+                self.prev_tab();
+                false
+            }
+            DashboardAction::ToggleHelp => {
+                self.show_help = !self.show_help;
+                false
+            }
+            DashboardAction::SelectPreviousHop => {
+                self.move_selection(-1);
+                false
+            }
+            DashboardAction::SelectNextHop => {
+                self.move_selection(1);
+                false
+            }
+            DashboardAction::ClearSelection => {
+                self.selected_ttl = None;
+                false
+            }
+            DashboardAction::CycleSort
+            | DashboardAction::StartSearch
+            | DashboardAction::SearchInput(_)
+            | DashboardAction::SearchBackspace
+            | DashboardAction::SubmitSearch
+            | DashboardAction::CancelSearch
+            | DashboardAction::ToggleUnresponsive
+            | DashboardAction::CycleHostDisplay
+            | DashboardAction::ToggleHeatmapMetric
+            | DashboardAction::ScrollHeatmap(_) => {
+                self.apply_view_action(action);
+                false
+            }
+            DashboardAction::Export(format) => {
+                self.export(format, SystemTime::now());
+                false
+            }
+            DashboardAction::CopyReport => {
+                self.copy_report();
+                false
+            }
+            DashboardAction::ShowSummary
+            | DashboardAction::OpenTarget
+            | DashboardAction::FocusTarget(_)
+            | DashboardAction::NextTarget
+            | DashboardAction::PreviousTarget => false,
+            DashboardAction::TogglePause | DashboardAction::Slower | DashboardAction::Faster
+                if self.probe.is_some() =>
+            {
+                self.apply_probe_action(action);
+                false
+            }
+            DashboardAction::TogglePause
+            | DashboardAction::Seek(_)
+            | DashboardAction::SeekStart
+            | DashboardAction::SeekEnd
+            | DashboardAction::Slower
+            | DashboardAction::Faster => {
+                self.apply_replay_action(action);
+                false
+            }
+            DashboardAction::ResetStats
+            | DashboardAction::CycleProtocol
+            | DashboardAction::EditMaxHops => {
+                self.apply_probe_action(action);
+                false
+            }
+            DashboardAction::PromptInput(_)
+            | DashboardAction::PromptBackspace
+            | DashboardAction::SubmitPrompt
+            | DashboardAction::CancelPrompt => {
+                self.apply_prompt_action(action);
+                false
+            }
+            DashboardAction::DismissAlerts => false,
+        }
+    }
+}
+
+/// Every target on screen. With more than one, a summary grid lists them and each opens into
+/// its own Overview/Hops/Charts/Heatmap views.
+pub(super) struct Dashboard {
+    pub(super) targets: Vec<DashboardApp>,
+    /// Target shown in the per-target views; `None` shows the summary grid.
+    pub(super) focus: Option<usize>,
+    /// Highlighted summary grid row.
+    pub(super) cursor: usize,
+    /// `None` unless alerting was asked for.
+    pub(super) alerts: Option<AlertConfig>,
+    /// Newest alert since the banner was last dismissed, and how many came before it.
+    pub(super) alert_banner: Option<AlertBanner>,
+    /// Ring the terminal bell before the next frame.
+    pending_bell: bool,
+    /// Colours of the summary grid, target tabs and alert banner; each target has its own copy.
+    pub(super) theme: Theme,
+    pub(super) keymap: Keymap,
+}
+
+impl Dashboard {
+    pub(super) fn new(targets: Vec<DashboardApp>) -> Self {
+        let focus = (targets.len() == 1).then_some(0);
+        Self {
+            targets,
+            focus,
+            cursor: 0,
+            alerts: None,
+            alert_banner: None,
+            pending_bell: false,
+            theme: Theme::default(),
+            keymap: Keymap::default(),
+        }
+    }
+
+    pub(super) fn ingest(&mut self, snapshot: TargetSnapshot) {
+        let Some(app) = self.targets.get_mut(snapshot.index) else {
+            return;
+        };
+        if app
+            .probe
+            .as_ref()
+            .is_some_and(|probe| probe.settings.epoch != snapshot.epoch)
+        {
+            return;
+        }
+        match snapshot.hops {
+            Ok(hops) => app.ingest_snapshot(hops),
+            Err(err) => {
+                app.ingest_error(err);
+                return;
+            }
+        }
+
+        let Some(config) = &self.alerts else {
+            return;
+        };
+        for alert in app.check_alerts(config.rounds, SystemTime::now()) {
+            if let Err(err) = config.dispatch(&alert) {
+                app.show_toast(format!("Alert action failed: {err:#}"));
+            }
+            self.pending_bell = true;
+            AlertBanner::push(&mut self.alert_banner, alert);
+        }
+    }
+
+    pub(super) fn tick(&mut self, now: Instant) {
+        for app in &mut self.targets {
+            app.tick(now);
+        }
+    }
+
+    /// Escape sequences for the terminal itself: an OSC 52 clipboard copy and the alert bell.
+    pub(super) fn take_terminal_output(&mut self) -> Option<String> {
+        let mut output = self
+            .targets
+            .iter_mut()
+            .find_map(|app| app.pending_clipboard.take())
+            .unwrap_or_default();
+        if std::mem::take(&mut self.pending_bell) {
+            output.push('\x07');
+        }
+        (!output.is_empty()).then_some(output)
+    }
+
+    pub(super) fn action_for_event(&self, key: KeyEvent) -> Option<DashboardAction> {
+        match self.focus {
+            Some(index) => self.targets[index].action_for_event(key, &self.keymap),
+            None => self.keymap.action_for_event(key),
+        }
+    }
+
+    pub(super) fn apply_action(&mut self, action: DashboardAction) -> bool {
+        if action == DashboardAction::DismissAlerts {
+            self.alert_banner = None;
+            return false;
+        }
+        let count = self.targets.len();
+        if count == 0 {
+            return action == DashboardAction::Quit;
+        }
+        let Some(index) = self.focus else {
+            match action {
+                DashboardAction::Quit => return true,
+                DashboardAction::SelectPreviousHop => {
+                    self.cursor = (self.cursor + count - 1) % count;
+                }
+                DashboardAction::SelectNextHop => self.cursor = (self.cursor + 1) % count,
+                DashboardAction::OpenTarget => self.focus = Some(self.cursor),
+                DashboardAction::FocusTarget(target) if target < count => {
+                    self.focus = Some(target);
+                }
+                _ => {}
+            }
+            return false;
+        };
+        if count == 1 {
+            return self.targets[index].apply_action(action);
+        }
+
+        match action {
+            DashboardAction::ShowSummary => {
+                self.focus = None;
+                self.cursor = index;
+            }
+            DashboardAction::FocusTarget(target) if target < count => {
+                self.focus = Some(target);
+            }
+            DashboardAction::NextTarget => self.focus = Some((index + 1) % count),
+            DashboardAction::PreviousTarget => self.focus = Some((index + count - 1) % count),
+            _ => return self.targets[index].apply_action(action),
+        }
+        false
+    }
+}
+
+/// Summary grid badge for a target's destination.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum Health {
+    Waiting,
+    Ok,
+    Warn,
+    Bad,
+    Down,
+    /// The latest poll failed; any metrics shown are stale.
+    Error,
+}
+
+impl Health {
+    pub(super) fn of(app: &DashboardApp) -> Self {
+        if app.last_error.is_some() {
+            return Self::Error;
+        }
+        let Some(destination) = app.hops.last() else {
+            return Self::Waiting;
+        };
+        if destination.loss_pct.is_some_and(|loss| loss >= 100.0) {
+            return Self::Down;
+        }
+        match Band::of_hop(destination, &app.enhanced_ui) {
+            Band::Ok => Self::Ok,
+            Band::Warn => Self::Warn,
+            Band::Bad => Self::Bad,
+        }
+    }
+
+    pub(super) fn label(self) -> &'static str {
+        match self {
+            Self::Waiting => "WAIT",
+            Self::Ok => "OK",
+            Self::Warn => "WARN",
+            Self::Bad => "BAD",
+            Self::Down => "DOWN",
+            Self::Error => "ERROR",
+        }
+    }
+
+    pub(super) fn color(self, theme: &Theme) -> Color {
+        match self {
+            Self::Waiting => theme.muted,
+            Self::Ok => theme.ok,
+            Self::Warn => theme.warn,
+            Self::Bad | Self::Down => theme.bad,
+            Self::Error => theme.error,
+        }
+    }
+}
+
+fn hop_matches(hop: &HopStat, needle: &str) -> bool {
+    hop.host.to_lowercase().contains(needle)
+        || hop.addresses.iter().any(|address| {
+            address.ip.to_lowercase().contains(needle)
+                || address
+                    .hostname
+                    .as_deref()
+                    .is_some_and(|hostname| hostname.to_lowercase().contains(needle))
+        })
+}
@@ -0,0 +1,247 @@
+//! Saving and copying the current view as a JSON, CSV, Markdown or text report.
+
+use super::app::{DashboardApp, HopStat};
+use super::render::format_clock;
+use crate::report_render::{SavedHost, render_markdown, render_text};
+use crate::report_schema::{HopTimeline, Report, ReportDocument};
+use anyhow::{Context, anyhow};
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File format of [`DashboardAction::Export`](super::DashboardAction::Export).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExportFormat {
+    /// Report document with the per-hop history, valid against `mtr schema --print`.
+    Json,
+    /// One row per hop per round of history.
+    Csv,
+    /// The Markdown report table followed by a per-hop history summary.
+    Markdown,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::Markdown => "md",
+        }
+    }
+}
+
+fn export_contents(document: ReportDocument, format: ExportFormat) -> anyhow::Result<String> {
+    let history = document.report.history.clone().unwrap_or_default();
+    match format {
+        ExportFormat::Json => Ok(format!("{}\n", serde_json::to_string_pretty(&document)?)),
+        ExportFormat::Csv => history_csv(&history),
+        ExportFormat::Markdown => Ok(format!(
+            "{}\n{}",
+            render_markdown(&document.into()),
+            history_markdown(&history)
+        )),
+    }
+}
+
+fn history_csv(history: &[HopTimeline]) -> anyhow::Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(["round", "hop", "hosts", "rtt_ms", "loss_pct"])?;
+    for hop in history {
+        let hosts = timeline_hosts(hop, "; ");
+        for round in &hop.rounds {
+            writer.write_record([
+                round.round.to_string(),
+                hop.ttl.to_string(),
+                hosts.clone(),
+                round.rtt_ms.map(|rtt| rtt.to_string()).unwrap_or_default(),
+                round
+                    .loss_pct
+                    .map(|loss| loss.to_string())
+                    .unwrap_or_default(),
+            ])?;
+        }
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+fn history_markdown(history: &[HopTimeline]) -> String {
+    let mut out = "#### History\n\n".to_string();
+    out.push_str("| Hop | Hosts seen | Rounds | Avg loss % | Avg RTT | Worst RTT |\n");
+    out.push_str("| ---: | --- | ---: | ---: | ---: | ---: |\n");
+    for hop in history {
+        let rtts = hop
+            .rounds
+            .iter()
+            .filter_map(|round| round.rtt_ms)
+            .collect::<Vec<_>>();
+        let losses = hop
+            .rounds
+            .iter()
+            .filter_map(|round| round.loss_pct)
+            .collect::<Vec<_>>();
+        let hosts = timeline_hosts(hop, ", ");
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {} | {} | {} |",
+            hop.ttl,
+            if hosts.is_empty() { "???" } else { &hosts }.replace('|', "\\|"),
+            hop.rounds.len(),
+            markdown_metric(mean(&losses)),
+            markdown_metric(mean(&rtts)),
+            markdown_metric(rtts.iter().copied().reduce(f64::max)),
+        );
+    }
+    out
+}
+
+/// Missing values as `???`, like the report table above the history.
+fn markdown_metric(value: Option<f64>) -> String {
+    value.map_or_else(|| "???".to_string(), |metric| format!("{metric:.1}"))
+}
+
+fn timeline_hosts(hop: &HopTimeline, separator: &str) -> String {
+    hop.hosts
+        .iter()
+        .map(SavedHost::label)
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+pub(super) fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// `mtr-<target>-20261018-142305Z.json`, with anything but letters, digits, `.` and `-` in the
+/// target replaced so IPv6 addresses make valid Windows file names.
+pub(super) fn export_file_name(target: &str, now: SystemTime, extension: &str) -> String {
+    let target = target
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let secs = now
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    format!("mtr-{target}-{}.{extension}", utc_timestamp(secs))
+}
+
+/// `YYYYMMDD-HHMMSSZ` for seconds since the Unix epoch.
+pub(super) fn utc_timestamp(secs: u64) -> String {
+    // Civil-from-days, Howard Hinnant's algorithm, for days since 1970-01-01.
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}{month:02}{day:02}-{}Z",
+        format_clock(secs % 86_400).replace(':', "")
+    )
+}
+
+/// Create `name` in `dir` without overwriting, adding `-2`, `-3`, ... before the extension when
+/// an export with the same timestamp already exists.
+fn write_new_file(dir: &Path, name: &str, contents: &str) -> anyhow::Result<PathBuf> {
+    let (stem, extension) = name.rsplit_once('.').unwrap_or((name, ""));
+    for attempt in 1..=100 {
+        let path = match attempt {
+            1 => dir.join(name),
+            n => dir.join(format!("{stem}-{n}.{extension}")),
+        };
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(contents.as_bytes())?;
+                return Ok(fs::canonicalize(&path).unwrap_or(path));
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Err(anyhow!("too many exports named {name}"))
+}
+
+/// OSC 52 "set clipboard" escape sequence; the terminal, not the OS, does the copying, so it
+/// also works over SSH.
+fn osc52_copy(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", BASE64.encode(text))
+}
+
+impl DashboardApp {
+    /// The current hops plus everything kept in the per-hop history.
+    fn export_document(&self) -> Option<ReportDocument> {
+        let (protocol, backend) = self.export_source?;
+        if self.hops.is_empty() {
+            return None;
+        }
+        Some(ReportDocument::new(Report {
+            target: self.target.clone(),
+            target_ip: self.target.parse::<IpAddr>().ok().map(|ip| ip.to_string()),
+            protocol,
+            backend,
+            hops: self.hops.iter().map(HopStat::to_saved).collect(),
+            session: None,
+            history: Some(
+                self.hop_history
+                    .iter()
+                    .map(|(ttl, history)| history.timeline(*ttl))
+                    .collect(),
+            ),
+        }))
+    }
+
+    pub(super) fn export(&mut self, format: ExportFormat, now: SystemTime) {
+        let message = match self.export_document() {
+            None => self.nothing_to_export().to_string(),
+            Some(document) => {
+                let name = export_file_name(&self.target, now, format.extension());
+                match export_contents(document, format).and_then(|contents| {
+                    write_new_file(&self.export_dir, &name, &contents)
+                        .with_context(|| format!("failed to write {name}"))
+                }) {
+                    Ok(path) => format!("Saved {}", path.display()),
+                    Err(err) => format!("Export failed: {err:#}"),
+                }
+            }
+        };
+        self.show_toast(message);
+    }
+
+    pub(super) fn copy_report(&mut self) {
+        let message = match self.export_document() {
+            None => self.nothing_to_export().to_string(),
+            Some(document) => {
+                self.pending_clipboard = Some(osc52_copy(&render_text(&document.into())));
+                "Copied the text report to the clipboard (OSC 52)".to_string()
+            }
+        };
+        self.show_toast(message);
+    }
+
+    fn nothing_to_export(&self) -> &'static str {
+        if self.export_source.is_none() {
+            "Saved reports are already on disk; use `mtr report` to convert them"
+        } else {
+            "Nothing to export until the first hop snapshot arrives"
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! The terminal dashboard behind `mtr --ui dashboard`, `mtr replay` and `mtr render --format ui`.
+//!
+//! Other tools can show their own probe results in it: send [`HopSnapshot`]s down a channel and
+//! either hand the receiver to [`run_snapshot_ui`], which takes over the terminal, or draw a
+//! [`DashboardView`] inside their own ratatui layout. [`DashboardOptions`] sets the targets,
+//! thresholds, [`Theme`] and [`Keymap`]. Needs the `tui` cargo feature.
+
+mod alerts;
+mod app;
+mod export;
+mod poller;
+mod render;
+#[cfg(test)]
+mod tests;
+mod view;
+
+pub use alerts::AlertConfig;
+pub use app::{DashboardAction, DashboardApp, Keymap, Theme};
+pub use export::ExportFormat;
+pub use poller::run_dashboard_ui;
+pub use view::{
+    DashboardOptions, DashboardTarget, DashboardView, HopSnapshot, run_replay_ui,
+    run_saved_report_ui, run_snapshot_ui,
+};
+
+use std::time::Duration;
+
+const FALLBACK_DASHBOARD_TITLE_PREFIX: &str = "windows-mtr fallback dashboard";
+const SAVED_REPORT_TITLE_PREFIX: &str = "windows-mtr saved report";
+const REPLAY_TITLE_PREFIX: &str = "windows-mtr replay";
+const EMBEDDED_TITLE_PREFIX: &str = "windows-mtr dashboard";
+
+/// Rounds kept in the latency and loss charts, for the destination and for each hop.
+const HISTORY_LEN: usize = 120;
+/// RTT samples kept per hop for the detail pane's histogram.
+const HOP_SAMPLE_LEN: usize = 1_000;
+/// Rounds per hop kept for the heatmap and exports; about 9 minutes of live polling.
+const HEATMAP_LEN: usize = 600;
+/// Rounds moved by one heatmap scroll key press.
+const HEATMAP_SCROLL_ROUNDS: i64 = 10;
+/// Hop number and host column in front of the heatmap cells.
+const HEATMAP_LABEL_WIDTH: usize = 17;
+/// Rounds in the hop table's Trend column.
+const SPARKLINE_WIDTH: usize = 16;
+/// Distinct addresses remembered per TTL; more than one means ECMP or a route change.
+const MAX_ADDRESSES_PER_HOP: usize = 16;
+const HISTOGRAM_BUCKETS: usize = 8;
+const REPLAY_SHORT_SEEK_MS: i64 = 10_000;
+const REPLAY_LONG_SEEK_MS: i64 = 5 * 60_000;
+const REPLAY_MIN_SPEED: f64 = 0.25;
+const REPLAY_MAX_SPEED: f64 = 64.0;
+/// Pause between live polls at startup; `-` and `+` double or halve it within these bounds.
+const POLL_INTERVAL: Duration = Duration::from_millis(900);
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(250);
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(60);
+/// How long an export or clipboard message stays in the Help bar.
+const TOAST_DURATION: Duration = Duration::from_secs(5);
+/// Rounds a destination loss or RTT alert must hold when `--alert-rounds` is not given.
+pub const DEFAULT_ALERT_ROUNDS: u32 = 3;
+
+const TAB_TITLES: [&str; 4] = ["Overview", "Hops", "Charts", "Heatmap"];
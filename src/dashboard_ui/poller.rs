@@ -0,0 +1,377 @@
+//! Live polling: one thread per target running the embedded trippy JSON snapshot, and the
+//! parser for its hop output.
+//!
+//! [`extract_hops`] is deliberately looser than [`crate::report_render::parse_hop`], which reads
+//! saved reports for `render` and `diff`. A snapshot is taken mid-run, so the dashboard looks for
+//! the hop array anywhere in the document, accepts `hop`/`min`/`max`/`loss` aliases and
+//! `20ms`/`1%` strings, and labels hops it cannot name instead of failing the poll. Saved
+//! reports keep the stricter parser so a file without hops is rejected rather than shown empty.
+
+use super::alerts::AlertConfig;
+use super::app::{Dashboard, DashboardApp, HopAddress, HopStat, ProbeSettings};
+use super::view::{DashboardTarget, TargetSnapshot, run_terminal_ui};
+use crate::native_icmp;
+use crate::report_schema::ReportProtocol;
+use crate::service::{EnhancedUiConfig, ProbeRequest, build_json_snapshot_args};
+use anyhow::{Context, anyhow};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+
+const EMBEDDED_TRIPPY_ENV: &str = "WINDOWS_MTR_EMBEDDED_TRIPPY";
+
+/// Run the live dashboard, polling every target concurrently. `protocol` is stamped on exports
+/// and `enhanced_ui` sets the colour thresholds and which extra columns and panels are shown.
+/// With `alerts`, each target's destination is checked against the alert rules every round.
+///
+/// TCP and UDP targets are polled by running `runner`, an `mtr` executable, as the embedded
+/// trippy runner; tools without one feed their own results to [`run_snapshot_ui`](super::run_snapshot_ui).
+pub fn run_dashboard_ui(
+    targets: Vec<DashboardTarget>,
+    protocol: ReportProtocol,
+    enhanced_ui: EnhancedUiConfig,
+    alerts: Option<AlertConfig>,
+    runner: &Path,
+) -> anyhow::Result<i32> {
+    let (snapshot_tx, snapshot_rx) = mpsc::channel::<TargetSnapshot>();
+    let apps = targets
+        .into_iter()
+        .enumerate()
+        .map(|(index, target)| {
+            let settings = ProbeSettings::new(protocol, &target.request);
+            let (app, control_rx) = DashboardApp::live(
+                &target.target,
+                settings,
+                target.native_icmp_config.is_some(),
+                enhanced_ui,
+            );
+            spawn_poller(
+                index,
+                target,
+                runner.to_path_buf(),
+                settings,
+                control_rx,
+                snapshot_tx.clone(),
+            );
+            app
+        })
+        .collect();
+
+    let mut dashboard = Dashboard::new(apps);
+    dashboard.alerts = alerts;
+    run_terminal_ui(dashboard, &snapshot_rx)
+}
+
+/// Poll `target` every `settings.interval` on its own thread, picking up new settings from
+/// `control_rx` as soon as they arrive.
+fn spawn_poller(
+    index: usize,
+    target: DashboardTarget,
+    runner: PathBuf,
+    mut settings: ProbeSettings,
+    control_rx: mpsc::Receiver<ProbeSettings>,
+    snapshot_tx: mpsc::Sender<TargetSnapshot>,
+) {
+    let DashboardTarget {
+        target,
+        request,
+        native_icmp_config,
+        mut recorder,
+    } = target;
+
+    thread::spawn(move || {
+        loop {
+            if !settings.paused {
+                let result = poll_target(
+                    &runner,
+                    &target,
+                    &request,
+                    native_icmp_config.as_ref(),
+                    &settings,
+                );
+                let recording_error = match (&result, recorder.as_mut()) {
+                    (Ok(hops), Some(active)) if !hops.is_empty() => active
+                        .record(hops.iter().map(HopStat::to_recorded).collect())
+                        .err(),
+                    _ => None,
+                };
+                let snapshot = TargetSnapshot {
+                    index,
+                    epoch: settings.epoch,
+                    hops: result,
+                };
+                if snapshot_tx.send(snapshot).is_err() {
+                    break;
+                }
+                if let Some(error) = recording_error {
+                    // Keep probing, but stop appending to a recording that can no longer be
+                    // trusted.
+                    recorder = None;
+                    let snapshot = TargetSnapshot {
+                        index,
+                        epoch: settings.epoch,
+                        hops: Err(anyhow!("recording stopped: {error}")),
+                    };
+                    if snapshot_tx.send(snapshot).is_err() {
+                        break;
+                    }
+                }
+            }
+
+            // Wait out the interval, or until the dashboard changes the settings. Paused
+            // pollers only wake for a change.
+            let next = if settings.paused {
+                control_rx
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected)
+            } else {
+                control_rx.recv_timeout(settings.interval)
+            };
+            match next {
+                Ok(next) => settings = next,
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    });
+}
+
+fn poll_target(
+    runner: &Path,
+    target: &str,
+    request: &ProbeRequest,
+    native_icmp_config: Option<&native_icmp::Config>,
+    settings: &ProbeSettings,
+) -> anyhow::Result<Vec<HopStat>> {
+    if settings.protocol == ReportProtocol::Icmp
+        && let Some(config) = native_icmp_config
+    {
+        let config = native_icmp::Config {
+            max_hops: settings.max_hops.unwrap_or(config.max_hops),
+            ..config.clone()
+        };
+        return fetch_native_icmp_snapshot(target, &config);
+    }
+    let snapshot_args = build_json_snapshot_args(&settings.apply_to(request), target)?;
+    fetch_hops_snapshot(runner, &snapshot_args, target)
+}
+
+fn fetch_hops_snapshot(
+    runner: &Path,
+    snapshot_args: &[String],
+    target: &str,
+) -> anyhow::Result<Vec<HopStat>> {
+    let output = Command::new(runner)
+        .env(EMBEDDED_TRIPPY_ENV, "1")
+        .args(snapshot_args.iter().skip(1))
+        .output()
+        .context("failed to run embedded trippy JSON poll")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("trippy poll failed: {}", stderr.trim()));
+    }
+
+    let value: Value = serde_json::from_slice(&output.stdout)
+        .context("failed to parse trippy JSON poll output")?;
+
+    Ok(extract_hops(&value, target))
+}
+
+fn fetch_native_icmp_snapshot(
+    target: &str,
+    config: &native_icmp::Config,
+) -> anyhow::Result<Vec<HopStat>> {
+    Ok(native_icmp::trace(target, config)?
+        .into_iter()
+        .map(|hop| HopStat {
+            hop: hop.ttl as usize,
+            host: hop
+                .address
+                .map(|address| address.to_string())
+                .unwrap_or_else(|| format!("hop-{}", hop.ttl)),
+            addresses: hop
+                .address
+                .map(|address| HopAddress::new(&address.to_string(), None))
+                .into_iter()
+                .collect(),
+            loss_pct: Some(hop.loss_pct()),
+            best_ms: hop.best(),
+            avg_ms: hop.avg(),
+            worst_ms: hop.worst(),
+            samples: hop.received,
+        })
+        .collect())
+}
+
+pub(super) fn extract_hops(value: &Value, target: &str) -> Vec<HopStat> {
+    let Some(array) = find_hop_array(value) else {
+        return Vec::new();
+    };
+
+    let mut hops = Vec::with_capacity(array.len());
+    for (index, item) in array.iter().enumerate() {
+        if !item.is_object() {
+            continue;
+        }
+
+        let hop = read_usize(item, &["ttl", "hop", "hop_index"]).unwrap_or(index + 1);
+        let addresses = read_addresses(item);
+        let host = read_string(item, &["host", "hostname", "ip", "addr"])
+            .or_else(|| addresses.first().map(HopAddress::label))
+            .unwrap_or_else(|| {
+                if index + 1 == array.len() {
+                    target.to_string()
+                } else {
+                    format!("hop-{hop}")
+                }
+            });
+        let loss_pct = read_loss_percent(item).map(|value| value.clamp(0.0, 100.0));
+        let avg_ms = read_f64(item, &["avg_ms", "avg", "average_ms", "last_ms", "last"]);
+        let best_ms = read_f64(item, &["best_ms", "best", "min_ms", "min"]).or(avg_ms);
+        let worst_ms = read_f64(item, &["worst_ms", "worst", "max_ms", "max"]).or(avg_ms);
+        // Trippy reports `0.0` for a hop that has not answered yet.
+        let samples = read_f64(item, &["last_ms", "last"])
+            .filter(|last| *last > 0.0)
+            .into_iter()
+            .collect();
+
+        hops.push(HopStat {
+            hop,
+            host,
+            addresses,
+            loss_pct,
+            best_ms,
+            avg_ms,
+            worst_ms,
+            samples,
+        });
+    }
+
+    hops
+}
+
+/// Trippy's `hosts: [{ip, hostname}]`, one entry per responder (several under ECMP).
+fn read_addresses(item: &Value) -> Vec<HopAddress> {
+    item.get("hosts")
+        .and_then(Value::as_array)
+        .map(|hosts| {
+            hosts
+                .iter()
+                .filter_map(|host| {
+                    let ip = host.get("ip").and_then(Value::as_str)?;
+                    Some(HopAddress::new(
+                        ip,
+                        host.get("hostname").and_then(Value::as_str),
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub(super) fn read_loss_percent(item: &Value) -> Option<f64> {
+    if let Some(value) = read_f64(item, &["loss_pct", "loss_percentage"]) {
+        return Some(value);
+    }
+    if let Some(value) = read_f64(item, &["loss_ratio"]) {
+        return Some(value * 100.0);
+    }
+    read_f64(item, &["loss"])
+}
+
+fn find_hop_array(value: &Value) -> Option<&Vec<Value>> {
+    match value {
+        Value::Array(array) => {
+            if looks_like_hop_array(array) {
+                return Some(array);
+            }
+            for item in array {
+                if let Some(found) = find_hop_array(item) {
+                    return Some(found);
+                }
+            }
+            None
+        }
+        Value::Object(map) => {
+            for candidate in ["hops", "hosts", "report", "result", "results", "data"] {
+                if let Some(next) = map.get(candidate)
+                    && let Some(found) = find_hop_array(next)
+                {
+                    return Some(found);
+                }
+            }
+            for next in map.values() {
+                if let Some(found) = find_hop_array(next) {
+                    return Some(found);
+                }
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+fn looks_like_hop_array(array: &[Value]) -> bool {
+    !array.is_empty()
+        && array.iter().all(|item| {
+            item.get("ttl").is_some()
+                || item.get("hop").is_some()
+                || item.get("avg_ms").is_some()
+                || item.get("avg").is_some()
+                || item.get("host").is_some()
+        })
+}
+
+fn read_usize(item: &Value, keys: &[&str]) -> Option<usize> {
+    read_f64(item, keys).and_then(|value| {
+        if value >= 0.0 && value <= usize::MAX as f64 {
+            Some(value as usize)
+        } else {
+            None
+        }
+    })
+}
+
+pub(super) fn read_string(item: &Value, keys: &[&str]) -> Option<String> {
+    for key in keys {
+        let Some(value) = item.get(*key) else {
+            continue;
+        };
+        if let Some(s) = value.as_str() {
+            return Some(s.to_string());
+        }
+    }
+    None
+}
+
+pub(super) fn read_f64(item: &Value, keys: &[&str]) -> Option<f64> {
+    for key in keys {
+        let Some(value) = item.get(*key) else {
+            continue;
+        };
+        match value {
+            Value::Number(number) => {
+                if let Some(n) = number.as_f64().filter(|n| n.is_finite()) {
+                    return Some(n);
+                }
+            }
+            Value::String(raw) => {
+                let trimmed = raw
+                    .trim()
+                    .trim_end_matches("ms")
+                    .trim_end_matches('%')
+                    .trim();
+                if let Ok(n) = trimmed.parse::<f64>()
+                    && n.is_finite()
+                {
+                    return Some(n);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
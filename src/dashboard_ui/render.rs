@@ -0,0 +1,1219 @@
+//! Drawing the dashboard: tabs, hop table, charts, heatmap, summary grid and help.
+
+use super::alerts::Alert;
+use super::app::{
+    ActionFilter, Band, Dashboard, DashboardAction, DashboardApp, DashboardMode, Health,
+    HopHistory, HopSort, HopStat, Keymap, Replay, Theme,
+};
+use super::{
+    EMBEDDED_TITLE_PREFIX, FALLBACK_DASHBOARD_TITLE_PREFIX, HEATMAP_LABEL_WIDTH, HISTOGRAM_BUCKETS,
+    REPLAY_LONG_SEEK_MS, REPLAY_SHORT_SEEK_MS, REPLAY_TITLE_PREFIX, SAVED_REPORT_TITLE_PREFIX,
+    SPARKLINE_WIDTH, TAB_TITLES,
+};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::symbols;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{
+    Axis, BarChart, Block, Borders, Cell, Chart, Clear, Dataset, GraphType, Paragraph, Row,
+    Sparkline, Table, TableState, Tabs,
+};
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::ops::RangeInclusive;
+
+/// Rows of the `h`/`?` overlay: what the keys do, and which actions they run. Rows whose
+/// actions have no key are left out.
+const HELP_ROWS: &[(&str, ActionFilter)] = &[
+    ("next view", |action| action == DashboardAction::NextTab),
+    ("previous view", |action| {
+        action == DashboardAction::PreviousTab
+    }),
+    ("select a hop, or close its details", |action| {
+        matches!(
+            action,
+            DashboardAction::SelectPreviousHop
+                | DashboardAction::SelectNextHop
+                | DashboardAction::ClearSelection
+        )
+    }),
+    ("cycle the sort column", |action| {
+        action == DashboardAction::CycleSort
+    }),
+    ("search hosts and IPs", |action| {
+        action == DashboardAction::StartSearch
+    }),
+    ("hide or show ??? hops", |action| {
+        action == DashboardAction::ToggleUnresponsive
+    }),
+    ("host names, IPs or both", |action| {
+        action == DashboardAction::CycleHostDisplay
+    }),
+    ("colour the heatmap by RTT or loss", |action| {
+        action == DashboardAction::ToggleHeatmapMetric
+    }),
+    ("scroll the heatmap", |action| {
+        matches!(action, DashboardAction::ScrollHeatmap(_))
+    }),
+    ("export JSON, CSV or Markdown", |action| {
+        matches!(action, DashboardAction::Export(_))
+    }),
+    ("copy the text report (OSC 52)", |action| {
+        action == DashboardAction::CopyReport
+    }),
+    ("pause probing or the replay", |action| {
+        action == DashboardAction::TogglePause
+    }),
+    ("reset the statistics", |action| {
+        action == DashboardAction::ResetStats
+    }),
+    ("poll interval, or replay speed", |action| {
+        matches!(action, DashboardAction::Slower | DashboardAction::Faster)
+    }),
+    ("switch ICMP, TCP and UDP", |action| {
+        action == DashboardAction::CycleProtocol
+    }),
+    ("set the max hops", |action| {
+        action == DashboardAction::EditMaxHops
+    }),
+    ("replay: seek 10 s / 5 min", |action| {
+        matches!(action, DashboardAction::Seek(_))
+    }),
+    ("replay: first / last round", |action| {
+        matches!(
+            action,
+            DashboardAction::SeekStart | DashboardAction::SeekEnd
+        )
+    }),
+    ("targets: grid, open, switch", |action| {
+        matches!(
+            action,
+            DashboardAction::ShowSummary
+                | DashboardAction::OpenTarget
+                | DashboardAction::FocusTarget(_)
+                | DashboardAction::NextTarget
+                | DashboardAction::PreviousTarget
+        )
+    }),
+    ("dismiss the alert banner", |action| {
+        action == DashboardAction::DismissAlerts
+    }),
+    ("close this help", |action| {
+        action == DashboardAction::ToggleHelp
+    }),
+    ("quit", |action| action == DashboardAction::Quit),
+];
+
+pub(super) fn draw_dashboard(
+    frame: &mut ratatui::Frame<'_>,
+    dashboard: &Dashboard,
+    mut area: Rect,
+) {
+    if let Some(alert_banner) = &dashboard.alert_banner {
+        let [banner, rest] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .areas(area);
+        frame.render_widget(
+            Paragraph::new(alert_banner_text(
+                &alert_banner.latest,
+                alert_banner.earlier,
+            ))
+            .style(
+                Style::default()
+                    .fg(dashboard.theme.text)
+                    .bg(dashboard.theme.bad)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            banner,
+        );
+        area = rest;
+    }
+
+    if let [app] = dashboard.targets.as_slice() {
+        draw_target(frame, app, &dashboard.keymap, area);
+        return;
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(area);
+    let titles = std::iter::once(Line::from("Summary"))
+        .chain(dashboard.targets.iter().enumerate().map(|(index, app)| {
+            Line::from(vec![
+                Span::raw(format!("{} {} ", index + 1, app.target)),
+                Span::styled(
+                    "●",
+                    Style::default().fg(Health::of(app).color(&dashboard.theme)),
+                ),
+            ])
+        }))
+        .collect::<Vec<_>>();
+    let tabs = Tabs::new(titles)
+        .block(
+            Block::default()
+                .title("Targets (g summary, 1-9 or PgUp/PgDn switch)")
+                .borders(Borders::ALL),
+        )
+        .select(dashboard.focus.map_or(0, |index| index + 1))
+        .style(Style::default().fg(dashboard.theme.text))
+        .highlight_style(
+            Style::default()
+                .fg(dashboard.theme.accent)
+                .add_modifier(Modifier::BOLD),
+        );
+    frame.render_widget(tabs, chunks[0]);
+
+    match dashboard.focus {
+        Some(index) => draw_target(
+            frame,
+            &dashboard.targets[index],
+            &dashboard.keymap,
+            chunks[1],
+        ),
+        None => render_summary(frame, dashboard, chunks[1]),
+    }
+}
+
+pub(super) fn alert_banner_text(alert: &Alert, earlier: usize) -> String {
+    let mut text = format!(
+        " ALERT {} UTC {}: {}",
+        format_clock(alert.time_ms / 1000 % 86_400),
+        alert.target,
+        alert.message
+    );
+    if earlier > 0 {
+        let _ = write!(text, " (+{earlier} earlier)");
+    }
+    text.push_str(" | x dismiss");
+    text
+}
+
+/// Target, hop count, destination loss/avg/worst and a health badge, one row per target.
+fn render_summary(
+    frame: &mut ratatui::Frame<'_>,
+    dashboard: &Dashboard,
+    area: ratatui::layout::Rect,
+) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(4), Constraint::Length(3)])
+        .split(area);
+
+    let rows = dashboard.targets.iter().enumerate().map(|(index, app)| {
+        let destination = app.hops.last();
+        let health = Health::of(app);
+        Row::new(vec![
+            Cell::from((index + 1).to_string()),
+            Cell::from(app.target.clone()),
+            Cell::from(if app.hops.is_empty() {
+                "-".to_string()
+            } else {
+                app.hops.len().to_string()
+            }),
+            Cell::from(format_metric(destination.and_then(|hop| hop.loss_pct))),
+            Cell::from(format_metric(destination.and_then(|hop| hop.avg_ms))),
+            Cell::from(format_metric(destination.and_then(|hop| hop.worst_ms))),
+            Cell::from(health.label()).style(
+                Style::default()
+                    .fg(health.color(&dashboard.theme))
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ])
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(3),
+            Constraint::Percentage(40),
+            Constraint::Length(6),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(7),
+        ],
+    )
+    .header(
+        Row::new(["#", "Target", "Hops", "Loss%", "Avg", "Worst", "Health"]).style(
+            Style::default()
+                .fg(dashboard.theme.header)
+                .add_modifier(Modifier::BOLD),
+        ),
+    )
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+    .highlight_symbol("> ")
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Summary: {} targets", dashboard.targets.len())),
+    );
+    let mut state = TableState::default().with_selected(Some(dashboard.cursor));
+    frame.render_stateful_widget(table, chunks[0], &mut state);
+
+    let keys = dashboard.keymap.hints(&[
+        (
+            |action| {
+                matches!(
+                    action,
+                    DashboardAction::SelectPreviousHop | DashboardAction::SelectNextHop
+                )
+            },
+            "select a target",
+        ),
+        (
+            |action| {
+                matches!(
+                    action,
+                    DashboardAction::OpenTarget | DashboardAction::FocusTarget(_)
+                )
+            },
+            "open it",
+        ),
+        (|action| action == DashboardAction::ShowSummary, "back here"),
+        (
+            |action| {
+                matches!(
+                    action,
+                    DashboardAction::NextTarget | DashboardAction::PreviousTarget
+                )
+            },
+            "switch targets",
+        ),
+        (|action| action == DashboardAction::Quit, "quit"),
+    ]);
+    let help = Paragraph::new(format!(
+        "{keys}. Loss, Avg and Worst are for each destination."
+    ))
+    .block(Block::default().borders(Borders::ALL).title("Help"));
+    frame.render_widget(help, chunks[1]);
+}
+
+pub(super) fn draw_target(
+    frame: &mut ratatui::Frame<'_>,
+    app: &DashboardApp,
+    keymap: &Keymap,
+    area: ratatui::layout::Rect,
+) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(12),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+    let titles = TAB_TITLES
+        .iter()
+        .map(|t| Line::from(*t))
+        .collect::<Vec<_>>();
+
+    let tabs = Tabs::new(titles)
+        .block(
+            Block::default()
+                .title(format!(
+                    "{} ({}){}",
+                    match app.mode {
+                        DashboardMode::Live => FALLBACK_DASHBOARD_TITLE_PREFIX,
+                        DashboardMode::Embedded => EMBEDDED_TITLE_PREFIX,
+                        DashboardMode::SavedReport => SAVED_REPORT_TITLE_PREFIX,
+                        DashboardMode::Replay(_) => REPLAY_TITLE_PREFIX,
+                    },
+                    app.target,
+                    app.probe
+                        .as_ref()
+                        .map(|probe| format!(" {}", probe.settings.label()))
+                        .unwrap_or_default()
+                ))
+                .borders(Borders::ALL),
+        )
+        .select(app.tab_index)
+        .style(Style::default().fg(app.theme.text))
+        .highlight_style(
+            Style::default()
+                .fg(app.theme.accent)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    frame.render_widget(tabs, chunks[0]);
+
+    match app.tab_index {
+        0 => render_overview(frame, app, chunks[1]),
+        1 => render_hop_table(frame, app, chunks[1]),
+        2 => render_charts(frame, app, chunks[1]),
+        _ => render_heatmap(frame, app, chunks[1]),
+    }
+
+    let help_text = build_help_text(app, keymap);
+    let help =
+        Paragraph::new(help_text).block(Block::default().borders(Borders::ALL).title("Help"));
+    frame.render_widget(help, chunks[2]);
+
+    if app.show_help {
+        render_help_overlay(frame, chunks[1], &app.theme, keymap);
+    }
+}
+
+/// Every bound key, centred over the current view.
+fn render_help_overlay(frame: &mut ratatui::Frame<'_>, area: Rect, theme: &Theme, keymap: &Keymap) {
+    let rows = HELP_ROWS
+        .iter()
+        .filter_map(|&(action, wanted)| keymap.keys_for(wanted, " ").map(|keys| (keys, action)))
+        .collect::<Vec<_>>();
+    let key_width = rows
+        .iter()
+        .map(|(keys, _)| keys.chars().count() + 2)
+        .max()
+        .unwrap_or_default();
+    let width = area.width.min(64);
+    let height = area.height.min(rows.len() as u16 + 2);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    let lines = rows
+        .iter()
+        .map(|(keys, action)| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{keys:<key_width$}"),
+                    Style::default()
+                        .fg(theme.accent)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(*action),
+            ])
+        })
+        .collect::<Vec<_>>();
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(
+            match keymap.keys_for(|action| action == DashboardAction::ToggleHelp, "/") {
+                Some(keys) => format!("Keys ({keys} closes)"),
+                None => "Keys".to_string(),
+            },
+        )),
+        popup,
+    );
+}
+
+fn render_overview(
+    frame: &mut ratatui::Frame<'_>,
+    app: &DashboardApp,
+    area: ratatui::layout::Rect,
+) {
+    let message = if let Some(error) = &app.last_error {
+        format!("Polling error: {error}\nThe dashboard will keep showing the last valid hop data.")
+    } else if app.hops.is_empty() {
+        format!("Loading probe snapshots for {}...", app.target)
+    } else if let DashboardMode::Replay(replay) = &app.mode {
+        let destination = app.hops.last().expect("non-empty hops checked above");
+        format!(
+            "{}\n{} hops. Destination: {}\nAvg: {} ms   Loss: {}%",
+            replay_status(replay),
+            app.hops.len(),
+            destination.host,
+            format_metric(destination.avg_ms),
+            format_metric(destination.loss_pct)
+        )
+    } else if app.is_read_only() {
+        let destination = app.hops.last().expect("non-empty hops checked above");
+        format!(
+            "Saved report, read-only: {} hops. Destination: {}\nAvg: {} ms   Loss: {}%",
+            app.hops.len(),
+            destination.host,
+            format_metric(destination.avg_ms),
+            format_metric(destination.loss_pct)
+        )
+    } else {
+        let destination = app.hops.last().expect("non-empty hops checked above");
+        format!(
+            "{} hops received. Destination: {}\nLatest avg: {} ms   Latest loss: {}%",
+            app.hops.len(),
+            destination.host,
+            format_metric(destination.avg_ms),
+            format_metric(destination.loss_pct)
+        )
+    };
+    let overview =
+        Paragraph::new(message).block(Block::default().borders(Borders::ALL).title("Overview"));
+    if !app.enhanced_ui.summary || app.hops.is_empty() {
+        frame.render_widget(overview, area);
+        return;
+    }
+
+    let split = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(5), Constraint::Min(4)])
+        .split(area);
+    frame.render_widget(overview, split[0]);
+    render_latency_summary(frame, app, split[1]);
+}
+
+/// RTT percentiles and jitter per hop, over the samples kept for the detail pane.
+fn render_latency_summary(
+    frame: &mut ratatui::Frame<'_>,
+    app: &DashboardApp,
+    area: ratatui::layout::Rect,
+) {
+    let rows = app.visible_hops().into_iter().map(|hop| {
+        let summary = app
+            .hop_history
+            .get(&hop.hop)
+            .and_then(|history| LatencySummary::of(&history.samples));
+        let metric = |value: fn(&LatencySummary) -> f64| format_metric(summary.as_ref().map(value));
+        Row::new(vec![
+            hop.hop.to_string(),
+            app.host_display.host_label(hop),
+            summary
+                .as_ref()
+                .map_or_else(|| "0".to_string(), |summary| summary.samples.to_string()),
+            metric(|summary| summary.p50),
+            metric(|summary| summary.p90),
+            metric(|summary| summary.p95),
+            metric(|summary| summary.p99),
+            metric(|summary| summary.jitter),
+            metric(|summary| summary.stddev),
+        ])
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(5),
+            Constraint::Percentage(30),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(8),
+        ],
+    )
+    .header(
+        Row::new([
+            "Hop", "Host", "Samples", "p50", "p90", "p95", "p99", "Jitter", "StdDev",
+        ])
+        .style(
+            Style::default()
+                .fg(app.theme.header)
+                .add_modifier(Modifier::BOLD),
+        ),
+    )
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Latency summary (ms)"),
+    );
+    frame.render_widget(table, area);
+}
+
+/// Percentiles and jitter over a hop's kept RTT samples.
+#[derive(Debug, PartialEq)]
+pub(super) struct LatencySummary {
+    pub(super) samples: usize,
+    pub(super) p50: f64,
+    pub(super) p90: f64,
+    pub(super) p95: f64,
+    pub(super) p99: f64,
+    /// Mean absolute difference between consecutive samples.
+    pub(super) jitter: f64,
+    pub(super) stddev: f64,
+}
+
+impl LatencySummary {
+    pub(super) fn of(samples: &VecDeque<f64>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let count = samples.len() as f64;
+        let mut sorted = samples.iter().copied().collect::<Vec<_>>();
+        sorted.sort_by(f64::total_cmp);
+        // Nearest-rank percentile.
+        let percentile = |p: f64| {
+            let rank = (p / 100.0 * count).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+        let mean = samples.iter().sum::<f64>() / count;
+        let variance = samples
+            .iter()
+            .map(|sample| (sample - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        let jitter = if samples.len() < 2 {
+            0.0
+        } else {
+            samples
+                .iter()
+                .zip(samples.iter().skip(1))
+                .map(|(previous, next)| (next - previous).abs())
+                .sum::<f64>()
+                / (count - 1.0)
+        };
+
+        Some(Self {
+            samples: samples.len(),
+            p50: percentile(50.0),
+            p90: percentile(90.0),
+            p95: percentile(95.0),
+            p99: percentile(99.0),
+            jitter,
+            stddev: variance.sqrt(),
+        })
+    }
+}
+
+/// `Round 12/340  +00:10:48 / 01:02:03  (23:14:05 UTC)  2x  paused`
+pub(super) fn replay_status(replay: &Replay) -> String {
+    let wall_clock_secs = (replay.started_at_ms + replay.clock_ms as u64) / 1000;
+    format!(
+        "Round {}/{}  +{} / {}  ({} UTC)  {}x{}",
+        replay.position + 1,
+        replay.rounds.len(),
+        format_clock(replay.clock_ms as u64 / 1000),
+        format_clock(replay.end_ms() as u64 / 1000),
+        format_clock(wall_clock_secs % 86_400),
+        replay.speed,
+        if replay.paused { "  paused" } else { "" }
+    )
+}
+
+pub(super) fn format_clock(secs: u64) -> String {
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+pub(super) fn build_help_text(app: &DashboardApp, keymap: &Keymap) -> String {
+    const NAVIGATE: (ActionFilter, &str) =
+        (|action| action == DashboardAction::NextTab, "navigate");
+    const HELP: (ActionFilter, &str) = (|action| action == DashboardAction::ToggleHelp, "help");
+    const QUIT: (ActionFilter, &str) = (|action| action == DashboardAction::Quit, "quit");
+    const PAUSE: (ActionFilter, &str) = (|action| action == DashboardAction::TogglePause, "pause");
+
+    let base = if app.show_help {
+        let keys = keymap.hints(&[
+            (|action| action == DashboardAction::ToggleHelp, "closes it"),
+            (|action| action == DashboardAction::Quit, "quits"),
+        ]);
+        format!("Keyboard help: {keys}. JSON polling has no hidden retries.")
+    } else {
+        match &app.mode {
+            DashboardMode::Live => {
+                let keys = keymap.hints(&[
+                    PAUSE,
+                    (|action| action == DashboardAction::ResetStats, "reset"),
+                    (
+                        |action| {
+                            matches!(action, DashboardAction::Slower | DashboardAction::Faster)
+                        },
+                        "interval",
+                    ),
+                    (
+                        |action| action == DashboardAction::CycleProtocol,
+                        "protocol",
+                    ),
+                    (|action| action == DashboardAction::EditMaxHops, "max hops"),
+                    NAVIGATE,
+                    HELP,
+                    QUIT,
+                ]);
+                format!("Fallback dashboard: JSON snapshot polling, limited fields. {keys}.")
+            }
+            DashboardMode::Embedded => {
+                let keys = keymap.hints(&[
+                    (|action| action == DashboardAction::CycleSort, "sort"),
+                    (|action| action == DashboardAction::StartSearch, "search"),
+                    (
+                        |action| matches!(action, DashboardAction::Export(_)),
+                        "export",
+                    ),
+                    NAVIGATE,
+                    HELP,
+                    QUIT,
+                ]);
+                format!("Live snapshots. {keys}.")
+            }
+            DashboardMode::SavedReport => {
+                let keys = keymap.hints(&[NAVIGATE, HELP, QUIT]);
+                format!("Saved report: read-only, nothing is probed. {keys}.")
+            }
+            DashboardMode::Replay(_) => {
+                let keys = keymap.hints(&[
+                    PAUSE,
+                    (
+                        |action| {
+                            matches!(action, DashboardAction::Seek(ms) if ms.abs() == REPLAY_SHORT_SEEK_MS)
+                        },
+                        "seek 10s",
+                    ),
+                    (
+                        |action| {
+                            matches!(action, DashboardAction::Seek(ms) if ms.abs() == REPLAY_LONG_SEEK_MS)
+                        },
+                        "seek 5m",
+                    ),
+                    (
+                        |action| {
+                            matches!(action, DashboardAction::SeekStart | DashboardAction::SeekEnd)
+                        },
+                        "first/last",
+                    ),
+                    (
+                        |action| matches!(action, DashboardAction::Slower | DashboardAction::Faster),
+                        "speed",
+                    ),
+                    NAVIGATE,
+                    QUIT,
+                ]);
+                format!("Replay: {keys}.")
+            }
+        }
+    };
+    // Lead with the export or clipboard result so a narrow Help bar does not cut it off.
+    let base = match app.active_toast() {
+        Some(toast) => format!("{toast} | {base}"),
+        None => base,
+    };
+    let mut notes = app.view_status();
+    if app.is_read_only() {
+        return join_help(&base, &notes);
+    }
+
+    if app.hops.is_empty() {
+        notes.push(format!(
+            "Awaiting hop data for {}s",
+            app.started_at.elapsed().as_secs()
+        ));
+    }
+
+    if let Some(err) = &app.last_error {
+        notes.push(format!("Last poll error: {err}"));
+    }
+
+    // The troubleshooting hints are about mtr's own probes.
+    if matches!(app.mode, DashboardMode::Embedded) {
+        return join_help(&base, &notes);
+    }
+
+    if app.hops.is_empty() && app.consecutive_poll_failures >= 3 {
+        notes.push(
+            "No hops yet. Try running as Administrator, checking firewall policy, or using report mode (-r)."
+                .to_string(),
+        );
+    }
+
+    if app.hops.is_empty() && app.started_at.elapsed().as_secs() >= 15 {
+        notes.push(
+            "Still no data after 15s. Press q to quit and retry with report mode (-r) for immediate diagnostics."
+                .to_string(),
+        );
+    }
+
+    join_help(&base, &notes)
+}
+
+fn join_help(base: &str, notes: &[String]) -> String {
+    if notes.is_empty() {
+        base.to_string()
+    } else {
+        format!("{base} | {}", notes.join(" | "))
+    }
+}
+
+fn render_hop_table(
+    frame: &mut ratatui::Frame<'_>,
+    app: &DashboardApp,
+    area: ratatui::layout::Rect,
+) {
+    if app.hops.is_empty() {
+        let state = app
+            .last_error
+            .as_deref()
+            .map(|error| format!("Unable to load hop data: {error}"))
+            .unwrap_or_else(|| format!("Loading hop data for {}...", app.target));
+        let placeholder =
+            Paragraph::new(state).block(Block::default().borders(Borders::ALL).title("Hop table"));
+        frame.render_widget(placeholder, area);
+        return;
+    }
+
+    let visible = app.visible_hops();
+    let selected = app
+        .selected_hop()
+        .filter(|selected| visible.iter().any(|hop| hop.hop == selected.hop));
+    let (table_area, detail_area) = if selected.is_some() {
+        let split = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+            .split(area);
+        (split[0], Some(split[1]))
+    } else {
+        (area, None)
+    };
+
+    let title = if visible.len() == app.hops.len() {
+        "Hop table (Up/Down select, Esc close)".to_string()
+    } else {
+        format!(
+            "Hop table: {} of {} hops (Up/Down select, Esc close)",
+            visible.len(),
+            app.hops.len()
+        )
+    };
+    let config = &app.enhanced_ui;
+    let rows = visible.iter().map(|hop| {
+        let mut cells = vec![
+            hop.hop.to_string(),
+            app.host_display.host_label(hop),
+            format_metric(hop.loss_pct),
+            format_metric(hop.best_ms),
+            format_metric(hop.avg_ms),
+            format_metric(hop.worst_ms),
+        ];
+        if config.sparklines {
+            cells.push(
+                app.hop_history
+                    .get(&hop.hop)
+                    .map(|history| rtt_sparkline(&history.rtt, SPARKLINE_WIDTH))
+                    .unwrap_or_default(),
+            );
+        }
+        let row = Row::new(cells);
+        let band = Band::of_hop(hop, config);
+        if config.row_coloring && band != Band::Ok {
+            row.style(Style::default().fg(band.color(&app.theme)))
+        } else {
+            row
+        }
+    });
+
+    let mut widths = vec![
+        Constraint::Length(5),
+        Constraint::Percentage(35),
+        Constraint::Length(9),
+        Constraint::Length(9),
+        Constraint::Length(9),
+        Constraint::Length(9),
+    ];
+    let mut header = [
+        HopSort::Hop,
+        HopSort::Host,
+        HopSort::Loss,
+        HopSort::Best,
+        HopSort::Avg,
+        HopSort::Worst,
+    ]
+    .map(|column| {
+        if column == app.sort {
+            format!("{}{}", column.label(), column.arrow())
+        } else {
+            column.label().to_string()
+        }
+    })
+    .to_vec();
+    if config.sparklines {
+        widths.push(Constraint::Length(SPARKLINE_WIDTH as u16));
+        header.push("Trend".to_string());
+    }
+
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(header).style(
+                Style::default()
+                    .fg(app.theme.header)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        )
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ")
+        .block(Block::default().borders(Borders::ALL).title(title));
+
+    let mut state = TableState::default()
+        .with_selected(selected.and_then(|hop| visible.iter().position(|row| row.hop == hop.hop)));
+    frame.render_stateful_widget(table, table_area, &mut state);
+
+    if let (Some(hop), Some(detail_area)) = (selected, detail_area) {
+        render_hop_detail(frame, app, hop, detail_area);
+    }
+}
+
+fn render_hop_detail(
+    frame: &mut ratatui::Frame<'_>,
+    app: &DashboardApp,
+    hop: &HopStat,
+    area: ratatui::layout::Rect,
+) {
+    let empty = HopHistory::default();
+    let history = app.hop_history.get(&hop.hop).unwrap_or(&empty);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(30),
+            Constraint::Percentage(40),
+            Constraint::Percentage(30),
+        ])
+        .split(area);
+
+    frame.render_widget(
+        Paragraph::new(hop_detail_text(hop, history)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Hop {}", hop.hop)),
+        ),
+        columns[0],
+    );
+
+    let rtt = history.rtt.iter().copied().collect::<Vec<_>>();
+    let x_min = rtt.first().map(|(x, _)| *x).unwrap_or_default();
+    let x_max = rtt
+        .last()
+        .map(|(x, _)| *x)
+        .unwrap_or_default()
+        .max(x_min + 1.0);
+    let y_max = rtt
+        .iter()
+        .map(|(_, y)| *y)
+        .reduce(f64::max)
+        .unwrap_or(10.0)
+        .max(1.0)
+        * 1.1;
+    let dataset = Dataset::default()
+        .name("RTT (ms)")
+        .graph_type(GraphType::Line)
+        .marker(symbols::Marker::Braille)
+        .style(Style::default().fg(app.theme.latency))
+        .data(&rtt);
+    let chart = Chart::new(vec![dataset])
+        .block(Block::default().title("RTT").borders(Borders::ALL))
+        .x_axis(
+            Axis::default()
+                .title("Round")
+                .bounds([x_min, x_max])
+                .labels(vec![
+                    Line::from(format!("{x_min:.0}")),
+                    Line::from(format!("{x_max:.0}")),
+                ]),
+        )
+        .y_axis(
+            Axis::default()
+                .title("ms")
+                .bounds([0.0, y_max])
+                .labels(vec![Line::from("0"), Line::from(format!("{y_max:.0}"))]),
+        );
+    frame.render_widget(chart, columns[1]);
+
+    let right = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(5), Constraint::Length(3)])
+        .split(columns[2]);
+
+    let samples = history.samples.iter().copied().collect::<Vec<_>>();
+    let buckets = latency_histogram(&samples, HISTOGRAM_BUCKETS)
+        .into_iter()
+        .map(|(start, count)| (format!("{start:.0}"), count))
+        .collect::<Vec<_>>();
+    let bars = buckets
+        .iter()
+        .map(|(label, count)| (label.as_str(), *count))
+        .collect::<Vec<_>>();
+    let histogram = BarChart::default()
+        .block(
+            Block::default()
+                .title(format!("RTT histogram, ms ({} samples)", samples.len()))
+                .borders(Borders::ALL),
+        )
+        .data(bars.as_slice())
+        .bar_width(4)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(app.theme.accent));
+    frame.render_widget(histogram, right[0]);
+
+    let loss = history
+        .loss
+        .iter()
+        .map(|(_, loss)| loss.round() as u64)
+        .collect::<Vec<_>>();
+    let timeline = Sparkline::default()
+        .block(Block::default().title("Loss %").borders(Borders::ALL))
+        .data(&loss)
+        .max(100)
+        .style(Style::default().fg(app.theme.loss));
+    frame.render_widget(timeline, right[1]);
+}
+
+pub(super) fn hop_detail_text(hop: &HopStat, history: &HopHistory) -> String {
+    let mut lines = Vec::new();
+    match history.addresses.len() {
+        0 => lines.push("No reply at this TTL".to_string()),
+        1 => lines.push("Address:".to_string()),
+        count => lines.push(format!("{count} addresses seen (ECMP or route change):")),
+    }
+    for address in &history.addresses {
+        let current = hop.addresses.iter().any(|seen| seen.ip == address.ip);
+        lines.push(format!(
+            "{} {}",
+            if current { "*" } else { " " },
+            address.label()
+        ));
+        if address.hostname.is_none() {
+            lines.push("    no reverse DNS".to_string());
+        }
+    }
+    lines.push(String::new());
+    lines.push(format!("Loss: {}%", format_metric(hop.loss_pct)));
+    lines.push(format!(
+        "Best/Avg/Worst: {} / {} / {} ms",
+        format_metric(hop.best_ms),
+        format_metric(hop.avg_ms),
+        format_metric(hop.worst_ms)
+    ));
+    lines.push(format!(
+        "History: {} rounds, {} samples",
+        history.loss.len().max(history.rtt.len()),
+        history.samples.len()
+    ));
+    lines.join("\n")
+}
+
+/// The last `width` RTTs as block characters, scaled from 0 to the largest of them.
+pub(super) fn rtt_sparkline(rtt: &VecDeque<(f64, f64)>, width: usize) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let recent = rtt
+        .iter()
+        .skip(rtt.len().saturating_sub(width))
+        .map(|(_, rtt)| *rtt)
+        .collect::<Vec<_>>();
+    let max = recent.iter().copied().fold(0.0, f64::max);
+    recent
+        .iter()
+        .map(|rtt| {
+            if max <= 0.0 {
+                BARS[0]
+            } else {
+                BARS[((rtt / max) * (BARS.len() - 1) as f64).round() as usize]
+            }
+        })
+        .collect()
+}
+
+/// Split `samples` into `buckets` equal-width RTT ranges as `(range start ms, count)`.
+pub(super) fn latency_histogram(samples: &[f64], buckets: usize) -> Vec<(f64, u64)> {
+    let (Some(min), Some(max)) = (
+        samples.iter().copied().reduce(f64::min),
+        samples.iter().copied().reduce(f64::max),
+    ) else {
+        return Vec::new();
+    };
+    if buckets <= 1 || max <= min {
+        return vec![(min, samples.len() as u64)];
+    }
+
+    let width = (max - min) / buckets as f64;
+    let mut counts = vec![0u64; buckets];
+    for sample in samples {
+        let index = (((sample - min) / width) as usize).min(buckets - 1);
+        counts[index] += 1;
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(index, count)| (min + width * index as f64, count))
+        .collect()
+}
+
+pub(super) fn format_metric(value: Option<f64>) -> String {
+    value
+        .map(|metric| format!("{metric:.1}"))
+        .unwrap_or_else(|| "N/A".to_string())
+}
+
+fn render_charts(frame: &mut ratatui::Frame<'_>, app: &DashboardApp, area: ratatui::layout::Rect) {
+    let charts = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(area);
+    render_latency_chart(frame, app, charts[0]);
+    render_loss_chart(frame, app, charts[1]);
+}
+
+fn render_latency_chart(
+    frame: &mut ratatui::Frame<'_>,
+    app: &DashboardApp,
+    area: ratatui::layout::Rect,
+) {
+    let inner = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(8), Constraint::Length(6)])
+        .split(area);
+
+    let dataset = Dataset::default()
+        .name("RTT avg (ms)")
+        .graph_type(GraphType::Line)
+        .marker(symbols::Marker::Braille)
+        .style(Style::default().fg(app.theme.latency))
+        .data(&app.latency_history);
+
+    let x_max = app
+        .latency_history
+        .last()
+        .map(|(x, _)| *x)
+        .unwrap_or(20.0)
+        .max(20.0);
+
+    let y_max = app
+        .latency_history
+        .iter()
+        .map(|(_, y)| *y)
+        .reduce(f64::max)
+        .unwrap_or(100.0)
+        .max(50.0);
+
+    let chart = Chart::new(vec![dataset])
+        .block(
+            Block::default()
+                .title("Latency chart")
+                .borders(Borders::ALL),
+        )
+        .x_axis(
+            Axis::default()
+                .title("Samples")
+                .bounds([0.0, x_max])
+                .labels(vec![Line::from("0"), Line::from(format!("{x_max:.0}"))]),
+        )
+        .y_axis(
+            Axis::default()
+                .title("ms")
+                .bounds([0.0, y_max])
+                .labels(vec![Line::from("0"), Line::from(format!("{y_max:.0}"))]),
+        );
+
+    frame.render_widget(chart, inner[0]);
+
+    let spark_data = app
+        .latency_history
+        .iter()
+        .map(|(_, y)| (*y as u64).min(300))
+        .collect::<Vec<_>>();
+    let spark = Sparkline::default()
+        .block(
+            Block::default()
+                .title("Latency sparkline")
+                .borders(Borders::ALL),
+        )
+        .data(&spark_data)
+        .style(Style::default().fg(app.theme.sparkline));
+    frame.render_widget(spark, inner[1]);
+}
+
+fn render_loss_chart(
+    frame: &mut ratatui::Frame<'_>,
+    app: &DashboardApp,
+    area: ratatui::layout::Rect,
+) {
+    let dataset = Dataset::default()
+        .name("Loss %")
+        .graph_type(GraphType::Line)
+        .marker(symbols::Marker::Dot)
+        .style(Style::default().fg(app.theme.loss))
+        .data(&app.loss_history);
+
+    let x_max = app
+        .loss_history
+        .last()
+        .map(|(x, _)| *x)
+        .unwrap_or(20.0)
+        .max(20.0);
+
+    let chart = Chart::new(vec![dataset])
+        .block(Block::default().title("Loss chart").borders(Borders::ALL))
+        .x_axis(
+            Axis::default()
+                .title("Samples")
+                .bounds([0.0, x_max])
+                .labels(vec![Line::from("0"), Line::from(format!("{x_max:.0}"))]),
+        )
+        .y_axis(Axis::default().title("%").bounds([0.0, 100.0]).labels(vec![
+            Line::from("0"),
+            Line::from("50"),
+            Line::from("100"),
+        ]));
+
+    frame.render_widget(chart, area);
+}
+
+fn render_heatmap(frame: &mut ratatui::Frame<'_>, app: &DashboardApp, area: ratatui::layout::Rect) {
+    let block = Block::default().borders(Borders::ALL);
+    if app.hops.is_empty() {
+        let placeholder = Paragraph::new(format!("Waiting for rounds from {}...", app.target))
+            .block(block.title("Heatmap"));
+        frame.render_widget(placeholder, area);
+        return;
+    }
+
+    let columns = usize::from(block.inner(area).width).saturating_sub(HEATMAP_LABEL_WIDTH);
+    let window = app.heatmap_window(columns);
+    let title = format!(
+        "Heatmap: {} by hop, rounds {}-{} of {}{} (v RTT/loss, ,/. scroll)",
+        app.heatmap_metric.label(),
+        window.start(),
+        window.end(),
+        app.rounds,
+        if *window.end() < app.rounds {
+            ", scrolled back"
+        } else {
+            ""
+        }
+    );
+    let paragraph = Paragraph::new(heatmap_lines(app, window)).block(block.title(title));
+    frame.render_widget(paragraph, area);
+}
+
+/// One row per visible hop, one cell per round, then the colour legend.
+pub(super) fn heatmap_lines(app: &DashboardApp, window: RangeInclusive<u64>) -> Vec<Line<'static>> {
+    let metric = app.heatmap_metric;
+    let limits = metric.thresholds(&app.enhanced_ui);
+    let (warn, bad) = limits;
+    let mut lines = app
+        .visible_hops()
+        .into_iter()
+        .map(|hop| {
+            let label = format!(
+                "{:>3} {:<12.12} ",
+                hop.hop,
+                app.host_display.host_label(hop)
+            );
+            let history = app.hop_history.get(&hop.hop);
+            let mut spans = vec![Span::raw(label)];
+            spans.extend(window.clone().map(|round| {
+                match history.and_then(|history| history.round(round)) {
+                    // The hop was not part of that round's snapshot.
+                    None => Span::raw(" "),
+                    Some(entry) => match metric.value(entry) {
+                        Some(value) => Span::styled(
+                            "█",
+                            Style::default().fg(Band::of(value, limits).color(&app.theme)),
+                        ),
+                        None => Span::styled("·", Style::default().fg(app.theme.muted)),
+                    },
+                }
+            }));
+            Line::from(spans)
+        })
+        .collect::<Vec<_>>();
+
+    let unit = metric.unit();
+    lines.push(Line::default());
+    lines.push(Line::from(vec![
+        Span::styled("█", Style::default().fg(app.theme.ok)),
+        Span::raw(format!(" < {warn}{unit}  ")),
+        Span::styled("█", Style::default().fg(app.theme.warn)),
+        Span::raw(format!(" {warn}-{bad}{unit}  ")),
+        Span::styled("█", Style::default().fg(app.theme.bad)),
+        Span::raw(format!(" >= {bad}{unit}  ")),
+        Span::styled("·", Style::default().fg(app.theme.muted)),
+        Span::raw(" no reply"),
+    ]));
+    lines
+}
@@ -0,0 +1,1826 @@
+use super::alerts::*;
+use super::app::*;
+use super::export::*;
+use super::poller::*;
+use super::render::*;
+use super::view::*;
+use super::*;
+use crate::recording::Recording;
+use crate::report_render::SavedReport;
+use crate::report_schema::{ReportBackend, ReportProtocol};
+use crate::service::{EnhancedUiConfig, ProbeRequest, UiMode, build_json_snapshot_args};
+use anyhow::anyhow;
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers};
+use ratatui::Terminal;
+use ratatui::style::Color;
+use ratatui::text::Line;
+use serde_json::{Value, json};
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+#[test]
+fn read_helpers_scan_all_candidate_keys() {
+    let value = json!({
+        "avg": "12.5ms",
+        "hostname": "example.com"
+    });
+
+    assert_eq!(read_f64(&value, &["avg_ms", "avg"]), Some(12.5));
+    assert_eq!(
+        read_string(&value, &["host", "hostname"]),
+        Some("example.com".to_string())
+    );
+}
+
+#[test]
+fn extract_hops_supports_nested_report_shapes_and_defaults_last_hop_host() {
+    let payload = json!({
+        "result": {
+            "hops": [
+                {"ttl": 1, "host": "10.0.0.1", "loss": "1%", "avg_ms": 10.0},
+                {"ttl": 2, "loss_pct": 0.5, "avg": "20ms", "min": 15.0, "max": 25.0}
+            ]
+        }
+    });
+
+    let hops = extract_hops(&payload, "target.example");
+    assert_eq!(hops.len(), 2);
+
+    assert_eq!(hops[0].hop, 1);
+    assert_eq!(hops[0].host, "10.0.0.1");
+    assert_eq!(hops[0].loss_pct, Some(1.0));
+
+    assert_eq!(hops[1].hop, 2);
+    assert_eq!(hops[1].host, "target.example");
+    assert_eq!(hops[1].loss_pct, Some(0.5));
+    assert_eq!(hops[1].best_ms, Some(15.0));
+    assert_eq!(hops[1].avg_ms, Some(20.0));
+    assert_eq!(hops[1].worst_ms, Some(25.0));
+}
+
+#[test]
+fn read_loss_percent_handles_percent_and_ratio_fields() {
+    let percent = json!({"loss_pct": 0.5});
+    let percent_large = json!({"loss_percentage": 5.0});
+    let ratio = json!({"loss_ratio": 0.05});
+
+    assert_eq!(read_loss_percent(&percent), Some(0.5));
+    assert_eq!(read_loss_percent(&percent_large), Some(5.0));
+    assert_eq!(read_loss_percent(&ratio), Some(5.0));
+}
+
+#[test]
+fn extract_hops_parses_trippy_013_fixture() {
+    let fixture = include_str!("../../tests/fixtures/trippy_0_13_report.json");
+    let payload: Value = serde_json::from_str(fixture).expect("fixture must parse");
+
+    let hops = extract_hops(&payload, "8.8.8.8");
+    assert!(!hops.is_empty());
+    assert_eq!(hops[0].hop, 1);
+    assert!(
+        hops.iter()
+            .any(|h| h.host.contains("8.8.8.8") || h.host.contains("dns.google"))
+    );
+}
+
+#[test]
+fn build_help_text_includes_live_troubleshooting_when_ui_has_no_data() {
+    let mut app = DashboardApp::new("example.com");
+    app.started_at = Instant::now() - Duration::from_secs(16);
+    app.ingest_error(anyhow!("poll failed"));
+    app.ingest_error(anyhow!("poll failed"));
+    app.ingest_error(anyhow!("poll failed"));
+
+    let help = build_help_text(&app, &Keymap::default());
+    assert!(help.contains("Awaiting hop data for"));
+    assert!(help.contains("Last poll error: poll failed"));
+    assert!(help.contains("No hops yet."));
+    assert!(help.contains("report mode (-r)"));
+    assert!(help.contains("Still no data after 15s."));
+}
+
+#[test]
+fn build_help_text_is_compact_when_data_stream_is_healthy() {
+    let mut app = DashboardApp::new("example.com");
+    app.ingest_snapshot(vec![HopStat {
+        hop: 1,
+        host: "1.1.1.1".to_string(),
+        addresses: vec![HopAddress::new("1.1.1.1", None)],
+        loss_pct: Some(0.0),
+        best_ms: Some(1.0),
+        avg_ms: Some(2.0),
+        worst_ms: Some(3.0),
+        samples: vec![2.0],
+    }]);
+
+    assert_eq!(
+        build_help_text(&app, &Keymap::default()),
+        "Fallback dashboard: JSON snapshot polling, limited fields. p/Space pause; r reset; -/+/= interval; t protocol; M max hops; Tab/Right/d navigate; h/? help; q quit."
+    );
+
+    let mut keymap = Keymap::default();
+    keymap.unbind(KeyCode::Char('r'));
+    keymap.unbind(KeyCode::Char('q'));
+    keymap.bind(KeyCode::F(10), DashboardAction::Quit);
+    let help = build_help_text(&app, &keymap);
+    assert!(!help.contains("reset"), "{help}");
+    assert!(help.ends_with("h/? help; F10 quit."), "{help}");
+}
+
+#[test]
+fn saved_report_app_is_read_only_and_preloaded() {
+    let fixture = include_str!("../../tests/fixtures/probe_diff_after.json");
+    let payload: Value = serde_json::from_str(fixture).expect("fixture must parse");
+    let report = SavedReport::from_json(&payload).expect("fixture must load");
+
+    let app = DashboardApp::saved(&report);
+    assert!(app.is_read_only());
+    assert_eq!(app.target, "8.8.8.8");
+    assert_eq!(app.hops.len(), 3);
+    assert_eq!(app.hops[2].host, "AS15169 dns.google (8.8.8.8)");
+    assert_eq!(app.hops[2].loss_pct, Some(12.5));
+    assert!(build_help_text(&app, &Keymap::default()).starts_with("Saved report: read-only"));
+}
+
+#[test]
+fn replay_plays_pauses_seeks_and_changes_speed() {
+    let fixture = include_bytes!("../../tests/fixtures/session_recording.jsonl");
+    let recording = Recording::read(&fixture[..]).expect("fixture must load");
+    let mut app = DashboardApp::replay(recording, 1.0);
+    let position = |app: &DashboardApp| match &app.mode {
+        DashboardMode::Replay(replay) => (replay.position, replay.paused, replay.speed),
+        _ => panic!("app should be replaying"),
+    };
+    assert_eq!(position(&app), (0, false, 1.0));
+    assert_eq!(app.hops[1].avg_ms, Some(20.0));
+
+    let start = Instant::now();
+    if let DashboardMode::Replay(replay) = &mut app.mode {
+        replay.last_tick = start;
+    }
+    app.tick(start + Duration::from_millis(1000));
+    assert_eq!(position(&app).0, 1);
+    assert_eq!(app.hops[1].loss_pct, Some(100.0));
+    assert_eq!(app.loss_history.len(), 2);
+
+    app.tick(start + Duration::from_secs(5));
+    assert_eq!(position(&app), (2, true, 1.0), "replay pauses at the end");
+    assert!(!app.apply_action(DashboardAction::Seek(-REPLAY_SHORT_SEEK_MS)));
+    assert_eq!(position(&app).0, 0);
+    assert_eq!(app.loss_history.len(), 1, "seeking rebuilds the charts");
+
+    app.apply_action(DashboardAction::SeekEnd);
+    assert_eq!(position(&app).0, 2);
+    assert_eq!(app.loss_history.len(), 3);
+
+    app.apply_action(DashboardAction::TogglePause);
+    assert_eq!(
+        position(&app),
+        (0, false, 1.0),
+        "resuming at the end restarts"
+    );
+    app.apply_action(DashboardAction::Faster);
+    app.apply_action(DashboardAction::Faster);
+    assert_eq!(position(&app).2, 4.0);
+    for _ in 0..10 {
+        app.apply_action(DashboardAction::Slower);
+    }
+    assert_eq!(position(&app).2, REPLAY_MIN_SPEED);
+
+    assert_eq!(
+        Keymap::default().action(KeyCode::Char(' ')),
+        Some(DashboardAction::TogglePause)
+    );
+    assert_eq!(
+        Keymap::default().action(KeyCode::Char('}')),
+        Some(DashboardAction::Seek(REPLAY_LONG_SEEK_MS))
+    );
+    assert!(build_help_text(&app, &Keymap::default()).starts_with("Replay:"));
+}
+
+#[test]
+fn replay_status_shows_position_offset_and_wall_clock() {
+    let fixture = include_bytes!("../../tests/fixtures/session_recording.jsonl");
+    let recording = Recording::read(&fixture[..]).expect("fixture must load");
+    let mut app = DashboardApp::replay(recording, 2.0);
+    app.apply_action(DashboardAction::TogglePause);
+
+    let DashboardMode::Replay(replay) = &app.mode else {
+        panic!("app should be replaying");
+    };
+    assert_eq!(
+        replay_status(replay),
+        "Round 1/3  +00:00:00 / 00:00:01  (23:00:00 UTC)  2x  paused"
+    );
+}
+
+#[test]
+fn dashboard_keyboard_actions_are_discoverable_and_apply_without_loop_io() {
+    assert_eq!(
+        Keymap::default().action(KeyCode::Tab),
+        Some(DashboardAction::NextTab)
+    );
+    assert_eq!(
+        Keymap::default().action(KeyCode::BackTab),
+        Some(DashboardAction::PreviousTab)
+    );
+    assert_eq!(
+        Keymap::default().action(KeyCode::Char('?')),
+        Some(DashboardAction::ToggleHelp)
+    );
+    assert_eq!(
+        Keymap::default().action(KeyCode::Char('q')),
+        Some(DashboardAction::Quit)
+    );
+
+    assert_eq!(
+        Keymap::default().action_for_event(KeyEvent::new(KeyCode::Right, KeyModifiers::NONE)),
+        Some(DashboardAction::NextTab)
+    );
+
+    let release = KeyEvent {
+        code: KeyCode::Right,
+        modifiers: KeyModifiers::NONE,
+        kind: KeyEventKind::Release,
+        state: KeyEventState::NONE,
+    };
+    assert_eq!(Keymap::default().action_for_event(release), None);
+
+    let mut app = DashboardApp::new("example.com");
+    assert!(!app.apply_action(DashboardAction::NextTab));
+    assert_eq!(app.tab_index, 1);
+    assert!(!app.apply_action(DashboardAction::ToggleHelp));
+    assert!(app.show_help);
+    assert!(app.apply_action(DashboardAction::Quit));
+}
+
+#[test]
+fn partial_or_malformed_metrics_remain_missing_and_do_not_enter_charts() {
+    let payload =
+        json!({"hops": [{"ttl": 1, "host": "router", "avg": "NaN", "loss": "not-a-number"}]});
+    let hops = extract_hops(&payload, "target.example");
+    assert_eq!(hops.len(), 1);
+    assert_eq!(hops[0].avg_ms, None);
+    assert_eq!(hops[0].loss_pct, None);
+    assert_eq!(format_metric(hops[0].avg_ms), "N/A");
+
+    let mut app = DashboardApp::new("target.example");
+    app.ingest_snapshot(hops);
+    assert!(app.latency_history.is_empty());
+    assert!(app.loss_history.is_empty());
+}
+
+fn live_hop(ttl: usize, ip: &str, hostname: Option<&str>, samples: Vec<f64>) -> HopStat {
+    let address = HopAddress::new(ip, hostname);
+    HopStat {
+        hop: ttl,
+        host: address.label(),
+        addresses: vec![address],
+        loss_pct: Some(0.0),
+        best_ms: samples.iter().copied().reduce(f64::min),
+        avg_ms: Some(samples.iter().sum::<f64>() / samples.len() as f64),
+        worst_ms: samples.iter().copied().reduce(f64::max),
+        samples,
+    }
+}
+
+#[test]
+fn extract_hops_reads_trippy_responders_asn_and_last_sample() {
+    let payload = json!({"hops": [
+        {"ttl": 1, "hosts": [], "loss_pct": "100.0", "last": "0.0", "avg": "0.0"},
+        {"ttl": 2, "hosts": [
+            {"ip": "1.1.1.1", "hostname": "AS13335 one.one.one.one"},
+            {"ip": "1.0.0.1", "hostname": "1.0.0.1"}
+        ], "loss_pct": "0.0", "last": "12.40", "avg": "12.71"}
+    ]});
+
+    let hops = extract_hops(&payload, "1.1.1.1");
+    assert!(hops[0].addresses.is_empty());
+    assert!(hops[0].samples.is_empty(), "trippy's 0.0 is not a sample");
+    assert_eq!(hops[1].host, "one.one.one.one (1.1.1.1)  AS13335");
+    assert_eq!(
+        hops[1].addresses,
+        vec![
+            HopAddress {
+                ip: "1.1.1.1".to_string(),
+                hostname: Some("one.one.one.one".to_string()),
+                asn: Some(13335),
+            },
+            HopAddress {
+                ip: "1.0.0.1".to_string(),
+                hostname: None,
+                asn: None,
+            },
+        ]
+    );
+    assert_eq!(hops[1].samples, vec![12.4]);
+}
+
+#[test]
+fn hop_history_is_kept_per_ttl_in_bounded_buffers() {
+    let mut app = DashboardApp::new("8.8.8.8");
+    for round in 0..HISTORY_LEN + 10 {
+        // TTL 2 alternates between two ECMP next hops.
+        let (ip, hostname) = if round % 2 == 0 {
+            ("10.0.0.1", Some("AS64500 edge-a.example"))
+        } else {
+            ("10.0.0.2", None)
+        };
+        app.ingest_snapshot(vec![
+            live_hop(1, "192.168.1.1", None, vec![1.0, 2.0]),
+            live_hop(2, ip, hostname, vec![10.0 + round as f64]),
+            live_hop(3, "8.8.8.8", Some("dns.google"), vec![20.0]),
+        ]);
+    }
+
+    let router = &app.hop_history[&1];
+    assert_eq!(router.rtt.len(), HISTORY_LEN);
+    assert_eq!(router.loss.len(), HISTORY_LEN);
+    assert_eq!(router.samples.len(), 2 * (HISTORY_LEN + 10));
+    assert_eq!(router.rtt.back(), Some(&((HISTORY_LEN + 10) as f64, 2.0)));
+
+    let edge = &app.hop_history[&2];
+    assert_eq!(edge.rtt.back().map(|(_, rtt)| *rtt), Some(139.0));
+    assert_eq!(
+        edge.addresses
+            .iter()
+            .map(HopAddress::label)
+            .collect::<Vec<_>>(),
+        ["edge-a.example (10.0.0.1)  AS64500", "10.0.0.2"]
+    );
+
+    let mut history = HopHistory::default();
+    for sample in 0..HOP_SAMPLE_LEN + 5 {
+        history.record(
+            sample as u64 + 1,
+            &live_hop(1, "10.0.0.1", None, vec![sample as f64]),
+        );
+    }
+    assert_eq!(history.samples.len(), HOP_SAMPLE_LEN);
+    assert_eq!(history.samples.front(), Some(&5.0));
+    assert_eq!(history.rounds.len(), HEATMAP_LEN);
+    assert_eq!(
+        history.rounds.front().map(|round| round.round),
+        Some((HOP_SAMPLE_LEN + 5 - HEATMAP_LEN + 1) as u64)
+    );
+    assert_eq!(
+        history
+            .round(HOP_SAMPLE_LEN as u64)
+            .and_then(|round| round.rtt_ms),
+        Some((HOP_SAMPLE_LEN - 1) as f64)
+    );
+    assert!(history.round(1).is_none());
+}
+
+#[test]
+fn arrow_keys_select_hops_and_escape_closes_the_detail_pane() {
+    assert_eq!(
+        Keymap::default().action(KeyCode::Down),
+        Some(DashboardAction::SelectNextHop)
+    );
+    assert_eq!(
+        Keymap::default().action(KeyCode::Char('k')),
+        Some(DashboardAction::SelectPreviousHop)
+    );
+    assert_eq!(
+        Keymap::default().action(KeyCode::Esc),
+        Some(DashboardAction::ClearSelection)
+    );
+
+    let mut app = DashboardApp::new("8.8.8.8");
+    app.apply_action(DashboardAction::SelectNextHop);
+    assert_eq!(
+        app.selected_ttl, None,
+        "nothing to select before data arrives"
+    );
+
+    app.ingest_snapshot(vec![
+        live_hop(1, "192.168.1.1", None, vec![1.0]),
+        live_hop(2, "10.0.0.1", None, vec![5.0]),
+        live_hop(3, "8.8.8.8", None, vec![20.0]),
+    ]);
+    app.apply_action(DashboardAction::SelectNextHop);
+    assert_eq!(app.selected_ttl, Some(1));
+    assert_eq!(app.tab_index, 1, "selecting a hop shows the hop table");
+    app.apply_action(DashboardAction::SelectNextHop);
+    app.apply_action(DashboardAction::SelectNextHop);
+    app.apply_action(DashboardAction::SelectNextHop);
+    assert_eq!(app.selected_hop().map(|hop| hop.hop), Some(3));
+    app.apply_action(DashboardAction::SelectPreviousHop);
+    assert_eq!(app.selected_ttl, Some(2));
+
+    let detail = hop_detail_text(
+        app.selected_hop().expect("hop 2 is selected"),
+        &app.hop_history[&2],
+    );
+    assert!(
+        detail.contains("* 10.0.0.1\n    no reverse DNS"),
+        "{detail}"
+    );
+    assert!(detail.contains("History: 1 rounds, 1 samples"), "{detail}");
+
+    app.apply_action(DashboardAction::ClearSelection);
+    assert_eq!(app.selected_ttl, None);
+    app.apply_action(DashboardAction::SelectPreviousHop);
+    assert_eq!(
+        app.selected_ttl,
+        Some(3),
+        "Up with no selection starts at the end"
+    );
+}
+
+#[test]
+fn latency_histogram_spreads_samples_over_equal_buckets() {
+    assert!(latency_histogram(&[], 4).is_empty());
+    assert_eq!(latency_histogram(&[5.0, 5.0], 4), vec![(5.0, 2)]);
+    assert_eq!(
+        latency_histogram(&[10.0, 11.0, 12.0, 19.0, 20.0], 2),
+        vec![(10.0, 3), (15.0, 2)]
+    );
+}
+
+#[test]
+fn hop_detail_pane_renders_for_the_selected_hop() {
+    let mut app = DashboardApp::new("8.8.8.8");
+    app.ingest_snapshot(vec![
+        live_hop(1, "192.168.1.1", None, vec![1.0, 1.5]),
+        live_hop(2, "8.8.8.8", Some("AS15169 dns.google"), vec![20.0, 24.0]),
+    ]);
+    app.apply_action(DashboardAction::SelectPreviousHop);
+
+    let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(140, 40))
+        .expect("test terminal should start");
+    terminal
+        .draw(|frame| draw_target(frame, &app, &Keymap::default(), frame.area()))
+        .expect("dashboard should draw");
+    let screen = terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol())
+        .collect::<String>();
+    assert!(screen.contains("Hop 2"), "{screen}");
+    assert!(screen.contains("dns.google (8.8.8.8)  AS15169"), "{screen}");
+    assert!(screen.contains("RTT histogram"), "{screen}");
+}
+
+fn five_hop_app() -> DashboardApp {
+    let mut app = DashboardApp::new("8.8.8.8");
+    let mut hops = (1..=5)
+        .map(|ttl| live_hop(ttl, &format!("10.0.0.{ttl}"), None, vec![ttl as f64 * 2.0]))
+        .collect::<Vec<_>>();
+    hops[1] = live_hop(2, "172.16.0.1", Some("core.isp.example"), vec![40.0]);
+    hops[2].loss_pct = Some(100.0);
+    hops[2].host = "???".to_string();
+    hops[2].addresses.clear();
+    hops[2].avg_ms = None;
+    hops[2].samples.clear();
+    hops[3].loss_pct = Some(20.0);
+    app.ingest_snapshot(hops);
+    app
+}
+
+fn visible_ttls(app: &DashboardApp) -> Vec<usize> {
+    app.visible_hops().iter().map(|hop| hop.hop).collect()
+}
+
+#[test]
+fn sort_key_cycles_columns_worst_first() {
+    let mut app = five_hop_app();
+    assert_eq!(visible_ttls(&app), [1, 2, 3, 4, 5]);
+
+    assert_eq!(
+        Keymap::default().action(KeyCode::Char('s')),
+        Some(DashboardAction::CycleSort)
+    );
+    app.apply_action(DashboardAction::CycleSort);
+    assert_eq!(app.sort, HopSort::Loss);
+    assert_eq!(visible_ttls(&app), [3, 4, 1, 2, 5]);
+    app.apply_action(DashboardAction::CycleSort);
+    assert_eq!(
+        visible_ttls(&app),
+        [2, 5, 4, 1, 3],
+        "missing averages sort last"
+    );
+    assert!(build_help_text(&app, &Keymap::default()).ends_with(" | Sort: Avg"));
+
+    for _ in 0..4 {
+        app.apply_action(DashboardAction::CycleSort);
+    }
+    assert_eq!(app.sort, HopSort::Hop, "the cycle returns to hop order");
+}
+
+#[test]
+fn search_prompt_captures_keys_and_filters_hosts_and_ips() {
+    let mut app = five_hop_app();
+    let press = |app: &mut DashboardApp, code| {
+        let action = app
+            .action_for_event(KeyEvent::new(code, KeyModifiers::NONE), &Keymap::default())
+            .expect("key should map to an action");
+        app.apply_action(action)
+    };
+
+    assert!(!press(&mut app, KeyCode::Char('/')));
+    for c in "CORE.q".chars() {
+        assert!(!press(&mut app, KeyCode::Char(c)), "typing q must not quit");
+    }
+    press(&mut app, KeyCode::Backspace);
+    press(&mut app, KeyCode::Backspace);
+    assert_eq!(visible_ttls(&app), [2], "the search applies while typing");
+    assert!(
+        build_help_text(&app, &Keymap::default())
+            .contains("Search: CORE_ (Enter apply, Esc cancel)")
+    );
+
+    press(&mut app, KeyCode::Enter);
+    assert_eq!(app.filter, "CORE");
+    assert!(build_help_text(&app, &Keymap::default()).contains("Filter: \"CORE\""));
+
+    press(&mut app, KeyCode::Char('/'));
+    press(&mut app, KeyCode::Backspace);
+    press(&mut app, KeyCode::Backspace);
+    press(&mut app, KeyCode::Backspace);
+    press(&mut app, KeyCode::Backspace);
+    for c in "10.0.0.".chars() {
+        press(&mut app, KeyCode::Char(c));
+    }
+    assert_eq!(visible_ttls(&app), [1, 4, 5]);
+    press(&mut app, KeyCode::Esc);
+    assert_eq!(visible_ttls(&app), [2], "Esc restores the committed filter");
+
+    app.apply_action(DashboardAction::SelectNextHop);
+    assert_eq!(
+        app.selected_ttl,
+        Some(2),
+        "selection moves over visible rows"
+    );
+}
+
+#[test]
+fn unresponsive_hops_hide_and_host_column_switches_display() {
+    let mut app = five_hop_app();
+    app.apply_action(DashboardAction::ToggleUnresponsive);
+    assert_eq!(visible_ttls(&app), [1, 2, 4, 5]);
+    assert!(build_help_text(&app, &Keymap::default()).ends_with(" | Hiding ??? hops"));
+
+    let core = app.hops[1].clone();
+    assert_eq!(
+        app.host_display.host_label(&core),
+        "core.isp.example (172.16.0.1)"
+    );
+    app.apply_action(DashboardAction::CycleHostDisplay);
+    assert_eq!(app.host_display.host_label(&core), "172.16.0.1");
+    app.apply_action(DashboardAction::CycleHostDisplay);
+    assert_eq!(app.host_display.host_label(&core), "core.isp.example");
+    assert_eq!(app.host_display.host_label(&app.hops[0]), "10.0.0.1");
+    assert_eq!(app.host_display.host_label(&app.hops[2]), "???");
+    assert!(
+        build_help_text(&app, &Keymap::default()).ends_with(" | Hiding ??? hops | Hosts: name")
+    );
+}
+
+fn exporting_app(dir: &Path) -> DashboardApp {
+    let mut app = five_hop_app();
+    app.export_source = Some((Some(ReportProtocol::Icmp), ReportBackend::Trippy));
+    app.export_dir = dir.to_path_buf();
+    let mut second = app.hops.clone();
+    second[4].samples = vec![11.0];
+    second[4].loss_pct = Some(50.0);
+    app.ingest_snapshot(second);
+    app
+}
+
+const EXPORTED_AT_SECS: u64 = 1_792_333_385;
+
+fn exported_at() -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(EXPORTED_AT_SECS)
+}
+
+#[test]
+fn utc_timestamps_name_exports_after_the_target() {
+    assert_eq!(utc_timestamp(0), "19700101-000000Z");
+    assert_eq!(utc_timestamp(951_782_400), "20000229-000000Z");
+    assert_eq!(utc_timestamp(EXPORTED_AT_SECS), "20261018-142305Z");
+    assert_eq!(
+        export_file_name("2001:db8::1", exported_at(), "csv"),
+        "mtr-2001_db8__1-20261018-142305Z.csv"
+    );
+}
+
+#[test]
+fn json_export_keeps_the_snapshot_and_history_and_matches_the_schema() {
+    let dir = tempfile::tempdir().expect("tempdir should exist");
+    let mut app = exporting_app(dir.path());
+
+    app.apply_action(DashboardAction::Export(ExportFormat::Json));
+    app.export(ExportFormat::Json, exported_at());
+    app.export(ExportFormat::Json, exported_at());
+
+    let first = dir.path().join("mtr-8.8.8.8-20261018-142305Z.json");
+    let second = dir.path().join("mtr-8.8.8.8-20261018-142305Z-2.json");
+    assert!(second.exists(), "same-second exports must not overwrite");
+    assert!(
+        app.active_toast()
+            .is_some_and(|toast| toast.starts_with("Saved ") && toast.ends_with("Z-2.json")),
+        "{:?}",
+        app.toast
+    );
+    assert!(build_help_text(&app, &Keymap::default()).starts_with("Saved "));
+
+    let value: Value =
+        serde_json::from_str(&fs::read_to_string(first).expect("export should be readable"))
+            .expect("export should be JSON");
+    let schema: Value =
+        serde_json::from_str(crate::report_schema::REPORT_SCHEMA).expect("schema should parse");
+    let validator = jsonschema::draft202012::new(&schema).expect("schema should compile");
+    assert!(validator.is_valid(&value), "{value:#}");
+
+    let report = &value["report"];
+    assert_eq!(report["target_ip"], "8.8.8.8");
+    assert_eq!(report["backend"], "trippy");
+    assert_eq!(report["hops"].as_array().map(Vec::len), Some(5));
+    assert_eq!(report["hops"][4]["last_ms"], 11.0);
+    assert_eq!(
+        report["history"][1]["hosts"],
+        json!([{"ip": "172.16.0.1", "hostname": "core.isp.example"}])
+    );
+    assert_eq!(
+        report["history"][4]["rounds"],
+        json!([
+            {"round": 1, "rtt_ms": 10.0, "loss_pct": 0.0},
+            {"round": 2, "rtt_ms": 11.0, "loss_pct": 50.0},
+        ])
+    );
+    assert_eq!(
+        report["history"][2]["rounds"][0],
+        json!({"round": 1, "rtt_ms": null, "loss_pct": 100.0})
+    );
+}
+
+#[test]
+fn csv_and_markdown_exports_include_the_history() {
+    let dir = tempfile::tempdir().expect("tempdir should exist");
+    let mut app = exporting_app(dir.path());
+
+    app.export(ExportFormat::Csv, exported_at());
+    app.export(ExportFormat::Markdown, exported_at());
+
+    let csv = fs::read_to_string(dir.path().join("mtr-8.8.8.8-20261018-142305Z.csv"))
+        .expect("csv export should exist");
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("round,hop,hosts,rtt_ms,loss_pct"));
+    assert_eq!(lines.next(), Some("1,1,10.0.0.1,2,0"));
+    assert!(csv.contains("1,3,,,100\n"));
+    assert!(csv.ends_with("2,5,10.0.0.5,11,50\n"));
+
+    let markdown = fs::read_to_string(dir.path().join("mtr-8.8.8.8-20261018-142305Z.md"))
+        .expect("markdown export should exist");
+    assert!(markdown.starts_with("### windows-mtr report for `8.8.8.8`"));
+    assert!(markdown.contains("#### History"));
+    assert!(markdown.contains("| 2 | core.isp.example (172.16.0.1) | 2 | 0.0 | 40.0 | 40.0 |"));
+    assert!(markdown.contains("| 3 | ??? | 2 | 100.0 | ??? | ??? |"));
+    assert!(markdown.contains("| 5 | 10.0.0.5 | 2 | 25.0 | 10.5 | 11.0 |"));
+}
+
+#[test]
+fn copy_queues_an_osc52_text_report() {
+    let dir = tempfile::tempdir().expect("tempdir should exist");
+    let mut app = exporting_app(dir.path());
+
+    assert_eq!(
+        Keymap::default().action(KeyCode::Char('y')),
+        Some(DashboardAction::CopyReport)
+    );
+    app.apply_action(DashboardAction::CopyReport);
+
+    let sequence = app
+        .pending_clipboard
+        .clone()
+        .expect("copy should queue OSC 52");
+    let payload = sequence
+        .strip_prefix("\x1b]52;c;")
+        .and_then(|rest| rest.strip_suffix('\x07'))
+        .expect("sequence should be OSC 52");
+    let text = String::from_utf8(BASE64.decode(payload).expect("payload is base64"))
+        .expect("payload is UTF-8");
+    assert!(text.contains("core.isp.example"));
+    assert!(
+        app.active_toast()
+            .is_some_and(|toast| toast.contains("clipboard"))
+    );
+    let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 20))
+        .expect("test terminal should start");
+    terminal
+        .draw(|frame| draw_target(frame, &app, &Keymap::default(), frame.area()))
+        .expect("dashboard should draw");
+    let help_row = (0..100)
+        .map(|x| terminal.backend().buffer()[(x, 18)].symbol())
+        .collect::<String>();
+    assert!(
+        help_row.contains("Copied the text report to the clipboard"),
+        "{help_row}"
+    );
+    assert_eq!(fs::read_dir(dir.path()).map(Iterator::count).ok(), Some(0));
+}
+
+#[test]
+fn saved_reports_and_empty_dashboards_explain_why_nothing_was_exported() {
+    let dir = tempfile::tempdir().expect("tempdir should exist");
+    let report = SavedReport::from_json(&json!({
+        "report": {"hops": [{"ttl": 1, "host": "10.0.0.1", "avg": 1.0}]}
+    }))
+    .expect("report should parse");
+    let mut saved = DashboardApp::saved(&report);
+    saved.export_dir = dir.path().to_path_buf();
+    saved.apply_action(DashboardAction::Export(ExportFormat::Json));
+    assert!(
+        saved
+            .active_toast()
+            .is_some_and(|toast| toast.contains("already on disk"))
+    );
+    assert!(build_help_text(&saved, &Keymap::default()).contains("already on disk"));
+
+    let (mut waiting, _control) = DashboardApp::live(
+        "8.8.8.8",
+        tcp_settings(),
+        false,
+        EnhancedUiConfig::default(),
+    );
+    waiting.export_dir = dir.path().to_path_buf();
+    waiting.apply_action(DashboardAction::CopyReport);
+    assert!(waiting.pending_clipboard.is_none());
+    assert!(
+        waiting
+            .active_toast()
+            .is_some_and(|toast| toast.starts_with("Nothing to export"))
+    );
+    assert_eq!(fs::read_dir(dir.path()).map(Iterator::count).ok(), Some(0));
+}
+
+fn heatmap_app(rounds: u64) -> DashboardApp {
+    let mut app = DashboardApp::new("8.8.8.8");
+    app.enhanced_ui = EnhancedUiConfig {
+        latency_warn_ms: 20.0,
+        latency_bad_ms: 50.0,
+        loss_warn_pct: 10.0,
+        loss_bad_pct: 40.0,
+        ..EnhancedUiConfig::default()
+    };
+    for round in 1..=rounds {
+        let mut edge = live_hop(2, "10.0.0.2", None, vec![round as f64 * 10.0]);
+        edge.loss_pct = Some(if round == 2 { 50.0 } else { 0.0 });
+        let mut hops = vec![live_hop(1, "10.0.0.1", None, vec![1.0]), edge];
+        if round == 3 {
+            hops[0].samples.clear();
+            hops[0].avg_ms = None;
+            hops.pop();
+        }
+        app.ingest_snapshot(hops);
+    }
+    app
+}
+
+fn cell_colors(line: &Line<'_>) -> Vec<Option<Color>> {
+    line.spans[1..]
+        .iter()
+        .map(|span| match span.content.as_ref() {
+            "█" => span.style.fg,
+            "·" => Some(Color::DarkGray),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn heatmap_cells_use_the_enhanced_ui_thresholds() {
+    let mut app = heatmap_app(6);
+
+    let lines = heatmap_lines(&app, 1..=6);
+    assert_eq!(lines[0].spans[0].content, "  1 10.0.0.1     ");
+    assert_eq!(
+        cell_colors(&lines[0]),
+        [
+            Some(Color::Green),
+            Some(Color::Green),
+            Some(Color::DarkGray),
+            Some(Color::Green),
+            Some(Color::Green),
+            Some(Color::Green),
+        ]
+    );
+    assert_eq!(
+        cell_colors(&lines[1]),
+        [
+            Some(Color::Green),
+            Some(Color::Yellow),
+            None,
+            Some(Color::Yellow),
+            Some(Color::Red),
+            Some(Color::Red),
+        ],
+        "10-60 ms against 20/50 ms, and no cell in the round hop 2 was missing"
+    );
+    let legend = lines.last().expect("legend line");
+    assert!(legend.to_string().contains(">= 50 ms"), "{legend}");
+
+    app.apply_action(DashboardAction::ToggleHeatmapMetric);
+    assert_eq!(app.tab_index, 3);
+    let lines = heatmap_lines(&app, 1..=2);
+    assert_eq!(
+        cell_colors(&lines[1]),
+        [Some(Color::Green), Some(Color::Red)]
+    );
+    assert!(lines.last().expect("legend").to_string().contains("10-40%"));
+}
+
+#[test]
+fn heatmap_scrolls_through_the_bounded_history() {
+    assert_eq!(
+        Keymap::default().action(KeyCode::Char(',')),
+        Some(DashboardAction::ScrollHeatmap(-HEATMAP_SCROLL_ROUNDS))
+    );
+    assert_eq!(
+        Keymap::default().action(KeyCode::Char('>')),
+        Some(DashboardAction::ScrollHeatmap(HEATMAP_SCROLL_ROUNDS))
+    );
+
+    let mut app = heatmap_app(30);
+    assert_eq!(app.heatmap_window(10), 21..=30);
+    assert_eq!(app.heatmap_window(100), 1..=30);
+
+    app.apply_action(DashboardAction::ScrollHeatmap(-HEATMAP_SCROLL_ROUNDS));
+    assert_eq!(app.heatmap_window(10), 11..=20);
+    for _ in 0..5 {
+        app.apply_action(DashboardAction::ScrollHeatmap(-HEATMAP_SCROLL_ROUNDS));
+    }
+    assert_eq!(app.heatmap_scroll, 29);
+    assert_eq!(app.heatmap_window(10), 1..=10, "stays full-width");
+    for _ in 0..5 {
+        app.apply_action(DashboardAction::ScrollHeatmap(HEATMAP_SCROLL_ROUNDS));
+    }
+    assert_eq!(
+        app.heatmap_window(10),
+        21..=30,
+        "back to following new rounds"
+    );
+
+    let mut long = heatmap_app(HEATMAP_LEN as u64 + 50);
+    long.heatmap_scroll = u64::MAX;
+    assert_eq!(
+        long.heatmap_window(10),
+        51..=60,
+        "older rounds were dropped"
+    );
+}
+
+#[test]
+fn heatmap_tab_renders_rows_per_hop() {
+    let mut app = heatmap_app(4);
+    for _ in 0..3 {
+        app.apply_action(DashboardAction::NextTab);
+    }
+    assert_eq!(app.tab_index, 3);
+
+    let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(120, 20))
+        .expect("test terminal should start");
+    terminal
+        .draw(|frame| draw_target(frame, &app, &Keymap::default(), frame.area()))
+        .expect("dashboard should draw");
+    let screen = terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol())
+        .collect::<String>();
+    assert!(
+        screen.contains("Heatmap: RTT by hop, rounds 1-4 of 4"),
+        "{screen}"
+    );
+    assert!(screen.contains("  2 10.0.0.2     ██ █ "), "{screen}");
+    assert!(screen.contains("  1 10.0.0.1     ██·█ "), "{screen}");
+
+    app.apply_action(DashboardAction::NextTab);
+    assert_eq!(app.tab_index, 0, "the heatmap is the last tab");
+}
+
+fn tcp_settings() -> ProbeSettings {
+    ProbeSettings {
+        protocol: ReportProtocol::Tcp,
+        port: Some(443),
+        max_hops: None,
+        interval: POLL_INTERVAL,
+        paused: false,
+        epoch: 0,
+    }
+}
+
+fn polled(index: usize, hops: anyhow::Result<Vec<HopStat>>) -> TargetSnapshot {
+    TargetSnapshot {
+        index,
+        epoch: 0,
+        hops,
+    }
+}
+
+fn three_target_dashboard() -> Dashboard {
+    let mut dashboard = Dashboard::new(
+        ["8.8.8.8", "1.1.1.1", "example.com"]
+            .into_iter()
+            .map(|target| {
+                let settings = ProbeSettings {
+                    protocol: ReportProtocol::Icmp,
+                    ..tcp_settings()
+                };
+                DashboardApp::live(target, settings, false, EnhancedUiConfig::default()).0
+            })
+            .collect(),
+    );
+    let mut slow = live_hop(2, "1.1.1.1", None, vec![300.0]);
+    slow.loss_pct = Some(3.0);
+    dashboard.ingest(polled(
+        0,
+        Ok(vec![live_hop(1, "8.8.8.8", None, vec![12.0])]),
+    ));
+    dashboard.ingest(polled(
+        1,
+        Ok(vec![live_hop(1, "10.0.0.1", None, vec![1.0]), slow]),
+    ));
+    dashboard.ingest(polled(2, Err(anyhow!("trippy poll failed"))));
+    dashboard.ingest(polled(7, Ok(Vec::new())));
+    dashboard
+}
+
+#[test]
+fn summary_grid_badges_each_target_against_the_thresholds() {
+    let mut dashboard = three_target_dashboard();
+    assert_eq!(
+        dashboard.targets.iter().map(Health::of).collect::<Vec<_>>(),
+        [Health::Ok, Health::Bad, Health::Error]
+    );
+    let mut dropping = live_hop(1, "8.8.8.8", None, vec![12.0]);
+    dropping.loss_pct = Some(100.0);
+    dashboard.ingest(polled(0, Ok(vec![dropping])));
+    assert_eq!(Health::of(&dashboard.targets[0]), Health::Down);
+    dashboard.targets[0].hops[0].loss_pct = Some(2.5);
+    assert_eq!(Health::of(&dashboard.targets[0]), Health::Warn);
+    assert_eq!(
+        Health::of(&DashboardApp::new("idle.example")),
+        Health::Waiting
+    );
+
+    let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(120, 24))
+        .expect("test terminal should start");
+    terminal
+        .draw(|frame| draw_dashboard(frame, &dashboard, frame.area()))
+        .expect("dashboard should draw");
+    let screen = terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol())
+        .collect::<String>();
+    assert!(screen.contains("Summary: 3 targets"), "{screen}");
+    assert!(screen.contains("2 1.1.1.1"), "{screen}");
+    assert!(screen.contains("300.0"), "{screen}");
+    assert!(screen.contains("BAD"), "{screen}");
+    assert!(screen.contains("ERROR"), "{screen}");
+}
+
+#[test]
+fn targets_open_from_the_grid_and_switch_without_leaving_their_views() {
+    let mut dashboard = three_target_dashboard();
+    assert_eq!(dashboard.focus, None, "several targets start on the grid");
+
+    assert!(!dashboard.apply_action(DashboardAction::SelectNextHop));
+    assert_eq!(dashboard.cursor, 1);
+    assert_eq!(
+        Keymap::default().action(KeyCode::Enter),
+        Some(DashboardAction::OpenTarget)
+    );
+    dashboard.apply_action(DashboardAction::OpenTarget);
+    assert_eq!(dashboard.focus, Some(1));
+
+    dashboard.apply_action(DashboardAction::NextTab);
+    assert_eq!(
+        dashboard.targets[1].tab_index, 1,
+        "keys drive the open target"
+    );
+    dashboard.apply_action(DashboardAction::NextTarget);
+    assert_eq!(dashboard.focus, Some(2));
+    dashboard.apply_action(DashboardAction::PreviousTarget);
+    assert_eq!(
+        dashboard.targets[1].tab_index, 1,
+        "each target keeps its view"
+    );
+    assert_eq!(
+        Keymap::default().action(KeyCode::Char('1')),
+        Some(DashboardAction::FocusTarget(0))
+    );
+    dashboard.apply_action(DashboardAction::FocusTarget(0));
+    assert_eq!(dashboard.focus, Some(0));
+    dashboard.apply_action(DashboardAction::FocusTarget(8));
+    assert_eq!(dashboard.focus, Some(0), "no ninth target");
+
+    dashboard.apply_action(DashboardAction::StartSearch);
+    let typed = dashboard.action_for_event(KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE));
+    assert_eq!(typed, Some(DashboardAction::SearchInput('g')));
+    dashboard.apply_action(DashboardAction::CancelSearch);
+
+    dashboard.apply_action(DashboardAction::ShowSummary);
+    assert_eq!((dashboard.focus, dashboard.cursor), (None, 0));
+    assert!(dashboard.apply_action(DashboardAction::Quit));
+}
+
+#[test]
+fn a_single_target_has_no_grid() {
+    let mut dashboard = Dashboard::new(vec![five_hop_app()]);
+    assert_eq!(dashboard.focus, Some(0));
+    dashboard.apply_action(DashboardAction::ShowSummary);
+    dashboard.apply_action(DashboardAction::OpenTarget);
+    assert_eq!(dashboard.focus, Some(0));
+
+    let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 24))
+        .expect("test terminal should start");
+    terminal
+        .draw(|frame| draw_dashboard(frame, &dashboard, frame.area()))
+        .expect("dashboard should draw");
+    let screen = terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol())
+        .collect::<String>();
+    assert!(!screen.contains("Targets"), "{screen}");
+    assert!(
+        screen.contains("windows-mtr fallback dashboard (8.8.8.8)"),
+        "{screen}"
+    );
+}
+
+#[test]
+fn navigating_an_empty_dashboard_does_not_panic() {
+    let mut dashboard = Dashboard::new(Vec::new());
+    for action in [
+        DashboardAction::SelectNextHop,
+        DashboardAction::SelectPreviousHop,
+        DashboardAction::OpenTarget,
+        DashboardAction::FocusTarget(0),
+    ] {
+        assert!(!dashboard.apply_action(action));
+    }
+    assert_eq!(dashboard.focus, None);
+    assert!(dashboard.apply_action(DashboardAction::Quit));
+}
+
+fn draw_screen(app: &DashboardApp, width: u16, height: u16) -> ratatui::buffer::Buffer {
+    let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(width, height))
+        .expect("test terminal should start");
+    terminal
+        .draw(|frame| draw_target(frame, app, &Keymap::default(), frame.area()))
+        .expect("dashboard should draw");
+    terminal.backend().buffer().clone()
+}
+
+fn buffer_text(buffer: &ratatui::buffer::Buffer) -> String {
+    buffer.content().iter().map(|cell| cell.symbol()).collect()
+}
+
+#[test]
+fn hop_rows_are_coloured_by_loss_and_latency_bands() {
+    let config = EnhancedUiConfig::default();
+    let mut hop = live_hop(1, "10.0.0.1", None, vec![20.0]);
+    assert_eq!(Band::of_hop(&hop, &config), Band::Ok);
+    hop.loss_pct = Some(3.0);
+    assert_eq!(Band::of_hop(&hop, &config), Band::Warn);
+    hop.avg_ms = Some(400.0);
+    assert_eq!(
+        Band::of_hop(&hop, &config),
+        Band::Bad,
+        "the worse band wins"
+    );
+    hop.loss_pct = None;
+    hop.avg_ms = None;
+    assert_eq!(Band::of_hop(&hop, &config), Band::Ok);
+
+    let mut app = five_hop_app();
+    app.tab_index = 1;
+    let buffer = draw_screen(&app, 120, 24);
+    // Rows start right after the table border: `│1    10.0.0.1 ...`.
+    let row_color = |ttl: u16| {
+        let y = (3..24)
+            .find(|y| buffer[(1, *y)].symbol() == ttl.to_string())
+            .expect("hop row should be drawn");
+        buffer[(1, y)].fg
+    };
+    assert_eq!(row_color(1), Color::Reset);
+    assert_eq!(row_color(3), Color::Red, "100% loss");
+    assert_eq!(
+        row_color(4),
+        Color::Red,
+        "20% loss is above the 5% bad threshold"
+    );
+
+    app.enhanced_ui.row_coloring = false;
+    let buffer = draw_screen(&app, 120, 24);
+    assert!(
+        buffer.content().iter().all(|cell| cell.fg != Color::Red),
+        "row colouring can be turned off"
+    );
+}
+
+#[test]
+fn hop_rows_get_an_rtt_trend_sparkline() {
+    let rtt = [(1.0, 1.0), (2.0, 2.0), (3.0, 4.0), (4.0, 8.0)]
+        .into_iter()
+        .collect::<VecDeque<_>>();
+    assert_eq!(rtt_sparkline(&rtt, 3), "▃▅█");
+    assert_eq!(rtt_sparkline(&rtt, 10), "▂▃▅█");
+    assert_eq!(rtt_sparkline(&[(1.0, 0.0)].into(), 4), "▁");
+
+    let mut app = five_hop_app();
+    app.ingest_snapshot(app.hops.clone());
+    app.tab_index = 1;
+    let screen = buffer_text(&draw_screen(&app, 120, 24));
+    assert!(screen.contains("Trend"), "{screen}");
+    assert!(screen.contains("██"), "{screen}");
+
+    app.enhanced_ui.sparklines = false;
+    let screen = buffer_text(&draw_screen(&app, 120, 24));
+    assert!(!screen.contains("Trend"), "{screen}");
+}
+
+#[test]
+fn latency_summary_reports_percentiles_and_jitter() {
+    let samples = (1..=10).map(f64::from).collect::<VecDeque<_>>();
+    let summary = LatencySummary::of(&samples).expect("samples present");
+    assert_eq!(summary.samples, 10);
+    assert_eq!(
+        (summary.p50, summary.p90, summary.p95, summary.p99),
+        (5.0, 9.0, 10.0, 10.0)
+    );
+    assert_eq!(summary.jitter, 1.0);
+    assert!((summary.stddev - 8.25_f64.sqrt()).abs() < 1e-9);
+    assert_eq!(LatencySummary::of(&VecDeque::new()), None);
+    assert_eq!(
+        LatencySummary::of(&[7.0].into()).map(|summary| summary.jitter),
+        Some(0.0)
+    );
+
+    let mut app = five_hop_app();
+    let screen = buffer_text(&draw_screen(&app, 120, 30));
+    assert!(screen.contains("Latency summary (ms)"), "{screen}");
+    assert!(screen.contains("p95"), "{screen}");
+
+    app.enhanced_ui.summary = false;
+    let screen = buffer_text(&draw_screen(&app, 120, 30));
+    assert!(!screen.contains("Latency summary"), "{screen}");
+}
+
+#[test]
+fn live_keys_pause_reset_and_change_the_poll_interval() {
+    for (key, action) in [
+        ('p', DashboardAction::TogglePause),
+        ('r', DashboardAction::ResetStats),
+        ('d', DashboardAction::NextTab),
+        ('t', DashboardAction::CycleProtocol),
+        ('M', DashboardAction::EditMaxHops),
+    ] {
+        assert_eq!(
+            Keymap::default().action(KeyCode::Char(key)),
+            Some(action),
+            "{key}"
+        );
+    }
+
+    let (mut app, control) = DashboardApp::live(
+        "8.8.8.8",
+        tcp_settings(),
+        false,
+        EnhancedUiConfig::default(),
+    );
+    app.ingest_snapshot(vec![live_hop(1, "8.8.8.8", None, vec![12.0])]);
+    app.apply_action(DashboardAction::TogglePause);
+    assert!(control.try_recv().is_ok_and(|settings| settings.paused));
+    app.apply_action(DashboardAction::Slower);
+    assert_eq!(
+        control.try_recv().map(|settings| settings.interval),
+        Ok(Duration::from_millis(1_800))
+    );
+    for _ in 0..4 {
+        app.apply_action(DashboardAction::Faster);
+    }
+    assert_eq!(
+        control.try_iter().last().map(|settings| settings.interval),
+        Some(MIN_POLL_INTERVAL)
+    );
+    for _ in 0..10 {
+        app.apply_action(DashboardAction::Slower);
+    }
+    let settings = control.try_iter().last();
+    assert_eq!(
+        settings.map(|settings| settings.interval),
+        Some(MAX_POLL_INTERVAL)
+    );
+    assert_eq!(
+        settings.map(|settings| settings.label()).as_deref(),
+        Some("TCP:443 | default max hops | every 60.0s | PAUSED")
+    );
+    assert!(
+        buffer_text(&draw_screen(&app, 100, 20))
+            .contains("(8.8.8.8) TCP:443 | default max hops | every 60.0s | PAUSED"),
+        "the header shows the effective settings"
+    );
+
+    app.apply_action(DashboardAction::ResetStats);
+    assert!(app.hops.is_empty() && app.hop_history.is_empty());
+    assert_eq!((app.rounds, app.latency_history.len()), (0, 0));
+    assert_eq!(app.active_toast(), Some("Statistics reset"));
+    assert!(
+        control.try_recv().is_err(),
+        "a reset does not touch the probe"
+    );
+}
+
+#[test]
+fn protocol_switches_prompt_for_a_port_and_drop_polls_of_the_old_protocol() {
+    let icmp = ProbeSettings {
+        protocol: ReportProtocol::Icmp,
+        ..tcp_settings()
+    };
+    let (app, control) = DashboardApp::live("8.8.8.8", icmp, true, EnhancedUiConfig::default());
+    let mut dashboard = Dashboard::new(vec![app]);
+    dashboard.ingest(polled(
+        0,
+        Ok(vec![live_hop(1, "8.8.8.8", None, vec![12.0])]),
+    ));
+    assert_eq!(
+        dashboard.targets[0].export_source,
+        Some((Some(ReportProtocol::Icmp), ReportBackend::WindowsIcmpHelper))
+    );
+
+    dashboard.apply_action(DashboardAction::CycleProtocol);
+    assert_eq!(
+        dashboard.targets[0].settings_prompt,
+        Some(SettingsPrompt::Port(ReportProtocol::Tcp, "443".to_string())),
+        "the prompt starts from the last port"
+    );
+    for key in [KeyCode::Backspace, KeyCode::Backspace, KeyCode::Backspace] {
+        let action = dashboard.action_for_event(KeyEvent::new(key, KeyModifiers::NONE));
+        assert_eq!(action, Some(DashboardAction::PromptBackspace));
+        dashboard.apply_action(DashboardAction::PromptBackspace);
+    }
+    dashboard.apply_action(DashboardAction::SubmitPrompt);
+    assert_eq!(
+        dashboard.targets[0].active_toast(),
+        Some("TCP needs a port from 1 to 65535")
+    );
+    assert!(dashboard.targets[0].settings_prompt.is_some());
+    for c in "8x080".chars() {
+        dashboard.apply_action(DashboardAction::PromptInput(c));
+    }
+    assert!(build_help_text(&dashboard.targets[0], &Keymap::default()).contains("TCP port: 8080_"));
+    dashboard.apply_action(DashboardAction::SubmitPrompt);
+
+    let app = &dashboard.targets[0];
+    assert!(app.settings_prompt.is_none());
+    let sent = control
+        .try_recv()
+        .expect("the poller gets the new settings");
+    assert_eq!(
+        (sent.protocol, sent.port, sent.epoch),
+        (ReportProtocol::Tcp, Some(8080), 1)
+    );
+    assert!(
+        app.hops.is_empty(),
+        "a new protocol starts the statistics over"
+    );
+    assert_eq!(
+        app.export_source,
+        Some((Some(ReportProtocol::Tcp), ReportBackend::Trippy))
+    );
+    dashboard.ingest(polled(
+        0,
+        Ok(vec![live_hop(1, "8.8.8.8", None, vec![12.0])]),
+    ));
+    assert!(dashboard.targets[0].hops.is_empty(), "ICMP poll is dropped");
+    dashboard.ingest(TargetSnapshot {
+        index: 0,
+        epoch: 1,
+        hops: Ok(vec![live_hop(1, "8.8.8.8", None, vec![30.0])]),
+    });
+    assert_eq!(dashboard.targets[0].rounds, 1);
+
+    dashboard.apply_action(DashboardAction::CycleProtocol);
+    dashboard.apply_action(DashboardAction::CancelPrompt);
+    dashboard.apply_action(DashboardAction::EditMaxHops);
+    dashboard.apply_action(DashboardAction::PromptInput('0'));
+    dashboard.apply_action(DashboardAction::SubmitPrompt);
+    assert_eq!(
+        dashboard.targets[0].active_toast(),
+        Some("Max hops must be from 1 to 255")
+    );
+    dashboard.apply_action(DashboardAction::PromptBackspace);
+    dashboard.apply_action(DashboardAction::PromptInput('2'));
+    dashboard.apply_action(DashboardAction::PromptInput('0'));
+    dashboard.apply_action(DashboardAction::SubmitPrompt);
+    let sent = control
+        .try_recv()
+        .expect("the poller gets the new max hops");
+    assert_eq!(
+        (sent.protocol, sent.max_hops, sent.epoch),
+        (ReportProtocol::Tcp, Some(20), 1),
+        "Esc kept TCP"
+    );
+    assert_eq!(
+        dashboard.targets[0].rounds, 1,
+        "max hops keeps the statistics"
+    );
+
+    dashboard.apply_action(DashboardAction::CycleProtocol);
+    dashboard.apply_action(DashboardAction::SubmitPrompt);
+    dashboard.apply_action(DashboardAction::CycleProtocol);
+    let sent = control.try_iter().last().expect("ICMP needs no prompt");
+    assert_eq!(
+        (sent.protocol, sent.port, sent.epoch),
+        (ReportProtocol::Icmp, Some(8080), 3)
+    );
+}
+
+#[test]
+fn probe_settings_override_the_request() {
+    // `mtr -T -P 443 --source-port 40000 -m 12 8.8.8.8`
+    let request = ProbeRequest {
+        host: "8.8.8.8".to_string(),
+        tcp: true,
+        udp: false,
+        port: Some(443),
+        source_port: Some(40000),
+        report: false,
+        json_output: None,
+        csv_output_path: None,
+        count: None,
+        interval_seconds: None,
+        timeout_seconds: None,
+        report_wide: false,
+        no_dns: false,
+        max_hops: Some(12),
+        show_asn: false,
+        dns_lookup_as_info: false,
+        packet_size: None,
+        src: None,
+        interface: None,
+        ecmp: None,
+        dns_cache_ttl_seconds: None,
+        trippy_flags: None,
+        ui_mode: UiMode::Dashboard,
+        enhanced_ui: EnhancedUiConfig::default(),
+        has_enhanced_overrides: false,
+    };
+    let settings = ProbeSettings::new(ReportProtocol::Tcp, &request);
+    assert_eq!((settings.port, settings.max_hops), (Some(443), Some(12)));
+
+    let udp = ProbeSettings {
+        protocol: ReportProtocol::Udp,
+        port: Some(33434),
+        max_hops: None,
+        ..settings
+    }
+    .apply_to(&request);
+    assert!(udp.udp && !udp.tcp);
+    assert_eq!(
+        (udp.port, udp.source_port, udp.max_hops),
+        (Some(33434), Some(40000), None)
+    );
+    let icmp = ProbeSettings {
+        protocol: ReportProtocol::Icmp,
+        ..settings
+    }
+    .apply_to(&request);
+    assert!(!icmp.udp && !icmp.tcp);
+    assert_eq!(
+        (icmp.port, icmp.source_port, icmp.max_hops),
+        (None, None, Some(12)),
+        "ports only apply to TCP and UDP"
+    );
+    assert!(
+        build_json_snapshot_args(&icmp, "8.8.8.8")
+            .is_ok_and(|args| !args.iter().any(|arg| arg == "--target-port"))
+    );
+}
+
+/// A step of a scripted dashboard session.
+enum Step {
+    /// Hand a poll result to the dashboard, as a poller thread would.
+    Poll(TargetSnapshot),
+    Key(KeyCode),
+}
+
+struct ScriptedEvents {
+    steps: VecDeque<Step>,
+    snapshot_tx: mpsc::Sender<TargetSnapshot>,
+}
+
+impl EventSource for ScriptedEvents {
+    fn next_event(&mut self, _timeout: Duration) -> anyhow::Result<Option<Event>> {
+        match self.steps.pop_front() {
+            Some(Step::Poll(snapshot)) => {
+                self.snapshot_tx.send(snapshot)?;
+                Ok(None)
+            }
+            Some(Step::Key(code)) => Ok(Some(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))),
+            None => Err(anyhow!("the script ended before the dashboard quit")),
+        }
+    }
+}
+
+/// Run the real UI loop on a 100x30 test terminal until the script presses `q`. Returns the
+/// last frame drawn and everything written to the clipboard.
+fn run_script(
+    dashboard: Dashboard,
+    steps: impl IntoIterator<Item = Step>,
+) -> (ratatui::buffer::Buffer, Vec<u8>) {
+    let (snapshot_tx, snapshot_rx) = mpsc::channel();
+    let mut events = ScriptedEvents {
+        steps: steps.into_iter().collect(),
+        snapshot_tx,
+    };
+    let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 30))
+        .expect("test terminal should start");
+    let mut clipboard = Vec::new();
+    let code = run_ui_loop(
+        &mut terminal,
+        dashboard,
+        &snapshot_rx,
+        &mut events,
+        &mut clipboard,
+    )
+    .expect("the dashboard should run the script");
+    assert_eq!(code, 0);
+    (terminal.backend().buffer().clone(), clipboard)
+}
+
+/// Twelve rounds to a four-hop path whose third hop never answers.
+fn golden_polls() -> Vec<Step> {
+    (1..=12)
+        .map(|round| {
+            let mut silent = live_hop(3, "10.9.9.9", None, Vec::new());
+            silent.host = "???".to_string();
+            silent.addresses.clear();
+            silent.loss_pct = Some(100.0);
+            silent.avg_ms = None;
+            let mut destination = live_hop(
+                4,
+                "8.8.8.8",
+                Some("dns.google"),
+                vec![30.0 + (round * 7 % 11) as f64],
+            );
+            if round == 6 {
+                destination.loss_pct = Some(25.0);
+            }
+            Step::Poll(polled(
+                0,
+                Ok(vec![
+                    live_hop(1, "10.0.0.1", None, vec![1.0 + (round % 3) as f64 * 0.5]),
+                    live_hop(
+                        2,
+                        "172.16.0.1",
+                        Some("core.isp.example"),
+                        vec![20.0 + round as f64],
+                    ),
+                    silent,
+                    destination,
+                ]),
+            ))
+        })
+        .collect()
+}
+
+fn golden_dashboard() -> Dashboard {
+    let (app, _control) = DashboardApp::live(
+        "8.8.8.8",
+        tcp_settings(),
+        false,
+        EnhancedUiConfig::default(),
+    );
+    Dashboard::new(vec![app])
+}
+
+/// Compare a frame with `tests/fixtures/dashboard/<name>.txt`. Set
+/// `WINDOWS_MTR_UPDATE_GOLDENS=1` to rewrite the file after an intended change, then review
+/// the diff.
+fn assert_golden(name: &str, buffer: &ratatui::buffer::Buffer) {
+    let screen = buffer
+        .content()
+        .chunks(buffer.area.width as usize)
+        .map(|row| {
+            let line = row.iter().map(|cell| cell.symbol()).collect::<String>();
+            format!("{}\n", line.trim_end())
+        })
+        .collect::<String>();
+    let path = Path::new("tests/fixtures/dashboard").join(format!("{name}.txt"));
+    if std::env::var_os("WINDOWS_MTR_UPDATE_GOLDENS").is_some() {
+        fs::create_dir_all("tests/fixtures/dashboard").expect("golden directory");
+        fs::write(&path, &screen).expect("golden should be written");
+        return;
+    }
+    let golden = fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!(
+            "{}: {err}; run with WINDOWS_MTR_UPDATE_GOLDENS=1 to create it",
+            path.display()
+        )
+    });
+    assert!(
+        screen == golden,
+        "the {name} screen no longer matches {}; rerun with WINDOWS_MTR_UPDATE_GOLDENS=1 if \
+         the change is intended\n--- expected\n{golden}--- actual\n{screen}",
+        path.display()
+    );
+}
+
+fn golden_tab(name: &str, keys: &[KeyCode]) {
+    let steps = golden_polls()
+        .into_iter()
+        .chain(keys.iter().map(|key| Step::Key(*key)))
+        .chain([Step::Key(KeyCode::Char('q'))]);
+    let (screen, _) = run_script(golden_dashboard(), steps);
+    assert_golden(name, &screen);
+}
+
+#[test]
+fn overview_tab_matches_its_golden_screen() {
+    golden_tab("overview", &[]);
+}
+
+#[test]
+fn hops_tab_matches_its_golden_screen() {
+    golden_tab("hops", &[KeyCode::Tab]);
+}
+
+#[test]
+fn hop_detail_pane_matches_its_golden_screen() {
+    golden_tab("hop_detail", &[KeyCode::Down, KeyCode::Down]);
+}
+
+#[test]
+fn charts_tab_matches_its_golden_screen() {
+    golden_tab("charts", &[KeyCode::Tab, KeyCode::Tab]);
+}
+
+#[test]
+fn heatmap_tab_matches_its_golden_screen() {
+    golden_tab(
+        "heatmap",
+        &[KeyCode::Char('d'), KeyCode::Char('d'), KeyCode::Char('d')],
+    );
+}
+
+#[test]
+fn help_overlay_matches_its_golden_screen() {
+    golden_tab("help", &[KeyCode::Char('?')]);
+}
+
+#[test]
+fn failing_polls_match_the_golden_error_screen() {
+    let failures = (0..3).map(|_| {
+        Step::Poll(polled(
+            0,
+            Err(anyhow!("trippy poll failed: permission denied")),
+        ))
+    });
+    let (screen, _) = run_script(
+        golden_dashboard(),
+        failures.chain([Step::Key(KeyCode::Char('q'))]),
+    );
+    assert_golden("error", &screen);
+}
+
+#[test]
+fn summary_grid_matches_its_golden_screen() {
+    let (screen, _) = run_script(
+        three_target_dashboard(),
+        [Step::Key(KeyCode::Down), Step::Key(KeyCode::Char('q'))],
+    );
+    assert_golden("summary", &screen);
+}
+
+#[test]
+fn scripted_sessions_reach_the_clipboard_and_stop_at_the_end_of_the_script() {
+    let steps = golden_polls()
+        .into_iter()
+        .chain([Step::Key(KeyCode::Char('y')), Step::Key(KeyCode::Char('q'))]);
+    let (screen, clipboard) = run_script(golden_dashboard(), steps);
+    let clipboard = String::from_utf8(clipboard).expect("OSC 52 is ASCII");
+    assert!(clipboard.starts_with("\x1b]52;c;"), "{clipboard:?}");
+    assert!(buffer_text(&screen).contains("Copied the text report"));
+
+    let (snapshot_tx, snapshot_rx) = mpsc::channel();
+    let mut events = ScriptedEvents {
+        steps: VecDeque::from([Step::Key(KeyCode::Tab)]),
+        snapshot_tx,
+    };
+    let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(40, 12))
+        .expect("test terminal should start");
+    let error = run_ui_loop(
+        &mut terminal,
+        golden_dashboard(),
+        &snapshot_rx,
+        &mut events,
+        &mut io::sink(),
+    )
+    .expect_err("the loop should stop when input ends");
+    assert!(error.to_string().contains("script ended"), "{error}");
+}
+
+fn destination_round(app: &mut DashboardApp, ip: Option<&str>, loss: f64, rtt: f64) {
+    let mut destination = live_hop(2, ip.unwrap_or("8.8.8.8"), None, vec![rtt]);
+    destination.loss_pct = Some(loss);
+    if ip.is_none() {
+        destination.addresses.clear();
+    }
+    app.ingest_snapshot(vec![live_hop(1, "10.0.0.1", None, vec![1.0]), destination]);
+}
+
+#[test]
+fn alert_rules_fire_once_per_excursion_and_on_responder_changes() {
+    let mut app = DashboardApp::new("dns.google");
+    let at = UNIX_EPOCH + Duration::from_secs(EXPORTED_AT_SECS);
+    let mut rules = Vec::new();
+    for (ip, loss, rtt) in [
+        (Some("8.8.8.8"), 10.0, 20.0),
+        (Some("8.8.8.8"), 10.0, 20.0),
+        (Some("8.8.8.8"), 10.0, 300.0),
+        (Some("8.8.8.8"), 10.0, 300.0),
+        (Some("8.8.8.8"), 0.0, 300.0),
+        (Some("8.8.4.4"), 10.0, 20.0),
+        (None, 100.0, 20.0),
+        (Some("8.8.4.4"), 10.0, 20.0),
+        (Some("8.8.4.4"), 10.0, 20.0),
+    ] {
+        destination_round(&mut app, ip, loss, rtt);
+        rules.push(
+            app.check_alerts(3, at)
+                .iter()
+                .map(|alert| (alert.round, alert.rule))
+                .collect::<Vec<_>>(),
+        );
+    }
+    assert_eq!(
+        rules.concat(),
+        [
+            (3, AlertRule::Loss),
+            (5, AlertRule::Rtt),
+            (6, AlertRule::ResponderChanged),
+            (8, AlertRule::Loss),
+        ],
+        "a round under the limit re-arms the rule; a silent round keeps the responder"
+    );
+
+    destination_round(&mut app, Some("8.8.8.8"), 0.0, 1.0);
+    let alert = app.check_alerts(3, at).pop().expect("responder changed");
+    assert_eq!(
+        alert.message,
+        "destination responder changed from 8.8.4.4 to 8.8.8.8"
+    );
+    let payload = serde_json::to_value(&alert).expect("alert should serialize");
+    assert_eq!(payload["target"], "dns.google");
+    assert_eq!(payload["rule"], "responder_changed");
+    assert_eq!(payload["time_ms"], EXPORTED_AT_SECS * 1000);
+    assert_eq!(payload["previous_responder"], "8.8.4.4");
+    assert!(payload.get("threshold").is_none());
+
+    app.reset_stats();
+    for _ in 0..2 {
+        destination_round(&mut app, Some("8.8.8.8"), 10.0, 1.0);
+    }
+    assert!(
+        app.check_alerts(2, at).is_empty(),
+        "a reset restarts the count"
+    );
+    destination_round(&mut app, Some("8.8.8.8"), 10.0, 1.0);
+    let loss = app.check_alerts(2, at).pop().expect("loss alert");
+    assert_eq!(loss.message, "destination loss 10.0% above 5% for 2 rounds");
+    assert_eq!((loss.value, loss.threshold), (Some(10.0), Some(5.0)));
+}
+
+#[test]
+fn alerts_ring_the_bell_show_a_banner_and_reach_the_log_and_the_command() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let log_path = dir.path().join("alerts.jsonl");
+    let hook_path = dir.path().join("hook.json");
+    let on_alert = if cfg!(windows) {
+        format!("more > \"{}\"", hook_path.display())
+    } else {
+        format!("cat > '{}'", hook_path.display())
+    };
+    let (app, _control) = DashboardApp::live(
+        "8.8.8.8",
+        tcp_settings(),
+        false,
+        EnhancedUiConfig::default(),
+    );
+    let mut dashboard = Dashboard::new(vec![app]);
+    dashboard.alerts = Some(AlertConfig {
+        rounds: 2,
+        on_alert: Some(on_alert),
+        log_path: Some(log_path.clone()),
+    });
+
+    let mut dropping = live_hop(1, "8.8.8.8", None, vec![12.0]);
+    dropping.loss_pct = Some(50.0);
+    dashboard.ingest(polled(0, Ok(vec![dropping.clone()])));
+    assert_eq!(dashboard.take_terminal_output(), None);
+    dashboard.ingest(polled(0, Err(anyhow!("trippy poll failed"))));
+    dashboard.ingest(polled(0, Ok(vec![dropping])));
+    assert_eq!(
+        dashboard.alert_banner.as_ref().map(|banner| banner.earlier),
+        Some(0)
+    );
+    assert_eq!(dashboard.take_terminal_output().as_deref(), Some("\x07"));
+    assert_eq!(dashboard.take_terminal_output(), None, "one bell per alert");
+
+    let log = fs::read_to_string(&log_path).expect("alert log should be written");
+    let logged: Value = serde_json::from_str(log.trim()).expect("one JSON line");
+    assert_eq!(logged["rule"], "loss");
+    assert_eq!(logged["value"], 50.0);
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let hook = loop {
+        match fs::read_to_string(&hook_path) {
+            Ok(hook) if hook.contains('}') => break hook,
+            _ if Instant::now() > deadline => panic!("--on-alert command did not run"),
+            _ => thread::sleep(Duration::from_millis(20)),
+        }
+    };
+    assert_eq!(hook.trim(), log.trim(), "the command gets the logged JSON");
+
+    let screen = buffer_text(&{
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(120, 24))
+            .expect("test terminal should start");
+        terminal
+            .draw(|frame| draw_dashboard(frame, &dashboard, frame.area()))
+            .expect("dashboard should draw");
+        terminal.backend().buffer().clone()
+    });
+    assert!(
+        screen.contains("8.8.8.8: destination loss 50.0% above 5% for 2 rounds | x dismiss"),
+        "{screen}"
+    );
+    assert_eq!(
+        Keymap::default().action(KeyCode::Char('x')),
+        Some(DashboardAction::DismissAlerts)
+    );
+    dashboard.apply_action(DashboardAction::DismissAlerts);
+    assert!(dashboard.alert_banner.is_none());
+}
+
+#[test]
+fn the_alert_banner_keeps_the_newest_alert_and_counts_the_rest() {
+    let mut dashboard = Dashboard::new(vec![five_hop_app()]);
+    dashboard.alerts = Some(AlertConfig {
+        rounds: 1,
+        on_alert: None,
+        log_path: None,
+    });
+    for round in 0..50 {
+        let mut hop = live_hop(1, "8.8.8.8", None, vec![12.0]);
+        hop.loss_pct = Some(if round % 2 == 0 { 50.0 } else { 0.0 });
+        dashboard.ingest(polled(0, Ok(vec![hop])));
+    }
+
+    let banner = dashboard.alert_banner.as_ref().expect("alerts fired");
+    assert_eq!(banner.earlier, 24);
+    assert_eq!(banner.latest.rule, AlertRule::Loss);
+    assert!(alert_banner_text(&banner.latest, banner.earlier).contains("(+24 earlier)"));
+}
+
+#[test]
+fn failed_alert_actions_are_reported_without_stopping_the_dashboard() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let mut dashboard = Dashboard::new(vec![five_hop_app()]);
+    dashboard.alerts = Some(AlertConfig {
+        rounds: 1,
+        on_alert: None,
+        log_path: Some(dir.path().join("missing").join("alerts.jsonl")),
+    });
+    let mut slow = live_hop(1, "8.8.8.8", None, vec![400.0]);
+    slow.loss_pct = Some(0.0);
+    dashboard.ingest(polled(0, Ok(vec![slow])));
+    assert!(dashboard.alert_banner.is_some());
+    assert!(
+        dashboard.targets[0].active_toast().is_some_and(
+            |toast| toast.starts_with("Alert action failed: failed to write alert log")
+        ),
+        "{:?}",
+        dashboard.targets[0].active_toast()
+    );
+}
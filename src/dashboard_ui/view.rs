@@ -0,0 +1,313 @@
+//! Entry points that run the dashboard in a terminal, and [`DashboardView`] for embedding it.
+
+use super::alerts::AlertConfig;
+use super::app::{Dashboard, DashboardApp, HopStat, Keymap, Theme};
+use super::render::draw_dashboard;
+use crate::native_icmp;
+use crate::recording::{Recorder, Recording};
+use crate::report_render::{SavedHop, SavedReport};
+use crate::report_schema::{ReportBackend, ReportProtocol};
+use crate::service::{EnhancedUiConfig, ProbeRequest};
+use anyhow::{Context, bail};
+use crossterm::event::{self, Event, KeyEvent};
+use crossterm::execute;
+use crossterm::terminal::{
+    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+};
+use ratatui::Terminal;
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::layout::Rect;
+use std::io::{self, Stdout, Write};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// One live dashboard target and how to poll it.
+pub struct DashboardTarget {
+    pub target: String,
+    /// Probe options. The embedded trippy JSON snapshot arguments are built from it with the
+    /// protocol, port and max hops currently chosen in the dashboard.
+    pub request: ProbeRequest,
+    /// Native probe settings, used instead of trippy while the protocol is ICMP.
+    pub native_icmp_config: Option<native_icmp::Config>,
+    /// Appends every round's hop snapshot to a recording.
+    pub recorder: Option<Recorder>,
+}
+
+/// A target's poll result.
+pub(super) struct TargetSnapshot {
+    /// Position of the target in the dashboard.
+    pub(super) index: usize,
+    /// [`ProbeSettings::epoch`](super::app::ProbeSettings::epoch) of the settings the poll ran with.
+    pub(super) epoch: u64,
+    pub(super) hops: anyhow::Result<Vec<HopStat>>,
+}
+
+impl From<HopSnapshot> for TargetSnapshot {
+    fn from(snapshot: HopSnapshot) -> Self {
+        Self {
+            index: snapshot.target,
+            epoch: 0,
+            hops: snapshot
+                .hops
+                .map(|hops| hops.iter().map(HopStat::from_saved).collect())
+                .map_err(anyhow::Error::msg),
+        }
+    }
+}
+
+/// One round of an embedding tool's probe of one target.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HopSnapshot {
+    /// Position of the target in [`DashboardOptions::targets`]; others are ignored.
+    pub target: usize,
+    /// Every hop's statistics so far, in TTL order, or why this round failed. The hop table
+    /// shows the latest snapshot as is; the charts, heatmap and alerts add one round per
+    /// snapshot. An empty list counts as a failed round.
+    pub hops: Result<Vec<SavedHop>, String>,
+}
+
+/// What a dashboard fed by [`HopSnapshot`]s shows, and how.
+#[derive(Clone, Debug)]
+pub struct DashboardOptions {
+    /// Names of the targets. With more than one, the dashboard opens on the summary grid.
+    pub targets: Vec<String>,
+    /// Stamped on exports.
+    pub protocol: Option<ReportProtocol>,
+    /// Stamped on exports.
+    pub backend: ReportBackend,
+    /// Warn/bad thresholds, also used by the alert rules, and the optional columns and panels.
+    pub enhanced_ui: EnhancedUiConfig,
+    pub theme: Theme,
+    pub keymap: Keymap,
+    /// `None` turns the alert rules off.
+    pub alerts: Option<AlertConfig>,
+}
+
+impl DashboardOptions {
+    /// Default thresholds, theme and keys, without alerts.
+    pub fn new(targets: Vec<String>, backend: ReportBackend) -> Self {
+        Self {
+            targets,
+            protocol: None,
+            backend,
+            enhanced_ui: EnhancedUiConfig::default(),
+            theme: Theme::default(),
+            keymap: Keymap::default(),
+            alerts: None,
+        }
+    }
+}
+
+/// Show a saved report in the dashboard without probing.
+pub fn run_saved_report_ui(report: &SavedReport) -> anyhow::Result<i32> {
+    let (_, no_snapshots) = mpsc::channel::<TargetSnapshot>();
+    run_terminal_ui(
+        Dashboard::new(vec![DashboardApp::saved(report)]),
+        &no_snapshots,
+    )
+}
+
+/// Play a recording back in the dashboard, starting at `speed` times real time.
+pub fn run_replay_ui(recording: Recording, speed: f64) -> anyhow::Result<i32> {
+    let (_, no_snapshots) = mpsc::channel::<TargetSnapshot>();
+    run_terminal_ui(
+        Dashboard::new(vec![DashboardApp::replay(recording, speed)]),
+        &no_snapshots,
+    )
+}
+
+/// Run the dashboard on the terminal until the quit key, showing whatever arrives on
+/// `snapshots`. The sender side may close at any time; the last snapshots stay on screen.
+pub fn run_snapshot_ui(
+    options: DashboardOptions,
+    snapshots: mpsc::Receiver<HopSnapshot>,
+) -> anyhow::Result<i32> {
+    let view = DashboardView::new(options, snapshots)?;
+    run_terminal_ui(view.dashboard, &view.snapshots)
+}
+
+/// The dashboard as a widget, for tools that run their own terminal and event loop.
+pub struct DashboardView {
+    pub(super) dashboard: Dashboard,
+    pub(super) snapshots: mpsc::Receiver<HopSnapshot>,
+}
+
+impl DashboardView {
+    /// Fails when `options` has no targets.
+    pub fn new(
+        options: DashboardOptions,
+        snapshots: mpsc::Receiver<HopSnapshot>,
+    ) -> anyhow::Result<Self> {
+        if options.targets.is_empty() {
+            bail!("the dashboard needs at least one target");
+        }
+        let apps = options
+            .targets
+            .iter()
+            .map(|target| DashboardApp::external(target, &options))
+            .collect();
+        let mut dashboard = Dashboard::new(apps);
+        dashboard.alerts = options.alerts;
+        dashboard.theme = options.theme;
+        dashboard.keymap = options.keymap;
+        Ok(Self {
+            dashboard,
+            snapshots,
+        })
+    }
+
+    /// Take in every snapshot that has arrived, without waiting. Call it before each draw.
+    pub fn update(&mut self) {
+        while let Some(snapshot) = self.snapshots.next_snapshot() {
+            self.dashboard.ingest(snapshot);
+        }
+        self.dashboard.tick(Instant::now());
+    }
+
+    /// Apply a key press. Returns `true` when it was the quit key.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        self.dashboard
+            .action_for_event(key)
+            .is_some_and(|action| self.dashboard.apply_action(action))
+    }
+
+    pub fn render(&self, frame: &mut ratatui::Frame<'_>, area: Rect) {
+        draw_dashboard(frame, &self.dashboard, area);
+    }
+
+    /// Escape sequences to write to the terminal as they are: OSC 52 clipboard copies from `y`
+    /// and the alert bell.
+    pub fn take_terminal_output(&mut self) -> Option<String> {
+        self.dashboard.take_terminal_output()
+    }
+}
+
+/// Run the dashboard on the real terminal: crossterm input, and clipboard and bell sequences
+/// written to stdout.
+pub(super) fn run_terminal_ui(
+    dashboard: Dashboard,
+    snapshot_rx: &impl SnapshotSource,
+) -> anyhow::Result<i32> {
+    with_terminal(|terminal| {
+        run_ui_loop(
+            terminal,
+            dashboard,
+            snapshot_rx,
+            &mut TerminalEvents,
+            &mut io::stdout(),
+        )
+    })
+}
+
+fn with_terminal(
+    run: impl FnOnce(&mut Terminal<CrosstermBackend<Stdout>>) -> anyhow::Result<i32>,
+) -> anyhow::Result<i32> {
+    enable_raw_mode().context("failed to enable raw mode for dashboard UI")?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen).context("failed to enter alternate screen")?;
+
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).context("failed to initialize terminal backend")?;
+
+    let result = run(&mut terminal);
+
+    let mut restore_error: Option<anyhow::Error> = None;
+
+    if let Err(err) = disable_raw_mode() {
+        restore_error = Some(anyhow::Error::new(err).context("failed to disable raw mode"));
+    }
+
+    if let Err(err) = execute!(terminal.backend_mut(), LeaveAlternateScreen) {
+        let leave_err = anyhow::Error::new(err).context("failed to leave alternate screen");
+        restore_error = Some(match restore_error {
+            Some(existing) => existing.context(leave_err.to_string()),
+            None => leave_err,
+        });
+    }
+
+    terminal
+        .show_cursor()
+        .context("failed to restore terminal cursor")?;
+
+    if let Some(err) = restore_error {
+        return Err(err);
+    }
+
+    result
+}
+
+/// Where the UI loop reads hop snapshots from.
+pub(super) trait SnapshotSource {
+    /// A snapshot that has already arrived, or `None` without waiting.
+    fn next_snapshot(&self) -> Option<TargetSnapshot>;
+}
+
+/// The pollers of [`run_dashboard_ui`](super::run_dashboard_ui).
+impl SnapshotSource for mpsc::Receiver<TargetSnapshot> {
+    fn next_snapshot(&self) -> Option<TargetSnapshot> {
+        self.try_recv().ok()
+    }
+}
+
+/// An embedding tool's probe.
+impl SnapshotSource for mpsc::Receiver<HopSnapshot> {
+    fn next_snapshot(&self) -> Option<TargetSnapshot> {
+        self.try_recv().ok().map(TargetSnapshot::from)
+    }
+}
+
+/// Where the UI loop reads terminal input from.
+pub(super) trait EventSource {
+    /// The next event, or `None` when nothing arrived within `timeout`.
+    fn next_event(&mut self, timeout: Duration) -> anyhow::Result<Option<Event>>;
+}
+
+/// Input from the terminal the dashboard runs in.
+struct TerminalEvents;
+
+impl EventSource for TerminalEvents {
+    fn next_event(&mut self, timeout: Duration) -> anyhow::Result<Option<Event>> {
+        if !event::poll(timeout).context("failed to poll terminal events")? {
+            return Ok(None);
+        }
+        event::read()
+            .context("failed to read terminal event")
+            .map(Some)
+    }
+}
+
+/// Ingest snapshots, draw and apply keys until the dashboard quits. `terminal_output` receives
+/// the OSC 52 sequences of `y` and the alert bell.
+pub(super) fn run_ui_loop<B: Backend>(
+    terminal: &mut Terminal<B>,
+    mut dashboard: Dashboard,
+    snapshot_rx: &impl SnapshotSource,
+    events: &mut impl EventSource,
+    terminal_output: &mut impl Write,
+) -> anyhow::Result<i32> {
+    let tick_rate = Duration::from_millis(250);
+
+    loop {
+        while let Some(snapshot) = snapshot_rx.next_snapshot() {
+            dashboard.ingest(snapshot);
+        }
+        dashboard.tick(Instant::now());
+
+        if let Some(sequence) = dashboard.take_terminal_output() {
+            terminal_output
+                .write_all(sequence.as_bytes())
+                .and_then(|()| terminal_output.flush())
+                .context("failed to write the clipboard or bell sequence to the terminal")?;
+        }
+        terminal
+            .draw(|f| draw_dashboard(f, &dashboard, f.area()))
+            .context("failed to draw the dashboard")?;
+
+        if let Some(Event::Key(key)) = events.next_event(tick_rate)?
+            && let Some(action) = dashboard.action_for_event(key)
+            && dashboard.apply_action(action)
+        {
+            return Ok(0);
+        }
+    }
+}
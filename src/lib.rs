@@ -1,6 +1,20 @@
 pub mod api_error;
+pub mod config_file;
+#[cfg(feature = "tui")]
+pub mod dashboard_ui;
 pub mod error;
 pub mod passthrough;
+pub mod recording;
+pub mod report_diff;
+pub mod report_render;
+pub mod report_schema;
 pub mod service;
 
+// The terminal crates in the dashboard's public API, so embedders build against the same
+// versions.
+#[cfg(feature = "tui")]
+pub use crossterm;
+#[cfg(feature = "tui")]
+pub use ratatui;
+
 pub mod native_icmp;
@@ -1,25 +1,47 @@
 use anyhow::Context;
-use clap::{Args, Parser, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use reqwest::Url;
+use std::collections::HashSet;
 use std::env;
+use std::ffi::OsString;
+use std::fs::OpenOptions;
 use std::net::{IpAddr, SocketAddr};
 use std::path::{Path, PathBuf};
 use std::process;
 use std::time::Duration;
-use windows_mtr::service::rest_api::{AuthStrategy, RestApiConfig};
-use windows_mtr::service::rest_server::run_rest_api_server;
+use windows_mtr::config_file::{
+    ApiSettings, ConfigLoader, LoadedConfig, OutputSetting, ProbeSettings, ProtocolSetting,
+    Settings,
+};
+use windows_mtr::dashboard_ui;
+use windows_mtr::recording::{Recorder, Recording};
+use windows_mtr::report_diff::{
+    diff_reports, parse_report_hops, render_markdown, render_text, report_target,
+};
+use windows_mtr::report_render::{self, SavedReport};
+use windows_mtr::report_schema::REPORT_SCHEMA;
+use windows_mtr::service::config_reload::{
+    ConfigReloadOptions, ConfigSource, DEFAULT_WATCH_INTERVAL,
+};
+use windows_mtr::service::rest_api::{AuthStrategy, NamedApiKey, RestApiConfig};
+use windows_mtr::service::rest_server::run_rest_api_server_with_reload;
+use windows_mtr::service::service_host::{
+    DEFAULT_SERVICE_NAME, ServiceHostOptions, run_service_host,
+};
+use windows_mtr::service::target_policy::{IpCidr, TargetRule};
+use windows_mtr::service::webhooks::parse_webhook_url;
 use windows_mtr::service::{
-    EnhancedUiConfig, JsonOutput, ProbeError, ProbeRequest, UiMode, build_probe_plan,
-    run_embedded_trippy,
+    CLI_JSON_SCHEMA_VERSION, EnhancedUiConfig, JsonOutput, ProbeError, ProbeRequest, UiMode,
+    build_probe_plan, run_embedded_trippy,
 };
 
-mod dashboard_ui;
 mod error;
 use error::MtrError;
 
 const EMBEDDED_TRIPPY_ENV: &str = "WINDOWS_MTR_EMBEDDED_TRIPPY";
 
 /// Windows-native clone of Linux mtr - a CLI that delivers ICMP/TCP/UDP traceroute & ping
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(author = "Benji Shohet (benjisho)", version, about, long_about = None)]
 #[command(after_help = "Examples:
   windows-mtr 8.8.8.8                           # Basic ICMP trace to Google DNS
@@ -28,13 +50,34 @@ const EMBEDDED_TRIPPY_ENV: &str = "WINDOWS_MTR_EMBEDDED_TRIPPY";
   windows-mtr -r -c 10 example.com              # Report mode with 10 pings per hop
   windows-mtr --json -c 20 example.com          # JSON report output
   windows-mtr --api                              # Run REST API runtime
+  windows-mtr --profile wan-check example.com    # Apply [profiles.wan-check] from the config file
+  windows-mtr --api --print-config               # Show effective API settings and exit
+  windows-mtr service install -- --config C:\\mtr\\config.toml  # Register the API as a Windows service
+  windows-mtr diff before.json after.json --format markdown   # Compare two --json reports
+  windows-mtr render capture.json --format wide  # Re-render a saved --json report offline
+  windows-mtr --record night.jsonl 8.8.8.8      # Dashboard session recorded for later replay
+  windows-mtr replay night.jsonl --speed 8      # Play a recording back (Space pauses, [ ] seek)
   windows-mtr --trippy-flags '--tui-refresh-rate 150ms' example.com
-  windows-mtr --ui dashboard 8.8.8.8          # Experimental dashboard fallback (alias: --ui native)")]
+  windows-mtr --ui dashboard 8.8.8.8          # Experimental dashboard fallback (alias: --ui native)
+  windows-mtr --ui dashboard 8.8.8.8 1.1.1.1 github.com  # Summary grid of several targets
+  windows-mtr --alert-log alerts.jsonl --on-alert ./page.sh 8.8.8.8  # Alert on loss, RTT or path changes")]
 struct Cli {
     /// Run in REST API mode instead of probe CLI mode
     #[arg(long = "api")]
     api: bool,
 
+    /// TOML config file (default: WINDOWS_MTR_CONFIG, then the per-user and system locations)
+    #[arg(long = "config", value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Named `[profiles.<NAME>]` section of the config file to apply (or WINDOWS_MTR_PROFILE)
+    #[arg(long = "profile", value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Print the effective configuration as TOML and exit (secrets are never printed)
+    #[arg(long = "print-config")]
+    print_config: bool,
+
     /// Bind address for API mode
     #[arg(long = "api-bind", value_name = "ADDR")]
     api_bind: Option<SocketAddr>,
@@ -44,17 +87,37 @@ struct Cli {
     api_auth: Option<ApiAuthPreset>,
 
     /// Inline API key for `--api-auth api-key` (prefer --api-key-env for safety)
-    #[arg(long = "api-key", value_name = "KEY", conflicts_with = "api_key_env")]
+    #[arg(long = "api-key", value_name = "KEY", conflicts_with_all = ["api_key_env", "api_key_file"])]
     api_key: Option<String>,
 
     /// Environment variable name that stores API key for `--api-auth api-key`
     #[arg(
         long = "api-key-env",
         value_name = "ENV_VAR",
-        conflicts_with = "api_key"
+        conflicts_with_all = ["api_key", "api_key_file"]
     )]
     api_key_env: Option<String>,
 
+    /// File holding the API key for `--api-auth api-key`; re-read on config reload for rotation
+    #[arg(
+        long = "api-key-file",
+        value_name = "PATH",
+        conflicts_with_all = ["api_key", "api_key_env"]
+    )]
+    api_key_file: Option<PathBuf>,
+
+    /// Named API key read from ENV_VAR; its callers are identified as principal NAME (repeatable)
+    #[arg(long = "api-named-key-env", value_name = "NAME=ENV_VAR")]
+    api_named_key_env: Vec<String>,
+
+    /// Principal granted the admin scope for `POST /api/v1/admin/reload` (repeatable)
+    #[arg(long = "api-admin-principal", value_name = "PRINCIPAL")]
+    api_admin_principal: Vec<String>,
+
+    /// Seconds between checks of the config and key files for hot reload; 0 disables the watcher
+    #[arg(long = "api-config-watch-seconds", value_name = "SECONDS")]
+    api_config_watch_seconds: Option<u64>,
+
     /// Maximum number of REST API requests allowed per fixed window
     #[arg(long = "api-max-requests-per-window", value_name = "COUNT")]
     api_max_requests_per_window: Option<usize>,
@@ -71,23 +134,274 @@ struct Cli {
     #[arg(long = "api-completed-job-ttl-seconds", value_name = "SECONDS")]
     api_completed_job_ttl_seconds: Option<u64>,
 
+    /// TTL in seconds for `Idempotency-Key` values on probe creation (default: 3600)
+    #[arg(long = "api-idempotency-ttl-seconds", value_name = "SECONDS")]
+    api_idempotency_ttl_seconds: Option<u64>,
+
+    /// Maximum accepted API probes waiting for a free slot before new requests get 503 (default: 64)
+    #[arg(long = "api-max-queued-probes", value_name = "COUNT")]
+    api_max_queued_probes: Option<usize>,
+
+    /// Start the queued API probe whose principal has the fewest running probes, not strict FIFO; `=false` keeps FIFO
+    #[arg(
+        long = "api-fair-queue",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_name = "BOOL"
+    )]
+    api_fair_queue: Option<bool>,
+
     /// Maximum execution time in seconds for an API-launched probe (default: 60)
     #[arg(long = "api-probe-timeout-seconds", value_name = "SECONDS")]
     api_probe_timeout_seconds: Option<u64>,
 
+    /// Seconds running API probes may finish after a shutdown signal before being cancelled (default: 30)
+    #[arg(long = "api-shutdown-grace-seconds", value_name = "SECONDS")]
+    api_shutdown_grace_seconds: Option<u64>,
+
     /// Trusted ingress source IP(s) allowed to forward mTLS identity headers (repeatable)
     #[arg(long = "api-mtls-trusted-ingress", value_name = "IP")]
     api_mtls_trusted_ingress: Vec<IpAddr>,
 
+    /// Allow API probes only to targets matching this CIDR or hostname glob (repeatable)
+    #[arg(long = "api-target-allow", value_name = "CIDR|GLOB")]
+    api_target_allow: Vec<TargetRule>,
+
+    /// Reject API probes to targets matching this CIDR or hostname glob (repeatable)
+    #[arg(long = "api-target-deny", value_name = "CIDR|GLOB")]
+    api_target_deny: Vec<TargetRule>,
+
+    /// Reject API probes to private, loopback, link-local, and other reserved addresses; `=false` allows them
+    #[arg(
+        long = "api-block-private-targets",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_name = "BOOL"
+    )]
+    api_block_private_targets: Option<bool>,
+
+    /// Per-principal allow rule layered on the server-wide target policy (repeatable)
+    #[arg(long = "api-principal-target-allow", value_name = "PRINCIPAL=RULE")]
+    api_principal_target_allow: Vec<String>,
+
+    /// Per-principal deny rule layered on the server-wide target policy (repeatable)
+    #[arg(long = "api-principal-target-deny", value_name = "PRINCIPAL=RULE")]
+    api_principal_target_deny: Vec<String>,
+
+    /// Source address range API callers may bind probes to via `src` (repeatable)
+    #[arg(long = "api-allow-source-address", value_name = "CIDR")]
+    api_allow_source_address: Vec<IpCidr>,
+
+    /// Network interface API callers may select via `interface` (repeatable)
+    #[arg(long = "api-allow-interface", value_name = "NAME")]
+    api_allow_interface: Vec<String>,
+
+    /// Server-wide webhook URL notified when API jobs finish or breach thresholds (repeatable)
+    #[arg(long = "api-webhook-url", value_name = "URL", value_parser = parse_webhook_url)]
+    api_webhook_url: Vec<Url>,
+
+    /// Environment variable holding the HMAC-SHA256 secret used to sign webhook payloads
+    #[arg(long = "api-webhook-secret-env", value_name = "ENV_VAR")]
+    api_webhook_secret_env: Option<String>,
+
+    /// Allow API callers to set a per-request `callback_url`; `=false` rejects it
+    #[arg(
+        long = "api-allow-callback-urls",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_name = "BOOL"
+    )]
+    api_allow_callback_urls: Option<bool>,
+
+    /// Send a threshold webhook when destination loss exceeds this percentage
+    #[arg(long = "api-webhook-loss-threshold-pct", value_name = "PERCENT")]
+    api_webhook_loss_threshold_pct: Option<f64>,
+
+    /// Send a threshold webhook when destination average latency exceeds this value
+    #[arg(long = "api-webhook-latency-threshold-ms", value_name = "MS")]
+    api_webhook_latency_threshold_ms: Option<f64>,
+
+    /// Maximum delivery attempts per webhook before it is dead-lettered
+    #[arg(long = "api-webhook-max-attempts", value_name = "COUNT")]
+    api_webhook_max_attempts: Option<u32>,
+
+    /// Append undeliverable webhook payloads to this JSON Lines file
+    #[arg(long = "api-webhook-dead-letter", value_name = "PATH")]
+    api_webhook_dead_letter: Option<PathBuf>,
+
     #[command(flatten)]
     trace: TraceCli,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// Host the REST API as a Windows service or a systemd notify daemon
+    Service {
+        #[command(subcommand)]
+        action: ServiceAction,
+    },
+    /// Compare two saved JSON reports (`--json` output) hop by hop
+    Diff(DiffArgs),
+    /// Re-render a saved JSON report (`--json` output) without probing again
+    Render(RenderArgs),
+    /// Play a `--record` session back in the dashboard, or export it as a report
+    Replay(ReplayArgs),
+    /// Show the JSON Schema that `--json` reports follow
+    Schema(SchemaArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+struct DiffArgs {
+    /// Report captured before the change
+    #[arg(value_name = "OLD_JSON")]
+    old: PathBuf,
+
+    /// Report captured after the change
+    #[arg(value_name = "NEW_JSON")]
+    new: PathBuf,
+
+    /// Output format
+    #[arg(long = "format", value_enum, default_value = "text")]
+    format: DiffOutput,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum DiffOutput {
+    Text,
+    Json,
+    Markdown,
+}
+
+#[derive(Args, Debug, Clone)]
+struct RenderArgs {
+    /// Saved report to render
+    #[arg(value_name = "JSON")]
+    report: PathBuf,
+
+    /// Output format; `ui` opens the report read-only in the dashboard
+    #[arg(long = "format", value_enum, default_value = "text")]
+    format: RenderOutput,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum RenderOutput {
+    Text,
+    Wide,
+    Csv,
+    Xml,
+    Markdown,
+    Ui,
+}
+
+#[derive(Args, Debug, Clone)]
+struct ReplayArgs {
+    /// Recording written by `--record`
+    #[arg(value_name = "FILE")]
+    recording: PathBuf,
+
+    /// Initial playback speed, relative to real time (0.25 to 64)
+    #[arg(long = "speed", value_name = "FACTOR", default_value_t = 1.0, value_parser = parse_replay_speed)]
+    speed: f64,
+
+    /// Print the whole session aggregated into one report instead of playing it
+    #[arg(long = "export", value_enum, value_name = "FORMAT")]
+    export: Option<ReplayExport>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum ReplayExport {
+    Json,
+    Csv,
 }
 
-#[derive(Args, Debug)]
+#[derive(Args, Debug, Clone)]
+struct SchemaArgs {
+    /// Print the full JSON Schema document instead of a summary
+    #[arg(long = "print")]
+    print: bool,
+}
+
+fn parse_replay_speed(value: &str) -> Result<f64, String> {
+    let speed = value
+        .parse::<f64>()
+        .map_err(|_| format!("`{value}` is not a number"))?;
+    if (0.25..=64.0).contains(&speed) {
+        Ok(speed)
+    } else {
+        Err("speed must be between 0.25 and 64".to_string())
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum ServiceAction {
+    /// Register the REST API with the Windows Service Control Manager (auto start)
+    Install(ServiceInstallArgs),
+    /// Stop and remove the Windows service
+    Uninstall(ServiceNameArgs),
+    /// Run the REST API under the service manager: the SCM on Windows, systemd notify elsewhere
+    Run(ServiceRunArgs),
+}
+
+#[derive(Args, Debug, Clone)]
+struct ServiceNameArgs {
+    /// Windows service name
+    #[arg(long = "service-name", value_name = "NAME", default_value = DEFAULT_SERVICE_NAME)]
+    service_name: String,
+}
+
+#[derive(Args, Debug, Clone)]
+struct ServiceRunArgs {
+    #[command(flatten)]
+    name: ServiceNameArgs,
+
+    /// Write the process id to this file while the service runs; removed on exit
+    #[arg(long = "pid-file", value_name = "PATH")]
+    pid_file: Option<PathBuf>,
+
+    /// Append server logs and crash output to this file instead of stderr
+    #[arg(long = "log-file", value_name = "PATH")]
+    log_file: Option<PathBuf>,
+}
+
+#[derive(Args, Debug, Clone)]
+struct ServiceInstallArgs {
+    #[command(flatten)]
+    run: ServiceRunArgs,
+
+    /// API flags the service starts with, after `--` (flags before `service` are kept too)
+    #[arg(last = true, value_name = "API_FLAGS")]
+    api_args: Vec<OsString>,
+}
+
+impl ServiceRunArgs {
+    fn host_options(&self) -> ServiceHostOptions {
+        ServiceHostOptions {
+            service_name: self.name.service_name.clone(),
+            pid_file: self.pid_file.clone(),
+            log_file: self.log_file.clone(),
+        }
+    }
+}
+
+#[derive(Args, Debug, Clone)]
 struct TraceCli {
     /// Target host to trace (hostname or IP)
     host: Option<String>,
 
+    /// More targets to watch side by side (`--ui dashboard` only)
+    #[arg(value_name = "MORE_HOSTS")]
+    more_hosts: Vec<String>,
+
+    /// Probe protocol; `icmp` overrides a TCP or UDP `protocol` in the config file
+    #[arg(long = "protocol", value_enum, conflicts_with_all = ["tcp", "udp"])]
+    protocol: Option<ProtocolPreset>,
+
     /// Use TCP SYN for probes (default is ICMP)
     #[arg(short = 'T', conflicts_with = "udp")]
     tcp: bool,
@@ -104,6 +418,14 @@ struct TraceCli {
     #[arg(long, value_name = "PORT", value_parser = clap::value_parser!(u16).range(1..=65535))]
     source_port: Option<u16>,
 
+    /// Output mode; `interactive` overrides a report or JSON `output` in the config file
+    #[arg(
+        long = "output",
+        value_enum,
+        conflicts_with_all = ["report", "report_wide", "json", "json_pretty", "csv"]
+    )]
+    output: Option<OutputPreset>,
+
     /// Report mode (no continuous updates)
     #[arg(short = 'r')]
     report: bool,
@@ -140,17 +462,31 @@ struct TraceCli {
     #[arg(short = 'w', long = "report-wide")]
     report_wide: bool,
 
-    /// Don't perform reverse DNS lookups (faster)
-    #[arg(short = 'n')]
-    no_dns: bool,
+    /// Don't perform reverse DNS lookups (faster); `--no-dns=false` turns lookups back on
+    #[arg(
+        short = 'n',
+        long = "no-dns",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_name = "BOOL"
+    )]
+    no_dns: Option<bool>,
 
     /// Maximum number of hops to trace
     #[arg(short = 'm')]
     max_hops: Option<u8>,
 
-    /// Show ASN data in reports and lookups (Linux parity)
-    #[arg(short = 'b', long = "show-asn")]
-    show_asn: bool,
+    /// Show ASN data in reports and lookups (Linux parity); `--show-asn=false` hides it
+    #[arg(
+        short = 'b',
+        long = "show-asn",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_name = "BOOL"
+    )]
+    show_asn: Option<bool>,
 
     /// DNS/ASN lookup mode (Linux parity shortcut)
     #[arg(short = 'z')]
@@ -184,35 +520,59 @@ struct TraceCli {
     )]
     trippy_flags: Option<String>,
 
+    /// Append every dashboard round to this new JSON Lines file for `mtr replay` (implies `--ui dashboard`)
+    #[arg(long = "record", value_name = "FILE")]
+    record: Option<PathBuf>,
+
+    /// Ring the bell and show a banner when the destination's loss or avg RTT stays above --loss-bad-pct / --latency-bad-ms, or its responder changes (implies `--ui dashboard`)
+    #[arg(long = "alert")]
+    alert: bool,
+
+    /// Rounds in a row the loss or RTT alert limit must be exceeded before alerting [default: 3] (implies `--alert`)
+    #[arg(long = "alert-rounds", value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    alert_rounds: Option<u32>,
+
+    /// Run this shell command for every alert, with the alert as JSON on stdin (implies `--alert`)
+    #[arg(long = "on-alert", value_name = "CMD")]
+    on_alert: Option<String>,
+
+    /// Append every alert to this JSON Lines file (implies `--alert`)
+    #[arg(long = "alert-log", value_name = "FILE")]
+    alert_log: Option<PathBuf>,
+
     /// UI preset for interactive mode (`enhanced` is currently unavailable with bundled Trippy 0.13.0; `dashboard` is an experimental fallback; `native` is a deprecated alias)
     #[arg(long = "ui", value_enum, default_value_t = UiPreset::Default)]
     ui: UiPreset,
 
-    /// Latency warning threshold in milliseconds for enhanced UI coloring
+    /// Latency warning threshold in milliseconds for dashboard row, heatmap and health coloring
     #[arg(long = "latency-warn-ms", value_name = "MS")]
     latency_warn_ms: Option<f32>,
 
-    /// Latency critical threshold in milliseconds for enhanced UI coloring
+    /// Latency critical threshold in milliseconds for dashboard row, heatmap and health coloring
     #[arg(long = "latency-bad-ms", value_name = "MS")]
     latency_bad_ms: Option<f32>,
 
-    /// Packet loss warning threshold percentage for enhanced UI coloring
+    /// Packet loss warning threshold percentage for dashboard row, heatmap and health coloring
     #[arg(long = "loss-warn-pct", value_name = "PCT")]
     loss_warn_pct: Option<f32>,
 
-    /// Packet loss critical threshold percentage for enhanced UI coloring
+    /// Packet loss critical threshold percentage for dashboard row, heatmap and health coloring
     #[arg(long = "loss-bad-pct", value_name = "PCT")]
     loss_bad_pct: Option<f32>,
 
-    /// Toggle row coloring bands in enhanced UI
+    /// Thresholds from the config file; unlike the flags above they are not tuning overrides.
+    #[arg(skip)]
+    config_enhanced_ui: EnhancedUiConfig,
+
+    /// Toggle loss/latency row coloring in the dashboard hop table
     #[arg(long = "enhanced-row-color", value_enum, value_name = "on|off")]
     enhanced_row_color: Option<OnOff>,
 
-    /// Toggle per-hop trend/sparkline column in enhanced UI
+    /// Toggle the per-hop RTT trend sparkline column in the dashboard hop table
     #[arg(long = "enhanced-sparklines", value_enum, value_name = "on|off")]
     enhanced_sparklines: Option<OnOff>,
 
-    /// Toggle percentile/jitter summary in enhanced UI
+    /// Toggle the percentile/jitter summary panel on the dashboard Overview
     #[arg(long = "enhanced-summary", value_enum, value_name = "on|off")]
     enhanced_summary: Option<OnOff>,
 }
@@ -237,6 +597,22 @@ enum OnOff {
     Off,
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum ProtocolPreset {
+    Icmp,
+    Tcp,
+    Udp,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum OutputPreset {
+    Interactive,
+    Report,
+    ReportWide,
+    Json,
+    JsonPretty,
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
 enum ApiAuthPreset {
     ApiKey,
@@ -250,6 +626,28 @@ impl OnOff {
     }
 }
 
+impl ProtocolPreset {
+    fn setting(self) -> ProtocolSetting {
+        match self {
+            Self::Icmp => ProtocolSetting::Icmp,
+            Self::Tcp => ProtocolSetting::Tcp,
+            Self::Udp => ProtocolSetting::Udp,
+        }
+    }
+}
+
+impl OutputPreset {
+    fn setting(self) -> OutputSetting {
+        match self {
+            Self::Interactive => OutputSetting::Interactive,
+            Self::Report => OutputSetting::Report,
+            Self::ReportWide => OutputSetting::ReportWide,
+            Self::Json => OutputSetting::Json,
+            Self::JsonPretty => OutputSetting::JsonPretty,
+        }
+    }
+}
+
 fn json_output_from_cli(args: &TraceCli) -> Option<JsonOutput> {
     if args.json {
         Some(JsonOutput::Compact)
@@ -303,6 +701,26 @@ fn auth_strategy_from_cli(value: ApiAuthPreset) -> AuthStrategy {
 }
 
 fn api_key_from_cli(args: &Cli) -> anyhow::Result<Option<String>> {
+    if args.api_key_env.is_some() && args.api_key_file.is_some() {
+        anyhow::bail!("set only one of api_key_env and api_key_file");
+    }
+
+    if let Some(path) = &args.api_key_file {
+        let raw = std::fs::read_to_string(path).with_context(|| {
+            format!(
+                "--api-key-file was set to `{}`, but it could not be read",
+                path.display()
+            )
+        })?;
+
+        let key = raw.trim().to_string();
+        if key.is_empty() {
+            anyhow::bail!("--api-key-file `{}` is empty", path.display());
+        }
+
+        return Ok(Some(key));
+    }
+
     if let Some(env_name) = &args.api_key_env {
         let raw = env::var(env_name).with_context(|| {
             format!(
@@ -323,6 +741,38 @@ fn api_key_from_cli(args: &Cli) -> anyhow::Result<Option<String>> {
     Ok(args.api_key.clone())
 }
 
+fn named_api_keys_from_cli(args: &Cli) -> anyhow::Result<Vec<NamedApiKey>> {
+    args.api_named_key_env
+        .iter()
+        .map(|raw| {
+            let (name, env_name) = raw.split_once('=').with_context(|| {
+                format!("--api-named-key-env expects NAME=ENV_VAR, got `{raw}`")
+            })?;
+            let name = name.trim();
+            if name.is_empty() {
+                anyhow::bail!("--api-named-key-env requires a non-empty name before `=`");
+            }
+
+            let key = env::var(env_name).with_context(|| {
+                format!(
+                    "--api-named-key-env for `{name}` names `{env_name}`, but that environment variable is not present"
+                )
+            })?;
+            let key = key.trim().to_string();
+            if key.is_empty() {
+                anyhow::bail!(
+                    "--api-named-key-env for `{name}` names `{env_name}`, but that environment variable is empty"
+                );
+            }
+
+            Ok(NamedApiKey {
+                name: name.to_string(),
+                key,
+            })
+        })
+        .collect()
+}
+
 fn apply_rest_api_cli_overrides(args: &Cli, config: &mut RestApiConfig) -> anyhow::Result<()> {
     if let Some(bind) = args.api_bind {
         config.bind_addr = bind;
@@ -337,19 +787,22 @@ fn apply_rest_api_cli_overrides(args: &Cli, config: &mut RestApiConfig) -> anyho
     }
 
     let api_key = api_key_from_cli(args)?;
-    if api_key.is_some() && config.auth_strategy != AuthStrategy::ApiKey {
+    let named_api_keys = named_api_keys_from_cli(args)?;
+    let has_key_input = api_key.is_some() || !named_api_keys.is_empty();
+    if has_key_input && config.auth_strategy != AuthStrategy::ApiKey {
         anyhow::bail!(
-            "API key input (--api-key/--api-key-env) requires '--api-auth api-key'; current strategy is '{:?}'",
+            "API key input (--api-key/--api-key-env/--api-named-key-env) requires '--api-auth api-key'; current strategy is '{:?}'",
             config.auth_strategy
         );
     }
-    if config.auth_strategy == AuthStrategy::ApiKey && api_key.is_none() {
+    if config.auth_strategy == AuthStrategy::ApiKey && !has_key_input {
         anyhow::bail!(
-            "'--api-auth api-key' requires key input via '--api-key-env <ENV_VAR>' (preferred) or '--api-key <KEY>'"
+            "'--api-auth api-key' requires key input via '--api-key-env <ENV_VAR>' (preferred), '--api-named-key-env <NAME=ENV_VAR>', or '--api-key <KEY>'"
         );
     }
 
     config.api_key = api_key;
+    config.named_api_keys = named_api_keys;
 
     if let Some(max_requests) = args.api_max_requests_per_window {
         config.max_requests_per_window = max_requests;
@@ -367,17 +820,540 @@ fn apply_rest_api_cli_overrides(args: &Cli, config: &mut RestApiConfig) -> anyho
         config.completed_job_ttl = Duration::from_secs(completed_job_ttl_seconds);
     }
 
+    if let Some(idempotency_ttl_seconds) = args.api_idempotency_ttl_seconds {
+        config.idempotency_key_ttl = Duration::from_secs(idempotency_ttl_seconds);
+    }
+
+    if let Some(max_queued_probes) = args.api_max_queued_probes {
+        config.max_queued_probes = max_queued_probes;
+    }
+    if let Some(fair_queueing) = args.api_fair_queue {
+        config.fair_queueing = fair_queueing;
+    }
+
     if let Some(probe_timeout_seconds) = args.api_probe_timeout_seconds {
         config.probe_execution_timeout = Duration::from_secs(probe_timeout_seconds);
     }
 
+    if let Some(grace_seconds) = args.api_shutdown_grace_seconds {
+        config.shutdown_grace_period = Duration::from_secs(grace_seconds);
+    }
+
     if !args.api_mtls_trusted_ingress.is_empty() {
         config.trusted_mtls_ingress_ips = args.api_mtls_trusted_ingress.clone();
     }
 
+    if !args.api_allow_source_address.is_empty() {
+        config.allowed_source_addresses = args.api_allow_source_address.clone();
+    }
+
+    if !args.api_allow_interface.is_empty() {
+        config.allowed_interfaces = args.api_allow_interface.clone();
+    }
+
+    if !args.api_admin_principal.is_empty() {
+        config.admin_principals = args.api_admin_principal.clone();
+    }
+
+    apply_webhook_cli_overrides(args, config)?;
+
+    apply_target_policy_cli_overrides(args, config)
+}
+
+fn apply_webhook_cli_overrides(args: &Cli, config: &mut RestApiConfig) -> anyhow::Result<()> {
+    let webhooks = &mut config.webhooks;
+    if !args.api_webhook_url.is_empty() {
+        webhooks.sinks = args.api_webhook_url.clone();
+    }
+    if let Some(allow) = args.api_allow_callback_urls {
+        webhooks.allow_callback_urls = allow;
+    }
+
+    if let Some(env_name) = &args.api_webhook_secret_env {
+        let secret = env::var(env_name).with_context(|| {
+            format!(
+                "--api-webhook-secret-env was set to `{env_name}`, but that environment variable is not present"
+            )
+        })?;
+        webhooks.secret = Some(secret.trim().to_string());
+    }
+
+    if let Some(threshold) = args.api_webhook_loss_threshold_pct {
+        webhooks.loss_threshold_pct = Some(threshold);
+    }
+    if let Some(threshold) = args.api_webhook_latency_threshold_ms {
+        webhooks.latency_threshold_ms = Some(threshold);
+    }
+    if let Some(max_attempts) = args.api_webhook_max_attempts {
+        webhooks.max_attempts = max_attempts;
+    }
+    if let Some(path) = &args.api_webhook_dead_letter {
+        webhooks.dead_letter_path = Some(path.clone());
+    }
+
+    Ok(())
+}
+
+fn apply_target_policy_cli_overrides(args: &Cli, config: &mut RestApiConfig) -> anyhow::Result<()> {
+    // Like the other repeatable flags, rules given on the command line replace the file's list
+    // instead of adding to it; per-principal flags replace that principal's list.
+    let policy = &mut config.target_policy;
+    if !args.api_target_allow.is_empty() {
+        policy.default.allow = args.api_target_allow.clone();
+    }
+    if !args.api_target_deny.is_empty() {
+        policy.default.deny = args.api_target_deny.clone();
+    }
+    if let Some(block) = args.api_block_private_targets {
+        policy.default.block_private_ranges = block;
+    }
+
+    let mut replaced = HashSet::new();
+    for raw in &args.api_principal_target_allow {
+        let (principal, rule) = parse_principal_target_rule(raw, "--api-principal-target-allow")?;
+        let rules = &mut policy
+            .principals
+            .entry(principal.clone())
+            .or_default()
+            .allow;
+        if replaced.insert(principal) {
+            rules.clear();
+        }
+        rules.push(rule);
+    }
+
+    replaced.clear();
+    for raw in &args.api_principal_target_deny {
+        let (principal, rule) = parse_principal_target_rule(raw, "--api-principal-target-deny")?;
+        let rules = &mut policy.principals.entry(principal.clone()).or_default().deny;
+        if replaced.insert(principal) {
+            rules.clear();
+        }
+        rules.push(rule);
+    }
+
+    Ok(())
+}
+
+fn parse_principal_target_rule(raw: &str, flag: &str) -> anyhow::Result<(String, TargetRule)> {
+    let (principal, rule) = raw
+        .rsplit_once('=')
+        .with_context(|| format!("{flag} expects PRINCIPAL=RULE, got `{raw}`"))?;
+    let principal = principal.trim();
+    if principal.is_empty() {
+        anyhow::bail!("{flag} requires a non-empty principal before `=`");
+    }
+
+    let rule = rule
+        .parse::<TargetRule>()
+        .with_context(|| format!("{flag} has an invalid rule for principal `{principal}`"))?;
+    Ok((principal.to_string(), rule))
+}
+
+/// Fill settings the command line left unset from the config file layers.
+fn apply_config_file_settings(args: &mut Cli, settings: &Settings) {
+    if args.api_key.is_none() && args.api_key_env.is_none() && args.api_key_file.is_none() {
+        args.api_key_env = settings.api.api_key_env.clone();
+        args.api_key_file = settings.api.api_key_file.clone();
+    }
+    if args.api_named_key_env.is_empty()
+        && let Some(named) = &settings.api.named_key_envs
+    {
+        args.api_named_key_env = named
+            .iter()
+            .map(|(name, env_name)| format!("{name}={env_name}"))
+            .collect();
+    }
+    if args.api_webhook_secret_env.is_none() {
+        args.api_webhook_secret_env = settings.api.webhooks.secret_env.clone();
+    }
+
+    apply_probe_settings(&mut args.trace, &settings.probe);
+}
+
+fn apply_probe_settings(args: &mut TraceCli, settings: &ProbeSettings) {
+    // `--protocol` and `--output` are folded into the `-T`/`-U` and report/JSON flags, which
+    // the rest of the binary reads; the file only fills in what neither form selected.
+    let protocol = match args.protocol {
+        Some(preset) => Some(preset.setting()),
+        None if args.tcp || args.udp => None,
+        None => settings.protocol,
+    };
+    match protocol {
+        Some(ProtocolSetting::Tcp) => args.tcp = true,
+        Some(ProtocolSetting::Udp) => args.udp = true,
+        Some(ProtocolSetting::Icmp) | None => {}
+    }
+
+    let output_flag_set =
+        args.report || args.report_wide || args.json || args.json_pretty || args.csv.is_some();
+    let output = match args.output {
+        Some(preset) => Some(preset.setting()),
+        None if output_flag_set => None,
+        None => settings.output,
+    };
+    match output {
+        Some(OutputSetting::Report) => args.report = true,
+        Some(OutputSetting::ReportWide) => args.report_wide = true,
+        Some(OutputSetting::Json) => args.json = true,
+        Some(OutputSetting::JsonPretty) => args.json_pretty = true,
+        Some(OutputSetting::Interactive) | None => {}
+    }
+
+    args.port = args.port.or(settings.port);
+    args.count = args.count.or(settings.count);
+    args.interval = args
+        .interval
+        .or(settings.interval_seconds.map(|v| v as f32));
+    args.timeout = args.timeout.or(settings.timeout_seconds.map(|v| v as f32));
+    args.max_hops = args.max_hops.or(settings.max_hops);
+    args.no_dns = args.no_dns.or(settings.no_dns);
+    args.show_asn = args.show_asn.or(settings.show_asn);
+
+    // Thresholds only color the dashboard, so a file value must not make a report run fail
+    // the "tuning flags require --ui dashboard" check.
+    let base = &mut args.config_enhanced_ui;
+    if let Some(value) = settings.latency_warn_ms {
+        base.latency_warn_ms = value as f32;
+    }
+    if let Some(value) = settings.latency_bad_ms {
+        base.latency_bad_ms = value as f32;
+    }
+    if let Some(value) = settings.loss_warn_pct {
+        base.loss_warn_pct = value as f32;
+    }
+    if let Some(value) = settings.loss_bad_pct {
+        base.loss_bad_pct = value as f32;
+    }
+}
+
+fn probe_settings_from_cli(args: &TraceCli) -> ProbeSettings {
+    // Go through the shortest decimal form so `0.1` does not print as `0.10000000149011612`.
+    let widen = |value: f32| value.to_string().parse::<f64>().unwrap_or(f64::from(value));
+    let thresholds = enhanced_ui_config_from_cli(args);
+    let protocol = if args.tcp {
+        ProtocolSetting::Tcp
+    } else if args.udp {
+        ProtocolSetting::Udp
+    } else {
+        ProtocolSetting::Icmp
+    };
+    let output = if args.json {
+        OutputSetting::Json
+    } else if args.json_pretty {
+        OutputSetting::JsonPretty
+    } else if args.report_wide {
+        OutputSetting::ReportWide
+    } else if args.report {
+        OutputSetting::Report
+    } else {
+        OutputSetting::Interactive
+    };
+
+    ProbeSettings {
+        protocol: Some(protocol),
+        port: args.port,
+        count: args.count,
+        interval_seconds: args.interval.map(widen),
+        timeout_seconds: args.timeout.map(widen),
+        max_hops: args.max_hops,
+        no_dns: Some(args.no_dns.unwrap_or(false)),
+        show_asn: Some(args.show_asn.unwrap_or(false)),
+        output: (args.csv.is_none()).then_some(output),
+        latency_warn_ms: Some(widen(thresholds.latency_warn_ms)),
+        latency_bad_ms: Some(widen(thresholds.latency_bad_ms)),
+        loss_warn_pct: Some(widen(thresholds.loss_warn_pct)),
+        loss_bad_pct: Some(widen(thresholds.loss_bad_pct)),
+    }
+}
+
+/// Render `--print-config` output; `api` is the resolved REST API config in `--api` mode.
+fn effective_config_toml(
+    args: &Cli,
+    loaded: &LoadedConfig,
+    api: Option<&RestApiConfig>,
+) -> anyhow::Result<String> {
+    let api = api
+        .map(|config| {
+            let mut settings = ApiSettings::from_config(config);
+            settings.api_key_env = args.api_key_env.clone();
+            settings.api_key_file = args.api_key_file.clone();
+            settings.named_key_envs = (!args.api_named_key_env.is_empty()).then(|| {
+                args.api_named_key_env
+                    .iter()
+                    .filter_map(|raw| raw.split_once('='))
+                    .map(|(name, env_name)| (name.trim().to_string(), env_name.to_string()))
+                    .collect()
+            });
+            settings.webhooks.secret_env = args.api_webhook_secret_env.clone();
+            settings
+        })
+        .unwrap_or_default();
+    let settings = Settings {
+        probe: probe_settings_from_cli(&args.trace),
+        api,
+    };
+
+    let source = loaded
+        .path
+        .as_ref()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|| "none (built-in defaults)".to_string());
+    let mut rendered = format!("# config file: {source}\n");
+    if let Some(profile) = &loaded.profile {
+        rendered.push_str(&format!("# profile: {profile}\n"));
+    }
+    rendered.push('\n');
+    rendered.push_str(
+        &toml::to_string_pretty(&settings).context("failed to render effective configuration")?,
+    );
+    Ok(rendered)
+}
+
+/// Apply the config file, environment, and profile layers underneath the command-line flags.
+fn layered_cli(cli: &Cli) -> anyhow::Result<(Cli, LoadedConfig)> {
+    let loaded = ConfigLoader::from_process_env(cli.config.clone(), cli.profile.clone())
+        .load()
+        .context("failed to load configuration")?;
+    let mut args = cli.clone();
+    apply_config_file_settings(&mut args, &loaded.settings);
+    Ok((args, loaded))
+}
+
+fn rest_api_config(args: &Cli, loaded: &LoadedConfig) -> anyhow::Result<RestApiConfig> {
+    let mut config = RestApiConfig::default();
+    loaded.settings.api.apply_to(&mut config);
+    apply_rest_api_cli_overrides(args, &mut config)?;
+    Ok(config)
+}
+
+fn validate_rest_api_config(config: &RestApiConfig) -> anyhow::Result<()> {
+    config
+        .validate_security_defaults()
+        .map_err(|e| anyhow::anyhow!(
+            "invalid REST API security configuration: {e}. Action: for remote binds, set '--api-bind 0.0.0.0:PORT --api-auth api-key --api-key-env <ENV_VAR>' or '--api-auth mtls'; keep default localhost when unauthenticated"
+        ))
+}
+
+fn run_diff_command(args: &DiffArgs) -> anyhow::Result<()> {
+    let read_hops = |path: &Path, label: &'static str| -> anyhow::Result<_> {
+        let body = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {label} report `{}`", path.display()))?;
+        let report: serde_json::Value = serde_json::from_str(&body)
+            .with_context(|| format!("{label} report `{}` is not valid JSON", path.display()))?;
+        let hops = parse_report_hops(&report, label)
+            .with_context(|| format!("failed to read hops from `{}`", path.display()))?;
+        Ok((report_target(&report), hops))
+    };
+    let (old_target, old_hops) = read_hops(&args.old, "old")?;
+    let (new_target, new_hops) = read_hops(&args.new, "new")?;
+
+    let mut diff = diff_reports(&old_hops, &new_hops);
+    diff.target = new_target.or(old_target);
+    match args.format {
+        DiffOutput::Text => print!("{}", render_text(&diff)),
+        DiffOutput::Markdown => print!("{}", render_markdown(&diff)),
+        DiffOutput::Json => {
+            let mut value = serde_json::to_value(&diff).context("failed to serialize diff")?;
+            if let Some(object) = value.as_object_mut() {
+                object.insert("schema_version".to_string(), CLI_JSON_SCHEMA_VERSION.into());
+            }
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&value).context("failed to serialize diff")?
+            );
+        }
+    }
+    Ok(())
+}
+
+fn run_render_command(args: &RenderArgs) -> anyhow::Result<()> {
+    let path = &args.report;
+    let body = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read report `{}`", path.display()))?;
+    let value: serde_json::Value = serde_json::from_str(&body)
+        .with_context(|| format!("report `{}` is not valid JSON", path.display()))?;
+    let report = SavedReport::from_json(&value)
+        .with_context(|| format!("cannot render `{}`", path.display()))?;
+
+    match args.format {
+        RenderOutput::Text => print!("{}", report_render::render_text(&report)),
+        RenderOutput::Wide => print!("{}", report_render::render_wide(&report)),
+        RenderOutput::Csv => print!("{}", report_render::render_csv(&report)?),
+        RenderOutput::Xml => print!("{}", report_render::render_xml(&report)),
+        RenderOutput::Markdown => print!("{}", report_render::render_markdown(&report)),
+        RenderOutput::Ui => {
+            let code = dashboard_ui::run_saved_report_ui(&report)?;
+            process::exit(code);
+        }
+    }
+    Ok(())
+}
+
+fn run_schema_command(args: &SchemaArgs) -> anyhow::Result<()> {
+    if args.print {
+        print!("{REPORT_SCHEMA}");
+        return Ok(());
+    }
+
+    let schema: serde_json::Value =
+        serde_json::from_str(REPORT_SCHEMA).context("embedded report schema is not valid JSON")?;
+    println!("JSON report schema_version {CLI_JSON_SCHEMA_VERSION}");
+    if let Some(id) = schema.get("$id").and_then(serde_json::Value::as_str) {
+        println!("{id}");
+    }
+    println!("Run `mtr schema --print` to print the JSON Schema document.");
     Ok(())
 }
 
+fn run_replay_command(args: &ReplayArgs) -> anyhow::Result<()> {
+    let path = &args.recording;
+    let recording =
+        Recording::load(path).with_context(|| format!("cannot replay `{}`", path.display()))?;
+
+    match args.export {
+        Some(ReplayExport::Json) => println!(
+            "{}",
+            serde_json::to_string_pretty(&recording.aggregate_report())
+                .context("failed to serialize recording report")?
+        ),
+        Some(ReplayExport::Csv) => {
+            let report = SavedReport::from(recording.aggregate_report());
+            print!("{}", report_render::render_csv(&report)?);
+        }
+        None => {
+            let code = dashboard_ui::run_replay_ui(recording, args.speed)?;
+            process::exit(code);
+        }
+    }
+    Ok(())
+}
+
+fn run_service_command(
+    cli: &Cli,
+    args: &Cli,
+    loaded: &LoadedConfig,
+    action: &ServiceAction,
+) -> anyhow::Result<()> {
+    match action {
+        ServiceAction::Run(run) => {
+            let config = rest_api_config(args, loaded)?;
+            validate_rest_api_config(&config)?;
+            let reload = config_reload_options(cli, args, loaded);
+            run_service_host(config, reload, run.host_options())
+        }
+        ServiceAction::Install(install) => {
+            let api_args = env::args_os()
+                .skip(1)
+                .take_while(|arg| arg != "service")
+                .chain(install.api_args.iter().cloned())
+                .collect::<Vec<_>>();
+            let service_cli = Cli::try_parse_from(
+                std::iter::once(OsString::from("mtr"))
+                    .chain(api_args.iter().cloned())
+                    .chain(["--api", "--print-config"].map(OsString::from)),
+            )
+            .context("invalid API flags for the service")?;
+            if service_cli.command.is_some() || service_cli.trace.host.is_some() {
+                anyhow::bail!("service API flags must not include a host or another subcommand");
+            }
+
+            install_service(install.run.host_options(), &api_args)
+        }
+        ServiceAction::Uninstall(name) => uninstall_service(&name.service_name),
+    }
+}
+
+#[cfg(windows)]
+fn install_service(mut options: ServiceHostOptions, api_args: &[OsString]) -> anyhow::Result<()> {
+    use windows_mtr::service::service_host::windows_command_line;
+
+    if options.log_file.is_none()
+        && let Some(program_data) = env::var_os("ProgramData")
+    {
+        options.log_file = Some(
+            PathBuf::from(program_data)
+                .join("windows-mtr")
+                .join(format!("{}.log", options.service_name)),
+        );
+    }
+
+    let exe = env::current_exe().context("failed to resolve current executable path")?;
+    let mut command = vec![exe.into_os_string()];
+    command.extend(options.run_arguments(api_args));
+    let command = command
+        .iter()
+        .map(|arg| {
+            arg.to_str()
+                .ok_or_else(|| anyhow::anyhow!("service arguments must be valid Unicode"))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    windows_mtr::service::windows_service::install(
+        &options.service_name,
+        &windows_command_line(command),
+    )?;
+    println!(
+        "Installed service `{}`. Start it with `sc start {}`.",
+        options.service_name, options.service_name
+    );
+    if let Some(log_file) = &options.log_file {
+        println!("Logs: {}", log_file.display());
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn install_service(_options: ServiceHostOptions, _api_args: &[OsString]) -> anyhow::Result<()> {
+    anyhow::bail!(
+        "`mtr service install` registers a Windows service; on Linux, install the systemd unit from docs/systemd/windows-mtr.service"
+    )
+}
+
+#[cfg(windows)]
+fn uninstall_service(name: &str) -> anyhow::Result<()> {
+    windows_mtr::service::windows_service::uninstall(name)?;
+    println!("Removed service `{name}`.");
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn uninstall_service(_name: &str) -> anyhow::Result<()> {
+    anyhow::bail!(
+        "`mtr service uninstall` removes a Windows service; on Linux, use `systemctl disable --now windows-mtr`"
+    )
+}
+
+/// Rebuild the API config from the same layers on reload, watching the config and key files.
+fn config_reload_options(cli: &Cli, args: &Cli, loaded: &LoadedConfig) -> ConfigReloadOptions {
+    let base = cli.clone();
+    let source = ConfigSource::new(move || {
+        let (args, loaded) = layered_cli(&base)?;
+        rest_api_config(&args, &loaded)
+    });
+
+    let watch_interval = args
+        .api_config_watch_seconds
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_WATCH_INTERVAL);
+    let watch_paths = if watch_interval.is_zero() {
+        Vec::new()
+    } else {
+        loaded
+            .path
+            .iter()
+            .chain(args.api_key_file.iter())
+            .cloned()
+            .collect()
+    };
+
+    ConfigReloadOptions {
+        source,
+        watch_paths,
+        watch_interval,
+    }
+}
+
 fn ui_mode_from_cli(ui: UiPreset) -> UiMode {
     match ui {
         UiPreset::Default => UiMode::Default,
@@ -386,15 +1362,37 @@ fn ui_mode_from_cli(ui: UiPreset) -> UiMode {
     }
 }
 
+/// `--record` and the alert flags only work in the dashboard, so they imply `--ui dashboard`
+/// unless a report, JSON or CSV was asked for.
+fn implied_ui_mode(args: &TraceCli) -> UiMode {
+    let needs_dashboard = args.record.is_some() || alert_config(args).is_some();
+    let report_output = args.report
+        || args.report_wide
+        || json_output_from_cli(args).is_some()
+        || args.csv.is_some();
+    if needs_dashboard && !report_output {
+        UiMode::Dashboard
+    } else {
+        ui_mode_from_cli(args.ui)
+    }
+}
+
 fn enhanced_ui_config_from_cli(args: &TraceCli) -> EnhancedUiConfig {
+    let defaults = args.config_enhanced_ui;
     EnhancedUiConfig {
-        latency_warn_ms: args.latency_warn_ms.unwrap_or(100.0),
-        latency_bad_ms: args.latency_bad_ms.unwrap_or(250.0),
-        loss_warn_pct: args.loss_warn_pct.unwrap_or(2.0),
-        loss_bad_pct: args.loss_bad_pct.unwrap_or(5.0),
-        row_coloring: args.enhanced_row_color.unwrap_or(OnOff::On).as_bool(),
-        sparklines: args.enhanced_sparklines.unwrap_or(OnOff::On).as_bool(),
-        summary: args.enhanced_summary.unwrap_or(OnOff::On).as_bool(),
+        latency_warn_ms: args.latency_warn_ms.unwrap_or(defaults.latency_warn_ms),
+        latency_bad_ms: args.latency_bad_ms.unwrap_or(defaults.latency_bad_ms),
+        loss_warn_pct: args.loss_warn_pct.unwrap_or(defaults.loss_warn_pct),
+        loss_bad_pct: args.loss_bad_pct.unwrap_or(defaults.loss_bad_pct),
+        row_coloring: args
+            .enhanced_row_color
+            .map_or(defaults.row_coloring, OnOff::as_bool),
+        sparklines: args
+            .enhanced_sparklines
+            .map_or(defaults.sparklines, OnOff::as_bool),
+        summary: args
+            .enhanced_summary
+            .map_or(defaults.summary, OnOff::as_bool),
     }
 }
 
@@ -425,9 +1423,9 @@ fn build_probe_request(args: &TraceCli) -> anyhow::Result<ProbeRequest> {
         interval_seconds: args.interval,
         timeout_seconds: args.timeout,
         report_wide: args.report_wide,
-        no_dns: args.no_dns,
+        no_dns: args.no_dns.unwrap_or(false),
         max_hops: args.max_hops,
-        show_asn: args.show_asn,
+        show_asn: args.show_asn.unwrap_or(false),
         dns_lookup_as_info: args.dns_lookup_as_info,
         packet_size: args.packet_size,
         src: args.src,
@@ -435,7 +1433,7 @@ fn build_probe_request(args: &TraceCli) -> anyhow::Result<ProbeRequest> {
         ecmp: args.ecmp.clone(),
         dns_cache_ttl_seconds: args.dns_cache_ttl,
         trippy_flags: args.trippy_flags.clone(),
-        ui_mode: ui_mode_from_cli(args.ui),
+        ui_mode: implied_ui_mode(args),
         enhanced_ui: enhanced_ui_config_from_cli(args),
         has_enhanced_overrides,
     })
@@ -459,6 +1457,46 @@ fn native_windows_icmp_config(_request: &ProbeRequest) -> Option<windows_mtr::na
     None
 }
 
+/// Alert settings when any alert flag was given.
+fn alert_config(trace: &TraceCli) -> Option<dashboard_ui::AlertConfig> {
+    (trace.alert
+        || trace.alert_rounds.is_some()
+        || trace.on_alert.is_some()
+        || trace.alert_log.is_some())
+    .then(|| dashboard_ui::AlertConfig {
+        rounds: trace
+            .alert_rounds
+            .unwrap_or(dashboard_ui::DEFAULT_ALERT_ROUNDS),
+        on_alert: trace.on_alert.clone(),
+        log_path: trace.alert_log.clone(),
+    })
+}
+
+fn dashboard_target(
+    request: &ProbeRequest,
+    validated_host: &str,
+    recorder: Option<Recorder>,
+) -> anyhow::Result<dashboard_ui::DashboardTarget> {
+    // The dashboard can switch to ICMP at runtime, so look for the native probe either way.
+    let native_icmp_config = native_windows_icmp_config(&ProbeRequest {
+        tcp: false,
+        udp: false,
+        ..request.clone()
+    });
+    if request.tcp || request.udp || native_icmp_config.is_none() {
+        windows_mtr::service::build_json_snapshot_args(request, validated_host)
+            .map_err(to_cli_error)
+            .map_err(|error| anyhow::anyhow!(error.to_string()))
+            .context("invalid --ui dashboard configuration")?;
+    }
+    Ok(dashboard_ui::DashboardTarget {
+        target: validated_host.to_string(),
+        request: request.clone(),
+        native_icmp_config,
+        recorder,
+    })
+}
+
 fn should_run_native_dashboard(
     ui_mode: UiMode,
     native_icmp_available: bool,
@@ -503,20 +1541,38 @@ fn main() -> anyhow::Result<()> {
         return trippy_tui::trippy();
     }
 
-    let args = Cli::parse();
+    let cli = Cli::parse();
+    match &cli.command {
+        Some(Command::Diff(diff)) => return run_diff_command(diff),
+        Some(Command::Render(render)) => return run_render_command(render),
+        Some(Command::Replay(replay)) => return run_replay_command(replay),
+        Some(Command::Schema(schema)) => return run_schema_command(schema),
+        _ => {}
+    }
+    let (args, loaded) = layered_cli(&cli)?;
+
+    if let Some(Command::Service { action }) = &args.command {
+        return run_service_command(&cli, &args, &loaded, action);
+    }
 
     if args.api {
-        let mut config = RestApiConfig::default();
-        apply_rest_api_cli_overrides(&args, &mut config)?;
+        let config = rest_api_config(&args, &loaded)?;
+
+        if args.print_config {
+            print!("{}", effective_config_toml(&args, &loaded, Some(&config))?);
+            return Ok(());
+        }
 
-        config
-            .validate_security_defaults()
-            .map_err(|e| anyhow::anyhow!(
-                "invalid REST API security configuration: {e}. Action: for remote binds, set '--api-bind 0.0.0.0:PORT --api-auth api-key --api-key-env <ENV_VAR>' or '--api-auth mtls'; keep default localhost when unauthenticated"
-            ))?;
+        validate_rest_api_config(&config)?;
 
         let runtime = tokio::runtime::Runtime::new().context("failed to start tokio runtime")?;
-        return runtime.block_on(run_rest_api_server(config));
+        let reload = config_reload_options(&cli, &args, &loaded);
+        return runtime.block_on(run_rest_api_server_with_reload(config, Some(reload)));
+    }
+
+    if args.print_config {
+        print!("{}", effective_config_toml(&args, &loaded, None)?);
+        return Ok(());
     }
 
     if should_print_banner(&args) {
@@ -534,19 +1590,74 @@ fn main() -> anyhow::Result<()> {
         && !request.report_wide
         && plan.json_output.is_none()
         && plan.csv_output_path.is_none();
-    if should_run_native_dashboard(plan.ui_mode, native_icmp_config.is_some(), interactive) {
-        let dashboard_args = if native_icmp_config.is_some() {
-            Vec::new()
-        } else {
-            windows_mtr::service::build_json_snapshot_args(&request, &plan.validated_host)
+    let alerts = alert_config(&args.trace);
+    match (&args.trace.record, &alerts) {
+        (Some(_), _) if !interactive => {
+            anyhow::bail!(
+                "--record needs the interactive dashboard and cannot be combined with -r, -w, --json or --csv"
+            )
+        }
+        (None, Some(_)) if !interactive => {
+            anyhow::bail!(
+                "--alert, --alert-rounds, --on-alert and --alert-log need the interactive dashboard and cannot be combined with -r, -w, --json or --csv"
+            )
+        }
+        _ => {}
+    }
+    let dashboard =
+        should_run_native_dashboard(plan.ui_mode, native_icmp_config.is_some(), interactive);
+    if !args.trace.more_hosts.is_empty() {
+        if !dashboard {
+            anyhow::bail!(
+                "several targets are only supported by the dashboard (--ui dashboard) and cannot be combined with -r, -w, --json or --csv"
+            );
+        }
+        if args.trace.record.is_some() {
+            anyhow::bail!("--record takes a single target");
+        }
+    }
+    if dashboard {
+        let recorder = args
+            .trace
+            .record
+            .as_deref()
+            .map(|path| {
+                Recorder::create(path, &plan.validated_host)
+                    .with_context(|| format!("failed to start recording `{}`", path.display()))
+            })
+            .transpose()?;
+        let mut targets = vec![dashboard_target(&request, &plan.validated_host, recorder)?];
+        for host in &args.trace.more_hosts {
+            let mut trace = args.trace.clone();
+            trace.host = Some(host.clone());
+            let request = build_probe_request(&trace)?;
+            let plan = build_probe_plan(&request)
                 .map_err(to_cli_error)
                 .map_err(|error| anyhow::anyhow!(error.to_string()))
-                .context("invalid --ui dashboard configuration")?
-        };
+                .with_context(|| format!("invalid target `{host}`"))?;
+            targets.push(dashboard_target(&request, &plan.validated_host, None)?);
+        }
+        if let Some(path) = alerts
+            .as_ref()
+            .and_then(|alerts| alerts.log_path.as_deref())
+        {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("failed to open alert log `{}`", path.display()))?;
+        }
+        // SAFETY: this path is used only to re-exec ourselves as the dashboard's poller,
+        // not for trust, auth, or authorization decisions.
+        let current_exe =
+            // nosemgrep: rust.lang.security.current-exe.current-exe
+            env::current_exe().context("failed to locate current executable for dashboard polling")?;
         let code = dashboard_ui::run_dashboard_ui(
-            &plan.validated_host,
-            &dashboard_args,
-            native_icmp_config,
+            targets,
+            plan.protocol,
+            request.enhanced_ui,
+            alerts,
+            &current_exe,
         )?;
         process::exit(code);
     }
@@ -571,14 +1682,8 @@ fn main() -> anyhow::Result<()> {
         // nosemgrep: rust.lang.security.current-exe.current-exe
         env::current_exe().context("failed to locate current executable")?;
 
-    let result = run_embedded_trippy(
-        &current_exe,
-        &plan.trippy_args,
-        plan.json_output,
-        EMBEDDED_TRIPPY_ENV,
-        plan.csv_output_path.as_deref(),
-    )
-    .context("failed to run embedded trippy")?;
+    let result = run_embedded_trippy(&current_exe, &plan, EMBEDDED_TRIPPY_ENV)
+        .context("failed to run embedded trippy")?;
 
     if should_print_interactive_troubleshooting_hint(&request, result.exit_code) {
         let diagnostic = windows_exit_diagnostic(result.exit_code)
@@ -792,6 +1897,72 @@ mod tests {
         assert!(matches!(alias.trace.ui, UiPreset::Dashboard));
     }
 
+    #[test]
+    fn any_alert_flag_turns_alerts_on() {
+        let cli = Cli::try_parse_from(["mtr", "8.8.8.8"]).expect("default should parse");
+        assert_eq!(alert_config(&cli.trace), None);
+
+        let cli = Cli::try_parse_from(["mtr", "--alert", "8.8.8.8"]).expect("--alert parses");
+        assert_eq!(
+            alert_config(&cli.trace),
+            Some(dashboard_ui::AlertConfig {
+                rounds: dashboard_ui::DEFAULT_ALERT_ROUNDS,
+                on_alert: None,
+                log_path: None,
+            })
+        );
+
+        let cli = Cli::try_parse_from([
+            "mtr",
+            "--alert-rounds",
+            "5",
+            "--on-alert",
+            "notify-send mtr",
+            "--alert-log",
+            "alerts.jsonl",
+            "8.8.8.8",
+        ])
+        .expect("alert flags should parse");
+        assert_eq!(
+            alert_config(&cli.trace),
+            Some(dashboard_ui::AlertConfig {
+                rounds: 5,
+                on_alert: Some("notify-send mtr".to_string()),
+                log_path: Some(PathBuf::from("alerts.jsonl")),
+            })
+        );
+    }
+
+    #[test]
+    fn dashboard_accepts_several_targets_each_with_its_own_poll_args() {
+        let cli = Cli::try_parse_from([
+            "mtr",
+            "--ui",
+            "dashboard",
+            "-T",
+            "-P",
+            "443",
+            "8.8.8.8",
+            "1.1.1.1",
+            "example.com",
+        ])
+        .expect("several targets should parse");
+        assert_eq!(cli.trace.host.as_deref(), Some("8.8.8.8"));
+        assert_eq!(cli.trace.more_hosts, ["1.1.1.1", "example.com"]);
+
+        let mut trace = cli.trace.clone();
+        trace.host = Some("1.1.1.1".to_string());
+        let request = build_probe_request(&trace).expect("request should build");
+        let target = dashboard_target(&request, "1.1.1.1", None).expect("target should build");
+        assert_eq!(target.target, "1.1.1.1");
+        assert!(target.request.tcp, "TCP polls embedded trippy");
+        let snapshot_args =
+            windows_mtr::service::build_json_snapshot_args(&target.request, &target.target)
+                .expect("snapshot arguments should build");
+        assert!(snapshot_args.iter().any(|arg| arg == "1.1.1.1"));
+        assert!(!snapshot_args.iter().any(|arg| arg == "8.8.8.8"));
+    }
+
     #[test]
     fn native_icmp_uses_dashboard_for_default_or_explicit_dashboard_ui() {
         assert!(should_run_native_dashboard(UiMode::Default, true, true));
@@ -888,6 +2059,8 @@ mod tests {
             "512",
             "--api-completed-job-ttl-seconds",
             "1200",
+            "--api-idempotency-ttl-seconds",
+            "7200",
         ])
         .expect("flags should parse for retention override validation");
 
@@ -896,6 +2069,288 @@ mod tests {
 
         assert_eq!(config.max_completed_jobs, 512);
         assert_eq!(config.completed_job_ttl, Duration::from_secs(1200));
+        assert_eq!(config.idempotency_key_ttl, Duration::from_secs(7200));
+    }
+
+    #[test]
+    fn api_mode_applies_queue_overrides() {
+        let cli = Cli::try_parse_from([
+            "mtr",
+            "--api",
+            "--api-max-queued-probes",
+            "16",
+            "--api-fair-queue",
+        ])
+        .expect("queue flags should parse");
+
+        let mut config = RestApiConfig::default();
+        apply_rest_api_cli_overrides(&cli, &mut config).expect("overrides should apply");
+
+        assert_eq!(config.max_queued_probes, 16);
+        assert!(config.fair_queueing);
+    }
+
+    #[test]
+    fn config_file_settings_fill_only_flags_left_unset() {
+        let mut cli = Cli::try_parse_from(["mtr", "-U", "-c", "3", "--csv", "out.csv", "host"])
+            .expect("probe flags should parse");
+        let settings = Settings {
+            probe: ProbeSettings {
+                protocol: Some(ProtocolSetting::Tcp),
+                port: Some(443),
+                count: Some(50),
+                interval_seconds: Some(0.5),
+                output: Some(OutputSetting::Json),
+                latency_warn_ms: Some(80.0),
+                ..ProbeSettings::default()
+            },
+            api: ApiSettings {
+                api_key_env: Some("MTR_KEY".to_string()),
+                ..ApiSettings::default()
+            },
+        };
+
+        apply_config_file_settings(&mut cli, &settings);
+
+        assert!(cli.trace.udp && !cli.trace.tcp);
+        assert_eq!(cli.trace.port, Some(443));
+        assert_eq!(cli.trace.count, Some(3));
+        assert_eq!(cli.trace.interval, Some(0.5));
+        assert!(!cli.trace.json);
+        assert_eq!(cli.trace.latency_warn_ms, None);
+        assert_eq!(
+            enhanced_ui_config_from_cli(&cli.trace).latency_warn_ms,
+            80.0
+        );
+        assert_eq!(cli.api_key_env.as_deref(), Some("MTR_KEY"));
+
+        let mut inline_key = Cli::try_parse_from(["mtr", "--api", "--api-key", "inline"])
+            .expect("api flags should parse");
+        apply_config_file_settings(&mut inline_key, &settings);
+        assert_eq!(inline_key.api_key_env, None);
+    }
+
+    #[test]
+    fn example_config_thresholds_do_not_require_the_dashboard() {
+        let example = concat!(env!("CARGO_MANIFEST_DIR"), "/docs/config.example.toml");
+        for extra in [&[][..], &["--profile", "wan-check"][..]] {
+            let mut argv = vec!["mtr", "--config", example];
+            argv.extend_from_slice(extra);
+            argv.push("127.0.0.1");
+            let cli = Cli::try_parse_from(argv).expect("flags should parse");
+            let (args, _) = layered_cli(&cli).expect("example config should load");
+            let request = build_probe_request(&args.trace).expect("host should build a request");
+
+            assert!(!request.has_enhanced_overrides);
+            assert_eq!(request.enhanced_ui.latency_warn_ms, 100.0);
+            assert!(build_probe_plan(&request).is_ok(), "{extra:?}");
+        }
+
+        let cli = Cli::try_parse_from(["mtr", "--config", example, "--loss-bad-pct", "9", "host"])
+            .expect("flags should parse");
+        let (args, _) = layered_cli(&cli).expect("example config should load");
+        let request = build_probe_request(&args.trace).expect("host should build a request");
+        assert!(request.has_enhanced_overrides);
+        assert_eq!(request.enhanced_ui.loss_bad_pct, 9.0);
+        assert_eq!(request.enhanced_ui.loss_warn_pct, 2.0);
+    }
+
+    #[test]
+    fn protocol_and_output_selectors_override_the_config_file() {
+        let settings = Settings {
+            probe: ProbeSettings {
+                protocol: Some(ProtocolSetting::Tcp),
+                output: Some(OutputSetting::Json),
+                ..ProbeSettings::default()
+            },
+            ..Settings::default()
+        };
+
+        let mut cli = Cli::try_parse_from([
+            "mtr",
+            "--protocol",
+            "icmp",
+            "--output",
+            "interactive",
+            "host",
+        ])
+        .expect("selectors should parse");
+        apply_config_file_settings(&mut cli, &settings);
+        assert!(!cli.trace.tcp && !cli.trace.udp);
+        assert!(!cli.trace.report && !cli.trace.json && !cli.trace.json_pretty);
+
+        let mut cli =
+            Cli::try_parse_from(["mtr", "--protocol=udp", "--output=report-wide", "host"])
+                .expect("selectors should parse");
+        apply_config_file_settings(&mut cli, &settings);
+        assert!(cli.trace.udp && !cli.trace.tcp);
+        assert!(cli.trace.report_wide && !cli.trace.json);
+        assert_eq!(
+            probe_settings_from_cli(&cli.trace).output,
+            Some(OutputSetting::ReportWide)
+        );
+
+        assert!(Cli::try_parse_from(["mtr", "-T", "--protocol", "udp", "host"]).is_err());
+        assert!(Cli::try_parse_from(["mtr", "-j", "--output", "report", "host"]).is_err());
+    }
+
+    #[test]
+    fn dns_and_asn_flags_override_the_config_file_both_ways() {
+        let settings = Settings {
+            probe: ProbeSettings {
+                no_dns: Some(true),
+                show_asn: Some(true),
+                ..ProbeSettings::default()
+            },
+            ..Settings::default()
+        };
+
+        let mut cli = Cli::try_parse_from(["mtr", "--no-dns=false", "host"])
+            .expect("probe flags should parse");
+        apply_config_file_settings(&mut cli, &settings);
+        assert_eq!(cli.trace.no_dns, Some(false));
+        assert_eq!(cli.trace.show_asn, Some(true));
+
+        let mut cli =
+            Cli::try_parse_from(["mtr", "-nb=false", "host"]).expect("probe flags should parse");
+        apply_config_file_settings(&mut cli, &Settings::default());
+        assert_eq!(cli.trace.no_dns, Some(true));
+        assert_eq!(cli.trace.show_asn, Some(false));
+    }
+
+    #[test]
+    fn service_subcommand_parses_alongside_host_argument() {
+        let cli = Cli::try_parse_from([
+            "mtr",
+            "--config",
+            "api.toml",
+            "service",
+            "run",
+            "--pid-file",
+            "/run/mtr.pid",
+        ])
+        .expect("service run should parse");
+        let Some(Command::Service {
+            action: ServiceAction::Run(run),
+        }) = &cli.command
+        else {
+            panic!("expected `service run`, got {:?}", cli.command);
+        };
+        assert_eq!(cli.config.as_deref(), Some(Path::new("api.toml")));
+        assert_eq!(
+            run.host_options(),
+            ServiceHostOptions {
+                service_name: DEFAULT_SERVICE_NAME.to_string(),
+                pid_file: Some(PathBuf::from("/run/mtr.pid")),
+                log_file: None,
+            }
+        );
+
+        let install = Cli::try_parse_from([
+            "mtr",
+            "service",
+            "install",
+            "--service-name",
+            "mtr-lab",
+            "--",
+            "--api-bind",
+            "127.0.0.1:4000",
+        ])
+        .expect("service install should parse");
+        let Some(Command::Service {
+            action: ServiceAction::Install(install),
+        }) = &install.command
+        else {
+            panic!("expected `service install`");
+        };
+        assert_eq!(install.run.name.service_name, "mtr-lab");
+        assert_eq!(install.api_args, ["--api-bind", "127.0.0.1:4000"]);
+
+        let trace = Cli::try_parse_from(["mtr", "-r", "8.8.8.8"]).expect("host should parse");
+        assert!(trace.command.is_none());
+        assert_eq!(trace.trace.host.as_deref(), Some("8.8.8.8"));
+    }
+
+    #[test]
+    fn reload_source_rereads_key_file_and_watches_config_files() {
+        let dir = tempfile::tempdir().expect("tempdir should exist");
+        let config_path = dir.path().join("config.toml");
+        let key_path = dir.path().join("api.key");
+        std::fs::write(&config_path, "[api]\nmax_requests_per_window = 12\n")
+            .expect("config file should be written");
+        std::fs::write(&key_path, "first-key\n").expect("key file should be written");
+
+        let cli = Cli::try_parse_from([
+            "mtr".as_ref(),
+            "--config".as_ref(),
+            config_path.as_os_str(),
+            "--api".as_ref(),
+            "--api-auth".as_ref(),
+            "api-key".as_ref(),
+            "--api-key-file".as_ref(),
+            key_path.as_os_str(),
+            "--api-admin-principal".as_ref(),
+            "api-key".as_ref(),
+        ])
+        .expect("api flags should parse");
+        let (args, loaded) = layered_cli(&cli).expect("config should load");
+        let config = rest_api_config(&args, &loaded).expect("config should build");
+        assert_eq!(config.api_key.as_deref(), Some("first-key"));
+        assert_eq!(config.admin_principals, vec!["api-key".to_string()]);
+        assert_eq!(config.max_requests_per_window, 12);
+
+        let reload = config_reload_options(&cli, &args, &loaded);
+        assert_eq!(
+            reload.watch_paths,
+            vec![config_path.clone(), key_path.clone()]
+        );
+
+        std::fs::write(&key_path, "second-key").expect("key file should be rewritten");
+        std::fs::write(&config_path, "[api]\nmax_requests_per_window = 30\n")
+            .expect("config file should be rewritten");
+        let reloaded = reload.source.load().expect("reload should succeed");
+        assert_eq!(reloaded.api_key.as_deref(), Some("second-key"));
+        assert_eq!(reloaded.max_requests_per_window, 30);
+
+        std::fs::write(&key_path, " \n").expect("key file should be rewritten");
+        let error = reload
+            .source
+            .load()
+            .expect_err("empty key file should be rejected");
+        assert!(format!("{error:#}").contains("is empty"));
+
+        let mut no_watch = cli.clone();
+        no_watch.api_config_watch_seconds = Some(0);
+        assert!(
+            config_reload_options(&no_watch, &no_watch, &loaded)
+                .watch_paths
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn print_config_describes_effective_settings_without_secrets() {
+        let cli = Cli::try_parse_from(["mtr", "--api", "-i", "0.1"]).expect("flags should parse");
+        let mut config = RestApiConfig {
+            api_key: Some("super-secret".to_string()),
+            ..RestApiConfig::default()
+        };
+        config.webhooks.secret = Some("webhook-secret".to_string());
+
+        let rendered = effective_config_toml(&cli, &LoadedConfig::default(), Some(&config))
+            .expect("config should render");
+
+        assert!(rendered.starts_with("# config file: none"));
+        assert!(rendered.contains("interval_seconds = 0.1\n"));
+        assert!(rendered.contains("max_queued_probes = 64"));
+        assert!(!rendered.contains("secret"));
+        let body = rendered
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let reparsed: Settings = toml::from_str(&body).expect("output should be valid config");
+        assert_eq!(reparsed.api.max_queued_probes, Some(64));
     }
 
     #[test]
@@ -909,6 +2364,17 @@ mod tests {
         assert_eq!(config.probe_execution_timeout, Duration::from_secs(120));
     }
 
+    #[test]
+    fn api_mode_applies_shutdown_grace_override() {
+        let cli = Cli::try_parse_from(["mtr", "--api", "--api-shutdown-grace-seconds", "0"])
+            .expect("flags should parse for shutdown grace override validation");
+
+        let mut config = RestApiConfig::default();
+        apply_rest_api_cli_overrides(&cli, &mut config).expect("overrides should apply");
+
+        assert_eq!(config.shutdown_grace_period, Duration::ZERO);
+    }
+
     #[test]
     fn api_mode_applies_mtls_trusted_ingress_overrides() {
         let cli = Cli::try_parse_from([
@@ -930,6 +2396,186 @@ mod tests {
         );
     }
 
+    #[test]
+    fn api_mode_applies_source_and_interface_allow_lists() {
+        let cli = Cli::try_parse_from([
+            "mtr",
+            "--api",
+            "--api-allow-source-address",
+            "192.0.2.0/24",
+            "--api-allow-interface",
+            "Ethernet 2",
+        ])
+        .expect("allow-list flags should parse");
+
+        let mut config = RestApiConfig::default();
+        apply_rest_api_cli_overrides(&cli, &mut config).expect("overrides should apply");
+
+        assert_eq!(
+            config.allowed_source_addresses,
+            vec!["192.0.2.0/24".parse::<IpCidr>().unwrap()]
+        );
+        assert_eq!(config.allowed_interfaces, vec!["Ethernet 2".to_string()]);
+    }
+
+    #[test]
+    fn api_mode_applies_webhook_overrides() {
+        let cli = Cli::try_parse_from([
+            "mtr",
+            "--api",
+            "--api-webhook-url",
+            "https://hooks.example/mtr",
+            "--api-allow-callback-urls",
+            "--api-webhook-loss-threshold-pct",
+            "5",
+            "--api-webhook-max-attempts",
+            "3",
+        ])
+        .expect("webhook flags should parse");
+
+        let mut config = RestApiConfig::default();
+        apply_rest_api_cli_overrides(&cli, &mut config).expect("overrides should apply");
+
+        assert_eq!(
+            config.webhooks.sinks,
+            vec![Url::parse("https://hooks.example/mtr").unwrap()]
+        );
+        assert!(config.webhooks.allow_callback_urls);
+        assert_eq!(config.webhooks.loss_threshold_pct, Some(5.0));
+        assert_eq!(config.webhooks.max_attempts, 3);
+        assert!(
+            config.validate_security_defaults().is_err(),
+            "webhooks without a signing secret must be rejected"
+        );
+
+        assert!(
+            Cli::try_parse_from(["mtr", "--api", "--api-webhook-url", "file:///etc/passwd"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn api_mode_applies_target_policy_overrides() {
+        let cli = Cli::try_parse_from([
+            "mtr",
+            "--api",
+            "--api-block-private-targets",
+            "--api-target-deny",
+            "*.corp.example",
+            "--api-target-allow",
+            "10.20.0.0/16",
+            "--api-principal-target-deny",
+            "CN=ops=192.0.2.0/24",
+        ])
+        .expect("target policy flags should parse");
+
+        let mut config = RestApiConfig::default();
+        apply_rest_api_cli_overrides(&cli, &mut config).expect("overrides should apply");
+
+        let policy = &config.target_policy;
+        assert!(policy.default.block_private_ranges);
+        assert_eq!(policy.default.allow, vec!["10.20.0.0/16".parse().unwrap()]);
+        assert_eq!(policy.default.deny, vec!["*.corp.example".parse().unwrap()]);
+        assert_eq!(
+            policy.principals["CN=ops"].deny,
+            vec!["192.0.2.0/24".parse::<TargetRule>().unwrap()]
+        );
+    }
+
+    #[test]
+    fn api_toggle_flags_override_the_config_file_both_ways() {
+        let mut from_file = RestApiConfig {
+            fair_queueing: true,
+            ..RestApiConfig::default()
+        };
+        from_file.webhooks.allow_callback_urls = true;
+        from_file.target_policy.default.block_private_ranges = true;
+
+        let off = Cli::try_parse_from([
+            "mtr",
+            "--api",
+            "--api-fair-queue=false",
+            "--api-allow-callback-urls=false",
+            "--api-block-private-targets=false",
+        ])
+        .expect("toggle flags should accept =false");
+        let mut config = from_file.clone();
+        apply_rest_api_cli_overrides(&off, &mut config).expect("overrides should apply");
+        assert!(!config.fair_queueing);
+        assert!(!config.webhooks.allow_callback_urls);
+        assert!(!config.target_policy.default.block_private_ranges);
+
+        let unset = Cli::try_parse_from(["mtr", "--api"]).expect("api flag should parse");
+        let mut config = from_file;
+        apply_rest_api_cli_overrides(&unset, &mut config).expect("overrides should apply");
+        assert!(config.fair_queueing);
+        assert!(config.webhooks.allow_callback_urls);
+        assert!(config.target_policy.default.block_private_ranges);
+
+        let on = Cli::try_parse_from([
+            "mtr",
+            "--api",
+            "--api-fair-queue",
+            "--api-allow-callback-urls",
+            "--api-block-private-targets=true",
+        ])
+        .expect("bare toggle flags should parse");
+        let mut config = RestApiConfig::default();
+        apply_rest_api_cli_overrides(&on, &mut config).expect("overrides should apply");
+        assert!(config.fair_queueing);
+        assert!(config.webhooks.allow_callback_urls);
+        assert!(config.target_policy.default.block_private_ranges);
+    }
+
+    #[test]
+    fn target_policy_flags_replace_the_rules_from_the_file() {
+        let cli = Cli::try_parse_from([
+            "mtr",
+            "--api",
+            "--api-target-deny",
+            "*.corp.example",
+            "--api-principal-target-allow",
+            "CN=ops=198.51.100.0/24",
+            "--api-principal-target-allow",
+            "CN=ops=203.0.113.0/24",
+        ])
+        .expect("target policy flags should parse");
+        let rule = |raw: &str| raw.parse::<TargetRule>().unwrap();
+        let mut config = RestApiConfig::default();
+        config.target_policy.default.allow = vec![rule("10.0.0.0/8")];
+        config.target_policy.default.deny = vec![rule("*.old.example")];
+        let ops = config
+            .target_policy
+            .principals
+            .entry("CN=ops".to_string())
+            .or_default();
+        ops.allow = vec![rule("192.0.2.0/24")];
+        ops.deny = vec![rule("192.0.2.1/32")];
+
+        apply_rest_api_cli_overrides(&cli, &mut config).expect("overrides should apply");
+
+        let policy = &config.target_policy;
+        assert_eq!(policy.default.allow, vec![rule("10.0.0.0/8")]);
+        assert_eq!(policy.default.deny, vec![rule("*.corp.example")]);
+        assert_eq!(
+            policy.principals["CN=ops"].allow,
+            vec![rule("198.51.100.0/24"), rule("203.0.113.0/24")]
+        );
+        assert_eq!(policy.principals["CN=ops"].deny, vec![rule("192.0.2.1/32")]);
+    }
+
+    #[test]
+    fn api_mode_rejects_malformed_target_policy_rules() {
+        assert!(Cli::try_parse_from(["mtr", "--api", "--api-target-deny", "10.0.0.0/40"]).is_err());
+
+        let cli = Cli::try_parse_from(["mtr", "--api", "--api-principal-target-allow", "no-rule"])
+            .expect("flags should parse for validation test");
+        let mut config = RestApiConfig::default();
+        let err = apply_rest_api_cli_overrides(&cli, &mut config)
+            .expect_err("principal rule without `=` should fail");
+        assert!(err.to_string().contains("expects PRINCIPAL=RULE"));
+    }
+
     #[test]
     fn api_mode_rejects_missing_api_key_for_api_key_auth() {
         let cli = Cli::try_parse_from(["mtr", "--api", "--api-auth", "api-key"])
@@ -1,9 +1,11 @@
 use anyhow::Context;
-use serde_json::{Value, json};
 use std::net::{IpAddr, Ipv4Addr, ToSocketAddrs};
 use std::path::Path;
 use std::time::Duration;
 
+use crate::report_render::{SavedHop, SavedHost};
+use crate::report_schema::{Report, ReportBackend, ReportDocument, ReportProtocol};
+
 #[derive(Clone, Debug)]
 pub struct Config {
     pub count: usize,
@@ -40,6 +42,18 @@ impl Hop {
     pub fn last(&self) -> Option<f64> {
         self.received.last().copied()
     }
+
+    /// Population standard deviation of the received round-trip times.
+    pub fn stddev(&self) -> Option<f64> {
+        let avg = self.avg()?;
+        let variance = self
+            .received
+            .iter()
+            .map(|rtt| (rtt - avg).powi(2))
+            .sum::<f64>()
+            / self.received.len() as f64;
+        Some(variance.sqrt())
+    }
 }
 
 pub fn resolve_ipv4(target: &str) -> anyhow::Result<Ipv4Addr> {
@@ -153,32 +167,41 @@ pub fn trace(_target: &str, _config: &Config) -> anyhow::Result<Vec<Hop>> {
     anyhow::bail!("native Windows ICMP probing is only available on Windows")
 }
 
-pub fn json_report(target: &str, hops: &[Hop]) -> Value {
+pub fn json_report(target: &str, hops: &[Hop]) -> ReportDocument {
     let hops = hops
         .iter()
-        .map(|hop| {
-            json!({
-                "ttl": hop.ttl,
-                "host": hop.address.map(|address| address.to_string()),
-                "loss_pct": hop.loss_pct(),
-                "sent": hop.sent,
-                "recv": hop.received.len(),
-                "last": hop.last(),
-                "avg": hop.avg(),
-                "best": hop.best(),
-                "worst": hop.worst(),
-            })
+        .map(|hop| SavedHop {
+            ttl: u32::from(hop.ttl),
+            hosts: hop
+                .address
+                .map(|address| SavedHost {
+                    ip: address.to_string(),
+                    hostname: None,
+                })
+                .into_iter()
+                .collect(),
+            loss_pct: Some(hop.loss_pct()),
+            sent: Some(hop.sent as u64),
+            recv: Some(hop.received.len() as u64),
+            last_ms: hop.last(),
+            avg_ms: hop.avg(),
+            best_ms: hop.best(),
+            worst_ms: hop.worst(),
+            stddev_ms: hop.stddev(),
         })
-        .collect::<Vec<_>>();
+        .collect();
 
-    json!({
-        "schema_version": "1.0",
-        "report": {
-            "target": target,
-            "protocol": "icmp",
-            "backend": "windows-icmp-helper",
-            "hops": hops,
-        }
+    ReportDocument::new(Report {
+        target: target.to_string(),
+        target_ip: target
+            .parse::<Ipv4Addr>()
+            .ok()
+            .map(|address| address.to_string()),
+        protocol: Some(ReportProtocol::Icmp),
+        backend: ReportBackend::WindowsIcmpHelper,
+        hops,
+        session: None,
+        history: None,
     })
 }
 
@@ -272,8 +295,12 @@ mod tests {
                 received: vec![],
             }],
         );
+        let report = serde_json::to_value(report).expect("report should serialize");
         assert_eq!(report["report"]["protocol"], "icmp");
+        assert_eq!(report["report"]["backend"], "windows-icmp-helper");
+        assert_eq!(report["report"]["target_ip"], "8.8.8.8");
         assert_eq!(report["report"]["hops"][0]["loss_pct"], 100.0);
-        assert!(report["report"]["hops"][0]["host"].is_null());
+        assert_eq!(report["report"]["hops"][0]["hosts"], serde_json::json!([]));
+        assert!(report["report"]["hops"][0]["avg_ms"].is_null());
     }
 }
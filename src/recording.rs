@@ -0,0 +1,356 @@
+//! Dashboard session recordings: `--record <file>` and `mtr replay <file>`.
+//!
+//! A recording is JSON Lines. The first line is a [`RecordingHeader`]; every later line is one
+//! [`RecordedRound`], the hop snapshot the dashboard showed for that round. Each line is written
+//! and flushed as a whole, so a recording cut short by a crash or Ctrl+C stays readable up to
+//! the last complete round.
+
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::report_render::{SavedHop, SavedHost};
+use crate::report_schema::{RecordedSession, Report, ReportBackend, ReportDocument};
+
+/// `format` value of a recording header.
+pub const RECORDING_FORMAT: &str = "windows-mtr-recording";
+/// Recording layout version written by this build.
+pub const RECORDING_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum RecordingError {
+    #[error("recording `{path}` already exists; choose a new file name")]
+    AlreadyExists { path: String },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("not a windows-mtr recording (the first line is not a `{RECORDING_FORMAT}` header)")]
+    NotARecording,
+    #[error(
+        "recording version {0} is not supported; this build of windows-mtr reads version {RECORDING_VERSION}"
+    )]
+    UnsupportedVersion(u32),
+    #[error("recording line {line} is not a valid round: {source}")]
+    InvalidRound {
+        line: usize,
+        source: serde_json::Error,
+    },
+    #[error("recording has no rounds")]
+    NoRounds,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordingHeader {
+    pub format: String,
+    pub version: u32,
+    pub target: String,
+    /// Wall-clock start of the session, in milliseconds since the Unix epoch.
+    pub started_at_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedRound {
+    /// Milliseconds since the session started.
+    pub t_ms: u64,
+    pub hops: Vec<RecordedHop>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedHop {
+    pub ttl: u32,
+    pub host: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loss_pct: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub best_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worst_ms: Option<f64>,
+}
+
+/// Appends rounds to a new recording file.
+#[derive(Debug)]
+pub struct Recorder {
+    file: File,
+    started: Instant,
+}
+
+impl Recorder {
+    /// Create `path` and write the header. Refuses to overwrite an existing recording.
+    pub fn create(path: &Path, target: &str) -> Result<Self, RecordingError> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map_err(|error| match error.kind() {
+                io::ErrorKind::AlreadyExists => RecordingError::AlreadyExists {
+                    path: path.display().to_string(),
+                },
+                _ => RecordingError::Io(error),
+            })?;
+        let header = RecordingHeader {
+            format: RECORDING_FORMAT.to_string(),
+            version: RECORDING_VERSION,
+            target: target.to_string(),
+            started_at_ms: unix_millis(SystemTime::now()),
+        };
+        write_line(&mut file, &header)?;
+        Ok(Self {
+            file,
+            started: Instant::now(),
+        })
+    }
+
+    /// Append one round, timestamped now.
+    pub fn record(&mut self, hops: Vec<RecordedHop>) -> io::Result<()> {
+        let round = RecordedRound {
+            t_ms: u64::try_from(self.started.elapsed().as_millis()).unwrap_or(u64::MAX),
+            hops,
+        };
+        write_line(&mut self.file, &round)
+    }
+}
+
+fn write_line(file: &mut File, value: &impl Serialize) -> io::Result<()> {
+    let mut line = serde_json::to_vec(value).map_err(io::Error::other)?;
+    line.push(b'\n');
+    file.write_all(&line)?;
+    file.flush()
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX))
+        .unwrap_or_default()
+}
+
+/// A recording read back from disk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    pub header: RecordingHeader,
+    /// Rounds in time order.
+    pub rounds: Vec<RecordedRound>,
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> Result<Self, RecordingError> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn read(reader: impl BufRead) -> Result<Self, RecordingError> {
+        let mut lines = reader.split(b'\n').enumerate().peekable();
+        let header = match lines.next() {
+            Some((_, line)) => serde_json::from_slice::<RecordingHeader>(&line?)
+                .ok()
+                .filter(|header| header.format == RECORDING_FORMAT)
+                .ok_or(RecordingError::NotARecording)?,
+            None => return Err(RecordingError::NotARecording),
+        };
+        if header.version != RECORDING_VERSION {
+            return Err(RecordingError::UnsupportedVersion(header.version));
+        }
+
+        let mut rounds = Vec::new();
+        while let Some((index, line)) = lines.next() {
+            let line = line?;
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            match serde_json::from_slice::<RecordedRound>(&line) {
+                Ok(round) => rounds.push(round),
+                // A session killed mid-write leaves a partial last line; keep what came before.
+                Err(_) if lines.peek().is_none() => break,
+                Err(source) => {
+                    return Err(RecordingError::InvalidRound {
+                        line: index + 1,
+                        source,
+                    });
+                }
+            }
+        }
+        if rounds.is_empty() {
+            return Err(RecordingError::NoRounds);
+        }
+        rounds.sort_by_key(|round| round.t_ms);
+        Ok(Self { header, rounds })
+    }
+
+    /// Length of the session, from its start to the last round.
+    pub fn duration_ms(&self) -> u64 {
+        self.rounds
+            .last()
+            .map(|round| round.t_ms)
+            .unwrap_or_default()
+    }
+
+    /// Fold every round into one report in the `--json` shape, readable by `mtr render` and
+    /// `mtr diff`. Loss and average latency are means over the rounds a hop appeared in; best
+    /// and worst are the extremes; `sent` counts those rounds.
+    pub fn aggregate_report(&self) -> ReportDocument {
+        #[derive(Default)]
+        struct Totals {
+            host: String,
+            rounds: u64,
+            loss: Vec<f64>,
+            avg: Vec<f64>,
+            best: Option<f64>,
+            worst: Option<f64>,
+        }
+
+        let mut by_ttl = BTreeMap::<u32, Totals>::new();
+        for hop in self.rounds.iter().flat_map(|round| &round.hops) {
+            let totals = by_ttl.entry(hop.ttl).or_default();
+            totals.host.clone_from(&hop.host);
+            totals.rounds += 1;
+            totals.loss.extend(hop.loss_pct);
+            totals.avg.extend(hop.avg_ms);
+            if let Some(best) = hop.best_ms {
+                totals.best = Some(totals.best.map_or(best, |current| current.min(best)));
+            }
+            if let Some(worst) = hop.worst_ms {
+                totals.worst = Some(totals.worst.map_or(worst, |current| current.max(worst)));
+            }
+        }
+
+        let mean = |values: &[f64]| {
+            (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+        };
+        let hops = by_ttl
+            .into_iter()
+            .map(|(ttl, totals)| SavedHop {
+                ttl,
+                hosts: SavedHost::from_label(&totals.host).into_iter().collect(),
+                loss_pct: mean(&totals.loss),
+                sent: Some(totals.rounds),
+                avg_ms: mean(&totals.avg),
+                best_ms: totals.best,
+                worst_ms: totals.worst,
+                ..SavedHop::default()
+            })
+            .collect();
+
+        ReportDocument::new(Report {
+            target: self.header.target.clone(),
+            target_ip: None,
+            protocol: None,
+            backend: ReportBackend::Recording,
+            hops,
+            session: Some(RecordedSession {
+                started_at_ms: self.header.started_at_ms,
+                duration_ms: self.duration_ms(),
+                rounds: self.rounds.len(),
+            }),
+            history: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::CLI_JSON_SCHEMA_VERSION;
+
+    fn hop(ttl: u32, loss_pct: f64, avg_ms: f64) -> RecordedHop {
+        RecordedHop {
+            ttl,
+            host: format!("10.0.0.{ttl}"),
+            loss_pct: Some(loss_pct),
+            best_ms: Some(avg_ms - 1.0),
+            avg_ms: Some(avg_ms),
+            worst_ms: Some(avg_ms + 1.0),
+        }
+    }
+
+    #[test]
+    fn recorder_output_loads_back_in_order() {
+        let dir = tempfile::tempdir().expect("tempdir should exist");
+        let path = dir.path().join("session.jsonl");
+
+        let mut recorder = Recorder::create(&path, "8.8.8.8").expect("recording should start");
+        recorder
+            .record(vec![hop(1, 0.0, 2.0)])
+            .expect("round should append");
+        recorder
+            .record(vec![hop(1, 50.0, 4.0), hop(2, 0.0, 10.0)])
+            .expect("round should append");
+        drop(recorder);
+
+        let recording = Recording::load(&path).expect("recording should load");
+        assert_eq!(recording.header.target, "8.8.8.8");
+        assert_eq!(recording.header.version, RECORDING_VERSION);
+        assert_eq!(recording.rounds.len(), 2);
+        assert_eq!(recording.rounds[1].hops[1].ttl, 2);
+
+        let error = Recorder::create(&path, "8.8.8.8").expect_err("existing file is kept");
+        assert!(matches!(error, RecordingError::AlreadyExists { .. }));
+    }
+
+    #[test]
+    fn truncated_last_line_is_dropped_but_corrupt_middle_lines_fail() {
+        let header = format!(
+            r#"{{"format":"{RECORDING_FORMAT}","version":1,"target":"1.1.1.1","started_at_ms":0}}"#
+        );
+        let round = r#"{"t_ms":0,"hops":[{"ttl":1,"host":"1.1.1.1"}]}"#;
+
+        let truncated = format!("{header}\n{round}\n{{\"t_ms\":10,\"ho");
+        let recording = Recording::read(truncated.as_bytes()).expect("partial tail is ignored");
+        assert_eq!(recording.rounds.len(), 1);
+
+        let corrupt = format!("{header}\nnot json\n{round}\n");
+        let error = Recording::read(corrupt.as_bytes()).expect_err("corrupt round fails");
+        assert!(matches!(
+            error,
+            RecordingError::InvalidRound { line: 2, .. }
+        ));
+
+        let error = Recording::read(round.as_bytes()).expect_err("header is required");
+        assert!(matches!(error, RecordingError::NotARecording));
+
+        let future = header.replace("\"version\":1", "\"version\":9");
+        let error = Recording::read(format!("{future}\n{round}\n").as_bytes())
+            .expect_err("newer layouts are rejected");
+        assert!(matches!(error, RecordingError::UnsupportedVersion(9)));
+    }
+
+    #[test]
+    fn aggregate_report_averages_rounds_per_hop() {
+        let recording = Recording {
+            header: RecordingHeader {
+                format: RECORDING_FORMAT.to_string(),
+                version: RECORDING_VERSION,
+                target: "8.8.8.8".to_string(),
+                started_at_ms: 1_000,
+            },
+            rounds: vec![
+                RecordedRound {
+                    t_ms: 0,
+                    hops: vec![hop(1, 0.0, 2.0)],
+                },
+                RecordedRound {
+                    t_ms: 900,
+                    hops: vec![hop(1, 50.0, 4.0), hop(2, 0.0, 10.0)],
+                },
+            ],
+        };
+
+        let report =
+            serde_json::to_value(recording.aggregate_report()).expect("report should serialize");
+        assert_eq!(report["schema_version"], CLI_JSON_SCHEMA_VERSION);
+        assert_eq!(report["report"]["backend"], "recording");
+        assert_eq!(report["report"]["rounds"], 2);
+        assert_eq!(report["report"]["duration_ms"], 900);
+        let first = &report["report"]["hops"][0];
+        assert_eq!(first["hosts"][0]["ip"], "10.0.0.1");
+        assert_eq!(first["sent"], 2);
+        assert_eq!(first["loss_pct"], 25.0);
+        assert_eq!(first["avg_ms"], 3.0);
+        assert_eq!(first["best_ms"], 1.0);
+        assert_eq!(first["worst_ms"], 5.0);
+        assert_eq!(report["report"]["hops"][1]["sent"], 1);
+    }
+}
@@ -0,0 +1,592 @@
+//! Compare two probe reports hop by hop: `mtr diff` and `GET /api/v1/probes/{id}/diff/{other_id}`.
+//!
+//! Accepts the JSON shapes this project writes: embedded trippy reports (`hops[].hosts`), the
+//! native Windows ICMP report (`report.hops[].host`), and the legacy `host: "name (ip)"` form.
+//! Hops are matched by TTL. The verdict looks at the destination (the last hop) and whether the
+//! path changed.
+
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::report_render::{SavedHop, parse_hop};
+
+/// Destination loss must move by more than this many percentage points to count as a change.
+pub const LOSS_DELTA_THRESHOLD_PCT: f64 = 1.0;
+/// Destination average latency must move by more than this many milliseconds...
+pub const LATENCY_DELTA_THRESHOLD_MS: f64 = 5.0;
+/// ...and by more than this fraction of the old average to count as a change.
+pub const LATENCY_DELTA_THRESHOLD_RATIO: f64 = 0.1;
+
+#[derive(Debug, Error, Eq, PartialEq)]
+pub enum ReportDiffError {
+    #[error("{0} report has no hops array")]
+    MissingHops(&'static str),
+    #[error("{0} report has no hops")]
+    EmptyReport(&'static str),
+}
+
+/// One hop as read from a report.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReportHop {
+    pub ttl: u32,
+    /// Responding addresses; empty when the hop did not answer.
+    pub hosts: Vec<String>,
+    pub asn: Option<u32>,
+    pub loss_pct: Option<f64>,
+    pub avg_ms: Option<f64>,
+}
+
+/// Diffs read hops with the same parser as `mtr render`, then keep what they compare.
+impl From<&SavedHop> for ReportHop {
+    fn from(hop: &SavedHop) -> Self {
+        Self {
+            ttl: hop.ttl,
+            hosts: hop.hosts.iter().map(|host| host.ip.clone()).collect(),
+            asn: hop
+                .hosts
+                .iter()
+                .find_map(|host| host.hostname.as_deref().and_then(parse_asn)),
+            loss_pct: hop.loss_pct,
+            avg_ms: hop.avg_ms,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffVerdict {
+    Unchanged,
+    Improved,
+    PathChanged,
+    Degraded,
+}
+
+impl DiffVerdict {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Unchanged => "unchanged",
+            Self::Improved => "improved",
+            Self::PathChanged => "path_changed",
+            Self::Degraded => "degraded",
+        }
+    }
+
+    /// Overall verdict for several diffs: the most severe one wins.
+    pub fn worst(verdicts: impl IntoIterator<Item = Self>) -> Self {
+        verdicts.into_iter().max().unwrap_or(Self::Unchanged)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HopChange {
+    Unchanged,
+    HostChanged,
+    Added,
+    Removed,
+}
+
+impl HopChange {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Unchanged => "unchanged",
+            Self::HostChanged => "host_changed",
+            Self::Added => "added",
+            Self::Removed => "removed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HopDiff {
+    pub ttl: u32,
+    pub change: HopChange,
+    pub old_hosts: Vec<String>,
+    pub new_hosts: Vec<String>,
+    pub old_asn: Option<u32>,
+    pub new_asn: Option<u32>,
+    pub asn_changed: bool,
+    pub old_loss_pct: Option<f64>,
+    pub new_loss_pct: Option<f64>,
+    pub loss_delta_pct: Option<f64>,
+    pub old_avg_ms: Option<f64>,
+    pub new_avg_ms: Option<f64>,
+    pub avg_delta_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReportDiff {
+    pub target: Option<String>,
+    pub verdict: DiffVerdict,
+    pub path_changed: bool,
+    pub destination_loss_delta_pct: Option<f64>,
+    pub destination_avg_delta_ms: Option<f64>,
+    pub hops: Vec<HopDiff>,
+}
+
+/// Read the hops of a report; `label` names the report in errors.
+pub fn parse_report_hops(
+    report: &Value,
+    label: &'static str,
+) -> Result<Vec<ReportHop>, ReportDiffError> {
+    let hops = report_hops(report).ok_or(ReportDiffError::MissingHops(label))?;
+    if hops.is_empty() {
+        return Err(ReportDiffError::EmptyReport(label));
+    }
+
+    let mut parsed = hops
+        .iter()
+        .enumerate()
+        .map(|(index, hop)| ReportHop::from(&parse_hop(hop, index)))
+        .collect::<Vec<_>>();
+    parsed.sort_by_key(|hop| hop.ttl);
+    Ok(parsed)
+}
+
+/// The hop array of a trippy report (`hops`) or a native report (`report.hops`).
+pub(crate) fn report_hops(report: &Value) -> Option<&Vec<Value>> {
+    report
+        .get("hops")
+        .or_else(|| report.get("report").and_then(|inner| inner.get("hops")))
+        .and_then(Value::as_array)
+}
+
+/// The probed target named in a report, if it records one.
+pub fn report_target(report: &Value) -> Option<String> {
+    let target = report
+        .pointer("/info/target/ip")
+        .or_else(|| report.pointer("/report/target"))?;
+    target.as_str().map(str::to_string)
+}
+
+pub fn diff_reports(old: &[ReportHop], new: &[ReportHop]) -> ReportDiff {
+    let ttls = old
+        .iter()
+        .chain(new)
+        .map(|hop| hop.ttl)
+        .collect::<BTreeSet<_>>();
+    let hops = ttls
+        .into_iter()
+        .map(|ttl| {
+            diff_hop(
+                ttl,
+                old.iter().find(|hop| hop.ttl == ttl),
+                new.iter().find(|hop| hop.ttl == ttl),
+            )
+        })
+        .collect::<Vec<_>>();
+    let path_changed = hops.iter().any(|hop| hop.change != HopChange::Unchanged);
+
+    let old_destination = old.last();
+    let new_destination = new.last();
+    let destination_loss_delta_pct = delta(
+        old_destination.and_then(|hop| hop.loss_pct),
+        new_destination.and_then(|hop| hop.loss_pct),
+    );
+    let destination_avg_delta_ms = delta(
+        old_destination.and_then(|hop| hop.avg_ms),
+        new_destination.and_then(|hop| hop.avg_ms),
+    );
+
+    let loss_trend = destination_loss_delta_pct
+        .map(|delta| trend(delta, LOSS_DELTA_THRESHOLD_PCT))
+        .unwrap_or(0);
+    let latency_trend = match (
+        old_destination.and_then(|hop| hop.avg_ms),
+        destination_avg_delta_ms,
+    ) {
+        (Some(old_avg), Some(delta)) => trend(
+            delta,
+            LATENCY_DELTA_THRESHOLD_MS.max(old_avg * LATENCY_DELTA_THRESHOLD_RATIO),
+        ),
+        _ => 0,
+    };
+    let verdict = if loss_trend > 0 || latency_trend > 0 {
+        DiffVerdict::Degraded
+    } else if path_changed {
+        DiffVerdict::PathChanged
+    } else if loss_trend < 0 || latency_trend < 0 {
+        DiffVerdict::Improved
+    } else {
+        DiffVerdict::Unchanged
+    };
+
+    ReportDiff {
+        target: None,
+        verdict,
+        path_changed,
+        destination_loss_delta_pct,
+        destination_avg_delta_ms,
+        hops,
+    }
+}
+
+/// Plain-text table for terminals.
+pub fn render_text(diff: &ReportDiff) -> String {
+    let mut out = String::new();
+    if let Some(target) = &diff.target {
+        let _ = writeln!(out, "Target: {target}");
+    }
+    let _ = writeln!(out, "Verdict: {}", diff.verdict.as_str());
+    let _ = writeln!(
+        out,
+        "Path changed: {}",
+        if diff.path_changed { "yes" } else { "no" }
+    );
+    let _ = writeln!(
+        out,
+        "Destination loss: {}  avg: {}",
+        format_delta(diff.destination_loss_delta_pct, "%"),
+        format_delta(diff.destination_avg_delta_ms, " ms"),
+    );
+    out.push('\n');
+    let _ = writeln!(
+        out,
+        "{:<4} {:<13} {:<32} {:<15} {:>15} {:>19}",
+        "Hop", "Change", "Host", "ASN", "Loss% (delta)", "Avg ms (delta)"
+    );
+    for hop in &diff.hops {
+        let _ = writeln!(
+            out,
+            "{:<4} {:<13} {:<32} {:<15} {:>15} {:>19}",
+            hop.ttl,
+            hop.change.as_str(),
+            host_transition(hop),
+            asn_transition(hop),
+            metric_with_delta(hop.new_loss_pct.or(hop.old_loss_pct), hop.loss_delta_pct),
+            metric_with_delta(hop.new_avg_ms.or(hop.old_avg_ms), hop.avg_delta_ms),
+        );
+    }
+    out
+}
+
+/// GitHub-flavoured Markdown, for change tickets and pull requests.
+pub fn render_markdown(diff: &ReportDiff) -> String {
+    let mut out = String::new();
+    match &diff.target {
+        Some(target) => {
+            let _ = writeln!(out, "### Probe diff for `{target}`");
+        }
+        None => out.push_str("### Probe diff\n"),
+    }
+    out.push('\n');
+    let _ = writeln!(out, "- **Verdict:** {}", diff.verdict.as_str());
+    let _ = writeln!(
+        out,
+        "- **Path changed:** {}",
+        if diff.path_changed { "yes" } else { "no" }
+    );
+    let _ = writeln!(
+        out,
+        "- **Destination loss:** {}",
+        format_delta(diff.destination_loss_delta_pct, "%")
+    );
+    let _ = writeln!(
+        out,
+        "- **Destination avg:** {}",
+        format_delta(diff.destination_avg_delta_ms, " ms")
+    );
+    out.push('\n');
+    out.push_str("| Hop | Change | Host | ASN | Loss % | Δ loss | Avg ms | Δ avg |\n");
+    out.push_str("| ---: | --- | --- | --- | ---: | ---: | ---: | ---: |\n");
+    for hop in &diff.hops {
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {} | {} | {} | {} | {} |",
+            hop.ttl,
+            hop.change.as_str(),
+            host_transition(hop),
+            asn_transition(hop),
+            format_metric(hop.new_loss_pct.or(hop.old_loss_pct)),
+            format_signed(hop.loss_delta_pct),
+            format_metric(hop.new_avg_ms.or(hop.old_avg_ms)),
+            format_signed(hop.avg_delta_ms),
+        );
+    }
+    out
+}
+
+/// Trippy prefixes hostnames with `AS<number>` when AS lookups are enabled.
+pub fn parse_asn(hostname: &str) -> Option<u32> {
+    let token = hostname.split_whitespace().next()?;
+    token.strip_prefix("AS")?.parse().ok()
+}
+
+/// Trippy serializes fixed-width floats as strings, so accept both representations.
+pub(crate) fn read_number(value: &Value, key: &str) -> Option<f64> {
+    match value.get(key)? {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+    .filter(|number| number.is_finite())
+}
+
+fn diff_hop(ttl: u32, old: Option<&ReportHop>, new: Option<&ReportHop>) -> HopDiff {
+    let change = match (old, new) {
+        (Some(old), Some(new)) if same_hosts(old, new) => HopChange::Unchanged,
+        (Some(_), Some(_)) => HopChange::HostChanged,
+        (None, _) => HopChange::Added,
+        (_, None) => HopChange::Removed,
+    };
+    let old_asn = old.and_then(|hop| hop.asn);
+    let new_asn = new.and_then(|hop| hop.asn);
+    let old_loss_pct = old.and_then(|hop| hop.loss_pct);
+    let new_loss_pct = new.and_then(|hop| hop.loss_pct);
+    let old_avg_ms = old.and_then(|hop| hop.avg_ms);
+    let new_avg_ms = new.and_then(|hop| hop.avg_ms);
+
+    HopDiff {
+        ttl,
+        change,
+        old_hosts: old.map(|hop| hop.hosts.clone()).unwrap_or_default(),
+        new_hosts: new.map(|hop| hop.hosts.clone()).unwrap_or_default(),
+        old_asn,
+        new_asn,
+        asn_changed: matches!((old_asn, new_asn), (Some(old), Some(new)) if old != new),
+        old_loss_pct,
+        new_loss_pct,
+        loss_delta_pct: delta(old_loss_pct, new_loss_pct),
+        old_avg_ms,
+        new_avg_ms,
+        avg_delta_ms: delta(old_avg_ms, new_avg_ms),
+    }
+}
+
+fn same_hosts(old: &ReportHop, new: &ReportHop) -> bool {
+    old.hosts.iter().collect::<BTreeSet<_>>() == new.hosts.iter().collect::<BTreeSet<_>>()
+}
+
+fn delta(old: Option<f64>, new: Option<f64>) -> Option<f64> {
+    Some(new? - old?)
+}
+
+/// `1` when `delta` exceeds `threshold` (worse), `-1` when it falls below `-threshold`.
+fn trend(delta: f64, threshold: f64) -> i8 {
+    if delta > threshold {
+        1
+    } else if delta < -threshold {
+        -1
+    } else {
+        0
+    }
+}
+
+fn hosts_label(hosts: &[String]) -> String {
+    if hosts.is_empty() {
+        "???".to_string()
+    } else {
+        hosts.join(", ")
+    }
+}
+
+fn host_transition(hop: &HopDiff) -> String {
+    match hop.change {
+        HopChange::Unchanged | HopChange::Added => hosts_label(&hop.new_hosts),
+        HopChange::Removed => hosts_label(&hop.old_hosts),
+        HopChange::HostChanged => format!(
+            "{} -> {}",
+            hosts_label(&hop.old_hosts),
+            hosts_label(&hop.new_hosts)
+        ),
+    }
+}
+
+fn asn_transition(hop: &HopDiff) -> String {
+    let label = |asn: Option<u32>| asn.map(|asn| format!("AS{asn}"));
+    if hop.asn_changed {
+        return format!(
+            "{} -> {}",
+            label(hop.old_asn).unwrap_or_default(),
+            label(hop.new_asn).unwrap_or_default()
+        );
+    }
+    label(hop.new_asn.or(hop.old_asn)).unwrap_or_else(|| "-".to_string())
+}
+
+fn format_metric(value: Option<f64>) -> String {
+    value
+        .map(|value| format!("{value:.1}"))
+        .unwrap_or_else(|| "???".to_string())
+}
+
+fn format_signed(value: Option<f64>) -> String {
+    value
+        .map(|value| format!("{value:+.1}"))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn metric_with_delta(value: Option<f64>, delta: Option<f64>) -> String {
+    format!("{} ({})", format_metric(value), format_signed(delta))
+}
+
+fn format_delta(delta: Option<f64>, unit: &str) -> String {
+    match delta {
+        Some(delta) => format!("{delta:+.1}{unit}"),
+        None => "n/a".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::report_render::SavedReport;
+
+    fn trippy_hop(ttl: u32, ip: &str, hostname: &str, loss: &str, avg: &str) -> Value {
+        json!({
+            "ttl": ttl,
+            "hosts": [{"ip": ip, "hostname": hostname}],
+            "loss_pct": loss,
+            "avg": avg,
+        })
+    }
+
+    #[test]
+    fn parses_trippy_native_and_legacy_report_shapes() {
+        let trippy = json!({
+            "info": {"target": {"ip": "1.1.1.1", "hostname": "one.one.one.one"}},
+            "hops": [
+                trippy_hop(2, "1.1.1.1", "AS13335 one.one.one.one", "0.0", "12.5"),
+                trippy_hop(1, "192.168.1.1", "router", "0.0", "1.2"),
+            ],
+        });
+        let hops = parse_report_hops(&trippy, "old").expect("trippy report should parse");
+        assert_eq!(hops[0].ttl, 1);
+        assert_eq!(hops[1].hosts, ["1.1.1.1"]);
+        assert_eq!(hops[1].asn, Some(13335));
+        assert_eq!(hops[1].avg_ms, Some(12.5));
+        assert_eq!(report_target(&trippy).as_deref(), Some("1.1.1.1"));
+
+        let native = json!({"report": {"target": "8.8.8.8", "hops": [
+            {"ttl": 1, "host": null, "loss_pct": 100.0, "avg": null},
+            {"ttl": 2, "host": "8.8.8.8", "loss_pct": 0.0, "avg": 9.0},
+        ]}});
+        let hops = parse_report_hops(&native, "new").expect("native report should parse");
+        assert!(hops[0].hosts.is_empty());
+        assert_eq!(hops[0].avg_ms, None);
+        assert_eq!(hops[1].hosts, ["8.8.8.8"]);
+
+        let legacy = json!({"report": {"hops": [
+            {"ttl": 1, "host": "router.local (192.168.1.1)", "avg_ms": 1.5, "loss_ratio": 0.05},
+        ]}});
+        let hops = parse_report_hops(&legacy, "old").expect("legacy report should parse");
+        assert_eq!(hops[0].hosts, ["192.168.1.1"]);
+        assert_eq!(hops[0].loss_pct, Some(5.0));
+
+        assert_eq!(
+            parse_report_hops(&json!({"hops": []}), "new"),
+            Err(ReportDiffError::EmptyReport("new"))
+        );
+        assert_eq!(
+            parse_report_hops(&json!({}), "old"),
+            Err(ReportDiffError::MissingHops("old"))
+        );
+    }
+
+    #[test]
+    fn diff_and_render_read_hops_the_same_way() {
+        let report = json!({"report": {"hops": [
+            {"ttl": 1, "host": "???", "loss_percentage": 100.0},
+            {"ttl": 2, "host": "AS64500 edge (10.0.0.1)", "avg_ms": 4.0},
+        ]}});
+        let saved = SavedReport::from_json(&report).expect("render should read the report");
+        let hops = parse_report_hops(&report, "old").expect("diff should read the report");
+
+        assert_eq!(
+            hops,
+            saved.hops.iter().map(ReportHop::from).collect::<Vec<_>>()
+        );
+        assert!(hops[0].hosts.is_empty());
+        assert_eq!(hops[0].loss_pct, Some(100.0));
+        assert_eq!(hops[1].hosts, ["10.0.0.1"]);
+        assert_eq!(hops[1].asn, Some(64500));
+    }
+
+    #[test]
+    fn diff_reports_hop_deltas_path_and_asn_changes() {
+        let old = parse_report_hops(
+            &json!({"hops": [
+                trippy_hop(1, "192.168.1.1", "router", "0.0", "1.0"),
+                trippy_hop(2, "10.0.0.1", "AS64500 edge-a", "0.0", "5.0"),
+                trippy_hop(3, "1.1.1.1", "AS13335 one", "0.0", "10.0"),
+            ]}),
+            "old",
+        )
+        .expect("old report should parse");
+        let new = parse_report_hops(
+            &json!({"hops": [
+                trippy_hop(1, "192.168.1.1", "router", "0.0", "1.5"),
+                trippy_hop(2, "10.0.0.2", "AS64501 edge-b", "0.0", "6.0"),
+                trippy_hop(3, "172.16.0.1", "", "0.0", "8.0"),
+                trippy_hop(4, "1.1.1.1", "AS13335 one", "10.0", "30.0"),
+            ]}),
+            "new",
+        )
+        .expect("new report should parse");
+
+        let diff = diff_reports(&old, &new);
+        assert_eq!(diff.verdict, DiffVerdict::Degraded);
+        assert!(diff.path_changed);
+        assert_eq!(diff.destination_loss_delta_pct, Some(10.0));
+        assert_eq!(diff.destination_avg_delta_ms, Some(20.0));
+
+        let changes = diff.hops.iter().map(|hop| hop.change).collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            [
+                HopChange::Unchanged,
+                HopChange::HostChanged,
+                HopChange::HostChanged,
+                HopChange::Added,
+            ]
+        );
+        assert_eq!(diff.hops[0].avg_delta_ms, Some(0.5));
+        assert!(diff.hops[1].asn_changed);
+        assert!(!diff.hops[2].asn_changed);
+
+        let text = render_text(&diff);
+        assert!(text.contains("Verdict: degraded"), "{text}");
+        assert!(text.contains("10.0.0.1 -> 10.0.0.2"), "{text}");
+        assert!(text.contains("AS64500 -> AS64501"), "{text}");
+        let markdown = render_markdown(&diff);
+        assert!(
+            markdown.contains("| 4 | added | 1.1.1.1 | AS13335 | 10.0 | - | 30.0 | - |"),
+            "{markdown}"
+        );
+    }
+
+    #[test]
+    fn verdict_ignores_small_changes_and_reports_improvements() {
+        let report = |avg: &str, loss: &str| {
+            parse_report_hops(
+                &json!({"hops": [trippy_hop(1, "1.1.1.1", "one", loss, avg)]}),
+                "report",
+            )
+            .expect("report should parse")
+        };
+
+        let baseline = report("100.0", "0.0");
+        assert_eq!(
+            diff_reports(&baseline, &report("108.0", "0.5")).verdict,
+            DiffVerdict::Unchanged
+        );
+        assert_eq!(
+            diff_reports(&baseline, &report("115.0", "0.0")).verdict,
+            DiffVerdict::Degraded
+        );
+        assert_eq!(
+            diff_reports(&report("100.0", "20.0"), &baseline).verdict,
+            DiffVerdict::Improved
+        );
+        assert_eq!(
+            DiffVerdict::worst([DiffVerdict::Improved, DiffVerdict::PathChanged]),
+            DiffVerdict::PathChanged
+        );
+    }
+}
@@ -0,0 +1,564 @@
+//! Re-render a saved JSON report without probing again: `mtr render <file.json>`.
+//!
+//! Reads version 2 reports ([`crate::report_schema`]) and the shapes older releases wrote: raw
+//! trippy reports (`hops[].hosts`), the version 1 native Windows ICMP report
+//! (`report.hops[].host`), and the legacy `host: "name (ip)"` form. Captures from releases that
+//! predate `schema_version` are read as version 1.
+
+use std::fmt::Write as _;
+
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::report_diff::{read_number, report_hops, report_target};
+use crate::service::CLI_JSON_SCHEMA_VERSION;
+
+/// Newest major `schema_version` this build reads. Minor versions only add fields.
+pub const SUPPORTED_SCHEMA_MAJOR: u32 = 2;
+/// Oldest major `schema_version` this build still reads.
+pub const OLDEST_SCHEMA_MAJOR: u32 = 1;
+
+#[derive(Debug, Error, Eq, PartialEq)]
+pub enum ReportRenderError {
+    #[error("report is not a JSON object")]
+    NotAnObject,
+    #[error(
+        "report schema_version `{0}` is not a `major.minor` version string; expected `{CLI_JSON_SCHEMA_VERSION}`"
+    )]
+    InvalidSchemaVersion(String),
+    #[error(
+        "report schema_version `{0}` is newer than this build of windows-mtr supports (`{CLI_JSON_SCHEMA_VERSION}`); upgrade windows-mtr to render it"
+    )]
+    NewerSchemaVersion(String),
+    #[error(
+        "report schema_version `{0}` is not supported; this build of windows-mtr reads `{CLI_JSON_SCHEMA_VERSION}` reports"
+    )]
+    UnsupportedSchemaVersion(String),
+    #[error("report has no hops array (expected `hops` or `report.hops`)")]
+    MissingHops,
+    #[error("report has no hops")]
+    EmptyReport,
+}
+
+/// A responding address and its reverse-DNS name, when the report has one.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SavedHost {
+    pub ip: String,
+    pub hostname: Option<String>,
+}
+
+impl SavedHost {
+    /// Parse a display label: `hostname (ip)`, a bare address, or `???` for no reply.
+    pub fn from_label(label: &str) -> Option<Self> {
+        let label = label.trim();
+        if label.is_empty() || label == "???" {
+            return None;
+        }
+        Some(
+            match label
+                .split_once(" (")
+                .and_then(|(name, rest)| Some((name, rest.strip_suffix(')')?)))
+            {
+                Some((hostname, ip)) => Self {
+                    ip: ip.to_string(),
+                    hostname: Some(hostname.to_string()),
+                },
+                None => Self {
+                    ip: label.to_string(),
+                    hostname: None,
+                },
+            },
+        )
+    }
+
+    /// `hostname (ip)`, or just the address when there is no distinct name.
+    pub fn label(&self) -> String {
+        match &self.hostname {
+            Some(hostname) => format!("{hostname} ({})", self.ip),
+            None => self.ip.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SavedHop {
+    pub ttl: u32,
+    /// Empty when the hop did not answer.
+    pub hosts: Vec<SavedHost>,
+    pub loss_pct: Option<f64>,
+    pub sent: Option<u64>,
+    pub recv: Option<u64>,
+    pub last_ms: Option<f64>,
+    pub avg_ms: Option<f64>,
+    pub best_ms: Option<f64>,
+    pub worst_ms: Option<f64>,
+    pub stddev_ms: Option<f64>,
+}
+
+impl SavedHop {
+    /// Short host column: the first responder's name, else its address.
+    pub fn host_label(&self) -> String {
+        match self.hosts.first() {
+            Some(host) => host.hostname.clone().unwrap_or_else(|| host.ip.clone()),
+            None => "???".to_string(),
+        }
+    }
+
+    /// Every responder as `hostname (ip)`.
+    pub fn wide_host_label(&self) -> String {
+        if self.hosts.is_empty() {
+            return "???".to_string();
+        }
+        self.hosts
+            .iter()
+            .map(SavedHost::label)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// A report loaded from disk, ready to render.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedReport {
+    /// `None` for captures written before reports were versioned.
+    pub schema_version: Option<String>,
+    pub target: Option<String>,
+    pub hops: Vec<SavedHop>,
+}
+
+impl SavedReport {
+    pub fn from_json(report: &Value) -> Result<Self, ReportRenderError> {
+        if !report.is_object() {
+            return Err(ReportRenderError::NotAnObject);
+        }
+        let schema_version = check_schema_version(report)?;
+        let hops = report_hops(report).ok_or(ReportRenderError::MissingHops)?;
+        if hops.is_empty() {
+            return Err(ReportRenderError::EmptyReport);
+        }
+
+        let mut hops = hops
+            .iter()
+            .enumerate()
+            .map(|(index, hop)| parse_hop(hop, index))
+            .collect::<Vec<_>>();
+        hops.sort_by_key(|hop| hop.ttl);
+        Ok(Self {
+            schema_version,
+            target: report_target(report),
+            hops,
+        })
+    }
+
+    fn title(&self) -> String {
+        match &self.target {
+            Some(target) => format!("windows-mtr report for {target}"),
+            None => "windows-mtr report".to_string(),
+        }
+    }
+}
+
+fn check_schema_version(report: &Value) -> Result<Option<String>, ReportRenderError> {
+    let Some(version) = report.get("schema_version") else {
+        return Ok(None);
+    };
+    let Some(version) = version.as_str() else {
+        return Err(ReportRenderError::InvalidSchemaVersion(version.to_string()));
+    };
+
+    let major = version
+        .split_once('.')
+        .and_then(|(major, minor)| {
+            minor.parse::<u32>().ok()?;
+            major.parse::<u32>().ok()
+        })
+        .ok_or_else(|| ReportRenderError::InvalidSchemaVersion(version.to_string()))?;
+    if major > SUPPORTED_SCHEMA_MAJOR {
+        Err(ReportRenderError::NewerSchemaVersion(version.to_string()))
+    } else if major < OLDEST_SCHEMA_MAJOR {
+        Err(ReportRenderError::UnsupportedSchemaVersion(
+            version.to_string(),
+        ))
+    } else {
+        Ok(Some(version.to_string()))
+    }
+}
+
+pub(crate) fn parse_hop(hop: &Value, index: usize) -> SavedHop {
+    let ttl = read_number(hop, "ttl")
+        .filter(|ttl| *ttl >= 0.0 && *ttl <= f64::from(u32::MAX))
+        .map(|ttl| ttl as u32)
+        .unwrap_or_else(|| u32::try_from(index + 1).unwrap_or(u32::MAX));
+
+    let hosts = if let Some(entries) = hop.get("hosts").and_then(Value::as_array) {
+        entries
+            .iter()
+            .filter_map(|entry| {
+                let ip = entry.get("ip").and_then(Value::as_str)?;
+                let hostname = entry
+                    .get("hostname")
+                    .and_then(Value::as_str)
+                    .filter(|hostname| !hostname.is_empty() && *hostname != ip);
+                Some(SavedHost {
+                    ip: ip.to_string(),
+                    hostname: hostname.map(str::to_string),
+                })
+            })
+            .collect()
+    } else if let Some(host) = hop.get("host").and_then(Value::as_str) {
+        // Legacy reports write `name (ip)`.
+        SavedHost::from_label(host).into_iter().collect()
+    } else {
+        Vec::new()
+    };
+
+    // Trippy writes `0.0` latencies for hops that never answered.
+    let latency = |keys| read_any(hop, keys).filter(|value| *value > 0.0);
+    let count = |key| {
+        read_number(hop, key)
+            .filter(|value| *value >= 0.0)
+            .map(|value| value as u64)
+    };
+
+    SavedHop {
+        ttl,
+        hosts,
+        loss_pct: read_number(hop, "loss_pct")
+            .or_else(|| read_number(hop, "loss_percentage"))
+            .or_else(|| read_number(hop, "loss_ratio").map(|ratio| ratio * 100.0)),
+        sent: count("sent"),
+        recv: count("recv"),
+        last_ms: latency(["last_ms", "last"]),
+        avg_ms: latency(["avg_ms", "avg"]),
+        best_ms: latency(["best_ms", "best"]),
+        worst_ms: latency(["worst_ms", "worst"]),
+        stddev_ms: read_any(hop, ["stddev_ms", "stddev"]),
+    }
+}
+
+fn read_any(hop: &Value, keys: [&str; 2]) -> Option<f64> {
+    keys.iter().find_map(|key| read_number(hop, key))
+}
+
+/// The `-r` report layout.
+pub fn render_text(report: &SavedReport) -> String {
+    let mut out = format!("{}\n", report.title());
+    out.push_str("Hop  Host             Loss%  Snt  Recv  Last   Avg   Best  Wrst\n");
+    for hop in &report.hops {
+        let _ = writeln!(
+            out,
+            "{:<4} {:<16} {:>5} {:>4} {:>5} {:>5} {:>5} {:>5} {:>5}",
+            hop.ttl,
+            hop.host_label(),
+            format_ms(hop.loss_pct),
+            format_count(hop.sent),
+            format_count(hop.recv),
+            format_ms(hop.last_ms),
+            format_ms(hop.avg_ms),
+            format_ms(hop.best_ms),
+            format_ms(hop.worst_ms),
+        );
+    }
+    out
+}
+
+/// The `-w` layout: every responder with name and address, never truncated, plus StDev.
+pub fn render_wide(report: &SavedReport) -> String {
+    let width = report
+        .hops
+        .iter()
+        .map(|hop| hop.wide_host_label().chars().count())
+        .max()
+        .unwrap_or_default()
+        .max("Host".len());
+
+    let mut out = format!("{}\n", report.title());
+    let _ = writeln!(
+        out,
+        "{:<4} {:<width$} {:>5} {:>4} {:>5} {:>5} {:>5} {:>5} {:>5} {:>5}",
+        "Hop", "Host", "Loss%", "Snt", "Recv", "Last", "Avg", "Best", "Wrst", "StDev"
+    );
+    for hop in &report.hops {
+        let _ = writeln!(
+            out,
+            "{:<4} {:<width$} {:>5} {:>4} {:>5} {:>5} {:>5} {:>5} {:>5} {:>5}",
+            hop.ttl,
+            hop.wide_host_label(),
+            format_ms(hop.loss_pct),
+            format_count(hop.sent),
+            format_count(hop.recv),
+            format_ms(hop.last_ms),
+            format_ms(hop.avg_ms),
+            format_ms(hop.best_ms),
+            format_ms(hop.worst_ms),
+            format_ms(hop.stddev_ms),
+        );
+    }
+    out
+}
+
+/// The `--csv` columns, one row per hop with its first responder.
+pub fn render_csv(report: &SavedReport) -> anyhow::Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record([
+        "hop", "ip", "hostname", "avg_ms", "best_ms", "worst_ms", "loss_pct",
+    ])?;
+    for hop in &report.hops {
+        let host = hop.hosts.first();
+        writer.write_record([
+            hop.ttl.to_string(),
+            host.map(|host| host.ip.clone()).unwrap_or_default(),
+            host.and_then(|host| host.hostname.clone())
+                .unwrap_or_default(),
+            csv_metric(hop.avg_ms),
+            csv_metric(hop.best_ms),
+            csv_metric(hop.worst_ms),
+            csv_metric(hop.loss_pct),
+        ])?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+/// XML modelled on `mtr --xml`: one `HUB` element per hop.
+pub fn render_xml(report: &SavedReport) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = write!(out, "<MTR");
+    if let Some(target) = &report.target {
+        let _ = write!(out, " DST=\"{}\"", xml_escape(target));
+    }
+    if let Some(version) = &report.schema_version {
+        let _ = write!(out, " SCHEMA_VERSION=\"{}\"", xml_escape(version));
+    }
+    out.push_str(">\n");
+    for hop in &report.hops {
+        let _ = writeln!(
+            out,
+            "  <HUB COUNT=\"{}\" HOST=\"{}\">",
+            hop.ttl,
+            xml_escape(&hop.wide_host_label())
+        );
+        for (name, value) in [
+            ("Loss", format_ms(hop.loss_pct)),
+            ("Snt", format_count(hop.sent)),
+            ("Rcv", format_count(hop.recv)),
+            ("Last", format_ms(hop.last_ms)),
+            ("Avg", format_ms(hop.avg_ms)),
+            ("Best", format_ms(hop.best_ms)),
+            ("Wrst", format_ms(hop.worst_ms)),
+            ("StDev", format_ms(hop.stddev_ms)),
+        ] {
+            let _ = writeln!(out, "    <{name}>{}</{name}>", xml_escape(&value));
+        }
+        out.push_str("  </HUB>\n");
+    }
+    out.push_str("</MTR>\n");
+    out
+}
+
+/// GitHub-flavoured Markdown, for tickets.
+pub fn render_markdown(report: &SavedReport) -> String {
+    let mut out = match &report.target {
+        Some(target) => format!("### windows-mtr report for `{target}`\n\n"),
+        None => "### windows-mtr report\n\n".to_string(),
+    };
+    out.push_str("| Hop | Host | Loss % | Snt | Recv | Last | Avg | Best | Wrst |\n");
+    out.push_str("| ---: | --- | ---: | ---: | ---: | ---: | ---: | ---: | ---: |\n");
+    for hop in &report.hops {
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {} | {} | {} | {} | {} | {} |",
+            hop.ttl,
+            hop.wide_host_label().replace('|', "\\|"),
+            format_ms(hop.loss_pct),
+            format_count(hop.sent),
+            format_count(hop.recv),
+            format_ms(hop.last_ms),
+            format_ms(hop.avg_ms),
+            format_ms(hop.best_ms),
+            format_ms(hop.worst_ms),
+        );
+    }
+    out
+}
+
+fn format_ms(value: Option<f64>) -> String {
+    value
+        .map(|value| format!("{value:.1}"))
+        .unwrap_or_else(|| "???".to_string())
+}
+
+fn format_count(value: Option<u64>) -> String {
+    value
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| "???".to_string())
+}
+
+fn csv_metric(value: Option<f64>) -> String {
+    value.map(|value| format!("{value:.1}")).unwrap_or_default()
+}
+
+fn xml_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn trippy_report() -> Value {
+        json!({
+            "info": { "target": { "ip": "8.8.8.8", "hostname": "dns.google" } },
+            "hops": [
+                {
+                    "ttl": 2,
+                    "hosts": [{ "ip": "8.8.8.8", "hostname": "dns.google" }],
+                    "loss_pct": "0.00", "sent": 10, "recv": 10,
+                    "last": "20.10", "avg": "20.50", "best": "19.90", "worst": "22.00",
+                    "stddev": "0.40"
+                },
+                {
+                    "ttl": 1,
+                    "hosts": [{ "ip": "192.168.1.1", "hostname": "192.168.1.1" }],
+                    "loss_pct": "0.00", "sent": 10, "recv": 10,
+                    "last": "1.00", "avg": "1.20", "best": "0.90", "worst": "1.80",
+                    "stddev": "0.10"
+                }
+            ],
+            "schema_version": "1.0"
+        })
+    }
+
+    #[test]
+    fn trippy_and_native_reports_load_with_sorted_hops() {
+        let report = SavedReport::from_json(&trippy_report()).expect("report should load");
+        assert_eq!(report.schema_version.as_deref(), Some("1.0"));
+        assert_eq!(report.target.as_deref(), Some("8.8.8.8"));
+        assert_eq!(report.hops[0].ttl, 1);
+        assert_eq!(report.hops[0].host_label(), "192.168.1.1");
+        assert_eq!(report.hops[1].wide_host_label(), "dns.google (8.8.8.8)");
+        assert_eq!(report.hops[1].stddev_ms, Some(0.4));
+
+        let native = json!({
+            "schema_version": "1.0",
+            "report": {
+                "target": "1.1.1.1",
+                "protocol": "icmp",
+                "hops": [
+                    { "ttl": 1, "host": null, "loss_pct": 100.0, "sent": 3, "recv": 0,
+                      "last": null, "avg": null, "best": null, "worst": null }
+                ]
+            }
+        });
+        let report = SavedReport::from_json(&native).expect("native report should load");
+        assert_eq!(report.target.as_deref(), Some("1.1.1.1"));
+        assert_eq!(report.hops[0].host_label(), "???");
+        assert_eq!(report.hops[0].recv, Some(0));
+        assert_eq!(report.hops[0].avg_ms, None);
+    }
+
+    #[test]
+    fn schema_versions_are_validated() {
+        let with_version = |version: Value| {
+            let mut report = trippy_report();
+            report["schema_version"] = version;
+            SavedReport::from_json(&report).map(|report| report.schema_version)
+        };
+
+        assert_eq!(with_version(json!("1.3")), Ok(Some("1.3".to_string())));
+        assert_eq!(with_version(json!("2.1")), Ok(Some("2.1".to_string())));
+        assert_eq!(
+            with_version(json!("3.0")),
+            Err(ReportRenderError::NewerSchemaVersion("3.0".to_string()))
+        );
+        assert_eq!(
+            with_version(json!("0.9")),
+            Err(ReportRenderError::UnsupportedSchemaVersion(
+                "0.9".to_string()
+            ))
+        );
+        assert_eq!(
+            with_version(json!(1)),
+            Err(ReportRenderError::InvalidSchemaVersion("1".to_string()))
+        );
+        assert!(
+            ReportRenderError::NewerSchemaVersion("3.0".to_string())
+                .to_string()
+                .contains("upgrade windows-mtr")
+        );
+
+        let mut unversioned = trippy_report();
+        unversioned
+            .as_object_mut()
+            .expect("report is an object")
+            .remove("schema_version");
+        let report = SavedReport::from_json(&unversioned).expect("legacy report should load");
+        assert_eq!(report.schema_version, None);
+    }
+
+    #[test]
+    fn renderers_emit_every_hop() {
+        let report = SavedReport::from_json(&trippy_report()).expect("report should load");
+
+        let text = render_text(&report);
+        assert!(text.starts_with("windows-mtr report for 8.8.8.8\nHop  Host"));
+        assert!(text.contains("2    dns.google         0.0   10    10  20.1  20.5  19.9  22.0"));
+
+        let wide = render_wide(&report);
+        assert!(wide.contains("dns.google (8.8.8.8)"), "{wide}");
+        assert!(wide.contains("StDev"), "{wide}");
+
+        let csv = render_csv(&report).expect("csv should render");
+        assert_eq!(
+            csv,
+            "hop,ip,hostname,avg_ms,best_ms,worst_ms,loss_pct\n\
+             1,192.168.1.1,,1.2,0.9,1.8,0.0\n\
+             2,8.8.8.8,dns.google,20.5,19.9,22.0,0.0\n"
+        );
+
+        let xml = render_xml(&report);
+        assert!(xml.contains("<MTR DST=\"8.8.8.8\" SCHEMA_VERSION=\"1.0\">"));
+        assert!(xml.contains("<HUB COUNT=\"2\" HOST=\"dns.google (8.8.8.8)\">"));
+        assert!(xml.contains("<Avg>20.5</Avg>"));
+
+        let markdown = render_markdown(&report);
+        assert!(markdown.contains("| 2 | dns.google (8.8.8.8) | 0.0 | 10 | 10 |"));
+    }
+
+    #[test]
+    fn xml_escapes_host_names() {
+        assert_eq!(
+            xml_escape("a&b <c> \"d\""),
+            "a&amp;b &lt;c&gt; &quot;d&quot;"
+        );
+    }
+
+    #[test]
+    fn host_labels_parse_back_into_hosts() {
+        assert_eq!(SavedHost::from_label("???"), None);
+        assert_eq!(
+            SavedHost::from_label("dns.google (8.8.8.8)"),
+            Some(SavedHost {
+                ip: "8.8.8.8".to_string(),
+                hostname: Some("dns.google".to_string()),
+            })
+        );
+        assert_eq!(
+            SavedHost::from_label("10.0.0.1").map(|host| host.hostname),
+            Some(None)
+        );
+    }
+}
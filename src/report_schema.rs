@@ -0,0 +1,172 @@
+//! The JSON report contract: what `--json`, `--json-pretty` and `mtr replay --export json`
+//! write, whichever backend produced the data.
+//!
+//! The embedded trippy engine and the native Windows ICMP probe report different raw shapes;
+//! both are normalized into a [`ReportDocument`] before printing. The document is described by
+//! the JSON Schema in `docs/schemas/report-v2.schema.json`, shipped in the binary as
+//! [`REPORT_SCHEMA`] and printed by `mtr schema --print`.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::report_diff::report_hops;
+use crate::report_render::{ReportRenderError, SavedHop, SavedHost, SavedReport, parse_hop};
+use crate::service::CLI_JSON_SCHEMA_VERSION;
+
+/// JSON Schema (draft 2020-12) for [`ReportDocument`] at [`CLI_JSON_SCHEMA_VERSION`].
+pub const REPORT_SCHEMA: &str = include_str!("../docs/schemas/report-v2.schema.json");
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportProtocol {
+    Icmp,
+    Tcp,
+    Udp,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReportBackend {
+    Trippy,
+    WindowsIcmpHelper,
+    /// A `--record` session folded into one report.
+    Recording,
+}
+
+/// Top-level `--json` document.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReportDocument {
+    pub schema_version: &'static str,
+    pub report: Report,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Report {
+    /// Target as given on the command line.
+    pub target: String,
+    /// Address that was probed, when known.
+    pub target_ip: Option<String>,
+    /// `None` only for recordings, which do not store the protocol.
+    pub protocol: Option<ReportProtocol>,
+    pub backend: ReportBackend,
+    pub hops: Vec<SavedHop>,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub session: Option<RecordedSession>,
+    /// Per-hop history, in dashboard exports only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history: Option<Vec<HopTimeline>>,
+}
+
+/// What the dashboard observed at one TTL over the rounds it kept.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HopTimeline {
+    pub ttl: u32,
+    /// Every address seen at this TTL, in first-seen order.
+    pub hosts: Vec<SavedHost>,
+    pub rounds: Vec<TimelineRound>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TimelineRound {
+    /// Dashboard round number, counted from 1.
+    pub round: u64,
+    pub rtt_ms: Option<f64>,
+    pub loss_pct: Option<f64>,
+}
+
+/// Extra fields for a report built from a session recording.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+pub struct RecordedSession {
+    pub started_at_ms: u64,
+    pub duration_ms: u64,
+    pub rounds: usize,
+}
+
+impl ReportDocument {
+    pub fn new(report: Report) -> Self {
+        Self {
+            schema_version: CLI_JSON_SCHEMA_VERSION,
+            report,
+        }
+    }
+
+    /// Normalize the JSON the embedded trippy runner prints (`info` plus `hops[].hosts`, with
+    /// numbers as strings and `0.0` for latencies that were never measured).
+    pub fn from_trippy(
+        trippy: &Value,
+        target: &str,
+        protocol: ReportProtocol,
+    ) -> Result<Self, ReportRenderError> {
+        let hops = report_hops(trippy).ok_or(ReportRenderError::MissingHops)?;
+        let mut hops = hops
+            .iter()
+            .enumerate()
+            .map(|(index, hop)| parse_hop(hop, index))
+            .collect::<Vec<_>>();
+        hops.sort_by_key(|hop| hop.ttl);
+
+        Ok(Self::new(Report {
+            target: target.to_string(),
+            target_ip: trippy
+                .pointer("/info/target/ip")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            protocol: Some(protocol),
+            backend: ReportBackend::Trippy,
+            hops,
+            session: None,
+            history: None,
+        }))
+    }
+}
+
+impl From<ReportDocument> for SavedReport {
+    fn from(document: ReportDocument) -> Self {
+        Self {
+            schema_version: Some(document.schema_version.to_string()),
+            target: Some(document.report.target),
+            hops: document.report.hops,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn trippy_output_is_normalized_to_the_report_shape() {
+        let trippy = json!({
+            "info": {"target": {"ip": "1.1.1.1", "hostname": "one.one.one.one"}},
+            "hops": [
+                {"ttl": 2, "hosts": [{"ip": "1.1.1.1", "hostname": "one.one.one.one"}],
+                 "loss_pct": "0.0", "sent": 3, "recv": 3, "last": "12.10", "avg": "12.50",
+                 "best": "11.90", "worst": "13.40", "stddev": "0.61"},
+                {"ttl": 1, "hosts": [], "loss_pct": "100.0", "sent": 3, "recv": 0,
+                 "last": "0.0", "avg": "0.0", "best": "0.0", "worst": "0.0", "stddev": "0.0"},
+            ],
+        });
+
+        let document = ReportDocument::from_trippy(&trippy, "one.one.one.one", ReportProtocol::Tcp)
+            .expect("trippy output should normalize");
+        let value = serde_json::to_value(&document).expect("document should serialize");
+
+        assert_eq!(value["schema_version"], CLI_JSON_SCHEMA_VERSION);
+        assert_eq!(value["report"]["target"], "one.one.one.one");
+        assert_eq!(value["report"]["target_ip"], "1.1.1.1");
+        assert_eq!(value["report"]["protocol"], "tcp");
+        assert_eq!(value["report"]["backend"], "trippy");
+        let silent = &value["report"]["hops"][0];
+        assert_eq!(silent["ttl"], 1);
+        assert_eq!(silent["hosts"], json!([]));
+        assert!(silent["avg_ms"].is_null(), "unmeasured latency is null");
+        let destination = &value["report"]["hops"][1];
+        assert_eq!(
+            destination["hosts"],
+            json!([{"ip": "1.1.1.1", "hostname": "one.one.one.one"}])
+        );
+        assert_eq!(destination["avg_ms"], 12.5);
+        assert!(value.get("session").is_none() && value["report"].get("rounds").is_none());
+    }
+}
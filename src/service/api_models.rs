@@ -1,6 +1,9 @@
+use std::net::IpAddr;
+
 use serde::{Deserialize, Serialize};
 
-use crate::service::rest_api::{CreateProbeApiRequest, ProbeProtocol};
+use crate::report_diff::{DiffVerdict, ReportDiff};
+use crate::service::rest_api::{CreateProbeApiRequest, ProbeEcmpStrategy, ProbeProtocol};
 use crate::service::rest_server::{
     ProbeExecutionResult, ProbeJob, ProbeJobStatus, ProbeTargetExecutionResult,
 };
@@ -17,6 +20,13 @@ pub struct CreateProbeRequestDto {
     pub include_asn: Option<bool>,
     pub interval_seconds: Option<f32>,
     pub timeout_seconds: Option<f32>,
+    pub source_port: Option<u16>,
+    pub packet_size: Option<u16>,
+    pub src: Option<IpAddr>,
+    pub interface: Option<String>,
+    pub ecmp: Option<ApiEcmpStrategy>,
+    pub dns_cache_ttl_seconds: Option<u64>,
+    pub callback_url: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, Deserialize, Serialize)]
@@ -37,6 +47,24 @@ impl From<ApiProbeProtocol> for ProbeProtocol {
     }
 }
 
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiEcmpStrategy {
+    Classic,
+    Paris,
+    Dublin,
+}
+
+impl From<ApiEcmpStrategy> for ProbeEcmpStrategy {
+    fn from(value: ApiEcmpStrategy) -> Self {
+        match value {
+            ApiEcmpStrategy::Classic => ProbeEcmpStrategy::Classic,
+            ApiEcmpStrategy::Paris => ProbeEcmpStrategy::Paris,
+            ApiEcmpStrategy::Dublin => ProbeEcmpStrategy::Dublin,
+        }
+    }
+}
+
 impl From<CreateProbeRequestDto> for CreateProbeApiRequest {
     fn from(value: CreateProbeRequestDto) -> Self {
         Self {
@@ -49,6 +77,13 @@ impl From<CreateProbeRequestDto> for CreateProbeApiRequest {
             include_asn: value.include_asn,
             interval_seconds: value.interval_seconds,
             timeout_seconds: value.timeout_seconds,
+            source_port: value.source_port,
+            packet_size: value.packet_size,
+            src: value.src,
+            interface: value.interface,
+            ecmp: value.ecmp.map(Into::into),
+            dns_cache_ttl_seconds: value.dns_cache_ttl_seconds,
+            callback_url: value.callback_url,
         }
     }
 }
@@ -73,6 +108,62 @@ pub struct HealthDataDto {
     pub version: &'static str,
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessResponseDto {
+    pub meta: ApiResponseMetaDto,
+    pub data: ReadinessDataDto,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessDataDto {
+    /// `ready` when every check passed, otherwise `not_ready`.
+    pub status: &'static str,
+    pub checks: Vec<RuntimeCheckDto>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeCheckDto {
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsResponseDto {
+    pub meta: ApiResponseMetaDto,
+    pub data: DiagnosticsDataDto,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsDataDto {
+    pub status: &'static str,
+    pub service: &'static str,
+    pub version: &'static str,
+    pub uptime_seconds: u64,
+    pub checks: Vec<RuntimeCheckDto>,
+    pub running_probes: usize,
+    pub queued_probes: usize,
+    pub max_concurrent_probes: usize,
+    pub max_queued_probes: usize,
+    /// Jobs held in the result store, including finished jobs not yet pruned.
+    pub stored_jobs: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeDiffResponseDto {
+    pub meta: ApiResponseMetaDto,
+    pub data: ProbeDiffDataDto,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeDiffDataDto {
+    pub from: String,
+    pub to: String,
+    /// Worst verdict across `targets`.
+    pub verdict: DiffVerdict,
+    pub targets: Vec<ReportDiff>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct CreateProbeResponseDto {
     pub meta: ApiResponseMetaDto,
@@ -83,6 +174,8 @@ pub struct CreateProbeResponseDto {
 pub struct CreateProbeDataDto {
     pub id: String,
     pub status: ApiProbeStatusDto,
+    /// 1-based place in the work queue while the job waits for a probe slot.
+    pub queue_position: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -95,6 +188,7 @@ pub struct ProbeResultResponseDto {
 pub struct ProbeResultDataDto {
     pub id: String,
     pub status: ApiProbeStatusDto,
+    pub queue_position: Option<usize>,
     pub result: Option<ProbeExecutionResultDto>,
     pub error: Option<String>,
 }
@@ -142,6 +236,7 @@ pub enum ApiProbeStatusDto {
     Running,
     Completed,
     Failed,
+    Interrupted,
 }
 
 impl From<ProbeJobStatus> for ApiProbeStatusDto {
@@ -151,10 +246,25 @@ impl From<ProbeJobStatus> for ApiProbeStatusDto {
             ProbeJobStatus::Running => Self::Running,
             ProbeJobStatus::Completed => Self::Completed,
             ProbeJobStatus::Failed => Self::Failed,
+            ProbeJobStatus::Interrupted => Self::Interrupted,
         }
     }
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigReloadResponseDto {
+    pub meta: ApiResponseMetaDto,
+    pub data: ConfigReloadDataDto,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigReloadDataDto {
+    /// Settings now in effect with new values; secret values are never included.
+    pub changed: Vec<&'static str>,
+    /// Settings that differ in the new configuration but need a restart.
+    pub restart_required: Vec<&'static str>,
+}
+
 impl From<&ProbeJob> for ProbeResultResponseDto {
     fn from(value: &ProbeJob) -> Self {
         Self {
@@ -165,6 +275,7 @@ impl From<&ProbeJob> for ProbeResultResponseDto {
             data: ProbeResultDataDto {
                 id: value.id.clone(),
                 status: value.status.into(),
+                queue_position: None,
                 result: value.result.clone().map(Into::into),
                 error: value.error.clone(),
             },
@@ -0,0 +1,143 @@
+//! Hot reload of the REST API configuration.
+//!
+//! A [`ConfigSource`] rebuilds [`RestApiConfig`] from the same layers used at startup (config
+//! file, environment, CLI flags). Reloads are triggered by `POST /api/v1/admin/reload` or by
+//! [`watch_config_files`], and applied with `RestServerState::reload`, which keeps the previous
+//! configuration when the new one does not validate.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::service::rest_api::{RestApiConfig, RestApiValidationError};
+use crate::service::rest_server::RestServerState;
+
+/// Default poll interval for [`watch_config_files`].
+pub const DEFAULT_WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+type LoadConfig = dyn Fn() -> anyhow::Result<RestApiConfig> + Send + Sync;
+
+/// Rebuilds the API configuration on demand.
+#[derive(Clone)]
+pub struct ConfigSource(Arc<LoadConfig>);
+
+impl ConfigSource {
+    pub fn new(load: impl Fn() -> anyhow::Result<RestApiConfig> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(load))
+    }
+
+    pub fn load(&self) -> anyhow::Result<RestApiConfig> {
+        (self.0)()
+    }
+}
+
+impl fmt::Debug for ConfigSource {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("ConfigSource")
+    }
+}
+
+/// Settings that differ after a successful reload.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ConfigReloadReport {
+    /// Settings now in effect with new values.
+    pub changed: Vec<&'static str>,
+    /// Settings that differ in the new configuration but only take effect after a restart.
+    pub restart_required: Vec<&'static str>,
+}
+
+impl fmt::Display for ConfigReloadReport {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.changed.is_empty() {
+            formatter.write_str("no changes")?;
+        } else {
+            write!(formatter, "changed {}", self.changed.join(", "))?;
+        }
+        if !self.restart_required.is_empty() {
+            write!(
+                formatter,
+                "; restart required for {}",
+                self.restart_required.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigReloadError {
+    #[error("config reload is not available: the server was started without a config source")]
+    Unavailable,
+
+    #[error("failed to load new configuration: {0:#}")]
+    Source(anyhow::Error),
+
+    #[error("new configuration is invalid: {0}")]
+    Invalid(#[from] RestApiValidationError),
+}
+
+/// Where to reload from, and which files to watch for changes.
+#[derive(Debug, Clone)]
+pub struct ConfigReloadOptions {
+    pub source: ConfigSource,
+    pub watch_paths: Vec<PathBuf>,
+    pub watch_interval: Duration,
+}
+
+/// Poll `paths` and reload whenever a modification time changes (or a file appears or
+/// disappears). Runs until the task is dropped.
+pub async fn watch_config_files(state: RestServerState, paths: Vec<PathBuf>, interval: Duration) {
+    if paths.is_empty() {
+        return;
+    }
+
+    let mut last_seen = modification_times(&paths);
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    ticker.tick().await;
+
+    loop {
+        ticker.tick().await;
+        let current = modification_times(&paths);
+        if current == last_seen {
+            continue;
+        }
+        last_seen = current;
+
+        match state.reload_from_source() {
+            Ok(report) => eprintln!("config reload: {report}"),
+            Err(error) => eprintln!("config reload rejected, keeping previous settings: {error}"),
+        }
+    }
+}
+
+fn modification_times(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|path| {
+            std::fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_lists_changed_and_restart_required_settings() {
+        assert_eq!(ConfigReloadReport::default().to_string(), "no changes");
+
+        let report = ConfigReloadReport {
+            changed: vec!["max_requests_per_window", "api_key"],
+            restart_required: vec!["bind_addr"],
+        };
+        assert_eq!(
+            report.to_string(),
+            "changed max_requests_per_window, api_key; restart required for bind_addr"
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//! Runtime checks behind `GET /api/v1/ready` and `GET /api/v1/diagnostics`.
+//!
+//! Unlike `/health`, these checks exercise the probe runner: the binary must exist and start,
+//! and a one-cycle ICMP probe to `127.0.0.1` must succeed, mirroring
+//! `tests/privilege_probe_smoke.rs`. A failing loopback probe almost always means the process
+//! lacks raw-socket/ICMP privileges.
+
+use std::path::Path;
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+/// Upper bound for each check's child process.
+pub const RUNTIME_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long check results are reused before the runner is exercised again.
+pub const RUNTIME_CHECK_TTL: Duration = Duration::from_secs(30);
+
+pub const PROBE_RUNNER_CHECK: &str = "probe_runner";
+pub const PROBE_PRIVILEGES_CHECK: &str = "probe_privileges";
+
+/// Outcome of one runtime check.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RuntimeCheck {
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+}
+
+/// Results of every runtime check, taken at `checked_at`.
+#[derive(Debug, Clone)]
+pub struct RuntimeChecks {
+    pub checks: Vec<RuntimeCheck>,
+    pub checked_at: Instant,
+}
+
+impl RuntimeChecks {
+    pub fn ready(&self) -> bool {
+        self.checks.iter().all(|check| check.ok)
+    }
+
+    /// Whether these results are still fresh enough to serve at `now`.
+    pub fn is_fresh(&self, now: Instant) -> bool {
+        now.duration_since(self.checked_at) < RUNTIME_CHECK_TTL
+    }
+}
+
+/// Run every check against `runner`. `privilege_probe_args` is the embedded trippy command line
+/// for the loopback probe (argument 0 is skipped, as with `capture_embedded_trippy_json`).
+pub fn run_runtime_checks(
+    runner: &Path,
+    privilege_probe_args: &[String],
+    embedded_env_name: &str,
+) -> RuntimeChecks {
+    let runner_check = check_probe_runner(runner);
+    let privileges_check = if runner_check.ok {
+        check_probe_privileges(runner, privilege_probe_args, embedded_env_name)
+    } else {
+        RuntimeCheck {
+            name: PROBE_PRIVILEGES_CHECK,
+            ok: false,
+            detail: "skipped because the probe runner is unavailable".to_string(),
+        }
+    };
+
+    RuntimeChecks {
+        checks: vec![runner_check, privileges_check],
+        checked_at: Instant::now(),
+    }
+}
+
+fn check_probe_runner(runner: &Path) -> RuntimeCheck {
+    let failed = |detail: String| RuntimeCheck {
+        name: PROBE_RUNNER_CHECK,
+        ok: false,
+        detail,
+    };
+
+    match std::fs::metadata(runner) {
+        Ok(metadata) if metadata.is_file() => {}
+        Ok(_) => return failed(format!("`{}` is not a file", runner.display())),
+        Err(error) => return failed(format!("`{}` is not accessible: {error}", runner.display())),
+    }
+
+    let mut command = Command::new(runner);
+    command.arg("--version");
+    match run_with_timeout(command, RUNTIME_CHECK_TIMEOUT) {
+        Ok(Some(status)) if status.success() => RuntimeCheck {
+            name: PROBE_RUNNER_CHECK,
+            ok: true,
+            detail: format!("`{}` executes", runner.display()),
+        },
+        Ok(Some(status)) => failed(format!(
+            "`{} --version` exited with {}",
+            runner.display(),
+            describe_status(status)
+        )),
+        Ok(None) => failed(format!(
+            "`{} --version` did not exit within {}s",
+            runner.display(),
+            RUNTIME_CHECK_TIMEOUT.as_secs()
+        )),
+        Err(error) => failed(format!("failed to execute `{}`: {error}", runner.display())),
+    }
+}
+
+fn check_probe_privileges(
+    runner: &Path,
+    probe_args: &[String],
+    embedded_env_name: &str,
+) -> RuntimeCheck {
+    let failed = |detail: String| RuntimeCheck {
+        name: PROBE_PRIVILEGES_CHECK,
+        ok: false,
+        detail,
+    };
+
+    let mut command = Command::new(runner);
+    command
+        .env(embedded_env_name, "1")
+        .args(probe_args.iter().skip(1));
+    match run_with_timeout(command, RUNTIME_CHECK_TIMEOUT) {
+        Ok(Some(status)) if status.success() => RuntimeCheck {
+            name: PROBE_PRIVILEGES_CHECK,
+            ok: true,
+            detail: "ICMP probe to 127.0.0.1 succeeded".to_string(),
+        },
+        Ok(Some(status)) => failed(format!(
+            "ICMP probe to 127.0.0.1 exited with {}; the process likely lacks raw-socket/ICMP \
+             privileges (run as Administrator, or grant CAP_NET_RAW on Linux)",
+            describe_status(status)
+        )),
+        Ok(None) => failed(format!(
+            "ICMP probe to 127.0.0.1 did not finish within {}s",
+            RUNTIME_CHECK_TIMEOUT.as_secs()
+        )),
+        Err(error) => failed(format!("failed to launch ICMP probe: {error}")),
+    }
+}
+
+/// Run `command` with null stdio, killing it if it outlives `limit`. `Ok(None)` means timeout.
+fn run_with_timeout(mut command: Command, limit: Duration) -> std::io::Result<Option<ExitStatus>> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    let deadline = Instant::now() + limit;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+fn describe_status(status: ExitStatus) -> String {
+    match status.code() {
+        Some(code) => format!("code {code}"),
+        None => "no exit code (terminated by a signal)".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn missing_runner_fails_both_checks() {
+        let runner = PathBuf::from("/nonexistent/windows-mtr-runner");
+        let checks = run_runtime_checks(&runner, &[], "UNUSED_ENV");
+
+        assert!(!checks.ready());
+        assert_eq!(checks.checks[0].name, PROBE_RUNNER_CHECK);
+        assert!(checks.checks[0].detail.contains("not accessible"));
+        assert_eq!(checks.checks[1].name, PROBE_PRIVILEGES_CHECK);
+        assert!(checks.checks[1].detail.starts_with("skipped"));
+    }
+
+    #[test]
+    fn results_expire_after_ttl() {
+        let checks = RuntimeChecks {
+            checks: Vec::new(),
+            checked_at: Instant::now(),
+        };
+
+        assert!(checks.ready());
+        assert!(checks.is_fresh(checks.checked_at + RUNTIME_CHECK_TTL / 2));
+        assert!(!checks.is_fresh(checks.checked_at + RUNTIME_CHECK_TTL));
+    }
+}
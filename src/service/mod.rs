@@ -1,6 +1,17 @@
 pub mod api_models;
+pub mod config_reload;
+pub mod diagnostics;
+pub mod openapi;
 pub mod rest_api;
 pub mod rest_server;
+#[cfg(unix)]
+pub mod sd_notify;
+pub mod service_host;
+pub mod target_policy;
+pub mod webhooks;
+#[cfg(windows)]
+pub mod windows_service;
+use crate::report_schema::{ReportDocument, ReportProtocol};
 use anyhow::Context;
 use std::io::Write;
 use std::net::{IpAddr, ToSocketAddrs};
@@ -20,7 +31,7 @@ pub enum JsonOutput {
     Compact,
     Pretty,
 }
-pub const CLI_JSON_SCHEMA_VERSION: &str = "1.0";
+pub const CLI_JSON_SCHEMA_VERSION: &str = "2.1";
 
 #[derive(Debug, Clone, Copy)]
 pub struct EnhancedUiConfig {
@@ -33,6 +44,20 @@ pub struct EnhancedUiConfig {
     pub summary: bool,
 }
 
+impl Default for EnhancedUiConfig {
+    fn default() -> Self {
+        Self {
+            latency_warn_ms: 100.0,
+            latency_bad_ms: 250.0,
+            loss_warn_pct: 2.0,
+            loss_bad_pct: 5.0,
+            row_coloring: true,
+            sparklines: true,
+            summary: true,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ProbeRequest {
     pub host: String,
@@ -66,6 +91,7 @@ pub struct ProbeRequest {
 pub struct ProbePlan {
     pub validated_host: String,
     pub trippy_args: Vec<String>,
+    pub protocol: ReportProtocol,
     pub json_output: Option<JsonOutput>,
     pub csv_output_path: Option<PathBuf>,
     pub ui_mode: UiMode,
@@ -148,18 +174,22 @@ pub fn verify_options(request: &ProbeRequest) -> Result<(), ProbeError> {
         )));
     }
 
-    if request.has_enhanced_overrides && request.ui_mode != UiMode::Enhanced {
+    if request.has_enhanced_overrides
+        && !matches!(request.ui_mode, UiMode::Enhanced | UiMode::Dashboard)
+    {
         return Err(ProbeError::InvalidOption(
-            "enhanced UI tuning flags require --ui enhanced".to_string(),
+            "enhanced UI tuning flags require --ui dashboard".to_string(),
         ));
     }
 
     if request.ui_mode == UiMode::Enhanced {
         return Err(ProbeError::InvalidOption(
-            "enhanced UI is not available with bundled Trippy 0.13.0; use default UI or --ui dashboard fallback".to_string(),
+            "enhanced UI is not available with bundled Trippy 0.13.0; use --ui dashboard, which supports the enhanced tuning flags".to_string(),
         ));
     }
 
+    verify_enhanced_ui(&request.enhanced_ui)?;
+
     if let Some(flags) = &request.trippy_flags {
         let parsed = parse_passthrough_flags(flags)?;
 
@@ -178,14 +208,58 @@ pub fn verify_options(request: &ProbeRequest) -> Result<(), ProbeError> {
     Ok(())
 }
 
+fn verify_enhanced_ui(config: &EnhancedUiConfig) -> Result<(), ProbeError> {
+    let latency = [
+        ("--latency-warn-ms", config.latency_warn_ms),
+        ("--latency-bad-ms", config.latency_bad_ms),
+    ];
+    let loss = [
+        ("--loss-warn-pct", config.loss_warn_pct),
+        ("--loss-bad-pct", config.loss_bad_pct),
+    ];
+
+    for (flag, value) in latency {
+        if !value.is_finite() || value < 0.0 {
+            return Err(ProbeError::InvalidOption(format!(
+                "{flag} must be a non-negative number of milliseconds"
+            )));
+        }
+    }
+    for (flag, value) in loss {
+        if !(0.0..=100.0).contains(&value) {
+            return Err(ProbeError::InvalidOption(format!(
+                "{flag} must be a percentage between 0 and 100"
+            )));
+        }
+    }
+    for [(warn_flag, warn), (bad_flag, bad)] in [latency, loss] {
+        if warn > bad {
+            return Err(ProbeError::InvalidOption(format!(
+                "{warn_flag} ({warn}) must not be above {bad_flag} ({bad})"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
 pub fn build_probe_plan(request: &ProbeRequest) -> Result<ProbePlan, ProbeError> {
     verify_options(request)?;
     let validated_host = validate_target(&request.host)?;
     let trippy_args = build_embedded_trippy_args(request, &validated_host)?;
 
+    let protocol = if request.tcp {
+        ReportProtocol::Tcp
+    } else if request.udp {
+        ReportProtocol::Udp
+    } else {
+        ReportProtocol::Icmp
+    };
+
     Ok(ProbePlan {
         validated_host,
         trippy_args,
+        protocol,
         json_output: request.json_output,
         csv_output_path: request.csv_output_path.clone(),
         ui_mode: request.ui_mode,
@@ -435,12 +509,11 @@ pub fn build_embedded_trippy_args(
 
 pub fn run_embedded_trippy(
     current_exe: &Path,
-    args: &[String],
-    json_output: Option<JsonOutput>,
+    plan: &ProbePlan,
     embedded_env_name: &str,
-    csv_output_path: Option<&Path>,
 ) -> anyhow::Result<ProbeResult> {
-    if json_output.is_some() || csv_output_path.is_some() {
+    let args = &plan.trippy_args;
+    if plan.json_output.is_some() || plan.csv_output_path.is_some() {
         let output = Command::new(current_exe)
             .env(embedded_env_name, "1")
             .args(args.iter().skip(1))
@@ -459,10 +532,12 @@ pub fn run_embedded_trippy(
         let trippy_value: serde_json::Value =
             serde_json::from_slice(&output.stdout).context("failed to parse trippy JSON output")?;
 
-        if let Some(path) = csv_output_path {
+        if let Some(path) = &plan.csv_output_path {
             write_csv_report(path, &trippy_value)?;
-        } else if let Some(format) = json_output {
-            let value = with_schema_version(trippy_value);
+        } else if let Some(format) = plan.json_output {
+            let value =
+                ReportDocument::from_trippy(&trippy_value, &plan.validated_host, plan.protocol)
+                    .context("failed to normalize trippy JSON output")?;
             match format {
                 JsonOutput::Compact => {
                     serde_json::to_writer(std::io::stdout(), &value)
@@ -492,14 +567,41 @@ pub fn run_embedded_trippy(
     })
 }
 
-fn with_schema_version(mut value: serde_json::Value) -> serde_json::Value {
-    if let Some(object) = value.as_object_mut() {
-        object.insert(
-            "schema_version".to_string(),
-            serde_json::Value::String(CLI_JSON_SCHEMA_VERSION.to_string()),
-        );
-    }
-    value
+/// Output of an embedded trippy run whose JSON report is kept in memory instead of printed.
+#[derive(Debug, Clone)]
+pub struct CapturedProbeOutput {
+    pub exit_code: i32,
+    pub report: Option<serde_json::Value>,
+}
+
+/// Run the embedded trippy runner and keep its JSON report. The child is killed if the returned
+/// future is dropped, so a timed-out or cancelled probe does not leave the runner behind.
+pub async fn capture_embedded_trippy_json(
+    current_exe: &Path,
+    args: &[String],
+    embedded_env_name: &str,
+) -> anyhow::Result<CapturedProbeOutput> {
+    let output = tokio::process::Command::new(current_exe)
+        .env(embedded_env_name, "1")
+        .args(args.iter().skip(1))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("failed to launch embedded trippy runner")?;
+
+    let report = if output.status.success() {
+        serde_json::from_slice(&output.stdout).ok()
+    } else {
+        None
+    };
+
+    Ok(CapturedProbeOutput {
+        exit_code: output.status.code().unwrap_or(2),
+        report,
+    })
 }
 
 fn split_host_parts(host: &str) -> (String, String) {
@@ -571,13 +673,6 @@ mod tests {
     use super::*;
     use serde_json::json;
 
-    #[test]
-    fn schema_version_is_added_to_json_output() {
-        let input = json!({"report":{"hops":[]}});
-        let output = with_schema_version(input);
-        assert_eq!(output["schema_version"], CLI_JSON_SCHEMA_VERSION);
-    }
-
     #[test]
     fn csv_writer_outputs_expected_header_and_values() {
         let fixture = json!({
@@ -0,0 +1,98 @@
+use std::sync::OnceLock;
+
+/// OpenAPI contract shipped with the binary; `docs/api/openapi.yaml` is the single source.
+pub const OPENAPI_YAML: &str = include_str!("../../docs/api/openapi.yaml");
+
+/// Self-contained API explorer page served at [`DOCS_PATH`].
+pub const EXPLORER_HTML: &str = include_str!("openapi_explorer.html");
+
+pub const HEALTH_PATH: &str = "/api/v1/health";
+pub const READY_PATH: &str = "/api/v1/ready";
+pub const DIAGNOSTICS_PATH: &str = "/api/v1/diagnostics";
+pub const PROBES_PATH: &str = "/api/v1/probes";
+pub const PROBE_PATH: &str = "/api/v1/probes/{id}";
+pub const PROBE_DIFF_PATH: &str = "/api/v1/probes/{id}/diff/{other_id}";
+pub const OPENAPI_JSON_PATH: &str = "/api/v1/openapi.json";
+pub const OPENAPI_YAML_PATH: &str = "/api/v1/openapi.yaml";
+pub const DOCS_PATH: &str = "/api/v1/docs";
+pub const ADMIN_RELOAD_PATH: &str = "/api/v1/admin/reload";
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ApiRoute {
+    pub method: &'static str,
+    pub path: &'static str,
+}
+
+/// Every route mounted by `build_router`, which mounts routes only from this list. Contract
+/// tests compare it against the `paths` section of [`OPENAPI_YAML`] so the two cannot drift
+/// apart.
+pub const API_ROUTES: &[ApiRoute] = &[
+    ApiRoute {
+        method: "get",
+        path: HEALTH_PATH,
+    },
+    ApiRoute {
+        method: "get",
+        path: READY_PATH,
+    },
+    ApiRoute {
+        method: "get",
+        path: DIAGNOSTICS_PATH,
+    },
+    ApiRoute {
+        method: "post",
+        path: PROBES_PATH,
+    },
+    ApiRoute {
+        method: "get",
+        path: PROBE_PATH,
+    },
+    ApiRoute {
+        method: "get",
+        path: PROBE_DIFF_PATH,
+    },
+    ApiRoute {
+        method: "get",
+        path: OPENAPI_JSON_PATH,
+    },
+    ApiRoute {
+        method: "get",
+        path: OPENAPI_YAML_PATH,
+    },
+    ApiRoute {
+        method: "get",
+        path: DOCS_PATH,
+    },
+    ApiRoute {
+        method: "post",
+        path: ADMIN_RELOAD_PATH,
+    },
+];
+
+/// The embedded contract converted to JSON once per process.
+pub fn openapi_json() -> &'static serde_json::Value {
+    static DOCUMENT: OnceLock<serde_json::Value> = OnceLock::new();
+    DOCUMENT.get_or_init(|| {
+        serde_yaml::from_str(OPENAPI_YAML).expect("embedded openapi.yaml must be valid yaml")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedded_document_parses_and_lists_every_route() {
+        let document = openapi_json();
+        assert_eq!(document["openapi"], "3.1.0");
+
+        for route in API_ROUTES {
+            assert!(
+                document["paths"][route.path][route.method].is_object(),
+                "{} {} is missing from openapi.yaml",
+                route.method,
+                route.path
+            );
+        }
+    }
+}
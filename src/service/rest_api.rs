@@ -1,17 +1,31 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 
+use reqwest::Url;
+
+use crate::service::target_policy::{IpCidr, TargetPolicyConfig};
+use crate::service::webhooks::{PinnedCallback, WebhookConfig, parse_webhook_url};
+
 const MAX_HOSTNAME_LEN: usize = 253;
 const MAX_LABEL_LEN: usize = 63;
+const MAX_INTERFACE_NAME_LEN: usize = 64;
 pub const MAX_API_PROBE_COUNT: usize = 100;
 pub const MIN_API_PROBE_INTERVAL_SECONDS: f32 = 0.01;
 pub const MAX_API_PROBE_INTERVAL_SECONDS: f32 = 60.0;
 pub const MIN_API_PROBE_TIMEOUT_SECONDS: f32 = 0.01;
 pub const MAX_API_PROBE_TIMEOUT_SECONDS: f32 = 60.0;
 pub const MAX_API_PROBE_EXECUTION_TIMEOUT: Duration = Duration::from_secs(300);
+pub const MAX_API_SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(600);
+/// Principal of callers using the unnamed `api_key`.
+pub const API_KEY_PRINCIPAL: &str = "api-key";
+pub const MIN_API_SOURCE_PORT: u16 = 1024;
+pub const MIN_API_PACKET_SIZE: u16 = 28;
+pub const MAX_API_PACKET_SIZE: u16 = 1024;
+pub const MAX_API_DNS_CACHE_TTL_SECONDS: u64 = 3600;
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum AuthStrategy {
@@ -27,22 +41,66 @@ pub enum ProbeProtocol {
     Udp,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ProbeEcmpStrategy {
+    Classic,
+    Paris,
+    Dublin,
+}
+
+impl ProbeEcmpStrategy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Classic => "classic",
+            Self::Paris => "paris",
+            Self::Dublin => "dublin",
+        }
+    }
+}
+
+/// An API key whose callers are identified as principal `name`, so per-principal target
+/// policies, the admin scope, and fair queueing can tell keys apart.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NamedApiKey {
+    pub name: String,
+    pub key: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct RestApiConfig {
     pub bind_addr: SocketAddr,
     pub allow_non_local_bind: bool,
     pub auth_strategy: AuthStrategy,
+    /// Unnamed key; its callers share the `api-key` principal.
     pub api_key: Option<String>,
+    /// Additional keys, each identifying its callers as its own principal.
+    pub named_api_keys: Vec<NamedApiKey>,
     pub request_timeout: Duration,
     pub max_concurrent_probes: usize,
+    /// Accepted jobs that may wait for a free probe slot before new submissions get `503`.
+    pub max_queued_probes: usize,
+    /// Start the next job from the principal with the fewest running probes instead of strict FIFO.
+    pub fair_queueing: bool,
     pub max_requests_per_window: usize,
     pub rate_limit_window: Duration,
     pub max_targets_per_request: usize,
     pub max_payload_bytes: usize,
     pub max_completed_jobs: usize,
     pub completed_job_ttl: Duration,
+    /// How long an `Idempotency-Key` stays bound to the job it created.
+    pub idempotency_key_ttl: Duration,
     pub probe_execution_timeout: Duration,
+    /// How long running probes may keep going after a shutdown signal before they are cancelled.
+    pub shutdown_grace_period: Duration,
     pub trusted_mtls_ingress_ips: Vec<IpAddr>,
+    pub target_policy: TargetPolicyConfig,
+    /// Source addresses callers may bind probes to via `src`; empty rejects the option.
+    pub allowed_source_addresses: Vec<IpCidr>,
+    /// Interface names callers may select via `interface`; empty rejects the option.
+    pub allowed_interfaces: Vec<String>,
+    pub webhooks: WebhookConfig,
+    /// Principals granted the `admin` scope (config reload); empty disables admin endpoints.
+    pub admin_principals: Vec<String>,
 }
 
 impl Default for RestApiConfig {
@@ -52,24 +110,124 @@ impl Default for RestApiConfig {
             allow_non_local_bind: false,
             auth_strategy: AuthStrategy::NoneLocalOnly,
             api_key: None,
+            named_api_keys: Vec::new(),
             request_timeout: Duration::from_secs(10),
             max_concurrent_probes: 8,
+            max_queued_probes: 64,
+            fair_queueing: false,
             max_requests_per_window: 8,
             rate_limit_window: Duration::from_secs(10),
             max_targets_per_request: 8,
             max_payload_bytes: 16 * 1024,
             max_completed_jobs: 1024,
             completed_job_ttl: Duration::from_secs(15 * 60),
+            idempotency_key_ttl: Duration::from_secs(60 * 60),
             probe_execution_timeout: Duration::from_secs(60),
+            shutdown_grace_period: Duration::from_secs(30),
             trusted_mtls_ingress_ips: vec![
                 IpAddr::from([127, 0, 0, 1]),
                 "::1".parse().expect("valid localhost ipv6 literal"),
             ],
+            target_policy: TargetPolicyConfig::default(),
+            allowed_source_addresses: Vec::new(),
+            allowed_interfaces: Vec::new(),
+            webhooks: WebhookConfig::default(),
+            admin_principals: Vec::new(),
         }
     }
 }
 
 impl RestApiConfig {
+    /// Names of the settings that differ from `other`; secret values are never included.
+    pub fn changed_fields(&self, other: &Self) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        let mut check = |name: &'static str, differs: bool| {
+            if differs {
+                changed.push(name);
+            }
+        };
+
+        check("bind_addr", self.bind_addr != other.bind_addr);
+        check(
+            "allow_non_local_bind",
+            self.allow_non_local_bind != other.allow_non_local_bind,
+        );
+        check("auth_strategy", self.auth_strategy != other.auth_strategy);
+        check("api_key", self.api_key != other.api_key);
+        check(
+            "named_api_keys",
+            self.named_api_keys != other.named_api_keys,
+        );
+        check(
+            "request_timeout",
+            self.request_timeout != other.request_timeout,
+        );
+        check(
+            "max_concurrent_probes",
+            self.max_concurrent_probes != other.max_concurrent_probes,
+        );
+        check(
+            "max_queued_probes",
+            self.max_queued_probes != other.max_queued_probes,
+        );
+        check("fair_queueing", self.fair_queueing != other.fair_queueing);
+        check(
+            "max_requests_per_window",
+            self.max_requests_per_window != other.max_requests_per_window,
+        );
+        check(
+            "rate_limit_window",
+            self.rate_limit_window != other.rate_limit_window,
+        );
+        check(
+            "max_targets_per_request",
+            self.max_targets_per_request != other.max_targets_per_request,
+        );
+        check(
+            "max_payload_bytes",
+            self.max_payload_bytes != other.max_payload_bytes,
+        );
+        check(
+            "max_completed_jobs",
+            self.max_completed_jobs != other.max_completed_jobs,
+        );
+        check(
+            "completed_job_ttl",
+            self.completed_job_ttl != other.completed_job_ttl,
+        );
+        check(
+            "idempotency_key_ttl",
+            self.idempotency_key_ttl != other.idempotency_key_ttl,
+        );
+        check(
+            "probe_execution_timeout",
+            self.probe_execution_timeout != other.probe_execution_timeout,
+        );
+        check(
+            "shutdown_grace_period",
+            self.shutdown_grace_period != other.shutdown_grace_period,
+        );
+        check(
+            "trusted_mtls_ingress_ips",
+            self.trusted_mtls_ingress_ips != other.trusted_mtls_ingress_ips,
+        );
+        check("target_policy", self.target_policy != other.target_policy);
+        check(
+            "allowed_source_addresses",
+            self.allowed_source_addresses != other.allowed_source_addresses,
+        );
+        check(
+            "allowed_interfaces",
+            self.allowed_interfaces != other.allowed_interfaces,
+        );
+        check("webhooks", self.webhooks != other.webhooks);
+        check(
+            "admin_principals",
+            self.admin_principals != other.admin_principals,
+        );
+        changed
+    }
+
     pub fn validate_security_defaults(&self) -> Result<(), RestApiValidationError> {
         if !self.allow_non_local_bind && !self.bind_addr.ip().is_loopback() {
             return Err(RestApiValidationError::NonLocalBindRequiresOptIn(
@@ -122,6 +280,11 @@ impl RestApiConfig {
                 "completed_job_ttl must be greater than zero".to_string(),
             ));
         }
+        if self.idempotency_key_ttl.is_zero() {
+            return Err(RestApiValidationError::InvalidOption(
+                "idempotency_key_ttl must be greater than zero".to_string(),
+            ));
+        }
 
         if self.probe_execution_timeout.is_zero() {
             return Err(RestApiValidationError::InvalidOption(
@@ -134,6 +297,16 @@ impl RestApiConfig {
                 MAX_API_PROBE_EXECUTION_TIMEOUT.as_secs()
             )));
         }
+        if self.shutdown_grace_period > MAX_API_SHUTDOWN_GRACE_PERIOD {
+            return Err(RestApiValidationError::InvalidOption(format!(
+                "shutdown_grace_period must not exceed {} seconds",
+                MAX_API_SHUTDOWN_GRACE_PERIOD.as_secs()
+            )));
+        }
+
+        self.webhooks
+            .validate()
+            .map_err(|error| RestApiValidationError::InvalidOption(error.to_string()))?;
 
         if self.auth_strategy == AuthStrategy::NoneLocalOnly && !self.bind_addr.ip().is_loopback() {
             return Err(RestApiValidationError::AuthStrategyViolation(
@@ -146,12 +319,41 @@ impl RestApiConfig {
                 .api_key
                 .as_ref()
                 .is_none_or(|key| key.trim().is_empty())
+            && self.named_api_keys.is_empty()
         {
             return Err(RestApiValidationError::AuthStrategyViolation(
                 "auth_strategy=api-key requires a non-empty api_key".to_string(),
             ));
         }
 
+        let mut names = HashSet::new();
+        let mut keys: HashSet<&str> = self.api_key.as_deref().into_iter().collect();
+        for named in &self.named_api_keys {
+            if named.name.trim().is_empty() || named.name == API_KEY_PRINCIPAL {
+                return Err(RestApiValidationError::AuthStrategyViolation(format!(
+                    "named API keys need a non-empty name other than `{API_KEY_PRINCIPAL}`"
+                )));
+            }
+            if named.key.trim().is_empty() {
+                return Err(RestApiValidationError::AuthStrategyViolation(format!(
+                    "named API key `{}` is empty",
+                    named.name
+                )));
+            }
+            if !names.insert(named.name.as_str()) {
+                return Err(RestApiValidationError::AuthStrategyViolation(format!(
+                    "named API key `{}` is configured more than once",
+                    named.name
+                )));
+            }
+            if !keys.insert(named.key.as_str()) {
+                return Err(RestApiValidationError::AuthStrategyViolation(format!(
+                    "named API key `{}` reuses another key's value",
+                    named.name
+                )));
+            }
+        }
+
         if self.auth_strategy == AuthStrategy::Mtls && self.trusted_mtls_ingress_ips.is_empty() {
             return Err(RestApiValidationError::AuthStrategyViolation(
                 "auth_strategy=mtls requires at least one trusted ingress IP".to_string(),
@@ -173,6 +375,13 @@ pub struct CreateProbeApiRequest {
     pub include_asn: Option<bool>,
     pub interval_seconds: Option<f32>,
     pub timeout_seconds: Option<f32>,
+    pub source_port: Option<u16>,
+    pub packet_size: Option<u16>,
+    pub src: Option<IpAddr>,
+    pub interface: Option<String>,
+    pub ecmp: Option<ProbeEcmpStrategy>,
+    pub dns_cache_ttl_seconds: Option<u64>,
+    pub callback_url: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -186,6 +395,29 @@ pub struct NormalizedCreateProbeRequest {
     pub include_asn: bool,
     pub interval_seconds: Option<f32>,
     pub timeout_seconds: Option<f32>,
+    pub source_port: Option<u16>,
+    pub packet_size: Option<u16>,
+    pub src: Option<IpAddr>,
+    pub interface: Option<String>,
+    pub ecmp: Option<ProbeEcmpStrategy>,
+    pub dns_cache_ttl_seconds: Option<u64>,
+    pub callback_url: Option<Url>,
+    /// Address `callback_url`'s host was checked at; set by the server once the host passes
+    /// the callback checks, and the only address deliveries connect to.
+    pub callback_address: Option<IpAddr>,
+    /// Address each hostname target was checked at by a restrictive target policy. Probes run
+    /// against this address rather than resolving the name again, so a name that passed the
+    /// policy cannot be rebound to a forbidden address before the probe starts.
+    pub target_addresses: HashMap<String, IpAddr>,
+}
+
+impl NormalizedCreateProbeRequest {
+    /// The callback to notify, once its host has been checked and pinned.
+    pub fn pinned_callback(&self) -> Option<PinnedCallback> {
+        let url = self.callback_url.clone()?;
+        let address = self.callback_address?;
+        Some(PinnedCallback { url, address })
+    }
 }
 
 impl CreateProbeApiRequest {
@@ -262,6 +494,15 @@ impl CreateProbeApiRequest {
             ));
         }
 
+        validate_optional_ecmp(self.protocol, self.ecmp)?;
+        let source_port =
+            validate_optional_source_port(self.protocol, self.ecmp, self.source_port)?;
+        let packet_size = validate_optional_packet_size(self.packet_size)?;
+        let dns_cache_ttl_seconds = validate_optional_dns_cache_ttl(self.dns_cache_ttl_seconds)?;
+        let src = validate_optional_src(self.src, config)?;
+        let interface = validate_optional_interface(self.interface, config)?;
+        let callback_url = validate_optional_callback_url(self.callback_url, config)?;
+
         let mut normalized_targets = Vec::with_capacity(self.targets.len());
         let mut dedupe = HashSet::with_capacity(self.targets.len());
 
@@ -282,6 +523,15 @@ impl CreateProbeApiRequest {
             include_asn,
             interval_seconds,
             timeout_seconds,
+            source_port,
+            packet_size,
+            src,
+            interface,
+            ecmp: self.ecmp,
+            dns_cache_ttl_seconds,
+            callback_url,
+            callback_address: None,
+            target_addresses: HashMap::new(),
         })
     }
 }
@@ -333,6 +583,157 @@ fn validate_optional_bounded_positive(
     Ok(Some(raw))
 }
 
+fn validate_optional_ecmp(
+    protocol: ProbeProtocol,
+    ecmp: Option<ProbeEcmpStrategy>,
+) -> Result<(), RestApiValidationError> {
+    match ecmp {
+        Some(strategy @ (ProbeEcmpStrategy::Paris | ProbeEcmpStrategy::Dublin))
+            if protocol != ProbeProtocol::Udp =>
+        {
+            Err(RestApiValidationError::InvalidOption(format!(
+                "ecmp={} is only supported for udp probes",
+                strategy.as_str()
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_optional_source_port(
+    protocol: ProbeProtocol,
+    ecmp: Option<ProbeEcmpStrategy>,
+    value: Option<u16>,
+) -> Result<Option<u16>, RestApiValidationError> {
+    let Some(raw) = value else {
+        return Ok(None);
+    };
+
+    if raw < MIN_API_SOURCE_PORT {
+        return Err(RestApiValidationError::InvalidPort(format!(
+            "source_port must be between {MIN_API_SOURCE_PORT} and 65535"
+        )));
+    }
+
+    // API probes always fix the destination port for tcp/udp, and trippy only allows fixing
+    // both ends for udp with the paris or dublin strategy.
+    let fixed_both_supported = protocol == ProbeProtocol::Udp
+        && matches!(
+            ecmp,
+            Some(ProbeEcmpStrategy::Paris | ProbeEcmpStrategy::Dublin)
+        );
+    if !fixed_both_supported {
+        return Err(RestApiValidationError::InvalidPort(
+            "source_port requires a udp probe with ecmp=paris or ecmp=dublin".to_string(),
+        ));
+    }
+
+    Ok(Some(raw))
+}
+
+fn validate_optional_packet_size(
+    value: Option<u16>,
+) -> Result<Option<u16>, RestApiValidationError> {
+    let Some(raw) = value else {
+        return Ok(None);
+    };
+
+    if !(MIN_API_PACKET_SIZE..=MAX_API_PACKET_SIZE).contains(&raw) {
+        return Err(RestApiValidationError::InvalidOption(format!(
+            "packet_size must be between {MIN_API_PACKET_SIZE} and {MAX_API_PACKET_SIZE} bytes"
+        )));
+    }
+
+    Ok(Some(raw))
+}
+
+fn validate_optional_dns_cache_ttl(
+    value: Option<u64>,
+) -> Result<Option<u64>, RestApiValidationError> {
+    let Some(raw) = value else {
+        return Ok(None);
+    };
+
+    if !(1..=MAX_API_DNS_CACHE_TTL_SECONDS).contains(&raw) {
+        return Err(RestApiValidationError::InvalidOption(format!(
+            "dns_cache_ttl_seconds must be between 1 and {MAX_API_DNS_CACHE_TTL_SECONDS}"
+        )));
+    }
+
+    Ok(Some(raw))
+}
+
+fn validate_optional_src(
+    value: Option<IpAddr>,
+    config: &RestApiConfig,
+) -> Result<Option<IpAddr>, RestApiValidationError> {
+    let Some(src) = value else {
+        return Ok(None);
+    };
+
+    if !config
+        .allowed_source_addresses
+        .iter()
+        .any(|cidr| cidr.contains(src))
+    {
+        return Err(RestApiValidationError::OptionForbidden(format!(
+            "src {src} is not in the server's allowed source addresses"
+        )));
+    }
+
+    Ok(Some(src))
+}
+
+fn validate_optional_interface(
+    value: Option<String>,
+    config: &RestApiConfig,
+) -> Result<Option<String>, RestApiValidationError> {
+    let Some(raw) = value else {
+        return Ok(None);
+    };
+
+    let interface = raw.trim();
+    if interface.is_empty()
+        || interface.len() > MAX_INTERFACE_NAME_LEN
+        || interface.chars().any(|ch| ch.is_control())
+    {
+        return Err(RestApiValidationError::InvalidOption(format!(
+            "interface must be 1 to {MAX_INTERFACE_NAME_LEN} printable characters"
+        )));
+    }
+
+    if !config
+        .allowed_interfaces
+        .iter()
+        .any(|allowed| allowed == interface)
+    {
+        return Err(RestApiValidationError::OptionForbidden(format!(
+            "interface `{interface}` is not in the server's allowed interfaces"
+        )));
+    }
+
+    Ok(Some(interface.to_string()))
+}
+
+fn validate_optional_callback_url(
+    value: Option<String>,
+    config: &RestApiConfig,
+) -> Result<Option<Url>, RestApiValidationError> {
+    let Some(raw) = value else {
+        return Ok(None);
+    };
+
+    if !config.webhooks.allow_callback_urls {
+        return Err(RestApiValidationError::OptionForbidden(
+            "callback_url is disabled on this server".to_string(),
+        ));
+    }
+
+    parse_webhook_url(&raw)
+        .map(Some)
+        .map_err(|error| RestApiValidationError::InvalidOption(format!("callback_url: {error}")))
+}
+
 fn normalize_target(raw: String) -> Result<String, RestApiValidationError> {
     let target = raw.trim();
     if target.is_empty() {
@@ -397,12 +798,31 @@ pub fn validate_payload_size(
     Ok(())
 }
 
+/// Keys are opaque to the server but must be printable ASCII so they are safe to log.
+pub fn validate_idempotency_key(key: &str) -> Result<(), RestApiValidationError> {
+    if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+        return Err(RestApiValidationError::InvalidIdempotencyKey(format!(
+            "key must be 1-{MAX_IDEMPOTENCY_KEY_LEN} characters"
+        )));
+    }
+    if !key.bytes().all(|byte| byte.is_ascii_graphic()) {
+        return Err(RestApiValidationError::InvalidIdempotencyKey(
+            "key must contain only printable ASCII characters without spaces".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// No longer used by the server, which limits running probes with [`ProbeWorkQueue`].
+#[deprecated(note = "the REST server queues jobs with `ProbeWorkQueue` instead")]
 #[derive(Debug)]
 pub struct ProbeConcurrencyGate {
     in_flight: AtomicUsize,
     limit: usize,
 }
 
+#[allow(deprecated)]
 impl ProbeConcurrencyGate {
     pub fn new(limit: usize) -> Result<Self, RestApiValidationError> {
         if limit == 0 {
@@ -433,16 +853,195 @@ impl ProbeConcurrencyGate {
     }
 }
 
+#[deprecated(note = "the REST server queues jobs with `ProbeWorkQueue` instead")]
+#[allow(deprecated)]
 pub struct ProbeConcurrencyPermit<'a> {
     gate: &'a ProbeConcurrencyGate,
 }
 
+#[allow(deprecated)]
 impl Drop for ProbeConcurrencyPermit<'_> {
     fn drop(&mut self) {
         self.gate.in_flight.fetch_sub(1, Ordering::AcqRel);
     }
 }
 
+#[derive(Debug)]
+pub struct QueuedProbe<T> {
+    pub id: String,
+    pub principal: String,
+    pub payload: T,
+}
+
+/// Bounded queue of accepted probe jobs waiting for one of `max_running` execution slots.
+///
+/// Jobs start in submission order. With fair queueing, the next job instead comes from the
+/// principal with the fewest running probes, so one bursty caller cannot starve the others.
+#[derive(Debug)]
+pub struct ProbeWorkQueue<T> {
+    max_running: usize,
+    max_depth: usize,
+    fair: bool,
+    waiting: VecDeque<QueuedProbe<T>>,
+    running_by_principal: HashMap<String, usize>,
+    running: usize,
+}
+
+impl<T> ProbeWorkQueue<T> {
+    pub fn new(
+        max_running: usize,
+        max_depth: usize,
+        fair: bool,
+    ) -> Result<Self, RestApiValidationError> {
+        if max_running == 0 {
+            return Err(RestApiValidationError::InvalidConcurrencyLimit(
+                "concurrency limit must be >= 1".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            max_running,
+            max_depth,
+            fair,
+            waiting: VecDeque::new(),
+            running_by_principal: HashMap::new(),
+            running: 0,
+        })
+    }
+
+    /// Accept a job unless every slot is busy and `max_depth` jobs are already waiting.
+    pub fn enqueue(
+        &mut self,
+        id: String,
+        principal: String,
+        payload: T,
+    ) -> Result<(), RestApiValidationError> {
+        let free_slots = self.max_running.saturating_sub(self.running);
+        if self.waiting.len() >= self.max_depth + free_slots {
+            return Err(RestApiValidationError::QueueFull {
+                depth: self.max_depth,
+            });
+        }
+
+        self.waiting.push_back(QueuedProbe {
+            id,
+            principal,
+            payload,
+        });
+        Ok(())
+    }
+
+    /// Claim a slot for every job that can start now; callers must `finish` each one.
+    pub fn start_ready(&mut self) -> Vec<QueuedProbe<T>> {
+        let mut started = Vec::new();
+        while self.running < self.max_running {
+            let Some(index) = self.next_index() else {
+                break;
+            };
+            let Some(job) = self.waiting.remove(index) else {
+                break;
+            };
+            self.running += 1;
+            *self
+                .running_by_principal
+                .entry(job.principal.clone())
+                .or_default() += 1;
+            started.push(job);
+        }
+        started
+    }
+
+    pub fn finish(&mut self, principal: &str) {
+        self.running = self.running.saturating_sub(1);
+        if let Some(count) = self.running_by_principal.get_mut(principal) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                self.running_by_principal.remove(principal);
+            }
+        }
+    }
+
+    /// 1-based place of a waiting job in start order, or `None` once it has started.
+    pub fn position(&self, id: &str) -> Option<usize> {
+        if !self.fair {
+            return self
+                .waiting
+                .iter()
+                .position(|job| job.id == id)
+                .map(|index| index + 1);
+        }
+
+        let mut running = self.running_by_principal.clone();
+        let mut remaining = (0..self.waiting.len()).collect::<Vec<_>>();
+        let mut position = 0;
+        while !remaining.is_empty() {
+            position += 1;
+            let index = self.fair_index(&running, remaining.iter().copied())?;
+            let job = &self.waiting[index];
+            if job.id == id {
+                return Some(position);
+            }
+            *running.entry(job.principal.clone()).or_default() += 1;
+            remaining.retain(|candidate| *candidate != index);
+        }
+        None
+    }
+
+    /// Apply new limits without touching waiting or running jobs. Lowering `max_running` lets
+    /// running jobs finish; new jobs start once the running count drops below the new limit.
+    pub fn reconfigure(
+        &mut self,
+        max_running: usize,
+        max_depth: usize,
+        fair: bool,
+    ) -> Result<(), RestApiValidationError> {
+        if max_running == 0 {
+            return Err(RestApiValidationError::InvalidConcurrencyLimit(
+                "concurrency limit must be >= 1".to_string(),
+            ));
+        }
+
+        self.max_running = max_running;
+        self.max_depth = max_depth;
+        self.fair = fair;
+        Ok(())
+    }
+
+    /// Remove every job that has not started yet, in queue order.
+    pub fn drain_waiting(&mut self) -> Vec<QueuedProbe<T>> {
+        self.waiting.drain(..).collect()
+    }
+
+    pub fn waiting_len(&self) -> usize {
+        self.waiting.len()
+    }
+
+    pub fn running_len(&self) -> usize {
+        self.running
+    }
+
+    fn next_index(&self) -> Option<usize> {
+        if self.waiting.is_empty() {
+            return None;
+        }
+        if !self.fair {
+            return Some(0);
+        }
+        self.fair_index(&self.running_by_principal, 0..self.waiting.len())
+    }
+
+    fn fair_index(
+        &self,
+        running: &HashMap<String, usize>,
+        candidates: impl Iterator<Item = usize>,
+    ) -> Option<usize> {
+        candidates.min_by_key(|index| {
+            let principal = &self.waiting[*index].principal;
+            (running.get(principal).copied().unwrap_or_default(), *index)
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct FixedWindowRateLimiter {
     max_requests: usize,
@@ -495,19 +1094,41 @@ impl FixedWindowRateLimiter {
         })
     }
 
+    /// Change the limits without starting a new window: requests already counted in the
+    /// current window still count against the new limit.
+    pub fn reconfigure(
+        &mut self,
+        max_requests: usize,
+        window: Duration,
+    ) -> Result<(), RestApiValidationError> {
+        let reconfigured = Self::new(max_requests, window, self.window_started_at)?;
+        *self = Self {
+            count: self.count,
+            ..reconfigured
+        };
+        Ok(())
+    }
+
     pub fn allow(&mut self, now: Instant) -> Result<(), RestApiValidationError> {
         if now.duration_since(self.window_started_at) >= self.window {
             self.window_started_at = now;
             self.count = 0;
         }
 
-        if self.count >= self.max_requests {
+        self.check(now)?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Whether [`Self::allow`] would let a request through, without counting one.
+    pub fn check(&self, now: Instant) -> Result<(), RestApiValidationError> {
+        if now.duration_since(self.window_started_at) < self.window
+            && self.count >= self.max_requests
+        {
             return Err(RestApiValidationError::RateLimitExceeded {
                 max_requests: self.max_requests,
             });
         }
-
-        self.count += 1;
         Ok(())
     }
 
@@ -532,6 +1153,9 @@ pub enum RestApiValidationError {
     #[error("invalid target: {0}")]
     InvalidTarget(String),
 
+    #[error("target forbidden by policy: {0}")]
+    TargetForbidden(String),
+
     #[error("invalid port: {0}")]
     InvalidPort(String),
 
@@ -541,6 +1165,18 @@ pub enum RestApiValidationError {
     #[error("invalid option: {0}")]
     InvalidOption(String),
 
+    #[error("option forbidden by policy: {0}")]
+    OptionForbidden(String),
+
+    #[error("invalid Idempotency-Key: {0}")]
+    InvalidIdempotencyKey(String),
+
+    #[error("Idempotency-Key reused with a different request: {0}")]
+    IdempotencyKeyMismatch(String),
+
+    #[error("Idempotency-Key conflict: {0}")]
+    IdempotencyKeyConflict(String),
+
     #[error("too many targets in request: provided {provided}, maximum {limit}")]
     TooManyTargets { provided: usize, limit: usize },
 
@@ -550,6 +1186,9 @@ pub enum RestApiValidationError {
     #[error("concurrency limit exceeded: max {limit} in-flight probes")]
     ConcurrencyLimitExceeded { limit: usize },
 
+    #[error("probe queue is full: max {depth} waiting probes")]
+    QueueFull { depth: usize },
+
     #[error("rate limit exceeded: max {max_requests} requests per window")]
     RateLimitExceeded { max_requests: usize },
 
@@ -609,6 +1248,13 @@ mod tests {
             include_asn: None,
             interval_seconds: Some(1.0),
             timeout_seconds: Some(2.0),
+            source_port: None,
+            packet_size: None,
+            src: None,
+            interface: None,
+            ecmp: None,
+            dns_cache_ttl_seconds: None,
+            callback_url: None,
         };
 
         let normalized = request
@@ -630,6 +1276,13 @@ mod tests {
             include_asn: None,
             interval_seconds: None,
             timeout_seconds: None,
+            source_port: None,
+            packet_size: None,
+            src: None,
+            interface: None,
+            ecmp: None,
+            dns_cache_ttl_seconds: None,
+            callback_url: None,
         };
 
         assert!(matches!(
@@ -650,6 +1303,13 @@ mod tests {
             include_asn: None,
             interval_seconds: None,
             timeout_seconds: None,
+            source_port: None,
+            packet_size: None,
+            src: None,
+            interface: None,
+            ecmp: None,
+            dns_cache_ttl_seconds: None,
+            callback_url: None,
         };
 
         assert!(matches!(
@@ -658,6 +1318,89 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn normalize_validates_advanced_probe_options() {
+        let base = CreateProbeApiRequest {
+            targets: vec!["1.1.1.1".to_string()],
+            protocol: ProbeProtocol::Udp,
+            port: Some(33434),
+            count: None,
+            max_hops: None,
+            resolve_dns: None,
+            include_asn: None,
+            interval_seconds: None,
+            timeout_seconds: None,
+            source_port: Some(40000),
+            packet_size: Some(64),
+            src: Some("10.0.0.5".parse().unwrap()),
+            interface: Some(" eth0 ".to_string()),
+            ecmp: Some(ProbeEcmpStrategy::Paris),
+            dns_cache_ttl_seconds: Some(60),
+            callback_url: None,
+        };
+        let config = RestApiConfig {
+            allowed_source_addresses: vec!["10.0.0.0/24".parse().unwrap()],
+            allowed_interfaces: vec!["eth0".to_string()],
+            ..RestApiConfig::default()
+        };
+
+        let normalized = base
+            .clone()
+            .normalize_and_validate(&config)
+            .expect("request should be valid");
+        assert_eq!(normalized.interface.as_deref(), Some("eth0"));
+        assert_eq!(normalized.source_port, Some(40000));
+
+        let cases = [
+            CreateProbeApiRequest {
+                source_port: Some(80),
+                ..base.clone()
+            },
+            CreateProbeApiRequest {
+                ecmp: Some(ProbeEcmpStrategy::Classic),
+                ..base.clone()
+            },
+            CreateProbeApiRequest {
+                packet_size: Some(MAX_API_PACKET_SIZE + 1),
+                ..base.clone()
+            },
+            CreateProbeApiRequest {
+                dns_cache_ttl_seconds: Some(0),
+                ..base.clone()
+            },
+            CreateProbeApiRequest {
+                protocol: ProbeProtocol::Tcp,
+                source_port: None,
+                ..base.clone()
+            },
+        ];
+        for request in cases {
+            assert!(
+                matches!(
+                    request.clone().normalize_and_validate(&config),
+                    Err(RestApiValidationError::InvalidPort(_)
+                        | RestApiValidationError::InvalidOption(_))
+                ),
+                "{request:?} should be rejected"
+            );
+        }
+
+        assert!(matches!(
+            base.clone()
+                .normalize_and_validate(&RestApiConfig::default()),
+            Err(RestApiValidationError::OptionForbidden(_))
+        ));
+        assert!(matches!(
+            CreateProbeApiRequest {
+                src: None,
+                interface: Some("wlan0".to_string()),
+                ..base
+            }
+            .normalize_and_validate(&config),
+            Err(RestApiValidationError::OptionForbidden(_))
+        ));
+    }
+
     #[test]
     fn oversized_payload_is_rejected() {
         let config = RestApiConfig::default();
@@ -668,6 +1411,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn concurrency_gate_enforces_limit() {
         let gate = ProbeConcurrencyGate::new(1).expect("valid gate");
         let first = gate.try_acquire().expect("first acquisition should pass");
@@ -682,6 +1426,141 @@ mod tests {
         assert!(gate.try_acquire().is_ok());
     }
 
+    #[test]
+    fn named_api_keys_need_distinct_names_and_values() {
+        let named = |name: &str, key: &str| NamedApiKey {
+            name: name.to_string(),
+            key: key.to_string(),
+        };
+        let mut config = RestApiConfig {
+            auth_strategy: AuthStrategy::ApiKey,
+            named_api_keys: vec![named("lab", "lab-key"), named("ci", "ci-key")],
+            ..RestApiConfig::default()
+        };
+        assert!(config.validate_security_defaults().is_ok());
+
+        for invalid in [
+            vec![named("lab", "lab-key"), named("lab", "other-key")],
+            vec![named("lab", "shared"), named("ci", "shared")],
+            vec![named(API_KEY_PRINCIPAL, "key")],
+            vec![named("lab", " ")],
+        ] {
+            config.named_api_keys = invalid;
+            assert!(matches!(
+                config.validate_security_defaults(),
+                Err(RestApiValidationError::AuthStrategyViolation(_))
+            ));
+        }
+    }
+
+    fn started_ids<T>(started: Vec<QueuedProbe<T>>) -> Vec<String> {
+        started.into_iter().map(|job| job.id).collect()
+    }
+
+    #[test]
+    fn work_queue_starts_jobs_fifo_and_rejects_when_full() {
+        let mut queue = ProbeWorkQueue::new(1, 2, false).expect("valid queue");
+        for id in ["a", "b", "c"] {
+            queue
+                .enqueue(id.to_string(), "local".to_string(), ())
+                .expect("slot plus two waiting jobs fit");
+        }
+        assert!(matches!(
+            queue.enqueue("d".to_string(), "local".to_string(), ()),
+            Err(RestApiValidationError::QueueFull { depth: 2 })
+        ));
+
+        assert_eq!(started_ids(queue.start_ready()), ["a"]);
+        assert_eq!(queue.position("a"), None);
+        assert_eq!(queue.position("b"), Some(1));
+        assert_eq!(queue.position("c"), Some(2));
+        assert!(queue.start_ready().is_empty());
+
+        queue.finish("local");
+        assert_eq!(started_ids(queue.start_ready()), ["b"]);
+        assert_eq!(queue.position("c"), Some(1));
+        assert_eq!((queue.running_len(), queue.waiting_len()), (1, 1));
+    }
+
+    #[test]
+    fn fair_work_queue_interleaves_principals() {
+        let mut queue = ProbeWorkQueue::new(1, 8, true).expect("valid queue");
+        for (id, principal) in [("a1", "a"), ("a2", "a"), ("a3", "a"), ("b1", "b")] {
+            queue
+                .enqueue(id.to_string(), principal.to_string(), ())
+                .expect("queue has room");
+        }
+
+        assert_eq!(started_ids(queue.start_ready()), ["a1"]);
+        assert_eq!(queue.position("b1"), Some(1));
+        assert_eq!(queue.position("a2"), Some(2));
+
+        queue.finish("a");
+        assert_eq!(queue.position("a2"), Some(1));
+        assert_eq!(queue.position("b1"), Some(2));
+        assert!(ProbeWorkQueue::<()>::new(0, 1, true).is_err());
+    }
+
+    #[test]
+    fn work_queue_reconfigure_keeps_jobs_and_applies_new_limits() {
+        let mut queue = ProbeWorkQueue::new(1, 1, false).expect("valid queue");
+        for id in ["a", "b"] {
+            queue
+                .enqueue(id.to_string(), "local".to_string(), ())
+                .expect("queue has room");
+        }
+        assert_eq!(started_ids(queue.start_ready()), ["a"]);
+
+        queue.reconfigure(2, 4, false).expect("valid limits");
+        assert_eq!(started_ids(queue.start_ready()), ["b"]);
+        assert_eq!((queue.running_len(), queue.waiting_len()), (2, 0));
+
+        queue.reconfigure(1, 0, false).expect("valid limits");
+        assert!(
+            queue
+                .enqueue("c".to_string(), "local".to_string(), ())
+                .is_err()
+        );
+        queue.finish("local");
+        queue.finish("local");
+        assert!(
+            queue
+                .enqueue("c".to_string(), "local".to_string(), ())
+                .is_ok()
+        );
+        assert!(queue.reconfigure(0, 1, false).is_err());
+    }
+
+    #[test]
+    fn work_queue_drain_waiting_keeps_running_jobs() {
+        let mut queue = ProbeWorkQueue::new(1, 4, false).expect("valid queue");
+        for id in ["a", "b", "c"] {
+            queue
+                .enqueue(id.to_string(), "local".to_string(), ())
+                .expect("queue has room");
+        }
+        assert_eq!(started_ids(queue.start_ready()), ["a"]);
+
+        assert_eq!(started_ids(queue.drain_waiting()), ["b", "c"]);
+        assert_eq!((queue.running_len(), queue.waiting_len()), (1, 0));
+        queue.finish("local");
+        assert!(queue.start_ready().is_empty());
+    }
+
+    #[test]
+    fn changed_fields_lists_setting_names_only() {
+        let current = RestApiConfig::default();
+        assert!(current.changed_fields(&current.clone()).is_empty());
+
+        let candidate = RestApiConfig {
+            max_requests_per_window: 100,
+            api_key: Some("rotated-secret".to_string()),
+            ..RestApiConfig::default()
+        };
+        let changed = current.changed_fields(&candidate);
+        assert_eq!(changed, ["api_key", "max_requests_per_window"]);
+    }
+
     #[test]
     fn fixed_window_rate_limiter_rejects_abusive_burst() {
         let now = Instant::now();
@@ -694,11 +1573,34 @@ mod tests {
             limiter.allow(now),
             Err(RestApiValidationError::RateLimitExceeded { .. })
         ));
+        assert!(limiter.check(now).is_err());
 
         let next_window = now + Duration::from_secs(1);
+        assert!(limiter.check(next_window).is_ok());
         assert!(limiter.allow(next_window).is_ok());
     }
 
+    #[test]
+    fn reconfiguring_the_rate_limiter_keeps_the_current_window() {
+        let now = Instant::now();
+        let mut limiter =
+            FixedWindowRateLimiter::new(2, Duration::from_secs(60), now).expect("valid limiter");
+        limiter.allow(now).expect("first request");
+        limiter.allow(now).expect("second request");
+
+        limiter
+            .reconfigure(3, Duration::from_secs(30))
+            .expect("valid limits");
+        let later = now + Duration::from_secs(10);
+        assert_eq!(limiter.snapshot(later).remaining, 1);
+        assert_eq!(limiter.snapshot(later).reset_after, Duration::from_secs(20));
+        assert!(limiter.allow(later).is_ok());
+        assert!(limiter.allow(later).is_err());
+
+        assert!(limiter.reconfigure(0, Duration::from_secs(30)).is_err());
+        assert_eq!(limiter.snapshot(later).limit, 3);
+    }
+
     #[test]
     fn target_limit_is_enforced() {
         let request = CreateProbeApiRequest {
@@ -721,6 +1623,13 @@ mod tests {
             include_asn: None,
             interval_seconds: None,
             timeout_seconds: None,
+            source_port: None,
+            packet_size: None,
+            src: None,
+            interface: None,
+            ecmp: None,
+            dns_cache_ttl_seconds: None,
+            callback_url: None,
         };
 
         assert!(matches!(
@@ -746,6 +1655,35 @@ mod tests {
             config.validate_security_defaults(),
             Err(RestApiValidationError::InvalidOption(_))
         ));
+
+        config.completed_job_ttl = Duration::from_secs(1);
+        config.idempotency_key_ttl = Duration::ZERO;
+        assert!(matches!(
+            config.validate_security_defaults(),
+            Err(RestApiValidationError::InvalidOption(_))
+        ));
+    }
+
+    #[test]
+    fn idempotency_keys_must_be_bounded_printable_ascii() {
+        assert!(validate_idempotency_key("retry-7f3a1c2e").is_ok());
+        assert!(validate_idempotency_key(&"k".repeat(MAX_IDEMPOTENCY_KEY_LEN)).is_ok());
+
+        for key in [
+            String::new(),
+            "k".repeat(MAX_IDEMPOTENCY_KEY_LEN + 1),
+            "has space".to_string(),
+            "tab\tkey".to_string(),
+            "ключ".to_string(),
+        ] {
+            assert!(
+                matches!(
+                    validate_idempotency_key(&key),
+                    Err(RestApiValidationError::InvalidIdempotencyKey(_))
+                ),
+                "{key:?} should be rejected"
+            );
+        }
     }
 
     #[test]
@@ -1,35 +1,63 @@
 use std::collections::HashMap;
 use std::env;
+use std::net::IpAddr;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, anyhow};
 use axum::body::{Body, to_bytes};
-use axum::extract::{ConnectInfo, Path, Request, State};
-use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{ConnectInfo, Extension, Path, Query, Request, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
 use axum::middleware::{Next, from_fn_with_state};
 use axum::response::IntoResponse;
-use axum::routing::{get, post};
+use axum::routing::{MethodRouter, get, post};
 use axum::{Json, Router};
+use reqwest::Url;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
 use subtle::ConstantTimeEq;
-use tokio::net::TcpListener;
+use tokio::net::{TcpListener, lookup_host};
 use tokio::signal;
+use tokio::sync::{Notify, watch};
 use tokio::time::timeout;
 
 use crate::api_error::ApiError;
+use crate::report_diff::{
+    DiffVerdict, ReportDiff, ReportHop, diff_reports, parse_report_hops, render_markdown,
+    render_text,
+};
 use crate::service::api_models::{
-    ApiResponseMetaDto, CreateProbeDataDto, CreateProbeRequestDto, CreateProbeResponseDto,
-    HealthDataDto, HealthResponseDto, ProbeResultResponseDto,
+    ApiResponseMetaDto, ConfigReloadDataDto, ConfigReloadResponseDto, CreateProbeDataDto,
+    CreateProbeRequestDto, CreateProbeResponseDto, DiagnosticsDataDto, DiagnosticsResponseDto,
+    HealthDataDto, HealthResponseDto, ProbeDiffDataDto, ProbeDiffResponseDto,
+    ProbeExecutionResultDto, ProbeResultResponseDto, ReadinessDataDto, ReadinessResponseDto,
+    RuntimeCheckDto,
+};
+use crate::service::config_reload::{
+    ConfigReloadError, ConfigReloadOptions, ConfigReloadReport, ConfigSource, watch_config_files,
+};
+use crate::service::diagnostics::{RuntimeChecks, run_runtime_checks};
+use crate::service::openapi::{
+    ADMIN_RELOAD_PATH, API_ROUTES, ApiRoute, DIAGNOSTICS_PATH, DOCS_PATH, EXPLORER_HTML,
+    HEALTH_PATH, OPENAPI_JSON_PATH, OPENAPI_YAML, OPENAPI_YAML_PATH, PROBE_DIFF_PATH, PROBE_PATH,
+    PROBES_PATH, READY_PATH, openapi_json,
 };
 use crate::service::rest_api::{
-    AuthStrategy, CreateProbeApiRequest, FixedWindowRateLimiter, NormalizedCreateProbeRequest,
-    ProbeConcurrencyGate, ProbeProtocol, RestApiConfig, RestApiValidationError,
-    validate_payload_size,
+    API_KEY_PRINCIPAL, AuthStrategy, CreateProbeApiRequest, FixedWindowRateLimiter,
+    NormalizedCreateProbeRequest, ProbeProtocol, ProbeWorkQueue, QueuedProbe, RestApiConfig,
+    RestApiValidationError, validate_idempotency_key, validate_payload_size,
+};
+use crate::service::target_policy::{TargetPolicy, TargetPolicyError, is_private_or_reserved};
+use crate::service::webhooks::{
+    PinnedCallback, WebhookDispatcher, WebhookEvent, WebhookEventKind, threshold_breaches,
+    unix_timestamp,
 };
 use crate::service::{
-    EnhancedUiConfig, ProbeRequest, UiMode, build_probe_plan, run_embedded_trippy,
+    EnhancedUiConfig, JsonOutput, ProbeRequest, UiMode, build_probe_plan,
+    capture_embedded_trippy_json,
 };
 
 const API_KEY_HEADER: &str = "X-API-Key";
@@ -42,9 +70,21 @@ const RATE_LIMIT_LIMIT_STANDARD_HEADER: &str = "RateLimit-Limit";
 const RATE_LIMIT_REMAINING_STANDARD_HEADER: &str = "RateLimit-Remaining";
 const RATE_LIMIT_RESET_STANDARD_HEADER: &str = "RateLimit-Reset";
 const RETRY_AFTER_HEADER: &str = "Retry-After";
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+const MAX_IDEMPOTENCY_KEYS: usize = 10_000;
+const QUEUE_FULL_RETRY_AFTER_SECONDS: u64 = 5;
+/// Recorded on jobs that were queued or still running when shutdown cancelled them.
+const SHUTDOWN_INTERRUPTED_ERROR: &str = "server shutting down";
+/// How long cancelled jobs get to record their `interrupted` state before the server exits.
+const CANCELLED_JOB_SETTLE_TIMEOUT: Duration = Duration::from_secs(5);
 const MTLS_CLIENT_CERT_HEADER: &str = "X-Client-Cert";
 const MTLS_VERIFY_HEADER: &str = "X-SSL-Client-Verify";
 const MTLS_VERIFY_SUCCESS: &str = "SUCCESS";
+const LOCAL_PRINCIPAL: &str = "local";
+const MTLS_PRINCIPAL: &str = "mtls";
+const EXPLORER_CONTENT_SECURITY_POLICY: &str =
+    "default-src 'none'; script-src 'unsafe-inline'; style-src 'unsafe-inline'; connect-src 'self'";
 
 type ApiResult<T> = Result<T, ApiError>;
 
@@ -105,6 +145,22 @@ impl RequestAuthError {
     }
 }
 
+/// Authenticated caller identity used to select per-principal target policies.
+///
+/// `none-local-only` callers are `local`, API-key callers are their key's name (`api-key` for
+/// the unnamed key), and mTLS callers are identified by the forwarded `X-Client-Cert` value (or
+/// `mtls` when only the verify header is present).
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct RequestPrincipal {
+    id: String,
+}
+
+impl RequestPrincipal {
+    fn new(id: impl Into<String>) -> Self {
+        Self { id: id.into() }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ProbeExecutionResult {
     pub targets: Vec<String>,
@@ -118,6 +174,50 @@ pub struct ProbeTargetExecutionResult {
     pub target: String,
     pub success: bool,
     pub error: Option<String>,
+    pub metrics: Option<ProbeTargetMetrics>,
+    /// Per-hop statistics from the trippy report, kept for probe diffs; empty without a report.
+    pub hops: Vec<ReportHop>,
+}
+
+/// Destination-hop statistics taken from the captured trippy JSON report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeTargetMetrics {
+    pub loss_pct: f64,
+    pub avg_ms: Option<f64>,
+    pub hop_count: usize,
+}
+
+impl ProbeTargetMetrics {
+    pub fn from_trippy_report(report: &serde_json::Value) -> Option<Self> {
+        let hops = report
+            .get("hops")
+            .or_else(|| report.get("report").and_then(|inner| inner.get("hops")))?
+            .as_array()?;
+        let destination = hops.last()?;
+
+        let loss_pct = match read_report_number(destination, "loss_pct") {
+            Some(loss_pct) => loss_pct,
+            None => read_report_number(destination, "loss_ratio")? * 100.0,
+        };
+        let avg_ms = read_report_number(destination, "avg_ms")
+            .or_else(|| read_report_number(destination, "avg"))
+            .filter(|avg| *avg > 0.0);
+
+        Some(Self {
+            loss_pct,
+            avg_ms,
+            hop_count: hops.len(),
+        })
+    }
+}
+
+/// Trippy serializes fixed-width floats as strings, so accept both representations.
+fn read_report_number(value: &serde_json::Value, key: &str) -> Option<f64> {
+    match value.get(key)? {
+        serde_json::Value::Number(number) => number.as_f64(),
+        serde_json::Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -126,6 +226,8 @@ pub enum ProbeJobStatus {
     Running,
     Completed,
     Failed,
+    /// Failed because the server shut down before the probe finished.
+    Interrupted,
 }
 
 #[derive(Debug, Clone)]
@@ -137,19 +239,78 @@ pub struct ProbeJob {
     pub finished_at: Option<Instant>,
 }
 
+/// An `Idempotency-Key` scoped to the principal that sent it, with a fingerprint of the body.
+#[derive(Debug, Clone)]
+struct IdempotentRequest {
+    principal: String,
+    key: String,
+    fingerprint: String,
+}
+
+impl IdempotentRequest {
+    fn new(principal: &RequestPrincipal, key: String, payload: &[u8]) -> Self {
+        Self {
+            principal: principal.id.clone(),
+            key,
+            fingerprint: request_fingerprint(payload),
+        }
+    }
+
+    fn scope(&self) -> (String, String) {
+        (self.principal.clone(), self.key.clone())
+    }
+}
+
+/// Hash the request as the DTO sees it, so retries that differ only in key order, whitespace,
+/// or explicit `null`s still match.
+fn request_fingerprint(payload: &[u8]) -> String {
+    let canonical = serde_json::from_slice::<CreateProbeRequestDto>(payload)
+        .ok()
+        .and_then(|dto| serde_json::to_vec(&dto).ok());
+    hex::encode(Sha256::digest(canonical.as_deref().unwrap_or(payload)))
+}
+
+#[derive(Debug)]
+struct IdempotencyRecord {
+    fingerprint: String,
+    job_id: String,
+    created_at: Instant,
+}
+
 #[derive(Debug)]
 struct ProbeStore {
     jobs: HashMap<String, ProbeJob>,
     max_completed_jobs: usize,
     completed_job_ttl: std::time::Duration,
+    idempotency_keys: HashMap<(String, String), IdempotencyRecord>,
+    idempotency_key_ttl: std::time::Duration,
 }
 
 impl ProbeStore {
+    fn new(config: &RestApiConfig) -> Self {
+        Self {
+            jobs: HashMap::new(),
+            max_completed_jobs: config.max_completed_jobs,
+            completed_job_ttl: config.completed_job_ttl,
+            idempotency_keys: HashMap::new(),
+            idempotency_key_ttl: config.idempotency_key_ttl,
+        }
+    }
+
+    fn reconfigure(&mut self, config: &RestApiConfig) {
+        self.max_completed_jobs = config.max_completed_jobs;
+        self.completed_job_ttl = config.completed_job_ttl;
+        self.idempotency_key_ttl = config.idempotency_key_ttl;
+        self.prune(Instant::now());
+    }
+
     fn prune(&mut self, now: Instant) {
         self.jobs.retain(|_, job| match job.finished_at {
             Some(finished_at) => now.duration_since(finished_at) < self.completed_job_ttl,
             None => true,
         });
+        self.idempotency_keys
+            .retain(|_, record| now.duration_since(record.created_at) < self.idempotency_key_ttl);
 
         let terminal_count = self
             .jobs
@@ -184,17 +345,86 @@ impl ProbeStore {
         self.prune(Instant::now());
         self.jobs.get(id).cloned()
     }
+
+    /// Return the job already created with this key, if any.
+    fn find_idempotent(
+        &mut self,
+        request: &IdempotentRequest,
+    ) -> Result<Option<ProbeJob>, RestApiValidationError> {
+        self.prune(Instant::now());
+        let Some(record) = self.idempotency_keys.get(&request.scope()) else {
+            return Ok(None);
+        };
+
+        if record.fingerprint != request.fingerprint {
+            return Err(RestApiValidationError::IdempotencyKeyMismatch(format!(
+                "key `{}` was first used for job {}",
+                request.key, record.job_id
+            )));
+        }
+
+        match self.jobs.get(&record.job_id) {
+            Some(job) => Ok(Some(job.clone())),
+            None => Err(RestApiValidationError::IdempotencyKeyConflict(format!(
+                "job {} created with key `{}` is no longer retained",
+                record.job_id, request.key
+            ))),
+        }
+    }
+
+    /// Whether `principal` has a live record for `key`, without looking at the request body.
+    fn has_idempotency_key(&mut self, principal: &str, key: &str) -> bool {
+        self.prune(Instant::now());
+        self.idempotency_keys
+            .contains_key(&(principal.to_string(), key.to_string()))
+    }
+
+    fn insert_idempotent(&mut self, job: ProbeJob, request: &IdempotentRequest) {
+        let now = Instant::now();
+        self.prune(now);
+        if self.idempotency_keys.len() >= MAX_IDEMPOTENCY_KEYS
+            && let Some(oldest) = self
+                .idempotency_keys
+                .iter()
+                .min_by_key(|(_, record)| record.created_at)
+                .map(|(scope, _)| scope.clone())
+        {
+            self.idempotency_keys.remove(&oldest);
+        }
+
+        self.idempotency_keys.insert(
+            request.scope(),
+            IdempotencyRecord {
+                fingerprint: request.fingerprint.clone(),
+                job_id: job.id.clone(),
+                created_at: now,
+            },
+        );
+        self.upsert(job);
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct RestServerState {
-    pub config: RestApiConfig,
-    pub concurrency_gate: Arc<ProbeConcurrencyGate>,
+    /// Swapped as a whole on reload; handlers take one snapshot per request.
+    config: Arc<RwLock<Arc<RestApiConfig>>>,
+    config_source: Option<ConfigSource>,
+    queue: Arc<Mutex<ProbeWorkQueue<NormalizedCreateProbeRequest>>>,
     probe_rate_limiter: Arc<Mutex<FixedWindowRateLimiter>>,
     store: Arc<Mutex<ProbeStore>>,
     next_job_id: Arc<AtomicU64>,
     next_request_id: Arc<AtomicU64>,
     probe_runner_path: Arc<PathBuf>,
+    webhooks: Arc<RwLock<WebhookDispatcher>>,
+    started_at: Instant,
+    /// Cached so frequent readiness polls do not spawn a probe each time.
+    runtime_checks: Arc<Mutex<Option<RuntimeChecks>>>,
+    /// Set once shutdown starts; new probe submissions are refused from then on.
+    draining: Arc<AtomicBool>,
+    /// Flips to `true` when the shutdown grace period ends and running jobs must stop.
+    cancel_jobs: Arc<watch::Sender<bool>>,
+    /// Woken whenever a running job releases its probe slot.
+    job_finished: Arc<Notify>,
 }
 
 impl RestServerState {
@@ -206,27 +436,218 @@ impl RestServerState {
         config: RestApiConfig,
         probe_runner_path: PathBuf,
     ) -> Result<Self, RestApiValidationError> {
-        let max_completed_jobs = config.max_completed_jobs;
-        let completed_job_ttl = config.completed_job_ttl;
-        let gate = Arc::new(ProbeConcurrencyGate::new(config.max_concurrent_probes)?);
+        let queue = Arc::new(Mutex::new(ProbeWorkQueue::new(
+            config.max_concurrent_probes,
+            config.max_queued_probes,
+            config.fair_queueing,
+        )?));
         let limiter = Arc::new(Mutex::new(FixedWindowRateLimiter::new(
             config.max_requests_per_window,
             config.rate_limit_window,
             Instant::now(),
         )?));
+        let webhooks = WebhookDispatcher::new(config.webhooks.clone())
+            .map_err(|error| RestApiValidationError::InvalidOption(error.to_string()))?;
+        let store = Arc::new(Mutex::new(ProbeStore::new(&config)));
 
         Ok(Self {
-            config,
-            concurrency_gate: gate,
+            config: Arc::new(RwLock::new(Arc::new(config))),
+            config_source: None,
+            queue,
             probe_rate_limiter: limiter,
-            store: Arc::new(Mutex::new(ProbeStore {
-                jobs: HashMap::new(),
-                max_completed_jobs,
-                completed_job_ttl,
-            })),
+            store,
             next_job_id: Arc::new(AtomicU64::new(1)),
             next_request_id: Arc::new(AtomicU64::new(1)),
             probe_runner_path: Arc::new(probe_runner_path),
+            webhooks: Arc::new(RwLock::new(webhooks)),
+            started_at: Instant::now(),
+            runtime_checks: Arc::new(Mutex::new(None)),
+            draining: Arc::new(AtomicBool::new(false)),
+            cancel_jobs: Arc::new(watch::Sender::new(false)),
+            job_finished: Arc::new(Notify::new()),
+        })
+    }
+
+    /// Enable `POST /api/v1/admin/reload` and file-watch reloads from `source`.
+    pub fn with_config_source(mut self, source: ConfigSource) -> Self {
+        self.config_source = Some(source);
+        self
+    }
+
+    /// The configuration currently in effect.
+    pub fn config(&self) -> Arc<RestApiConfig> {
+        self.config
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn webhooks(&self) -> WebhookDispatcher {
+        self.webhooks
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Acquire)
+    }
+
+    /// Stop accepting probes and wind down the work queue before the server exits.
+    ///
+    /// Jobs that have not started are marked `interrupted` at once. Running jobs get the
+    /// configured shutdown grace period to finish; any still running afterwards are cancelled,
+    /// which kills their probe runner processes, and are marked `interrupted` as well.
+    pub async fn drain(&self) {
+        if self.draining.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let waiting = self
+            .queue
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .drain_waiting();
+        for job in waiting {
+            interrupt_job(self, &job.id, job.payload.pinned_callback().as_ref());
+        }
+
+        let grace_period = self.config().shutdown_grace_period;
+        if self.wait_for_running_jobs(grace_period).await {
+            return;
+        }
+
+        eprintln!(
+            "shutdown grace period of {grace_period:.1?} elapsed; cancelling {} running probe(s)",
+            self.running_jobs()
+        );
+        self.cancel_jobs.send_replace(true);
+        if !self
+            .wait_for_running_jobs(CANCELLED_JOB_SETTLE_TIMEOUT)
+            .await
+        {
+            eprintln!(
+                "{} cancelled probe(s) did not stop in time",
+                self.running_jobs()
+            );
+        }
+    }
+
+    /// Wait up to `limit` for every running job to release its slot; `false` on timeout.
+    async fn wait_for_running_jobs(&self, limit: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + limit;
+        loop {
+            let finished = self.job_finished.notified();
+            if self.running_jobs() == 0 {
+                return true;
+            }
+            if tokio::time::timeout_at(deadline, finished).await.is_err() {
+                return self.running_jobs() == 0;
+            }
+        }
+    }
+
+    fn running_jobs(&self) -> usize {
+        self.queue
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .running_len()
+    }
+
+    /// Load a new configuration from the config source and apply it with [`Self::reload`].
+    pub fn reload_from_source(&self) -> Result<ConfigReloadReport, ConfigReloadError> {
+        let source = self
+            .config_source
+            .as_ref()
+            .ok_or(ConfigReloadError::Unavailable)?;
+        let candidate = source.load().map_err(ConfigReloadError::Source)?;
+        self.reload(candidate)
+    }
+
+    /// Validate `candidate` and swap it in together with the rate limits, queue limits, job
+    /// retention, and webhook settings. Queued and running probes, and the requests already
+    /// counted in the current rate limit window, are kept. On error nothing changes.
+    ///
+    /// The listener cannot move, so a different `bind_addr` is reported in
+    /// [`ConfigReloadReport::restart_required`] and the current address is kept.
+    pub fn reload(
+        &self,
+        mut candidate: RestApiConfig,
+    ) -> Result<ConfigReloadReport, ConfigReloadError> {
+        let mut current = self.config.write().unwrap_or_else(PoisonError::into_inner);
+
+        let mut restart_required = Vec::new();
+        if candidate.bind_addr != current.bind_addr {
+            restart_required.push("bind_addr");
+            candidate.bind_addr = current.bind_addr;
+            candidate.allow_non_local_bind = current.allow_non_local_bind;
+        }
+        candidate.validate_security_defaults()?;
+
+        let changed = current.changed_fields(&candidate);
+        if changed.is_empty() {
+            return Ok(ConfigReloadReport {
+                changed,
+                restart_required,
+            });
+        }
+
+        // Build everything that can fail before touching shared state.
+        FixedWindowRateLimiter::new(
+            candidate.max_requests_per_window,
+            candidate.rate_limit_window,
+            Instant::now(),
+        )?;
+        let webhooks = if candidate.webhooks != current.webhooks {
+            Some(
+                self.webhooks()
+                    .reconfigured(candidate.webhooks.clone())
+                    .map_err(|error| RestApiValidationError::InvalidOption(error.to_string()))?,
+            )
+        } else {
+            None
+        };
+
+        {
+            let mut queue = self.queue.lock().unwrap_or_else(PoisonError::into_inner);
+            queue.reconfigure(
+                candidate.max_concurrent_probes,
+                candidate.max_queued_probes,
+                candidate.fair_queueing,
+            )?;
+        }
+        // Keep the counts of the current window so a reload never hands out a fresh burst.
+        self.probe_rate_limiter
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .reconfigure(
+                candidate.max_requests_per_window,
+                candidate.rate_limit_window,
+            )?;
+        if let Some(webhooks) = webhooks {
+            *self
+                .webhooks
+                .write()
+                .unwrap_or_else(PoisonError::into_inner) = webhooks;
+        }
+        self.store
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .reconfigure(&candidate);
+        *current = Arc::new(candidate);
+        drop(current);
+
+        // A higher concurrency limit frees slots for jobs that are already waiting.
+        if let Err(error) = start_queued_jobs(self) {
+            eprintln!(
+                "config reload: failed to start waiting jobs: {}",
+                error.detail
+            );
+        }
+
+        Ok(ConfigReloadReport {
+            changed,
+            restart_required,
         })
     }
 
@@ -242,18 +663,11 @@ impl RestServerState {
 }
 
 pub fn build_router(state: RestServerState) -> Router {
-    let probe_guard_state = state.clone();
-
-    Router::new()
-        .route("/api/v1/health", get(get_health))
-        .route(
-            "/api/v1/probes",
-            post(create_probe).route_layer(from_fn_with_state(
-                probe_guard_state,
-                enforce_probe_request_guards,
-            )),
-        )
-        .route("/api/v1/probes/{id}", get(get_probe))
+    API_ROUTES
+        .iter()
+        .fold(Router::new(), |router, route| {
+            router.route(route.path, route_handler(route, &state))
+        })
         .layer(from_fn_with_state(
             state.clone(),
             attach_request_id_response_header,
@@ -261,6 +675,27 @@ pub fn build_router(state: RestServerState) -> Router {
         .with_state(state)
 }
 
+/// Handler for one of [`API_ROUTES`]. The router mounts nothing but that list, so the OpenAPI
+/// contract tests comparing it with the spec cover every route the server serves.
+fn route_handler(route: &ApiRoute, state: &RestServerState) -> MethodRouter<RestServerState> {
+    match (route.method, route.path) {
+        ("get", HEALTH_PATH) => get(get_health),
+        ("get", READY_PATH) => get(get_ready),
+        ("get", DIAGNOSTICS_PATH) => get(get_diagnostics),
+        ("post", PROBES_PATH) => post(create_probe).route_layer(from_fn_with_state(
+            state.clone(),
+            enforce_probe_request_guards,
+        )),
+        ("get", PROBE_PATH) => get(get_probe),
+        ("get", PROBE_DIFF_PATH) => get(get_probe_diff),
+        ("get", OPENAPI_JSON_PATH) => get(get_openapi_json),
+        ("get", OPENAPI_YAML_PATH) => get(get_openapi_yaml),
+        ("get", DOCS_PATH) => get(get_api_explorer),
+        ("post", ADMIN_RELOAD_PATH) => post(reload_config),
+        (method, path) => unreachable!("API_ROUTES lists {method} {path} without a handler"),
+    }
+}
+
 async fn attach_request_id_response_header(
     State(state): State<RestServerState>,
     request: Request,
@@ -284,7 +719,66 @@ async fn enforce_probe_request_guards(
         .get::<ConnectInfo<std::net::SocketAddr>>()
         .map(|connect_info| connect_info.0)
         .ok_or_else(|| internal_error_response("missing remote address for probe request"))?;
-    enforce_request_auth(&state.config, remote_addr, request.headers())?;
+    let config = state.config();
+    let principal = enforce_request_auth(&config, remote_addr, request.headers())?;
+    let key = idempotency_key(request.headers()).map_err(validation_error_response)?;
+
+    // A client over its limit gets 429 before its body is read, so it cannot make the server
+    // buffer payloads. Retries of a key the server already knows go on to be replayed.
+    let known_key = match &key {
+        Some(key) => state
+            .store
+            .lock()
+            .map_err(|_| internal_error_response("failed to lock probe store"))?
+            .has_idempotency_key(&principal.id, key),
+        None => false,
+    };
+    if !known_key {
+        let (snapshot, check_result) = {
+            let limiter = state
+                .probe_rate_limiter
+                .lock()
+                .map_err(|_| internal_error_response("failed to lock probe rate limiter"))?;
+            let now = Instant::now();
+            (limiter.snapshot(now), limiter.check(now))
+        };
+        if let Err(error) = check_result {
+            return rate_limited_response(error, snapshot);
+        }
+    }
+
+    let (parts, body) = request.into_parts();
+    let payload = to_bytes(body, config.max_payload_bytes + 1)
+        .await
+        .map_err(|_| {
+            validation_error_response(RestApiValidationError::OversizedPayload(
+                "request body exceeds configured payload limit".to_string(),
+            ))
+        })?;
+
+    validate_payload_size(payload.len(), &config).map_err(validation_error_response)?;
+
+    // Replays are answered before the rate limiter so a client retry never spends a slot.
+    let idempotent_request = key.map(|key| IdempotentRequest::new(&principal, key, &payload));
+    if let Some(idempotent_request) = &idempotent_request {
+        let replayed = state
+            .store
+            .lock()
+            .map_err(|_| internal_error_response("failed to lock probe store"))?
+            .find_idempotent(idempotent_request)
+            .map_err(validation_error_response)?;
+        if let Some(job) = replayed {
+            let queue_position = queue_position(&state, &job)?;
+            let snapshot = state
+                .probe_rate_limiter
+                .lock()
+                .map_err(|_| internal_error_response("failed to lock probe rate limiter"))?
+                .snapshot(Instant::now());
+            let mut response = accepted_probe_response(&job, queue_position, true);
+            attach_rate_limit_headers(&mut response, snapshot)?;
+            return Ok(response);
+        }
+    }
 
     let (snapshot, allow_result) = {
         let mut limiter = state
@@ -297,28 +791,48 @@ async fn enforce_probe_request_guards(
         (snapshot, allow_result)
     };
     if let Err(error) = allow_result {
-        let mut response = validation_error_response(error).into_response();
-        attach_rate_limit_headers(&mut response, snapshot)?;
-        return Ok(response);
+        return rate_limited_response(error, snapshot);
     }
 
-    let (parts, body) = request.into_parts();
-    let payload = to_bytes(body, state.config.max_payload_bytes + 1)
-        .await
-        .map_err(|_| {
-            validation_error_response(RestApiValidationError::OversizedPayload(
-                "request body exceeds configured payload limit".to_string(),
-            ))
-        })?;
-
-    validate_payload_size(payload.len(), &state.config).map_err(validation_error_response)?;
-
-    let request = Request::from_parts(parts, Body::from(payload));
+    let mut request = Request::from_parts(parts, Body::from(payload));
+    request.extensions_mut().insert(principal);
+    if let Some(idempotent_request) = idempotent_request {
+        request.extensions_mut().insert(idempotent_request);
+    }
     let mut response = next.run(request).await;
     attach_rate_limit_headers(&mut response, snapshot)?;
     Ok(response)
 }
 
+fn rate_limited_response(
+    error: RestApiValidationError,
+    snapshot: crate::service::rest_api::RateLimitSnapshot,
+) -> ApiResult<axum::response::Response> {
+    let mut response = validation_error_response(error).into_response();
+    attach_rate_limit_headers(&mut response, snapshot)?;
+    Ok(response)
+}
+
+fn idempotency_key(headers: &HeaderMap) -> Result<Option<String>, RestApiValidationError> {
+    let mut values = headers.get_all(IDEMPOTENCY_KEY_HEADER).iter();
+    let Some(value) = values.next() else {
+        return Ok(None);
+    };
+    if values.next().is_some() {
+        return Err(RestApiValidationError::InvalidIdempotencyKey(
+            "header must be sent once".to_string(),
+        ));
+    }
+
+    let key = value.to_str().map_err(|_| {
+        RestApiValidationError::InvalidIdempotencyKey(
+            "key must contain only printable ASCII characters without spaces".to_string(),
+        )
+    })?;
+    validate_idempotency_key(key)?;
+    Ok(Some(key.to_string()))
+}
+
 fn attach_rate_limit_headers(
     response: &mut axum::response::Response,
     snapshot: crate::service::rest_api::RateLimitSnapshot,
@@ -363,6 +877,30 @@ fn attach_rate_limit_headers(
 }
 
 pub async fn run_rest_api_server(config: RestApiConfig) -> anyhow::Result<()> {
+    run_rest_api_server_with_reload(config, None).await
+}
+
+/// Like [`run_rest_api_server`], with hot reload from `reload.source` through the admin
+/// endpoint and, when `reload.watch_paths` is non-empty, a file watcher.
+pub async fn run_rest_api_server_with_reload(
+    config: RestApiConfig,
+    reload: Option<ConfigReloadOptions>,
+) -> anyhow::Result<()> {
+    serve_rest_api(config, reload, shutdown_signal(), |_| {}).await
+}
+
+/// Run the API server until `shutdown` resolves. `on_listening` receives the bound address
+/// once the listener accepts connections; service hosts use it to report readiness.
+///
+/// After `shutdown` resolves the server keeps answering requests while it drains the probe
+/// queue (see [`RestServerState::drain`]), so clients can still fetch results and new
+/// submissions get `503 shutting_down`.
+pub async fn serve_rest_api(
+    config: RestApiConfig,
+    reload: Option<ConfigReloadOptions>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+    on_listening: impl FnOnce(std::net::SocketAddr),
+) -> anyhow::Result<()> {
     config
         .validate_security_defaults()
         .map_err(|e| anyhow!("REST API configuration error: {e}"))
@@ -374,132 +912,632 @@ pub async fn run_rest_api_server(config: RestApiConfig) -> anyhow::Result<()> {
             .map_err(|e| anyhow!("failed to resolve probe runner path: {e}"))?,
     )
     .map_err(|e| anyhow!("failed to initialize REST API runtime state: {e}"))?;
-    let app = build_router(state);
 
-    let listener = TcpListener::bind(config.bind_addr)
-        .await
-        .with_context(|| format!("failed to bind REST API on {}", config.bind_addr))?;
+    let mut watcher = None;
+    let state = match reload {
+        Some(reload) => {
+            let state = state.with_config_source(reload.source);
+            watcher = Some(tokio::spawn(watch_config_files(
+                state.clone(),
+                reload.watch_paths,
+                reload.watch_interval,
+            )));
+            state
+        }
+        None => state,
+    };
+    let app = build_router(state.clone());
+
+    let listener = TcpListener::bind(config.bind_addr)
+        .await
+        .with_context(|| format!("failed to bind REST API on {}", config.bind_addr))?;
+    on_listening(
+        listener
+            .local_addr()
+            .context("failed to read REST API listener address")?,
+    );
+
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        shutdown.await;
+        eprintln!("REST API shutting down; draining probe queue");
+        state.drain().await;
+    })
+    .await
+    .context("REST API server failed")?;
+
+    if let Some(watcher) = watcher {
+        watcher.abort();
+    }
+    Ok(())
+}
+
+async fn get_health(
+    ConnectInfo(remote_addr): ConnectInfo<std::net::SocketAddr>,
+    State(state): State<RestServerState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<HealthResponseDto>> {
+    enforce_request_auth(&state.config(), remote_addr, &headers)?;
+    Ok(Json(HealthResponseDto {
+        meta: ApiResponseMetaDto {
+            schema_version: "v1",
+            request_id: None,
+        },
+        data: HealthDataDto {
+            status: "ok",
+            service: "windows-mtr",
+            version: env!("CARGO_PKG_VERSION"),
+        },
+    }))
+}
+
+/// `200` when probes can actually run, `503` otherwise; both carry the individual checks.
+async fn get_ready(
+    ConnectInfo(remote_addr): ConnectInfo<std::net::SocketAddr>,
+    State(state): State<RestServerState>,
+    headers: HeaderMap,
+) -> ApiResult<impl IntoResponse> {
+    enforce_request_auth(&state.config(), remote_addr, &headers)?;
+    let checks = runtime_checks(&state).await?;
+    let status = if checks.ready() && !state.is_draining() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    Ok((
+        status,
+        Json(ReadinessResponseDto {
+            meta: ApiResponseMetaDto {
+                schema_version: "v1",
+                request_id: None,
+            },
+            data: ReadinessDataDto {
+                status: readiness_status(&state, &checks),
+                checks: runtime_check_dtos(&checks),
+            },
+        }),
+    ))
+}
+
+async fn get_diagnostics(
+    ConnectInfo(remote_addr): ConnectInfo<std::net::SocketAddr>,
+    State(state): State<RestServerState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<DiagnosticsResponseDto>> {
+    let config = state.config();
+    enforce_request_auth(&config, remote_addr, &headers)?;
+    let checks = runtime_checks(&state).await?;
+
+    let (running_probes, queued_probes) = {
+        let queue = state
+            .queue
+            .lock()
+            .map_err(|_| internal_error_response("failed to lock probe queue"))?;
+        (queue.running_len(), queue.waiting_len())
+    };
+    let stored_jobs = {
+        let mut store = state
+            .store
+            .lock()
+            .map_err(|_| internal_error_response("failed to lock probe store"))?;
+        store.prune(Instant::now());
+        store.jobs.len()
+    };
+
+    Ok(Json(DiagnosticsResponseDto {
+        meta: ApiResponseMetaDto {
+            schema_version: "v1",
+            request_id: None,
+        },
+        data: DiagnosticsDataDto {
+            status: readiness_status(&state, &checks),
+            service: "windows-mtr",
+            version: env!("CARGO_PKG_VERSION"),
+            uptime_seconds: state.started_at.elapsed().as_secs(),
+            checks: runtime_check_dtos(&checks),
+            running_probes,
+            queued_probes,
+            max_concurrent_probes: config.max_concurrent_probes,
+            max_queued_probes: config.max_queued_probes,
+            stored_jobs,
+        },
+    }))
+}
+
+/// Cached runtime checks, re-run on a blocking thread once they are stale.
+async fn runtime_checks(state: &RestServerState) -> ApiResult<RuntimeChecks> {
+    let cache = state.runtime_checks.clone();
+    let runner = state.probe_runner_path.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut cached = cache.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(checks) = cached.as_ref()
+            && checks.is_fresh(Instant::now())
+        {
+            return checks.clone();
+        }
+
+        let checks = run_runtime_checks(&runner, &privilege_probe_args(), EMBEDDED_TRIPPY_ENV);
+        *cached = Some(checks.clone());
+        checks
+    })
+    .await
+    .map_err(|_| internal_error_response("runtime check task failed"))
+}
+
+/// Embedded trippy arguments for a one-cycle ICMP probe to `127.0.0.1`, the same probe as
+/// `tests/privilege_probe_smoke.rs` runs by default.
+fn privilege_probe_args() -> Vec<String> {
+    let loopback = NormalizedCreateProbeRequest {
+        targets: vec!["127.0.0.1".to_string()],
+        protocol: ProbeProtocol::Icmp,
+        port: None,
+        count: Some(1),
+        max_hops: None,
+        resolve_dns: false,
+        include_asn: false,
+        interval_seconds: None,
+        timeout_seconds: None,
+        source_port: None,
+        packet_size: None,
+        src: None,
+        interface: None,
+        ecmp: None,
+        dns_cache_ttl_seconds: None,
+        callback_url: None,
+        callback_address: None,
+        target_addresses: HashMap::new(),
+    };
+
+    build_probe_plan(&normalized_to_probe_request(
+        &loopback,
+        loopback.targets[0].clone(),
+    ))
+    .map(|plan| plan.trippy_args)
+    .unwrap_or_default()
+}
+
+fn readiness_status(state: &RestServerState, checks: &RuntimeChecks) -> &'static str {
+    if state.is_draining() {
+        "draining"
+    } else if checks.ready() {
+        "ready"
+    } else {
+        "not_ready"
+    }
+}
+
+fn runtime_check_dtos(checks: &RuntimeChecks) -> Vec<RuntimeCheckDto> {
+    checks
+        .checks
+        .iter()
+        .map(|check| RuntimeCheckDto {
+            name: check.name,
+            ok: check.ok,
+            detail: check.detail.clone(),
+        })
+        .collect()
+}
+
+async fn get_openapi_json(
+    ConnectInfo(remote_addr): ConnectInfo<std::net::SocketAddr>,
+    State(state): State<RestServerState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<&'static serde_json::Value>> {
+    enforce_request_auth(&state.config(), remote_addr, &headers)?;
+    Ok(Json(openapi_json()))
+}
+
+async fn get_openapi_yaml(
+    ConnectInfo(remote_addr): ConnectInfo<std::net::SocketAddr>,
+    State(state): State<RestServerState>,
+    headers: HeaderMap,
+) -> ApiResult<impl IntoResponse> {
+    enforce_request_auth(&state.config(), remote_addr, &headers)?;
+    Ok(([(header::CONTENT_TYPE, "application/yaml")], OPENAPI_YAML))
+}
+
+/// Re-read the configuration and swap it in without dropping queued or running probes.
+async fn reload_config(
+    ConnectInfo(remote_addr): ConnectInfo<std::net::SocketAddr>,
+    State(state): State<RestServerState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ConfigReloadResponseDto>> {
+    let config = state.config();
+    let principal = enforce_request_auth(&config, remote_addr, &headers)?;
+    if !config.admin_principals.contains(&principal.id) {
+        return Err(error_response(
+            StatusCode::FORBIDDEN,
+            "admin_scope_required",
+            "Admin scope required",
+            format!("principal `{}` does not have the admin scope", principal.id),
+        ));
+    }
+
+    let reload_state = state.clone();
+    let report = tokio::task::spawn_blocking(move || reload_state.reload_from_source())
+        .await
+        .map_err(|_| internal_error_response("config reload task failed"))?
+        .map_err(|error| match error {
+            ConfigReloadError::Unavailable => error_response(
+                StatusCode::CONFLICT,
+                "reload_unavailable",
+                "Reload unavailable",
+                error.to_string(),
+            ),
+            ConfigReloadError::Source(_) | ConfigReloadError::Invalid(_) => error_response(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "invalid_config",
+                "Invalid configuration",
+                error.to_string(),
+            ),
+        })?;
+    eprintln!(
+        "config reload via {ADMIN_RELOAD_PATH} by {}: {report}",
+        principal.id
+    );
+
+    Ok(Json(ConfigReloadResponseDto {
+        meta: ApiResponseMetaDto {
+            schema_version: "v1",
+            request_id: None,
+        },
+        data: ConfigReloadDataDto {
+            changed: report.changed,
+            restart_required: report.restart_required,
+        },
+    }))
+}
+
+/// The explorer page is static and carries no data, so it is served without auth; the
+/// requests it issues go through the normal auth checks.
+async fn get_api_explorer() -> impl IntoResponse {
+    (
+        [
+            (header::CONTENT_TYPE, "text/html; charset=utf-8"),
+            (
+                header::CONTENT_SECURITY_POLICY,
+                EXPLORER_CONTENT_SECURITY_POLICY,
+            ),
+        ],
+        EXPLORER_HTML,
+    )
+}
+
+async fn create_probe(
+    State(state): State<RestServerState>,
+    Extension(principal): Extension<RequestPrincipal>,
+    idempotent_request: Option<Extension<IdempotentRequest>>,
+    Json(payload): Json<CreateProbeRequestDto>,
+) -> ApiResult<axum::response::Response> {
+    let config = state.config();
+    run_with_timeout(config.request_timeout, async move {
+        let create_request: CreateProbeApiRequest = payload.into();
+        let mut normalized = create_request
+            .normalize_and_validate(&config)
+            .map_err(validation_error_response)?;
+
+        let policy = config.target_policy.for_principal(&principal.id);
+        normalized.target_addresses = enforce_target_policy(&policy, &normalized.targets).await?;
+        if let Some(callback_url) = &normalized.callback_url {
+            normalized.callback_address = Some(check_callback_host(&policy, callback_url).await?);
+        }
+
+        let queued = ProbeJob {
+            id: state.next_job_id(),
+            status: ProbeJobStatus::Queued,
+            result: None,
+            error: None,
+            finished_at: None,
+        };
+
+        {
+            let mut store = state
+                .store
+                .lock()
+                .map_err(|_| internal_error_response("failed to lock probe store"))?;
+            let idempotent_request = idempotent_request.map(|Extension(request)| request);
+            if let Some(request) = &idempotent_request {
+                // A concurrent retry may have created the job since the guard checked.
+                if let Some(job) = store
+                    .find_idempotent(request)
+                    .map_err(validation_error_response)?
+                {
+                    drop(store);
+                    let queue_position = queue_position(&state, &job)?;
+                    return Ok(accepted_probe_response(&job, queue_position, true));
+                }
+            }
+
+            let enqueued = {
+                let mut queue = state
+                    .queue
+                    .lock()
+                    .map_err(|_| internal_error_response("failed to lock probe queue"))?;
+                // Checked under the queue lock so a job cannot slip in after `drain` empties it.
+                if state.is_draining() {
+                    return Err(shutting_down_response());
+                }
+                queue.enqueue(queued.id.clone(), principal.id.clone(), normalized)
+            };
+            if let Err(error) = enqueued {
+                return Ok(queue_full_response(error));
+            }
+
+            match &idempotent_request {
+                Some(request) => store.insert_idempotent(queued.clone(), request),
+                None => store.upsert(queued.clone()),
+            }
+        }
+
+        start_queued_jobs(&state)?;
+        let queue_position = queue_position(&state, &queued)?;
+        Ok(accepted_probe_response(&queued, queue_position, false))
+    })
+    .await
+}
+
+fn queue_full_response(error: RestApiValidationError) -> axum::response::Response {
+    let mut response = validation_error_response(error).into_response();
+    response.headers_mut().insert(
+        RETRY_AFTER_HEADER,
+        HeaderValue::from(QUEUE_FULL_RETRY_AFTER_SECONDS),
+    );
+    response
+}
+
+fn shutting_down_response() -> ApiError {
+    error_response(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "shutting_down",
+        "Server shutting down",
+        "the server is shutting down and no longer accepts probes".to_string(),
+    )
+}
+
+/// Place of a queued job in the work queue; `None` once it has been handed a probe slot.
+fn queue_position(state: &RestServerState, job: &ProbeJob) -> ApiResult<Option<usize>> {
+    if !matches!(job.status, ProbeJobStatus::Queued) {
+        return Ok(None);
+    }
+
+    let queue = state
+        .queue
+        .lock()
+        .map_err(|_| internal_error_response("failed to lock probe queue"))?;
+    Ok(queue.position(&job.id))
+}
+
+/// Hand every free probe slot to the next waiting job.
+fn start_queued_jobs(state: &RestServerState) -> ApiResult<()> {
+    let started = state
+        .queue
+        .lock()
+        .map_err(|_| internal_error_response("failed to lock probe queue"))?
+        .start_ready();
+    for job in started {
+        let state_for_job = state.clone();
+        tokio::spawn(async move {
+            run_probe_job(state_for_job, job).await;
+        });
+    }
+    Ok(())
+}
+
+/// Releases a running job's queue slot and starts the next job, even if the job task panics.
+struct QueueSlot {
+    state: RestServerState,
+    principal: String,
+}
+
+impl Drop for QueueSlot {
+    fn drop(&mut self) {
+        match self.state.queue.lock() {
+            Ok(mut queue) => queue.finish(&self.principal),
+            Err(_) => {
+                eprintln!("probe queue: failed to release slot for {}", self.principal);
+                return;
+            }
+        }
+        self.state.job_finished.notify_waiters();
+        if let Err(error) = start_queued_jobs(&self.state) {
+            eprintln!(
+                "probe queue: failed to start waiting jobs: {}",
+                error.detail
+            );
+        }
+    }
+}
+
+fn accepted_probe_response(
+    job: &ProbeJob,
+    queue_position: Option<usize>,
+    replayed: bool,
+) -> axum::response::Response {
+    let mut response = (
+        StatusCode::ACCEPTED,
+        Json(CreateProbeResponseDto {
+            meta: ApiResponseMetaDto {
+                schema_version: "v1",
+                request_id: None,
+            },
+            data: CreateProbeDataDto {
+                id: job.id.clone(),
+                status: job.status.into(),
+                queue_position,
+            },
+        }),
+    )
+        .into_response();
+    if replayed {
+        response
+            .headers_mut()
+            .insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    }
+    response
+}
+
+/// Resolve a callback host once and check every address it resolves to.
+///
+/// Callbacks may never reach loopback, link-local, private or reserved addresses, whatever the
+/// caller's target policy says, and must also pass that policy so webhooks cannot reach hosts
+/// the caller could not probe. Returns the address deliveries are pinned to.
+async fn check_callback_host(policy: &TargetPolicy, url: &Url) -> ApiResult<IpAddr> {
+    let forbidden = |message: String| {
+        validation_error_response(RestApiValidationError::TargetForbidden(message))
+    };
 
-    axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
-    )
-    .with_graceful_shutdown(shutdown_signal())
-    .await
-    .context("REST API server failed")?;
+    let host = callback_host(url);
+    let (hostname, addresses) = match host.parse::<IpAddr>() {
+        Ok(address) => (None, vec![address]),
+        Err(_) => {
+            if !policy.is_unrestricted() {
+                policy
+                    .check_hostname(&host)
+                    .map_err(|error| forbidden(error.to_string()))?;
+            }
+            (Some(host.as_str()), resolve_target_addresses(&host).await?)
+        }
+    };
 
-    Ok(())
+    if let Some(address) = addresses
+        .iter()
+        .find(|&&address| is_private_or_reserved(address))
+    {
+        return Err(forbidden(format!(
+            "callback_url host {host} resolves to private or reserved address {address}"
+        )));
+    }
+    if !policy.is_unrestricted() {
+        policy
+            .evaluate(&host, hostname, &addresses)
+            .map_err(|error| forbidden(error.to_string()))?;
+    }
+
+    Ok(addresses[0])
 }
 
-async fn get_health(
-    ConnectInfo(remote_addr): ConnectInfo<std::net::SocketAddr>,
-    State(state): State<RestServerState>,
-    headers: HeaderMap,
-) -> ApiResult<Json<HealthResponseDto>> {
-    enforce_request_auth(&state.config, remote_addr, &headers)?;
-    Ok(Json(HealthResponseDto {
-        meta: ApiResponseMetaDto {
-            schema_version: "v1",
-            request_id: None,
-        },
-        data: HealthDataDto {
-            status: "ok",
-            service: "windows-mtr",
-            version: env!("CARGO_PKG_VERSION"),
-        },
-    }))
+fn callback_host(url: &Url) -> String {
+    url.host_str()
+        .unwrap_or_default()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_ascii_lowercase()
 }
 
-async fn create_probe(
-    State(state): State<RestServerState>,
-    Json(payload): Json<CreateProbeRequestDto>,
-) -> ApiResult<(StatusCode, Json<CreateProbeResponseDto>)> {
-    run_with_timeout(state.config.request_timeout, async move {
-        let create_request: CreateProbeApiRequest = payload.into();
-        let normalized = create_request
-            .normalize_and_validate(&state.config)
-            .map_err(validation_error_response)?;
+/// Check every target against `policy`, returning the address each hostname target was checked
+/// at so the probe can be pinned to it.
+async fn enforce_target_policy(
+    policy: &TargetPolicy,
+    targets: &[String],
+) -> ApiResult<HashMap<String, IpAddr>> {
+    let mut pinned = HashMap::new();
+    if policy.is_unrestricted() {
+        return Ok(pinned);
+    }
 
-        let id = state.next_job_id();
-        let queued = ProbeJob {
-            id: id.clone(),
-            status: ProbeJobStatus::Queued,
-            result: None,
-            error: None,
-            finished_at: None,
+    let forbidden = |error: TargetPolicyError| {
+        validation_error_response(RestApiValidationError::TargetForbidden(error.to_string()))
+    };
+
+    for target in targets {
+        let (hostname, addresses) = match target.parse::<IpAddr>() {
+            Ok(address) => (None, vec![address]),
+            Err(_) => {
+                policy.check_hostname(target).map_err(forbidden)?;
+                (
+                    Some(target.as_str()),
+                    resolve_target_addresses(target).await?,
+                )
+            }
         };
 
-        {
-            let mut store = state
-                .store
-                .lock()
-                .map_err(|_| internal_error_response("failed to lock probe store"))?;
-            store.upsert(queued);
+        policy
+            .evaluate(target, hostname, &addresses)
+            .map_err(forbidden)?;
+        if hostname.is_some() {
+            pinned.insert(target.clone(), addresses[0]);
         }
+    }
 
-        let state_for_job = state.clone();
-        let job_id = id.clone();
-        tokio::spawn(async move {
-            run_probe_job(state_for_job, job_id, normalized).await;
-        });
+    Ok(pinned)
+}
 
-        Ok((
-            StatusCode::ACCEPTED,
-            Json(CreateProbeResponseDto {
-                meta: ApiResponseMetaDto {
-                    schema_version: "v1",
-                    request_id: None,
-                },
-                data: CreateProbeDataDto {
-                    id,
-                    status: ProbeJobStatus::Queued.into(),
-                },
-            }),
-        ))
-    })
-    .await
+async fn resolve_target_addresses(hostname: &str) -> ApiResult<Vec<IpAddr>> {
+    let addresses = lookup_host((hostname, 0))
+        .await
+        .map_err(|_| {
+            validation_error_response(RestApiValidationError::InvalidTarget(format!(
+                "failed to resolve {hostname} for target policy evaluation"
+            )))
+        })?
+        .map(|address| address.ip())
+        .collect::<Vec<_>>();
+
+    if addresses.is_empty() {
+        return Err(validation_error_response(
+            RestApiValidationError::InvalidTarget(format!(
+                "{hostname} did not resolve to any address for target policy evaluation"
+            )),
+        ));
+    }
+
+    Ok(addresses)
 }
 
-async fn run_probe_job(
-    state: RestServerState,
-    id: String,
-    normalized: NormalizedCreateProbeRequest,
-) {
-    let permit = match state.concurrency_gate.try_acquire() {
-        Ok(permit) => permit,
-        Err(error) => {
-            let message = error.to_string();
-            let _ = update_job_status(
-                &state,
-                &id,
-                ProbeJobStatus::Failed,
-                None,
-                Some(message.clone()),
-            );
-            eprintln!("probe {id}: failed to acquire concurrency permit: {message}");
-            return;
-        }
+async fn run_probe_job(state: RestServerState, job: QueuedProbe<NormalizedCreateProbeRequest>) {
+    let QueuedProbe {
+        id,
+        principal,
+        payload: normalized,
+    } = job;
+    let _slot = QueueSlot {
+        state: state.clone(),
+        principal,
     };
+    let callback = normalized.pinned_callback();
 
     if let Err(error) = update_job_status(&state, &id, ProbeJobStatus::Running, None, None) {
         eprintln!("probe {id}: failed to set running state: {error}");
         return;
     }
 
-    let probe_timeout = state.config.probe_execution_timeout;
-    let probe_result = timeout(
-        probe_timeout,
-        execute_probe(normalized, state.probe_runner_path.clone()),
-    )
-    .await;
+    let probe_timeout = state.config().probe_execution_timeout;
+    let mut cancel_jobs = state.cancel_jobs.subscribe();
+    let probe_result = tokio::select! {
+        result = timeout(
+            probe_timeout,
+            execute_probe(normalized, state.probe_runner_path.clone()),
+        ) => result,
+        _ = cancel_jobs.wait_for(|cancelled| *cancelled) => {
+            eprintln!("probe {id}: cancelled by shutdown");
+            interrupt_job(&state, &id, callback.as_ref());
+            return;
+        }
+    };
 
     match probe_result {
         Ok(Ok(result)) => {
-            if let Err(error) =
-                update_job_status(&state, &id, ProbeJobStatus::Completed, Some(result), None)
-            {
+            if let Err(error) = update_job_status(
+                &state,
+                &id,
+                ProbeJobStatus::Completed,
+                Some(result.clone()),
+                None,
+            ) {
                 eprintln!("probe {id}: failed to set completed state: {error}");
             }
+            notify_job_finished(
+                &state,
+                &id,
+                callback.as_ref(),
+                ProbeJobStatus::Completed,
+                Some(result),
+                None,
+            );
         }
         Ok(Err(error)) => {
             if let Err(store_error) = update_job_status(
@@ -511,19 +1549,112 @@ async fn run_probe_job(
             ) {
                 eprintln!("probe {id}: failed to set failed state: {store_error}");
             }
+            notify_job_finished(
+                &state,
+                &id,
+                callback.as_ref(),
+                ProbeJobStatus::Failed,
+                None,
+                Some(error),
+            );
         }
         Err(_elapsed) => {
             let message = format!("probe timed out after {probe_timeout:.1?}");
             eprintln!("probe {id}: {message}");
-            if let Err(store_error) =
-                update_job_status(&state, &id, ProbeJobStatus::Failed, None, Some(message))
-            {
+            if let Err(store_error) = update_job_status(
+                &state,
+                &id,
+                ProbeJobStatus::Failed,
+                None,
+                Some(message.clone()),
+            ) {
                 eprintln!("probe {id}: failed to set timed-out state: {store_error}");
             }
+            notify_job_finished(
+                &state,
+                &id,
+                callback.as_ref(),
+                ProbeJobStatus::Failed,
+                None,
+                Some(message),
+            );
         }
     }
+}
+
+/// Record that shutdown stopped a queued or running job before it finished.
+fn interrupt_job(state: &RestServerState, id: &str, callback: Option<&PinnedCallback>) {
+    let error = SHUTDOWN_INTERRUPTED_ERROR.to_string();
+    if let Err(store_error) = update_job_status(
+        state,
+        id,
+        ProbeJobStatus::Interrupted,
+        None,
+        Some(error.clone()),
+    ) {
+        eprintln!("probe {id}: failed to set interrupted state: {store_error}");
+    }
+    notify_job_finished(
+        state,
+        id,
+        callback,
+        ProbeJobStatus::Interrupted,
+        None,
+        Some(error),
+    );
+}
+
+/// Emit `probe.completed`/`probe.failed`, plus `probe.threshold_breached` when any target's
+/// destination metrics exceed the configured thresholds. Interrupted jobs emit `probe.failed`.
+fn notify_job_finished(
+    state: &RestServerState,
+    id: &str,
+    callback: Option<&PinnedCallback>,
+    status: ProbeJobStatus,
+    result: Option<ProbeExecutionResult>,
+    error: Option<String>,
+) {
+    let webhooks = state.webhooks();
+    let config = webhooks.config();
+    if !config.is_enabled() {
+        return;
+    }
+
+    let breaches = result
+        .as_ref()
+        .map(|result| {
+            threshold_breaches(
+                config,
+                result.target_results.iter().filter_map(|target| {
+                    target
+                        .metrics
+                        .as_ref()
+                        .map(|metrics| (target.target.as_str(), metrics))
+                }),
+            )
+        })
+        .unwrap_or_default();
+
+    let kind = match status {
+        ProbeJobStatus::Completed => WebhookEventKind::ProbeCompleted,
+        _ => WebhookEventKind::ProbeFailed,
+    };
+    let mut event = WebhookEvent {
+        event: kind,
+        job_id: id.to_string(),
+        status: status.into(),
+        occurred_at: unix_timestamp(),
+        result: result.map(ProbeExecutionResultDto::from),
+        error,
+        breaches: Vec::new(),
+    };
+    webhooks.dispatch(callback, &event);
 
-    drop(permit);
+    if !breaches.is_empty() {
+        event.event = WebhookEventKind::ThresholdBreached;
+        event.breaches = breaches;
+        webhooks.dispatch(callback, &event);
+    }
 }
 
 async fn execute_probe(
@@ -544,7 +1675,9 @@ async fn execute_probe(
     let mut target_results = Vec::with_capacity(normalized.targets.len());
 
     for host in &normalized.targets {
-        let request = normalized_to_probe_request(&normalized, host.clone());
+        let pinned = normalized.target_addresses.get(host);
+        let probe_host = pinned.map_or_else(|| host.clone(), IpAddr::to_string);
+        let request = normalized_to_probe_request(&normalized, probe_host);
         let plan = match build_probe_plan(&request) {
             Ok(plan) => plan,
             Err(error) => {
@@ -552,64 +1685,65 @@ async fn execute_probe(
                     target: host.clone(),
                     success: false,
                     error: Some(format!("failed to build probe plan: {error}")),
+                    metrics: None,
+                    hops: Vec::new(),
                 });
                 targets.push(host.clone());
                 continue;
             }
         };
 
-        let validated_target = plan.validated_host.clone();
-        let trippy_args = plan.trippy_args;
-        let json_output = plan.json_output;
-        let runner_path = probe_runner_path.clone();
-
-        let probe_result = tokio::task::spawn_blocking(move || {
-            run_embedded_trippy(
-                runner_path.as_ref(),
-                &trippy_args,
-                json_output,
-                EMBEDDED_TRIPPY_ENV,
-                None,
-            )
-        })
+        let validated_target = match pinned {
+            Some(_) => host.clone(),
+            None => plan.validated_host.clone(),
+        };
+        let probe_result = capture_embedded_trippy_json(
+            probe_runner_path.as_ref(),
+            &plan.trippy_args,
+            EMBEDDED_TRIPPY_ENV,
+        )
         .await;
 
         match probe_result {
-            Ok(Ok(result)) if result.exit_code == 0 => {
+            Ok(output) if output.exit_code == 0 => {
                 targets.push(validated_target.clone());
                 target_results.push(ProbeTargetExecutionResult {
                     target: validated_target,
                     success: true,
                     error: None,
+                    metrics: output
+                        .report
+                        .as_ref()
+                        .and_then(ProbeTargetMetrics::from_trippy_report),
+                    hops: output
+                        .report
+                        .as_ref()
+                        .and_then(|report| parse_report_hops(report, "probe").ok())
+                        .unwrap_or_default(),
                 });
             }
-            Ok(Ok(result)) => {
+            Ok(output) => {
                 targets.push(validated_target.clone());
                 target_results.push(ProbeTargetExecutionResult {
                     target: validated_target,
                     success: false,
                     error: Some(format!(
                         "probe execution failed with exit code {}",
-                        result.exit_code
+                        output.exit_code
                     )),
-                });
-            }
-            Ok(Err(error)) => {
-                eprintln!("probe execution failed for {validated_target}: {error}");
-                targets.push(validated_target.clone());
-                target_results.push(ProbeTargetExecutionResult {
-                    target: validated_target,
-                    success: false,
-                    error: Some("probe execution failed".to_string()),
+                    metrics: None,
+                    hops: Vec::new(),
                 });
             }
             Err(error) => {
-                eprintln!("probe task panicked for {validated_target}: {error}");
+                eprintln!("probe execution failed for {validated_target}: {error:#}");
                 targets.push(validated_target.clone());
                 target_results.push(ProbeTargetExecutionResult {
                     target: validated_target,
                     success: false,
                     error: Some("probe execution failed".to_string()),
+                    metrics: None,
+                    hops: Vec::new(),
                 });
             }
         }
@@ -667,9 +1801,9 @@ fn normalized_to_probe_request(
         tcp: matches!(normalized.protocol, ProbeProtocol::Tcp),
         udp: matches!(normalized.protocol, ProbeProtocol::Udp),
         port: normalized.port,
-        source_port: None,
+        source_port: normalized.source_port,
         report: true,
-        json_output: None,
+        json_output: Some(JsonOutput::Compact),
         csv_output_path: None,
         count: normalized.count.or(Some(1)),
         interval_seconds: normalized.interval_seconds,
@@ -679,11 +1813,13 @@ fn normalized_to_probe_request(
         max_hops: normalized.max_hops,
         show_asn: normalized.include_asn,
         dns_lookup_as_info: normalized.include_asn,
-        packet_size: None,
-        src: None,
-        interface: None,
-        ecmp: None,
-        dns_cache_ttl_seconds: None,
+        packet_size: normalized.packet_size,
+        src: normalized.src,
+        interface: normalized.interface.clone(),
+        ecmp: normalized
+            .ecmp
+            .map(|strategy| strategy.as_str().to_string()),
+        dns_cache_ttl_seconds: normalized.dns_cache_ttl_seconds,
         trippy_flags: None,
         ui_mode: UiMode::Default,
         enhanced_ui: EnhancedUiConfig {
@@ -717,7 +1853,9 @@ fn update_job_status(
         result,
         error,
         finished_at: match status {
-            ProbeJobStatus::Completed | ProbeJobStatus::Failed => Some(Instant::now()),
+            ProbeJobStatus::Completed | ProbeJobStatus::Failed | ProbeJobStatus::Interrupted => {
+                Some(Instant::now())
+            }
             ProbeJobStatus::Queued | ProbeJobStatus::Running => None,
         },
     });
@@ -731,36 +1869,174 @@ async fn get_probe(
     headers: HeaderMap,
     Path(id): Path<String>,
 ) -> ApiResult<Json<ProbeResultResponseDto>> {
-    enforce_request_auth(&state.config, remote_addr, &headers)?;
-
-    run_with_timeout(state.config.request_timeout, async move {
-        if id.trim().is_empty() || id.chars().any(char::is_whitespace) {
-            return Err(error_response(
-                StatusCode::BAD_REQUEST,
-                "invalid_probe_id",
-                "Invalid probe id",
-                "probe id must not be empty or contain whitespace".to_string(),
-            ));
-        }
+    let config = state.config();
+    enforce_request_auth(&config, remote_addr, &headers)?;
+
+    run_with_timeout(config.request_timeout, async move {
+        let job = find_job(&state, &id)?;
+        let mut response = ProbeResultResponseDto::from(&job);
+        response.data.queue_position = queue_position(&state, &job)?;
+        Ok(Json(response))
+    })
+    .await
+}
 
-        let mut store = state
-            .store
-            .lock()
-            .map_err(|_| internal_error_response("failed to lock probe store"))?;
-        let job = store.get(&id).ok_or_else(|| {
+fn find_job(state: &RestServerState, id: &str) -> ApiResult<ProbeJob> {
+    if id.trim().is_empty() || id.chars().any(char::is_whitespace) {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "invalid_probe_id",
+            "Invalid probe id",
+            "probe id must not be empty or contain whitespace".to_string(),
+        ));
+    }
+
+    state
+        .store
+        .lock()
+        .map_err(|_| internal_error_response("failed to lock probe store"))?
+        .get(id)
+        .ok_or_else(|| {
             error_response(
                 StatusCode::NOT_FOUND,
                 "probe_not_found",
                 "Probe not found",
                 format!("probe not found: {id}"),
             )
-        })?;
+        })
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ProbeDiffQuery {
+    #[serde(default)]
+    format: ProbeDiffFormat,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ProbeDiffFormat {
+    #[default]
+    Json,
+    Text,
+    Markdown,
+}
+
+/// Compare the per-hop results of two completed jobs, target by target. `format=text` and
+/// `format=markdown` return the same renderings as `mtr diff`.
+async fn get_probe_diff(
+    ConnectInfo(remote_addr): ConnectInfo<std::net::SocketAddr>,
+    State(state): State<RestServerState>,
+    headers: HeaderMap,
+    Path((id, other_id)): Path<(String, String)>,
+    query: Result<Query<ProbeDiffQuery>, QueryRejection>,
+) -> ApiResult<axum::response::Response> {
+    let config = state.config();
+    enforce_request_auth(&config, remote_addr, &headers)?;
+    let Query(query) = query.map_err(|rejection| {
+        error_response(
+            StatusCode::BAD_REQUEST,
+            "invalid_request",
+            "Invalid request",
+            rejection.body_text(),
+        )
+    })?;
+
+    run_with_timeout(config.request_timeout, async move {
+        let from = find_job(&state, &id)?;
+        let to = find_job(&state, &other_id)?;
+        let targets = diff_jobs(&from, &to)?;
+        let verdict = DiffVerdict::worst(targets.iter().map(|diff| diff.verdict));
 
-        Ok(Json(ProbeResultResponseDto::from(&job)))
+        let response = match query.format {
+            ProbeDiffFormat::Json => Json(ProbeDiffResponseDto {
+                meta: ApiResponseMetaDto {
+                    schema_version: "v1",
+                    request_id: None,
+                },
+                data: ProbeDiffDataDto {
+                    from: id,
+                    to: other_id,
+                    verdict,
+                    targets,
+                },
+            })
+            .into_response(),
+            ProbeDiffFormat::Text => {
+                let mut body = format!(
+                    "Probe diff: {id} -> {other_id}\nOverall verdict: {}\n",
+                    verdict.as_str()
+                );
+                for diff in &targets {
+                    body.push('\n');
+                    body.push_str(&render_text(diff));
+                }
+                ([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], body).into_response()
+            }
+            ProbeDiffFormat::Markdown => {
+                let mut body = format!(
+                    "## Probe diff: `{id}` -> `{other_id}`\n\n**Overall verdict:** {}\n",
+                    verdict.as_str()
+                );
+                for diff in &targets {
+                    body.push('\n');
+                    body.push_str(&render_markdown(diff));
+                }
+                (
+                    [(header::CONTENT_TYPE, "text/markdown; charset=utf-8")],
+                    body,
+                )
+                    .into_response()
+            }
+        };
+        Ok(response)
     })
     .await
 }
 
+/// Diff every target that both jobs probed and recorded hops for, in `from`'s target order.
+fn diff_jobs(from: &ProbeJob, to: &ProbeJob) -> ApiResult<Vec<ReportDiff>> {
+    let target_hops = |job: &ProbeJob| -> ApiResult<Vec<(String, Vec<ReportHop>)>> {
+        match (job.status, &job.result) {
+            (ProbeJobStatus::Completed, Some(result)) => Ok(result
+                .target_results
+                .iter()
+                .filter(|target| !target.hops.is_empty())
+                .map(|target| (target.target.clone(), target.hops.clone()))
+                .collect()),
+            _ => Err(error_response(
+                StatusCode::CONFLICT,
+                "probe_not_comparable",
+                "Probe not comparable",
+                format!("probe {} has not completed", job.id),
+            )),
+        }
+    };
+    let old_targets = target_hops(from)?;
+    let new_targets = target_hops(to)?;
+
+    let diffs = old_targets
+        .iter()
+        .filter_map(|(target, old_hops)| {
+            let (_, new_hops) = new_targets.iter().find(|(other, _)| other == target)?;
+            let mut diff = diff_reports(old_hops, new_hops);
+            diff.target = Some(target.clone());
+            Some(diff)
+        })
+        .collect::<Vec<_>>();
+    if diffs.is_empty() {
+        return Err(error_response(
+            StatusCode::CONFLICT,
+            "probe_not_comparable",
+            "Probe not comparable",
+            format!(
+                "probes {} and {} have no target with hop data in common",
+                from.id, to.id
+            ),
+        ));
+    }
+    Ok(diffs)
+}
+
 async fn run_with_timeout<T>(
     duration: std::time::Duration,
     future: impl std::future::Future<Output = ApiResult<T>>,
@@ -795,6 +2071,36 @@ fn validation_error_response(error: RestApiValidationError) -> ApiError {
             "Rate limited",
             error.to_string(),
         ),
+        RestApiValidationError::QueueFull { .. } => error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "queue_full",
+            "Probe queue full",
+            error.to_string(),
+        ),
+        RestApiValidationError::TargetForbidden(_) => error_response(
+            StatusCode::FORBIDDEN,
+            "target_forbidden",
+            "Target forbidden",
+            error.to_string(),
+        ),
+        RestApiValidationError::OptionForbidden(_) => error_response(
+            StatusCode::FORBIDDEN,
+            "option_forbidden",
+            "Option forbidden",
+            error.to_string(),
+        ),
+        RestApiValidationError::IdempotencyKeyMismatch(_) => error_response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "idempotency_key_mismatch",
+            "Idempotency key mismatch",
+            error.to_string(),
+        ),
+        RestApiValidationError::IdempotencyKeyConflict(_) => error_response(
+            StatusCode::CONFLICT,
+            "idempotency_key_conflict",
+            "Idempotency key conflict",
+            error.to_string(),
+        ),
         RestApiValidationError::OversizedPayload(_) => error_response(
             StatusCode::PAYLOAD_TOO_LARGE,
             "payload_too_large",
@@ -837,11 +2143,13 @@ fn enforce_request_auth(
     config: &RestApiConfig,
     remote_addr: std::net::SocketAddr,
     headers: &HeaderMap,
-) -> ApiResult<()> {
+) -> ApiResult<RequestPrincipal> {
     let request_is_loopback = remote_addr.ip().is_loopback();
 
     match config.auth_strategy {
-        AuthStrategy::NoneLocalOnly if request_is_loopback => Ok(()),
+        AuthStrategy::NoneLocalOnly if request_is_loopback => {
+            Ok(RequestPrincipal::new(LOCAL_PRINCIPAL))
+        }
         AuthStrategy::NoneLocalOnly => {
             Err(RequestAuthError::NoneLocalOnlyRemoteAccessDenied.into_api_error())
         }
@@ -853,24 +2161,32 @@ fn enforce_request_auth(
                 .filter(|value| !value.is_empty())
                 .ok_or_else(|| RequestAuthError::MissingApiKeyHeader.into_api_error())?;
 
-            let expected = config
+            let unnamed = config
                 .api_key
                 .as_deref()
-                .filter(|value| !value.trim().is_empty())
-                .ok_or_else(|| {
-                    error_response(
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        "auth_configuration_error",
-                        "Internal server error",
-                        "auth_strategy=api-key requires configured api_key".to_string(),
-                    )
-                })?;
-
-            if constant_time_equals(provided.as_bytes(), expected.as_bytes()) {
-                Ok(())
-            } else {
-                Err(RequestAuthError::InvalidApiKey.into_api_error())
+                .filter(|value| !value.trim().is_empty());
+            if unnamed.is_none() && config.named_api_keys.is_empty() {
+                return Err(error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "auth_configuration_error",
+                    "Internal server error",
+                    "auth_strategy=api-key requires configured api_key".to_string(),
+                ));
             }
+
+            let matches =
+                |expected: &str| constant_time_equals(provided.as_bytes(), expected.as_bytes());
+            config
+                .named_api_keys
+                .iter()
+                .find(|named| matches(&named.key))
+                .map(|named| RequestPrincipal::new(named.name.as_str()))
+                .or_else(|| {
+                    unnamed
+                        .filter(|expected| matches(expected))
+                        .map(|_| RequestPrincipal::new(API_KEY_PRINCIPAL))
+                })
+                .ok_or_else(|| RequestAuthError::InvalidApiKey.into_api_error())
         }
         AuthStrategy::Mtls => {
             let ingress_is_trusted = config
@@ -882,12 +2198,14 @@ fn enforce_request_auth(
                 return Err(RequestAuthError::UntrustedMtlsIngress.into_api_error());
             }
 
-            validate_mtls_identity_headers(headers).map_err(RequestAuthError::into_api_error)
+            let certificate = validate_mtls_identity_headers(headers)
+                .map_err(RequestAuthError::into_api_error)?;
+            Ok(RequestPrincipal::new(certificate.unwrap_or(MTLS_PRINCIPAL)))
         }
     }
 }
 
-fn validate_mtls_identity_headers(headers: &HeaderMap) -> Result<(), RequestAuthError> {
+fn validate_mtls_identity_headers(headers: &HeaderMap) -> Result<Option<&str>, RequestAuthError> {
     let certificate = single_nonempty_header_value(headers, MTLS_CLIENT_CERT_HEADER)?;
     let verification = single_nonempty_header_value(headers, MTLS_VERIFY_HEADER)?;
 
@@ -899,7 +2217,7 @@ fn validate_mtls_identity_headers(headers: &HeaderMap) -> Result<(), RequestAuth
         return Err(RequestAuthError::InvalidMtlsIdentity);
     }
 
-    Ok(())
+    Ok(certificate)
 }
 
 fn single_nonempty_header_value<'a>(
@@ -930,27 +2248,29 @@ fn constant_time_equals(a: &[u8], b: &[u8]) -> bool {
     a.ct_eq(b).into()
 }
 
-async fn shutdown_signal() {
-    let ctrl_c = async {
+/// Resolves on Ctrl+C or, on Unix, SIGTERM. The Unix handlers are installed when this is
+/// called rather than when the future is first polled, so a signal that arrives while the
+/// server is still starting is not lost to the default action. Must be called inside a tokio
+/// runtime.
+pub fn shutdown_signal() -> impl Future<Output = ()> + Send + 'static {
+    #[cfg(unix)]
+    let mut interrupt = signal::unix::signal(signal::unix::SignalKind::interrupt())
+        .expect("failed to install SIGINT signal handler");
+    #[cfg(unix)]
+    let mut terminate = signal::unix::signal(signal::unix::SignalKind::terminate())
+        .expect("failed to install SIGTERM signal handler");
+
+    async move {
+        #[cfg(unix)]
+        tokio::select! {
+            _ = interrupt.recv() => {},
+            _ = terminate.recv() => {},
+        }
+
+        #[cfg(not(unix))]
         signal::ctrl_c()
             .await
             .expect("failed to install CTRL+C signal handler");
-    };
-
-    #[cfg(unix)]
-    let terminate = async {
-        signal::unix::signal(signal::unix::SignalKind::terminate())
-            .expect("failed to install SIGTERM signal handler")
-            .recv()
-            .await;
-    };
-
-    #[cfg(not(unix))]
-    let terminate = std::future::pending::<()>();
-
-    tokio::select! {
-        _ = ctrl_c => {},
-        _ = terminate => {},
     }
 }
 
@@ -958,6 +2278,70 @@ async fn shutdown_signal() {
 mod tests {
     use super::*;
 
+    #[test]
+    fn reloads_keep_the_rate_limiter_window() {
+        let config = RestApiConfig {
+            max_requests_per_window: 2,
+            ..RestApiConfig::default()
+        };
+        let state = RestServerState::new_with_probe_runner(config.clone(), PathBuf::from("mtr"))
+            .expect("state should initialize");
+        let remaining = || {
+            state
+                .probe_rate_limiter
+                .lock()
+                .unwrap()
+                .snapshot(Instant::now())
+                .remaining
+        };
+        for _ in 0..2 {
+            state
+                .probe_rate_limiter
+                .lock()
+                .unwrap()
+                .allow(Instant::now())
+                .expect("within the limit");
+        }
+
+        let report = state
+            .reload(RestApiConfig {
+                max_queued_probes: config.max_queued_probes + 1,
+                ..config.clone()
+            })
+            .expect("reload should apply");
+        assert_eq!(report.changed, ["max_queued_probes"]);
+        assert_eq!(remaining(), 0);
+
+        state
+            .reload(RestApiConfig {
+                max_requests_per_window: 5,
+                ..config
+            })
+            .expect("reload should apply");
+        assert_eq!(remaining(), 3);
+    }
+
+    #[tokio::test]
+    async fn restrictive_policies_pin_hostname_targets_to_the_checked_address() {
+        let policy = TargetPolicy {
+            allow: vec!["127.0.0.0/8".parse().unwrap(), "::1".parse().unwrap()],
+            deny: Vec::new(),
+            block_private_ranges: false,
+        };
+        let targets = vec!["localhost".to_string(), "127.0.0.2".to_string()];
+
+        let pinned = enforce_target_policy(&policy, &targets)
+            .await
+            .expect("loopback targets are allowed");
+        assert_eq!(pinned.len(), 1, "IP literals need no pinning: {pinned:?}");
+        assert!(pinned["localhost"].is_loopback());
+
+        let unpinned = enforce_target_policy(&TargetPolicy::default(), &targets)
+            .await
+            .expect("the default policy allows everything");
+        assert!(unpinned.is_empty());
+    }
+
     #[test]
     fn constant_time_equals_handles_equal_and_mismatched_inputs() {
         assert!(constant_time_equals(b"secret-key", b"secret-key"));
@@ -971,6 +2355,8 @@ mod tests {
             jobs: HashMap::new(),
             max_completed_jobs: 1,
             completed_job_ttl: std::time::Duration::from_millis(50),
+            idempotency_keys: HashMap::new(),
+            idempotency_key_ttl: std::time::Duration::from_secs(60),
         };
 
         let old = Instant::now() - std::time::Duration::from_millis(100);
@@ -1003,6 +2389,8 @@ mod tests {
             jobs: HashMap::new(),
             max_completed_jobs: 1,
             completed_job_ttl: std::time::Duration::from_secs(60),
+            idempotency_keys: HashMap::new(),
+            idempotency_key_ttl: std::time::Duration::from_secs(60),
         };
 
         store.upsert(ProbeJob {
@@ -1024,4 +2412,56 @@ mod tests {
         assert_eq!(store.jobs.len(), 1);
         assert!(store.jobs.contains_key("completed-2"));
     }
+
+    #[test]
+    fn probe_store_replays_idempotent_requests_by_principal_and_fingerprint() {
+        let mut store = ProbeStore::new(&RestApiConfig::default());
+        let principal = RequestPrincipal::new("api-key");
+        let payload = br#"{"targets":["1.1.1.1"],"protocol":"icmp"}"#;
+        let request = IdempotentRequest::new(&principal, "retry-1".to_string(), payload);
+        let job = ProbeJob {
+            id: "probe-1".to_string(),
+            status: ProbeJobStatus::Queued,
+            result: None,
+            error: None,
+            finished_at: None,
+        };
+
+        assert!(store.find_idempotent(&request).unwrap().is_none());
+        store.insert_idempotent(job, &request);
+
+        let reordered = IdempotentRequest::new(
+            &principal,
+            "retry-1".to_string(),
+            br#"{ "protocol": "icmp", "targets": ["1.1.1.1"], "count": null }"#,
+        );
+        let replayed = store.find_idempotent(&reordered).unwrap();
+        assert_eq!(replayed.map(|job| job.id), Some("probe-1".to_string()));
+
+        let other_principal = IdempotentRequest::new(
+            &RequestPrincipal::new("local"),
+            "retry-1".to_string(),
+            payload,
+        );
+        assert!(store.find_idempotent(&other_principal).unwrap().is_none());
+
+        let changed = IdempotentRequest::new(
+            &principal,
+            "retry-1".to_string(),
+            br#"{"targets":["8.8.8.8"],"protocol":"icmp"}"#,
+        );
+        assert!(matches!(
+            store.find_idempotent(&changed),
+            Err(RestApiValidationError::IdempotencyKeyMismatch(_))
+        ));
+
+        store.jobs.clear();
+        assert!(matches!(
+            store.find_idempotent(&request),
+            Err(RestApiValidationError::IdempotencyKeyConflict(_))
+        ));
+
+        store.idempotency_key_ttl = std::time::Duration::ZERO;
+        assert!(store.find_idempotent(&request).unwrap().is_none());
+    }
 }
@@ -0,0 +1,179 @@
+//! Minimal `sd_notify(3)` client for running the REST API as a systemd `Type=notify` service.
+//!
+//! Only the datagram protocol is implemented: state strings such as `READY=1` are sent to the
+//! socket named by `NOTIFY_SOCKET`. Without that variable every call is a no-op, so the same
+//! binary works outside systemd.
+
+use std::ffi::OsString;
+use std::io;
+use std::os::unix::net::{SocketAddr, UnixDatagram};
+use std::time::Duration;
+
+pub const NOTIFY_SOCKET_ENV: &str = "NOTIFY_SOCKET";
+pub const WATCHDOG_USEC_ENV: &str = "WATCHDOG_USEC";
+pub const WATCHDOG_PID_ENV: &str = "WATCHDOG_PID";
+
+/// Connection to the service manager's notification socket.
+#[derive(Debug)]
+pub struct SystemdNotifier {
+    socket: UnixDatagram,
+    address: SocketAddr,
+    watchdog_interval: Option<Duration>,
+}
+
+impl SystemdNotifier {
+    /// Read `NOTIFY_SOCKET` and the watchdog variables from the process environment.
+    pub fn from_env() -> io::Result<Option<Self>> {
+        Self::from_vars(|name| std::env::var_os(name), std::process::id())
+    }
+
+    /// Like [`SystemdNotifier::from_env`], with an injectable variable lookup and pid.
+    pub fn from_vars(var: impl Fn(&str) -> Option<OsString>, pid: u32) -> io::Result<Option<Self>> {
+        let Some(socket_name) = var(NOTIFY_SOCKET_ENV).filter(|value| !value.is_empty()) else {
+            return Ok(None);
+        };
+
+        let address = notify_address(&socket_name)?;
+        let watchdog_interval = watchdog_interval(&var, pid);
+        Ok(Some(Self {
+            socket: UnixDatagram::unbound()?,
+            address,
+            watchdog_interval,
+        }))
+    }
+
+    /// Send newline-separated `KEY=VALUE` assignments.
+    pub fn notify(&self, state: &str) -> io::Result<()> {
+        self.socket
+            .send_to_addr(state.as_bytes(), &self.address)
+            .map(|_| ())
+    }
+
+    pub fn ready(&self, status: &str) -> io::Result<()> {
+        self.notify(&format!(
+            "READY=1\nSTATUS={status}\nMAINPID={}",
+            std::process::id()
+        ))
+    }
+
+    pub fn stopping(&self, status: &str) -> io::Result<()> {
+        self.notify(&format!("STOPPING=1\nSTATUS={status}"))
+    }
+
+    pub fn watchdog(&self) -> io::Result<()> {
+        self.notify("WATCHDOG=1")
+    }
+
+    /// How often to send `WATCHDOG=1`: half of `WATCHDOG_USEC`, as `sd_watchdog_enabled(3)`
+    /// recommends. `None` when the watchdog is disabled or meant for another process.
+    pub fn watchdog_interval(&self) -> Option<Duration> {
+        self.watchdog_interval
+    }
+}
+
+fn notify_address(socket_name: &OsString) -> io::Result<SocketAddr> {
+    let bytes = socket_name.as_encoded_bytes();
+    match bytes.first() {
+        Some(b'/') => SocketAddr::from_pathname(socket_name),
+        #[cfg(target_os = "linux")]
+        Some(b'@') => {
+            use std::os::linux::net::SocketAddrExt;
+            SocketAddr::from_abstract_name(&bytes[1..])
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "unsupported {NOTIFY_SOCKET_ENV} address `{}`",
+                socket_name.to_string_lossy()
+            ),
+        )),
+    }
+}
+
+fn watchdog_interval(var: &impl Fn(&str) -> Option<OsString>, pid: u32) -> Option<Duration> {
+    if let Some(watchdog_pid) = var(WATCHDOG_PID_ENV)
+        && watchdog_pid.to_str().and_then(|value| value.parse().ok()) != Some(pid)
+    {
+        return None;
+    }
+
+    let usec = var(WATCHDOG_USEC_ENV)?.to_str()?.parse::<u64>().ok()?;
+    (usec > 0).then(|| Duration::from_micros(usec) / 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn vars(entries: &[(&str, &str)]) -> impl Fn(&str) -> Option<OsString> {
+        let entries = entries
+            .iter()
+            .map(|(name, value)| (name.to_string(), OsString::from(value)))
+            .collect::<HashMap<_, _>>();
+        move |name| entries.get(name).cloned()
+    }
+
+    #[test]
+    fn missing_notify_socket_disables_notifications() {
+        let notifier = SystemdNotifier::from_vars(vars(&[]), 42).expect("lookup should succeed");
+        assert!(notifier.is_none());
+    }
+
+    #[test]
+    fn notifications_reach_the_socket_and_watchdog_uses_half_the_timeout() {
+        let dir = tempfile::tempdir().expect("tempdir should exist");
+        let path = dir.path().join("notify.sock");
+        let receiver = UnixDatagram::bind(&path).expect("fake notify socket should bind");
+        let socket = path.to_str().expect("utf-8 temp path");
+
+        let notifier = SystemdNotifier::from_vars(
+            vars(&[
+                (NOTIFY_SOCKET_ENV, socket),
+                (WATCHDOG_USEC_ENV, "2000000"),
+                (WATCHDOG_PID_ENV, "42"),
+            ]),
+            42,
+        )
+        .expect("notifier should connect")
+        .expect("NOTIFY_SOCKET is set");
+        assert_eq!(notifier.watchdog_interval(), Some(Duration::from_secs(1)));
+
+        notifier.ready("serving").expect("ready should send");
+        let mut buffer = [0u8; 256];
+        let len = receiver.recv(&mut buffer).expect("datagram should arrive");
+        let message = String::from_utf8_lossy(&buffer[..len]);
+        assert!(message.starts_with("READY=1\nSTATUS=serving\nMAINPID="));
+
+        notifier.watchdog().expect("watchdog should send");
+        let len = receiver.recv(&mut buffer).expect("datagram should arrive");
+        assert_eq!(&buffer[..len], b"WATCHDOG=1");
+    }
+
+    #[test]
+    fn watchdog_for_another_pid_is_ignored() {
+        let dir = tempfile::tempdir().expect("tempdir should exist");
+        let path = dir.path().join("notify.sock");
+        let _receiver = UnixDatagram::bind(&path).expect("fake notify socket should bind");
+
+        let notifier = SystemdNotifier::from_vars(
+            vars(&[
+                (NOTIFY_SOCKET_ENV, path.to_str().expect("utf-8 temp path")),
+                (WATCHDOG_USEC_ENV, "2000000"),
+                (WATCHDOG_PID_ENV, "7"),
+            ]),
+            42,
+        )
+        .expect("notifier should connect")
+        .expect("NOTIFY_SOCKET is set");
+        assert_eq!(notifier.watchdog_interval(), None);
+    }
+
+    #[test]
+    fn relative_socket_paths_are_rejected() {
+        let error = SystemdNotifier::from_vars(vars(&[(NOTIFY_SOCKET_ENV, "notify.sock")]), 42)
+            .expect_err("relative path should be rejected");
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,347 @@
+//! Service host mode: run the REST API under the Windows Service Control Manager or as a
+//! systemd `Type=notify` daemon instead of a foreground console process.
+//!
+//! Both platforms share the pid file and log file handling here. Platform glue lives in
+//! [`crate::service::sd_notify`] and `crate::service::windows_service`.
+
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::service::config_reload::ConfigReloadOptions;
+use crate::service::rest_api::RestApiConfig;
+
+pub const DEFAULT_SERVICE_NAME: &str = "windows-mtr";
+pub const SERVICE_DISPLAY_NAME: &str = "windows-mtr REST API";
+pub const SERVICE_DESCRIPTION: &str =
+    "Runs the windows-mtr REST API for remote traceroute and ping probes.";
+
+/// How `mtr service run` hosts the API.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ServiceHostOptions {
+    /// Service Control Manager name; ignored outside Windows.
+    pub service_name: String,
+    pub pid_file: Option<PathBuf>,
+    /// Append stderr (server logs and panics) to this file instead of the console.
+    pub log_file: Option<PathBuf>,
+}
+
+impl ServiceHostOptions {
+    /// Arguments that make the binary run as this service: `api_args`, then
+    /// `service run` with these options.
+    pub fn run_arguments(&self, api_args: &[OsString]) -> Vec<OsString> {
+        let mut args = api_args.to_vec();
+        args.extend(["service", "run", "--service-name"].map(OsString::from));
+        args.push(OsString::from(&self.service_name));
+        if let Some(pid_file) = &self.pid_file {
+            args.push(OsString::from("--pid-file"));
+            args.push(pid_file.clone().into_os_string());
+        }
+        if let Some(log_file) = &self.log_file {
+            args.push(OsString::from("--log-file"));
+            args.push(log_file.clone().into_os_string());
+        }
+        args
+    }
+}
+
+/// Run the API until the service manager stops it. Blocks the calling thread.
+pub fn run_service_host(
+    config: RestApiConfig,
+    reload: ConfigReloadOptions,
+    options: ServiceHostOptions,
+) -> anyhow::Result<()> {
+    if let Some(log_file) = &options.log_file {
+        redirect_stderr(log_file)?;
+    }
+    let _pid_file = options
+        .pid_file
+        .as_deref()
+        .map(PidFile::create)
+        .transpose()?;
+
+    platform::run(config, reload, &options)
+}
+
+/// Writes the current process id on creation and removes the file when dropped.
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    pub fn create(path: &Path) -> anyhow::Result<Self> {
+        create_parent_dir(path)?;
+        let mut file = File::create(path)
+            .with_context(|| format!("failed to create pid file `{}`", path.display()))?;
+        writeln!(file, "{}", std::process::id())
+            .with_context(|| format!("failed to write pid file `{}`", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn create_parent_dir(path: &Path) -> anyhow::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create directory `{}`", parent.display())),
+        _ => Ok(()),
+    }
+}
+
+/// Point the process-wide stderr (and so every `eprintln!`, panic message, and inherited
+/// child stderr) at `path`, appending.
+fn redirect_stderr(path: &Path) -> anyhow::Result<()> {
+    create_parent_dir(path)?;
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open log file `{}`", path.display()))?;
+    platform::set_stderr(file)
+        .with_context(|| format!("failed to redirect logs to `{}`", path.display()))
+}
+
+/// Quote `args` into a Windows command line, following the rules `CommandLineToArgvW` and
+/// the MSVC runtime use to split it again.
+pub fn windows_command_line<'a>(args: impl IntoIterator<Item = &'a str>) -> String {
+    let mut line = String::new();
+    for arg in args {
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+            line.push_str(arg);
+            continue;
+        }
+
+        line.push('"');
+        let mut backslashes = 0;
+        for ch in arg.chars() {
+            match ch {
+                '\\' => backslashes += 1,
+                '"' => {
+                    line.extend(std::iter::repeat_n('\\', backslashes * 2 + 1));
+                    line.push('"');
+                    backslashes = 0;
+                }
+                _ => {
+                    line.extend(std::iter::repeat_n('\\', backslashes));
+                    line.push(ch);
+                    backslashes = 0;
+                }
+            }
+        }
+        line.extend(std::iter::repeat_n('\\', backslashes * 2));
+        line.push('"');
+    }
+    line
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::fs::File;
+    use std::io;
+    use std::os::fd::AsRawFd;
+    use std::sync::Arc;
+
+    use anyhow::Context;
+
+    use super::ServiceHostOptions;
+    use crate::service::config_reload::ConfigReloadOptions;
+    use crate::service::rest_api::RestApiConfig;
+    use crate::service::rest_server::{serve_rest_api, shutdown_signal};
+    use crate::service::sd_notify::SystemdNotifier;
+
+    pub(super) fn run(
+        config: RestApiConfig,
+        reload: ConfigReloadOptions,
+        _options: &ServiceHostOptions,
+    ) -> anyhow::Result<()> {
+        let notifier = SystemdNotifier::from_env()
+            .context("failed to open the systemd notification socket")?
+            .map(Arc::new);
+        let runtime = tokio::runtime::Runtime::new().context("failed to start tokio runtime")?;
+
+        runtime.block_on(async move {
+            let stopping = notifier.clone();
+            let signal = shutdown_signal();
+            let shutdown = async move {
+                signal.await;
+                if let Some(notifier) = stopping {
+                    report(notifier.stopping("Shutting down"));
+                }
+            };
+
+            let result = serve_rest_api(config, Some(reload), shutdown, |addr| {
+                eprintln!("REST API listening on {addr}");
+                if let Some(notifier) = &notifier {
+                    report(notifier.ready(&format!("Serving REST API on {addr}")));
+                    if let Some(interval) = notifier.watchdog_interval() {
+                        tokio::spawn(keep_watchdog_alive(notifier.clone(), interval));
+                    }
+                }
+            })
+            .await;
+
+            if let (Err(error), Some(notifier)) = (&result, &notifier) {
+                report(notifier.notify(&format!("STATUS=Failed: {error:#}")));
+            }
+            result
+        })
+    }
+
+    /// Pings the watchdog from the async runtime, so a wedged runtime stops the pings and
+    /// systemd restarts the service.
+    async fn keep_watchdog_alive(notifier: Arc<SystemdNotifier>, interval: std::time::Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            report(notifier.watchdog());
+        }
+    }
+
+    fn report(result: io::Result<()>) {
+        if let Err(error) = result {
+            eprintln!("systemd notification failed: {error}");
+        }
+    }
+
+    pub(super) fn set_stderr(file: File) -> io::Result<()> {
+        // SAFETY: both descriptors are valid for the duration of the call; `dup2` atomically
+        // replaces descriptor 2 and leaves `file` to be closed normally when dropped.
+        // nosemgrep: rust.lang.security.unsafe-usage.unsafe-usage
+        let result = unsafe { libc::dup2(file.as_raw_fd(), libc::STDERR_FILENO) };
+        if result == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::fs::File;
+    use std::io;
+    use std::os::windows::io::IntoRawHandle;
+
+    use super::ServiceHostOptions;
+    use crate::service::config_reload::ConfigReloadOptions;
+    use crate::service::rest_api::RestApiConfig;
+    use crate::service::windows_service;
+
+    pub(super) fn run(
+        config: RestApiConfig,
+        reload: ConfigReloadOptions,
+        options: &ServiceHostOptions,
+    ) -> anyhow::Result<()> {
+        windows_service::run_dispatcher(&options.service_name, config, reload)
+    }
+
+    pub(super) fn set_stderr(file: File) -> io::Result<()> {
+        use windows_sys::Win32::System::Console::{STD_ERROR_HANDLE, SetStdHandle};
+
+        // The handle is intentionally leaked: it stays the process stderr until exit.
+        let handle = file.into_raw_handle();
+        // SAFETY: `handle` is an open file handle owned by this process for its lifetime.
+        // nosemgrep: rust.lang.security.unsafe-usage.unsafe-usage
+        if unsafe { SetStdHandle(STD_ERROR_HANDLE, handle) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use std::fs::File;
+    use std::io;
+
+    use super::ServiceHostOptions;
+    use crate::service::config_reload::ConfigReloadOptions;
+    use crate::service::rest_api::RestApiConfig;
+
+    pub(super) fn run(
+        _config: RestApiConfig,
+        _reload: ConfigReloadOptions,
+        _options: &ServiceHostOptions,
+    ) -> anyhow::Result<()> {
+        anyhow::bail!("service host mode is only available on Windows and Unix")
+    }
+
+    pub(super) fn set_stderr(_file: File) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_arguments_append_service_options_after_api_flags() {
+        let options = ServiceHostOptions {
+            service_name: "mtr-api".to_string(),
+            pid_file: None,
+            log_file: Some(PathBuf::from("C:\\ProgramData\\windows-mtr\\api.log")),
+        };
+        let args = options.run_arguments(&[
+            OsString::from("--config"),
+            OsString::from("C:\\mtr\\config.toml"),
+        ]);
+
+        assert_eq!(
+            args,
+            [
+                "--config",
+                "C:\\mtr\\config.toml",
+                "service",
+                "run",
+                "--service-name",
+                "mtr-api",
+                "--log-file",
+                "C:\\ProgramData\\windows-mtr\\api.log",
+            ]
+            .map(OsString::from)
+        );
+    }
+
+    #[test]
+    fn windows_command_line_quotes_only_when_needed() {
+        assert_eq!(
+            windows_command_line([
+                "C:\\Program Files\\mtr\\mtr.exe",
+                "--api-bind",
+                "127.0.0.1:3000",
+                "",
+                "say \"hi\"",
+                "C:\\dir with space\\",
+            ]),
+            "\"C:\\Program Files\\mtr\\mtr.exe\" --api-bind 127.0.0.1:3000 \"\" \
+             \"say \\\"hi\\\"\" \"C:\\dir with space\\\\\""
+        );
+    }
+
+    #[test]
+    fn pid_file_holds_process_id_until_dropped() {
+        let dir = tempfile::tempdir().expect("tempdir should exist");
+        let path = dir.path().join("run").join("mtr.pid");
+
+        let pid_file = PidFile::create(&path).expect("pid file should be created");
+        let contents = std::fs::read_to_string(&path).expect("pid file should be readable");
+        assert_eq!(contents.trim(), std::process::id().to_string());
+
+        drop(pid_file);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,593 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// IPv4 ranges rejected by the built-in private/reserved preset.
+const RESERVED_IPV4_RANGES: &[(Ipv4Addr, u8)] = &[
+    (Ipv4Addr::new(0, 0, 0, 0), 8),
+    (Ipv4Addr::new(10, 0, 0, 0), 8),
+    (Ipv4Addr::new(100, 64, 0, 0), 10),
+    (Ipv4Addr::new(127, 0, 0, 0), 8),
+    (Ipv4Addr::new(169, 254, 0, 0), 16),
+    (Ipv4Addr::new(172, 16, 0, 0), 12),
+    (Ipv4Addr::new(192, 0, 0, 0), 24),
+    (Ipv4Addr::new(192, 0, 2, 0), 24),
+    (Ipv4Addr::new(192, 88, 99, 0), 24),
+    (Ipv4Addr::new(192, 168, 0, 0), 16),
+    (Ipv4Addr::new(198, 18, 0, 0), 15),
+    (Ipv4Addr::new(198, 51, 100, 0), 24),
+    (Ipv4Addr::new(203, 0, 113, 0), 24),
+    (Ipv4Addr::new(224, 0, 0, 0), 4),
+    (Ipv4Addr::new(240, 0, 0, 0), 4),
+];
+
+/// IPv6 ranges rejected by the built-in private/reserved preset.
+const RESERVED_IPV6_RANGES: &[(Ipv6Addr, u8)] = &[
+    (Ipv6Addr::UNSPECIFIED, 128),
+    (Ipv6Addr::LOCALHOST, 128),
+    (Ipv6Addr::new(0x64, 0xff9b, 0, 0, 0, 0, 0, 0), 96),
+    (Ipv6Addr::new(0x100, 0, 0, 0, 0, 0, 0, 0), 64),
+    (Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32),
+    (Ipv6Addr::new(0xfc00, 0, 0, 0, 0, 0, 0, 0), 7),
+    (Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 0), 10),
+    (Ipv6Addr::new(0xff00, 0, 0, 0, 0, 0, 0, 0), 8),
+];
+
+/// An IPv4 or IPv6 network in CIDR notation.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct IpCidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpCidr {
+    pub fn new(address: IpAddr, prefix_len: u8) -> Result<Self, TargetPolicyError> {
+        let max_prefix = match address {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        if prefix_len > max_prefix {
+            return Err(TargetPolicyError::InvalidRule(format!(
+                "prefix length /{prefix_len} exceeds /{max_prefix} for {address}"
+            )));
+        }
+
+        Ok(Self {
+            network: mask_address(address, prefix_len),
+            prefix_len,
+        })
+    }
+
+    pub fn contains(&self, address: IpAddr) -> bool {
+        let address = canonical_address(address);
+        match (self.network, address) {
+            (IpAddr::V4(_), IpAddr::V4(_)) | (IpAddr::V6(_), IpAddr::V6(_)) => {
+                mask_address(address, self.prefix_len) == self.network
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for IpCidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = TargetPolicyError;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let (address, prefix) = match raw.split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (raw, None),
+        };
+        let address = IpAddr::from_str(address.trim())
+            .map_err(|_| TargetPolicyError::InvalidRule(format!("invalid CIDR address: {raw}")))?;
+        let prefix_len = match prefix {
+            Some(prefix) => prefix.trim().parse::<u8>().map_err(|_| {
+                TargetPolicyError::InvalidRule(format!("invalid CIDR prefix length: {raw}"))
+            })?,
+            None if address.is_ipv4() => 32,
+            None => 128,
+        };
+
+        Self::new(address, prefix_len)
+    }
+}
+
+impl Serialize for IpCidr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for IpCidr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        raw.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// A single allow/deny entry: either a CIDR range or a hostname glob (`*`, `?`).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum TargetRule {
+    Cidr(IpCidr),
+    HostnameGlob(String),
+}
+
+impl TargetRule {
+    fn matches_hostname(&self, hostname: Option<&str>) -> bool {
+        match (self, hostname) {
+            (Self::HostnameGlob(pattern), Some(hostname)) => glob_matches(pattern, hostname),
+            _ => false,
+        }
+    }
+
+    fn matches_address(&self, address: IpAddr) -> bool {
+        match self {
+            Self::Cidr(cidr) => cidr.contains(address),
+            Self::HostnameGlob(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for TargetRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cidr(cidr) => cidr.fmt(f),
+            Self::HostnameGlob(pattern) => f.write_str(pattern),
+        }
+    }
+}
+
+impl FromStr for TargetRule {
+    type Err = TargetPolicyError;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let rule = raw.trim();
+        if rule.is_empty() {
+            return Err(TargetPolicyError::InvalidRule(
+                "target policy rule must not be empty".to_string(),
+            ));
+        }
+
+        if rule.contains('/') || IpAddr::from_str(rule).is_ok() {
+            return IpCidr::from_str(rule).map(Self::Cidr);
+        }
+
+        let pattern = rule.to_ascii_lowercase();
+        let valid = pattern
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '.' | '*' | '?'));
+        if !valid {
+            return Err(TargetPolicyError::InvalidRule(format!(
+                "hostname glob may only contain letters, digits, '-', '.', '*' and '?': {rule}"
+            )));
+        }
+
+        Ok(Self::HostnameGlob(pattern))
+    }
+}
+
+impl Serialize for TargetRule {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TargetRule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        raw.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Allow/deny lists applied to API-launched probe targets.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TargetPolicy {
+    pub allow: Vec<TargetRule>,
+    pub deny: Vec<TargetRule>,
+    pub block_private_ranges: bool,
+}
+
+impl TargetPolicy {
+    pub fn is_unrestricted(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty() && !self.block_private_ranges
+    }
+
+    /// Layers a principal-specific policy on top of this one.
+    ///
+    /// Deny lists are combined, the principal's allow list replaces the base allow list when
+    /// it is non-empty, and the private/reserved preset stays enabled if either side enables it.
+    pub fn merged_with(&self, overlay: &TargetPolicy) -> TargetPolicy {
+        TargetPolicy {
+            allow: if overlay.allow.is_empty() {
+                self.allow.clone()
+            } else {
+                overlay.allow.clone()
+            },
+            deny: self
+                .deny
+                .iter()
+                .chain(overlay.deny.iter())
+                .cloned()
+                .collect(),
+            block_private_ranges: self.block_private_ranges || overlay.block_private_ranges,
+        }
+    }
+
+    /// Rejects hostnames matching a deny glob; callers run this before DNS resolution so denied
+    /// names are never looked up.
+    pub fn check_hostname(&self, hostname: &str) -> Result<(), TargetPolicyError> {
+        match self
+            .deny
+            .iter()
+            .find(|rule| rule.matches_hostname(Some(hostname)))
+        {
+            Some(rule) => Err(TargetPolicyError::Forbidden(format!(
+                "target {hostname} matches deny rule {rule}"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Evaluates a normalized target and the addresses it resolved to.
+    ///
+    /// `hostname` is `None` for IP-literal targets. Every resolved address must pass: a deny
+    /// match always wins, and a non-empty allow list rejects anything whose hostname and
+    /// address it does not match. Hostname globs only decide whether the name is acceptable;
+    /// only an explicit CIDR allow exempts an address from the private/reserved preset.
+    pub fn evaluate(
+        &self,
+        target: &str,
+        hostname: Option<&str>,
+        addresses: &[IpAddr],
+    ) -> Result<(), TargetPolicyError> {
+        if let Some(hostname) = hostname {
+            self.check_hostname(hostname)?;
+        }
+
+        let hostname_allowed = self
+            .allow
+            .iter()
+            .any(|rule| rule.matches_hostname(hostname));
+
+        for &address in addresses {
+            if let Some(rule) = self.deny.iter().find(|rule| rule.matches_address(address)) {
+                return Err(TargetPolicyError::Forbidden(format!(
+                    "target {target} resolves to {address}, which matches deny rule {rule}"
+                )));
+            }
+
+            let address_allowed = self.allow.iter().any(|rule| rule.matches_address(address));
+            if !self.allow.is_empty() && !hostname_allowed && !address_allowed {
+                return Err(TargetPolicyError::Forbidden(format!(
+                    "target {target} ({address}) does not match any allow rule"
+                )));
+            }
+
+            if self.block_private_ranges && !address_allowed && is_private_or_reserved(address) {
+                return Err(TargetPolicyError::Forbidden(format!(
+                    "target {target} resolves to private or reserved address {address}"
+                )));
+            }
+        }
+
+        if addresses.is_empty() && !self.allow.is_empty() && !hostname_allowed {
+            return Err(TargetPolicyError::Forbidden(format!(
+                "target {target} does not match any allow rule"
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Server-wide default policy plus optional per-principal overlays.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TargetPolicyConfig {
+    pub default: TargetPolicy,
+    pub principals: HashMap<String, TargetPolicy>,
+}
+
+impl TargetPolicyConfig {
+    pub fn for_principal(&self, principal: &str) -> TargetPolicy {
+        match self.principals.get(principal) {
+            Some(overlay) => self.default.merged_with(overlay),
+            None => self.default.clone(),
+        }
+    }
+
+    pub fn is_unrestricted(&self) -> bool {
+        self.default.is_unrestricted()
+            && self.principals.values().all(TargetPolicy::is_unrestricted)
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, Eq, PartialEq)]
+pub enum TargetPolicyError {
+    #[error("invalid target policy rule: {0}")]
+    InvalidRule(String),
+
+    #[error("{0}")]
+    Forbidden(String),
+}
+
+pub fn is_private_or_reserved(address: IpAddr) -> bool {
+    match canonical_address(address) {
+        IpAddr::V4(v4) => {
+            v4 == Ipv4Addr::BROADCAST
+                || RESERVED_IPV4_RANGES.iter().any(|&(network, prefix)| {
+                    mask_address(IpAddr::V4(v4), prefix) == IpAddr::V4(network)
+                })
+        }
+        IpAddr::V6(v6) => RESERVED_IPV6_RANGES
+            .iter()
+            .any(|&(network, prefix)| mask_address(IpAddr::V6(v6), prefix) == IpAddr::V6(network)),
+    }
+}
+
+/// Unwraps IPv4-mapped IPv6 addresses so `::ffff:10.0.0.1` is evaluated as `10.0.0.1`.
+fn canonical_address(address: IpAddr) -> IpAddr {
+    match address {
+        IpAddr::V6(v6) => v6
+            .to_ipv4_mapped()
+            .map(IpAddr::V4)
+            .unwrap_or(IpAddr::V6(v6)),
+        IpAddr::V4(_) => address,
+    }
+}
+
+fn mask_address(address: IpAddr, prefix_len: u8) -> IpAddr {
+    match address {
+        IpAddr::V4(v4) => {
+            let mask = u32::MAX
+                .checked_shl(32 - u32::from(prefix_len))
+                .unwrap_or(0);
+            IpAddr::V4(Ipv4Addr::from(u32::from(v4) & mask))
+        }
+        IpAddr::V6(v6) => {
+            let mask = u128::MAX
+                .checked_shl(128 - u32::from(prefix_len))
+                .unwrap_or(0);
+            IpAddr::V6(Ipv6Addr::from(u128::from(v6) & mask))
+        }
+    }
+}
+
+fn glob_matches(pattern: &str, candidate: &str) -> bool {
+    let pattern = pattern.as_bytes();
+    let candidate = candidate.as_bytes();
+    let (mut p, mut c) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while c < candidate.len() {
+        if p < pattern.len()
+            && (pattern[p] == b'?' || pattern[p].eq_ignore_ascii_case(&candidate[c]))
+        {
+            p += 1;
+            c += 1;
+        } else if p < pattern.len() && pattern[p] == b'*' {
+            backtrack = Some((p, c));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            c = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&byte| byte == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(raw: &str) -> IpAddr {
+        raw.parse().expect("valid test address")
+    }
+
+    fn rules(raw: &[&str]) -> Vec<TargetRule> {
+        raw.iter()
+            .map(|rule| rule.parse().expect("valid test rule"))
+            .collect()
+    }
+
+    #[test]
+    fn cidr_parsing_masks_host_bits_and_rejects_bad_prefixes() {
+        let cidr: IpCidr = "10.1.2.3/8".parse().expect("valid cidr");
+        assert_eq!(cidr.to_string(), "10.0.0.0/8");
+        assert!(cidr.contains(ip("10.255.0.1")));
+        assert!(!cidr.contains(ip("11.0.0.1")));
+        assert!(cidr.contains(ip("::ffff:10.0.0.1")));
+
+        assert!("10.0.0.0/33".parse::<IpCidr>().is_err());
+        assert!("fe80::/129".parse::<IpCidr>().is_err());
+        assert!("not-an-ip/8".parse::<IpCidr>().is_err());
+    }
+
+    #[test]
+    fn rule_parsing_distinguishes_cidrs_and_hostname_globs() {
+        assert_eq!(
+            "192.0.2.7".parse::<TargetRule>(),
+            Ok(TargetRule::Cidr("192.0.2.7/32".parse().unwrap()))
+        );
+        assert_eq!(
+            "*.Example.COM".parse::<TargetRule>(),
+            Ok(TargetRule::HostnameGlob("*.example.com".to_string()))
+        );
+        assert!("bad host".parse::<TargetRule>().is_err());
+        assert!("".parse::<TargetRule>().is_err());
+    }
+
+    #[test]
+    fn glob_matching_supports_star_and_question_mark() {
+        assert!(glob_matches("*.example.com", "api.example.com"));
+        assert!(glob_matches("*.example.com", "a.b.example.com"));
+        assert!(!glob_matches("*.example.com", "example.com"));
+        assert!(glob_matches("host-?.lan", "host-1.lan"));
+        assert!(!glob_matches("host-?.lan", "host-10.lan"));
+        assert!(glob_matches("*", "anything"));
+    }
+
+    #[test]
+    fn preset_blocks_private_link_local_and_metadata_addresses() {
+        for address in [
+            "10.0.0.1",
+            "172.16.5.4",
+            "192.168.1.1",
+            "127.0.0.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "fe80::1",
+            "fd00:ec2::254",
+            "::ffff:192.168.0.1",
+        ] {
+            assert!(
+                is_private_or_reserved(ip(address)),
+                "{address} should be reserved"
+            );
+        }
+
+        for address in ["1.1.1.1", "8.8.8.8", "2606:4700:4700::1111"] {
+            assert!(
+                !is_private_or_reserved(ip(address)),
+                "{address} should be public"
+            );
+        }
+    }
+
+    #[test]
+    fn deny_rules_win_over_allow_rules() {
+        let policy = TargetPolicy {
+            allow: rules(&["10.0.0.0/8"]),
+            deny: rules(&["10.0.0.5"]),
+            block_private_ranges: true,
+        };
+
+        assert!(policy.evaluate("10.0.0.4", None, &[ip("10.0.0.4")]).is_ok());
+        assert!(matches!(
+            policy.evaluate("10.0.0.5", None, &[ip("10.0.0.5")]),
+            Err(TargetPolicyError::Forbidden(_))
+        ));
+        assert!(matches!(
+            policy.evaluate("1.1.1.1", None, &[ip("1.1.1.1")]),
+            Err(TargetPolicyError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn hostname_rules_are_checked_alongside_every_resolved_address() {
+        let policy = TargetPolicy {
+            allow: Vec::new(),
+            deny: rules(&["*.internal.example"]),
+            block_private_ranges: true,
+        };
+
+        assert!(
+            policy
+                .evaluate(
+                    "db.internal.example",
+                    Some("db.internal.example"),
+                    &[ip("1.2.3.4")]
+                )
+                .is_err()
+        );
+        assert!(
+            policy
+                .evaluate(
+                    "rebind.example",
+                    Some("rebind.example"),
+                    &[ip("1.2.3.4"), ip("169.254.169.254")]
+                )
+                .is_err()
+        );
+        assert!(
+            policy
+                .evaluate("ok.example", Some("ok.example"), &[ip("1.2.3.4")])
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn hostname_allow_globs_do_not_exempt_addresses_from_the_preset() {
+        let policy = TargetPolicy {
+            allow: rules(&["*.example.com", "10.20.0.0/16"]),
+            deny: Vec::new(),
+            block_private_ranges: true,
+        };
+
+        assert!(
+            policy
+                .evaluate("www.example.com", Some("www.example.com"), &[ip("1.2.3.4")])
+                .is_ok()
+        );
+        assert!(matches!(
+            policy.evaluate(
+                "rebind.example.com",
+                Some("rebind.example.com"),
+                &[ip("169.254.169.254")]
+            ),
+            Err(TargetPolicyError::Forbidden(_))
+        ));
+        assert!(
+            policy
+                .evaluate(
+                    "lab.example.com",
+                    Some("lab.example.com"),
+                    &[ip("10.20.1.1")]
+                )
+                .is_ok()
+        );
+        assert!(
+            policy
+                .evaluate("lab.other.net", Some("lab.other.net"), &[ip("10.20.1.1")])
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn principal_overlay_extends_deny_and_replaces_allow() {
+        let mut config = TargetPolicyConfig {
+            default: TargetPolicy {
+                allow: rules(&["*.example.com"]),
+                deny: rules(&["192.0.2.0/24"]),
+                block_private_ranges: false,
+            },
+            principals: HashMap::new(),
+        };
+        config.principals.insert(
+            "team-a".to_string(),
+            TargetPolicy {
+                allow: rules(&["198.51.100.0/24"]),
+                deny: rules(&["198.51.100.9"]),
+                block_private_ranges: true,
+            },
+        );
+
+        let team_a = config.for_principal("team-a");
+        assert_eq!(team_a.allow, rules(&["198.51.100.0/24"]));
+        assert_eq!(team_a.deny.len(), 2);
+        assert!(team_a.block_private_ranges);
+        assert!(
+            team_a
+                .evaluate("198.51.100.9", None, &[ip("198.51.100.9")])
+                .is_err()
+        );
+
+        assert_eq!(config.for_principal("someone-else"), config.default);
+        assert!(!config.is_unrestricted());
+        assert!(TargetPolicyConfig::default().is_unrestricted());
+    }
+}
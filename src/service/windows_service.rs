@@ -0,0 +1,318 @@
+//! Windows Service Control Manager integration behind `mtr service install|uninstall|run`.
+//!
+//! `run_dispatcher` hands the calling thread to the SCM. The SCM then calls `service_main` on
+//! its own thread, which serves the API until a stop or shutdown control arrives.
+
+use std::ffi::{OsStr, c_void};
+use std::io;
+use std::iter::once;
+use std::os::windows::ffi::OsStrExt;
+use std::ptr::{null, null_mut};
+use std::sync::atomic::{AtomicPtr, AtomicU32, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use anyhow::Context;
+use tokio::sync::oneshot;
+use windows_sys::Win32::Foundation::{
+    ERROR_CALL_NOT_IMPLEMENTED, ERROR_FAILED_SERVICE_CONTROLLER_CONNECT,
+    ERROR_SERVICE_SPECIFIC_ERROR, NO_ERROR,
+};
+use windows_sys::Win32::Storage::FileSystem::DELETE;
+use windows_sys::Win32::System::Services::{
+    ChangeServiceConfig2W, CloseServiceHandle, ControlService, CreateServiceW, DeleteService,
+    OpenSCManagerW, OpenServiceW, RegisterServiceCtrlHandlerExW, SC_HANDLE, SC_MANAGER_CONNECT,
+    SC_MANAGER_CREATE_SERVICE, SERVICE_ACCEPT_SHUTDOWN, SERVICE_ACCEPT_STOP, SERVICE_AUTO_START,
+    SERVICE_CHANGE_CONFIG, SERVICE_CONFIG_DESCRIPTION, SERVICE_CONTROL_INTERROGATE,
+    SERVICE_CONTROL_SHUTDOWN, SERVICE_CONTROL_STOP, SERVICE_DESCRIPTIONW, SERVICE_ERROR_NORMAL,
+    SERVICE_QUERY_STATUS, SERVICE_RUNNING, SERVICE_START_PENDING, SERVICE_STATUS,
+    SERVICE_STATUS_CURRENT_STATE, SERVICE_STOP, SERVICE_STOP_PENDING, SERVICE_STOPPED,
+    SERVICE_TABLE_ENTRYW, SERVICE_WIN32_OWN_PROCESS, SetServiceStatus, StartServiceCtrlDispatcherW,
+};
+
+use crate::service::config_reload::ConfigReloadOptions;
+use crate::service::rest_api::RestApiConfig;
+use crate::service::rest_server::serve_rest_api;
+use crate::service::service_host::{SERVICE_DESCRIPTION, SERVICE_DISPLAY_NAME};
+
+/// Service-specific exit code reported to the SCM when the server fails.
+const SERVER_FAILED_EXIT_CODE: u32 = 1;
+const PENDING_WAIT_HINT_MS: u32 = 30_000;
+
+struct Launch {
+    name: Vec<u16>,
+    config: RestApiConfig,
+    reload: ConfigReloadOptions,
+}
+
+static LAUNCH: Mutex<Option<Launch>> = Mutex::new(None);
+static RESULT: Mutex<Option<anyhow::Result<()>>> = Mutex::new(None);
+static STOP: Mutex<Option<oneshot::Sender<()>>> = Mutex::new(None);
+static STATUS_HANDLE: AtomicPtr<c_void> = AtomicPtr::new(null_mut());
+static CHECKPOINT: AtomicU32 = AtomicU32::new(0);
+/// Raised to cover the configured shutdown grace period, so the SCM waits for the drain.
+static STOP_WAIT_HINT_MS: AtomicU32 = AtomicU32::new(PENDING_WAIT_HINT_MS);
+
+fn wide(value: impl AsRef<OsStr>) -> Vec<u16> {
+    value.as_ref().encode_wide().chain(once(0)).collect()
+}
+
+struct ScHandle(SC_HANDLE);
+
+impl ScHandle {
+    fn new(handle: SC_HANDLE, action: &str) -> anyhow::Result<Self> {
+        if handle.is_null() {
+            return Err(io::Error::last_os_error()).with_context(|| action.to_string());
+        }
+        Ok(Self(handle))
+    }
+}
+
+impl Drop for ScHandle {
+    fn drop(&mut self) {
+        // SAFETY: the handle came from the SCM, is non-null, and this `Drop` runs once.
+        // nosemgrep: rust.lang.security.unsafe-usage.unsafe-usage
+        unsafe { CloseServiceHandle(self.0) };
+    }
+}
+
+fn open_manager(access: u32) -> anyhow::Result<ScHandle> {
+    // SAFETY: null machine and database names select the local active services database.
+    // nosemgrep: rust.lang.security.unsafe-usage.unsafe-usage
+    let handle = unsafe { OpenSCManagerW(null(), null(), access) };
+    ScHandle::new(
+        handle,
+        "failed to open the Service Control Manager (run from an elevated prompt)",
+    )
+}
+
+/// Register an auto-start service that runs `command_line` as LocalSystem.
+pub fn install(name: &str, command_line: &str) -> anyhow::Result<()> {
+    let manager = open_manager(SC_MANAGER_CREATE_SERVICE)?;
+    let service_name = wide(name);
+    let display_name = wide(SERVICE_DISPLAY_NAME);
+    let command_line = wide(command_line);
+
+    // SAFETY: every string is NUL-terminated and outlives the call; null optional arguments
+    // mean no load order group, no dependencies, and the LocalSystem account.
+    // nosemgrep: rust.lang.security.unsafe-usage.unsafe-usage
+    let handle = unsafe {
+        CreateServiceW(
+            manager.0,
+            service_name.as_ptr(),
+            display_name.as_ptr(),
+            SERVICE_CHANGE_CONFIG,
+            SERVICE_WIN32_OWN_PROCESS,
+            SERVICE_AUTO_START,
+            SERVICE_ERROR_NORMAL,
+            command_line.as_ptr(),
+            null(),
+            null_mut(),
+            null(),
+            null(),
+            null(),
+        )
+    };
+    let service = ScHandle::new(handle, &format!("failed to create service `{name}`"))?;
+
+    let mut description = wide(SERVICE_DESCRIPTION);
+    let info = SERVICE_DESCRIPTIONW {
+        lpDescription: description.as_mut_ptr(),
+    };
+    // SAFETY: `info` points at a NUL-terminated description that outlives the call.
+    // nosemgrep: rust.lang.security.unsafe-usage.unsafe-usage
+    let described = unsafe {
+        ChangeServiceConfig2W(
+            service.0,
+            SERVICE_CONFIG_DESCRIPTION,
+            (&info as *const SERVICE_DESCRIPTIONW).cast::<c_void>(),
+        )
+    };
+    if described == 0 {
+        eprintln!(
+            "warning: failed to set the description of service `{name}`: {}",
+            io::Error::last_os_error()
+        );
+    }
+    Ok(())
+}
+
+/// Stop the service if it is running, then delete it.
+pub fn uninstall(name: &str) -> anyhow::Result<()> {
+    let manager = open_manager(SC_MANAGER_CONNECT)?;
+    let service_name = wide(name);
+    // SAFETY: `service_name` is NUL-terminated and outlives the call.
+    // nosemgrep: rust.lang.security.unsafe-usage.unsafe-usage
+    let handle = unsafe {
+        OpenServiceW(
+            manager.0,
+            service_name.as_ptr(),
+            SERVICE_STOP | SERVICE_QUERY_STATUS | DELETE,
+        )
+    };
+    let service = ScHandle::new(handle, &format!("failed to open service `{name}`"))?;
+
+    let mut status = SERVICE_STATUS::default();
+    // SAFETY: `status` is a valid out-pointer. Failure (for example, already stopped) is fine.
+    // nosemgrep: rust.lang.security.unsafe-usage.unsafe-usage
+    unsafe { ControlService(service.0, SERVICE_CONTROL_STOP, &mut status) };
+
+    // SAFETY: the handle was opened with `DELETE` access.
+    // nosemgrep: rust.lang.security.unsafe-usage.unsafe-usage
+    if unsafe { DeleteService(service.0) } == 0 {
+        return Err(io::Error::last_os_error())
+            .with_context(|| format!("failed to delete service `{name}`"));
+    }
+    Ok(())
+}
+
+/// Connect to the SCM and serve the API as service `name` until it is stopped.
+pub fn run_dispatcher(
+    name: &str,
+    config: RestApiConfig,
+    reload: ConfigReloadOptions,
+) -> anyhow::Result<()> {
+    let mut service_name = wide(name);
+    *LAUNCH.lock().unwrap_or_else(PoisonError::into_inner) = Some(Launch {
+        name: service_name.clone(),
+        config,
+        reload,
+    });
+
+    let table = [
+        SERVICE_TABLE_ENTRYW {
+            lpServiceName: service_name.as_mut_ptr(),
+            lpServiceProc: Some(service_main),
+        },
+        SERVICE_TABLE_ENTRYW::default(),
+    ];
+    // SAFETY: the table is terminated by a null entry and outlives the blocking call.
+    // nosemgrep: rust.lang.security.unsafe-usage.unsafe-usage
+    if unsafe { StartServiceCtrlDispatcherW(table.as_ptr()) } == 0 {
+        let error = io::Error::last_os_error();
+        if error.raw_os_error() == Some(ERROR_FAILED_SERVICE_CONTROLLER_CONNECT as i32) {
+            anyhow::bail!(
+                "`mtr service run` must be started by the Service Control Manager; install it \
+                 with `mtr service install`, or use `mtr --api` to run in the foreground"
+            );
+        }
+        return Err(error).context("failed to start the service control dispatcher");
+    }
+
+    RESULT
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take()
+        .unwrap_or(Ok(()))
+}
+
+unsafe extern "system" fn service_main(_argc: u32, _argv: *mut windows_sys::core::PWSTR) {
+    let Some(launch) = LAUNCH.lock().unwrap_or_else(PoisonError::into_inner).take() else {
+        return;
+    };
+
+    // SAFETY: the name is NUL-terminated and `control_handler` matches `LPHANDLER_FUNCTION_EX`.
+    // nosemgrep: rust.lang.security.unsafe-usage.unsafe-usage
+    let handle = unsafe {
+        RegisterServiceCtrlHandlerExW(launch.name.as_ptr(), Some(control_handler), null())
+    };
+    let result = if handle.is_null() {
+        Err(io::Error::last_os_error()).context("failed to register the service control handler")
+    } else {
+        STATUS_HANDLE.store(handle, Ordering::SeqCst);
+        set_status(SERVICE_START_PENDING, NO_ERROR);
+        serve(launch.config, launch.reload)
+    };
+
+    match &result {
+        Ok(()) => set_status(SERVICE_STOPPED, NO_ERROR),
+        Err(error) => {
+            eprintln!("windows-mtr service failed: {error:#}");
+            set_status(SERVICE_STOPPED, ERROR_SERVICE_SPECIFIC_ERROR);
+        }
+    }
+    *RESULT.lock().unwrap_or_else(PoisonError::into_inner) = Some(result);
+}
+
+fn serve(config: RestApiConfig, reload: ConfigReloadOptions) -> anyhow::Result<()> {
+    let (stop_tx, stop_rx) = oneshot::channel();
+    *STOP.lock().unwrap_or_else(PoisonError::into_inner) = Some(stop_tx);
+    STOP_WAIT_HINT_MS.store(
+        stop_wait_hint_ms(config.shutdown_grace_period),
+        Ordering::SeqCst,
+    );
+
+    let runtime = tokio::runtime::Runtime::new().context("failed to start tokio runtime")?;
+    runtime.block_on(serve_rest_api(
+        config,
+        Some(reload),
+        async {
+            let _ = stop_rx.await;
+        },
+        |addr| {
+            eprintln!("REST API listening on {addr}");
+            set_status(SERVICE_RUNNING, NO_ERROR);
+        },
+    ))
+}
+
+unsafe extern "system" fn control_handler(
+    control: u32,
+    _event_type: u32,
+    _event_data: *mut c_void,
+    _context: *mut c_void,
+) -> u32 {
+    match control {
+        SERVICE_CONTROL_STOP | SERVICE_CONTROL_SHUTDOWN => {
+            set_status(SERVICE_STOP_PENDING, NO_ERROR);
+            if let Some(stop) = STOP.lock().unwrap_or_else(PoisonError::into_inner).take() {
+                let _ = stop.send(());
+            }
+            NO_ERROR
+        }
+        SERVICE_CONTROL_INTERROGATE => NO_ERROR,
+        _ => ERROR_CALL_NOT_IMPLEMENTED,
+    }
+}
+
+fn stop_wait_hint_ms(grace_period: Duration) -> u32 {
+    let hint = grace_period
+        .as_millis()
+        .saturating_add(u128::from(PENDING_WAIT_HINT_MS));
+    u32::try_from(hint).unwrap_or(u32::MAX)
+}
+
+fn set_status(state: SERVICE_STATUS_CURRENT_STATE, exit_code: u32) {
+    let pending = state == SERVICE_START_PENDING || state == SERVICE_STOP_PENDING;
+    let wait_hint = if state == SERVICE_STOP_PENDING {
+        STOP_WAIT_HINT_MS.load(Ordering::SeqCst)
+    } else {
+        PENDING_WAIT_HINT_MS
+    };
+    let status = SERVICE_STATUS {
+        dwServiceType: SERVICE_WIN32_OWN_PROCESS,
+        dwCurrentState: state,
+        dwControlsAccepted: if state == SERVICE_RUNNING {
+            SERVICE_ACCEPT_STOP | SERVICE_ACCEPT_SHUTDOWN
+        } else {
+            0
+        },
+        dwWin32ExitCode: exit_code,
+        dwServiceSpecificExitCode: if exit_code == ERROR_SERVICE_SPECIFIC_ERROR {
+            SERVER_FAILED_EXIT_CODE
+        } else {
+            0
+        },
+        dwCheckPoint: if pending {
+            CHECKPOINT.fetch_add(1, Ordering::SeqCst) + 1
+        } else {
+            0
+        },
+        dwWaitHint: if pending { wait_hint } else { 0 },
+    };
+
+    // SAFETY: the handle was returned by `RegisterServiceCtrlHandlerExW` and `status` is a
+    // fully initialized `SERVICE_STATUS`.
+    // nosemgrep: rust.lang.security.unsafe-usage.unsafe-usage
+    unsafe { SetServiceStatus(STATUS_HANDLE.load(Ordering::SeqCst), &status) };
+}
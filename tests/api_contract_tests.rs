@@ -329,6 +329,13 @@ fn openapi_create_probe_request_fields_match_rust_dto_fields() {
         include_asn: None,
         interval_seconds: None,
         timeout_seconds: None,
+        source_port: None,
+        packet_size: None,
+        src: None,
+        interface: None,
+        ecmp: None,
+        dns_cache_ttl_seconds: None,
+        callback_url: None,
     };
 
     let dto_value = serde_json::to_value(dto).expect("dto should serialize");
@@ -353,7 +360,7 @@ fn openapi_create_probe_request_fields_match_rust_dto_fields() {
     );
 
     let mut icmp_expected_fields = dto_fields.clone();
-    icmp_expected_fields.retain(|field| field != "port");
+    icmp_expected_fields.retain(|field| field != "port" && field != "source_port");
     let icmp_fields = sorted(create_probe_request_property_names(
         &openapi,
         "CreateProbeRequestIcmp",
@@ -363,3 +370,247 @@ fn openapi_create_probe_request_fields_match_rust_dto_fields() {
         "CreateProbeRequestIcmp properties must match Rust DTO fields except protocol-specific port"
     );
 }
+
+const HTTP_METHODS: [&str; 5] = ["get", "post", "put", "patch", "delete"];
+
+fn documented_operations(openapi: &Value) -> Vec<(String, String)> {
+    let paths = as_mapping(map_get(as_mapping(openapi), "paths"));
+    let mut operations = Vec::new();
+    for (path, item) in paths {
+        let path = path.as_str().expect("path keys should be strings");
+        for method in HTTP_METHODS {
+            if map_get_optional(as_mapping(item), method).is_some() {
+                operations.push((method.to_string(), path.to_string()));
+            }
+        }
+    }
+    operations.sort();
+    operations
+}
+
+#[test]
+fn openapi_paths_match_mounted_router_routes() {
+    use windows_mtr::service::openapi::API_ROUTES;
+
+    let mut mounted: Vec<(String, String)> = API_ROUTES
+        .iter()
+        .map(|route| (route.method.to_string(), route.path.to_string()))
+        .collect();
+    mounted.sort();
+
+    assert_eq!(
+        documented_operations(&load_openapi()),
+        mounted,
+        "docs/api/openapi.yaml operations must match the routes mounted by build_router"
+    );
+}
+
+#[tokio::test]
+async fn router_serves_every_documented_operation() {
+    use axum::body::{Body, to_bytes};
+    use axum::extract::ConnectInfo;
+    use axum::http::{Method, Request, StatusCode};
+    use std::net::SocketAddr;
+    use tower::util::ServiceExt;
+    use windows_mtr::service::rest_api::RestApiConfig;
+    use windows_mtr::service::rest_server::{RestServerState, build_router};
+
+    let state = RestServerState::new(RestApiConfig::default()).expect("state should initialize");
+    let router = build_router(state);
+
+    for (method, path) in documented_operations(&load_openapi()) {
+        let uri = path.replace("{id}", "probe-contract-check");
+        let mut request = Request::builder()
+            .method(Method::from_bytes(method.to_ascii_uppercase().as_bytes()).unwrap())
+            .uri(&uri)
+            .header("content-type", "application/json")
+            .body(Body::from("{}"))
+            .expect("request should build");
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 40000))));
+
+        let response = router
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("router should respond");
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body should read");
+
+        assert_ne!(
+            status,
+            StatusCode::METHOD_NOT_ALLOWED,
+            "{method} {path} is documented but the router does not accept that method"
+        );
+        assert!(
+            status != StatusCode::NOT_FOUND || !body.is_empty(),
+            "{method} {path} is documented but not mounted"
+        );
+    }
+}
+
+fn schema_property_names(openapi: &Value, schema_name: &str) -> Vec<String> {
+    sorted(create_probe_request_property_names(openapi, schema_name))
+}
+
+fn object_keys(value: &serde_json::Value) -> Vec<String> {
+    sorted(
+        value
+            .as_object()
+            .expect("value should be a json object")
+            .keys()
+            .cloned()
+            .collect(),
+    )
+}
+
+#[test]
+fn openapi_response_schemas_match_rust_dto_fields() {
+    use windows_mtr::report_diff::{ReportHop, diff_reports};
+    use windows_mtr::service::api_models::{
+        ApiProbeStatusDto, ApiResponseMetaDto, ConfigReloadDataDto, ConfigReloadResponseDto,
+        CreateProbeDataDto, CreateProbeResponseDto, DiagnosticsDataDto, DiagnosticsResponseDto,
+        HealthDataDto, HealthResponseDto, ProbeDiffDataDto, ProbeDiffResponseDto,
+        ProbeExecutionResultDto, ProbeResultDataDto, ProbeResultResponseDto,
+        ProbeTargetExecutionResultDto, ReadinessDataDto, ReadinessResponseDto, RuntimeCheckDto,
+    };
+
+    let meta = || ApiResponseMetaDto {
+        schema_version: "v1",
+        request_id: Some("req-1".to_string()),
+    };
+    let openapi = load_openapi();
+
+    let health = serde_json::to_value(HealthResponseDto {
+        meta: meta(),
+        data: HealthDataDto {
+            status: "ok",
+            service: "windows-mtr",
+            version: "0.0.0",
+        },
+    })
+    .expect("health dto should serialize");
+    let created = serde_json::to_value(CreateProbeResponseDto {
+        meta: meta(),
+        data: CreateProbeDataDto {
+            id: "probe-1".to_string(),
+            status: ApiProbeStatusDto::Queued,
+            queue_position: Some(1),
+        },
+    })
+    .expect("create dto should serialize");
+    let fetched = serde_json::to_value(ProbeResultResponseDto {
+        meta: meta(),
+        data: ProbeResultDataDto {
+            id: "probe-1".to_string(),
+            status: ApiProbeStatusDto::Completed,
+            queue_position: None,
+            result: Some(ProbeExecutionResultDto {
+                targets: vec!["1.1.1.1".to_string()],
+                protocol: "icmp",
+                completed: true,
+                target_results: vec![ProbeTargetExecutionResultDto {
+                    target: "1.1.1.1".to_string(),
+                    success: true,
+                    error: None,
+                }],
+            }),
+            error: None,
+        },
+    })
+    .expect("probe result dto should serialize");
+    let reloaded = serde_json::to_value(ConfigReloadResponseDto {
+        meta: meta(),
+        data: ConfigReloadDataDto {
+            changed: vec!["max_requests_per_window"],
+            restart_required: Vec::new(),
+        },
+    })
+    .expect("reload dto should serialize");
+    let check = || RuntimeCheckDto {
+        name: "probe_runner",
+        ok: true,
+        detail: "runner executes".to_string(),
+    };
+    let ready = serde_json::to_value(ReadinessResponseDto {
+        meta: meta(),
+        data: ReadinessDataDto {
+            status: "ready",
+            checks: vec![check()],
+        },
+    })
+    .expect("readiness dto should serialize");
+    let diagnostics = serde_json::to_value(DiagnosticsResponseDto {
+        meta: meta(),
+        data: DiagnosticsDataDto {
+            status: "ready",
+            service: "windows-mtr",
+            version: "0.0.0",
+            uptime_seconds: 5,
+            checks: vec![check()],
+            running_probes: 1,
+            queued_probes: 0,
+            max_concurrent_probes: 8,
+            max_queued_probes: 64,
+            stored_jobs: 3,
+        },
+    })
+    .expect("diagnostics dto should serialize");
+    let hop = |ttl, host: &str, avg_ms| ReportHop {
+        ttl,
+        hosts: vec![host.to_string()],
+        asn: None,
+        loss_pct: Some(0.0),
+        avg_ms: Some(avg_ms),
+    };
+    let mut target_diff = diff_reports(
+        &[hop(1, "192.168.1.1", 1.0), hop(2, "1.1.1.1", 10.0)],
+        &[hop(1, "192.168.1.1", 1.0), hop(2, "1.1.1.1", 30.0)],
+    );
+    target_diff.target = Some("1.1.1.1".to_string());
+    let diff = serde_json::to_value(ProbeDiffResponseDto {
+        meta: meta(),
+        data: ProbeDiffDataDto {
+            from: "probe-1".to_string(),
+            to: "probe-2".to_string(),
+            verdict: target_diff.verdict,
+            targets: vec![target_diff],
+        },
+    })
+    .expect("probe diff dto should serialize");
+
+    for (value, schema_name) in [
+        (&health, "HealthResponse"),
+        (&health["meta"], "EnvelopeMeta"),
+        (&health["data"], "HealthData"),
+        (&created, "CreateProbeResponse"),
+        (&created["data"], "CreateProbeData"),
+        (&fetched, "GetProbeResponse"),
+        (&fetched["data"], "ProbeResultData"),
+        (&fetched["data"]["result"], "ProbeExecutionResult"),
+        (
+            &fetched["data"]["result"]["target_results"][0],
+            "ProbeTargetExecutionResult",
+        ),
+        (&reloaded, "ConfigReloadResponse"),
+        (&reloaded["data"], "ConfigReloadData"),
+        (&ready, "ReadinessResponse"),
+        (&ready["data"], "ReadinessData"),
+        (&ready["data"]["checks"][0], "RuntimeCheck"),
+        (&diagnostics, "DiagnosticsResponse"),
+        (&diagnostics["data"], "DiagnosticsData"),
+        (&diff, "ProbeDiffResponse"),
+        (&diff["data"], "ProbeDiffData"),
+        (&diff["data"]["targets"][0], "ReportDiff"),
+        (&diff["data"]["targets"][0]["hops"][0], "HopDiff"),
+    ] {
+        assert_eq!(
+            object_keys(value),
+            schema_property_names(&openapi, schema_name),
+            "{schema_name} properties must match the serialized Rust DTO"
+        );
+    }
+}
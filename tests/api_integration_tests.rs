@@ -1,6 +1,7 @@
 use std::sync::mpsc;
 use std::thread;
 use std::time::Duration;
+#[allow(deprecated)]
 use windows_mtr::service::rest_api::{
     CreateProbeApiRequest, ProbeConcurrencyGate, ProbeProtocol, RestApiConfig,
     RestApiValidationError,
@@ -45,6 +46,13 @@ fn api_happy_path_accepts_valid_request_and_completes_before_timeout() {
         include_asn: Some(true),
         interval_seconds: Some(0.5),
         timeout_seconds: Some(1.0),
+        source_port: None,
+        packet_size: None,
+        src: None,
+        interface: None,
+        ecmp: None,
+        dns_cache_ttl_seconds: None,
+        callback_url: None,
     };
 
     let normalized = request
@@ -82,6 +90,13 @@ fn api_validation_errors_reject_invalid_payload() {
         include_asn: Some(true),
         interval_seconds: Some(-0.1),
         timeout_seconds: Some(0.05),
+        source_port: None,
+        packet_size: None,
+        src: None,
+        interface: None,
+        ecmp: None,
+        dns_cache_ttl_seconds: None,
+        callback_url: None,
     };
 
     assert!(matches!(
@@ -107,6 +122,7 @@ fn api_timeout_path_returns_timed_out_outcome() {
 }
 
 #[test]
+#[allow(deprecated)]
 fn api_cancellation_releases_concurrency_slot_for_follow_up_request() {
     let gate = ProbeConcurrencyGate::new(1).expect("gate should initialize");
 
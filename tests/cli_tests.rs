@@ -50,6 +50,31 @@ fn test_enhanced_ui_is_soft_disabled_with_actionable_error() {
     assert!(stderr.contains("enhanced UI is not available with bundled Trippy 0.13.0"));
 }
 
+#[test]
+fn test_enhanced_tuning_flags_are_validated_for_the_dashboard() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--ui",
+            "dashboard",
+            "--latency-warn-ms",
+            "300",
+            "--latency-bad-ms",
+            "100",
+            "127.0.0.1",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).expect("Invalid UTF-8");
+    assert!(
+        stderr.contains("--latency-warn-ms (300) must not be above --latency-bad-ms (100)"),
+        "{stderr}"
+    );
+}
+
 #[test]
 fn test_insufficient_privileges_diagnostic_contract_is_stable() {
     let error = MtrError::InsufficientPrivileges;
@@ -134,3 +159,315 @@ fn test_basic_execution() {
     assert!(stdout.contains("Host"));
     assert!(stdout.contains("Loss%"));
 }
+
+#[test]
+fn test_print_config_layers_profile_env_and_cli() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--config",
+            "docs/config.example.toml",
+            "--profile",
+            "wan-check",
+            "-c",
+            "3",
+            "--print-config",
+        ])
+        .env("WINDOWS_MTR__PROBE__PORT", "8443")
+        .env("WINDOWS_MTR__PROBE__COUNT", "99")
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8(output.stdout).expect("Invalid UTF-8");
+    assert!(output.status.success(), "{stdout}");
+    assert!(stdout.contains("# profile: wan-check"));
+    assert!(stdout.contains("protocol = \"tcp\""));
+    assert!(stdout.contains("port = 8443"));
+    assert!(stdout.contains("count = 3"));
+    assert!(stdout.contains("output = \"json-pretty\""));
+    assert!(!stdout.contains("[api]"));
+}
+
+#[test]
+fn test_invalid_config_file_reports_path_and_line() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "--config", "Cargo.toml", "--print-config"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8(output.stderr).expect("Invalid UTF-8");
+    assert!(stderr.contains("Cargo.toml"));
+    assert!(stderr.contains("line 1"));
+}
+
+#[test]
+fn test_diff_compares_saved_reports() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "diff",
+            "tests/fixtures/trippy_0_13_report.json",
+            "tests/fixtures/probe_diff_after.json",
+            "--format",
+            "markdown",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8(output.stdout).expect("Invalid UTF-8");
+    assert!(output.status.success(), "{stdout}");
+    assert!(stdout.contains("### Probe diff for `8.8.8.8`"), "{stdout}");
+    assert!(stdout.contains("- **Verdict:** degraded"), "{stdout}");
+    assert!(
+        stdout.contains("| 2 | host_changed | 8.8.8.8 -> 10.20.0.1 | AS64500 |"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("| 3 | added | 8.8.8.8 | AS15169 |"),
+        "{stdout}"
+    );
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "diff",
+            "tests/fixtures/trippy_0_13_report.json",
+            "tests/fixtures/probe_diff_after.json",
+            "--format",
+            "json",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let value: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("diff output should be JSON");
+    assert_eq!(value["schema_version"], "2.1");
+    assert_eq!(value["verdict"], "degraded");
+    assert_eq!(value["path_changed"], true);
+    let first_hop_delta = value["hops"][0]["avg_delta_ms"]
+        .as_f64()
+        .expect("first hop should have a latency delta");
+    assert!((first_hop_delta - 0.1).abs() < 1e-9, "{value}");
+}
+
+#[test]
+fn test_render_reemits_saved_report() {
+    let render = |format: &str| {
+        Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "render",
+                "tests/fixtures/probe_diff_after.json",
+                "--format",
+                format,
+            ])
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    let output = render("wide");
+    let stdout = String::from_utf8(output.stdout).expect("Invalid UTF-8");
+    assert!(output.status.success(), "{stdout}");
+    assert!(
+        stdout.starts_with("windows-mtr report for 8.8.8.8\n"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("AS15169 dns.google (8.8.8.8)"), "{stdout}");
+
+    let output = render("csv");
+    let stdout = String::from_utf8(output.stdout).expect("Invalid UTF-8");
+    assert!(output.status.success(), "{stdout}");
+    assert!(
+        stdout.contains("3,8.8.8.8,AS15169 dns.google,41.0,,,12.5"),
+        "{stdout}"
+    );
+
+    let dir = tempfile::tempdir().expect("tempdir should exist");
+    let future = dir.path().join("future.json");
+    std::fs::write(&future, r#"{"schema_version": "3.0", "hops": []}"#)
+        .expect("report should write");
+    let output = Command::new("cargo")
+        .args(["run", "--", "render"])
+        .arg(&future)
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).expect("Invalid UTF-8");
+    assert!(
+        stderr.contains("schema_version `3.0` is newer than this build"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("upgrade windows-mtr"), "{stderr}");
+}
+
+#[test]
+fn test_replay_exports_recording_as_aggregate_report() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "replay",
+            "tests/fixtures/session_recording.jsonl",
+            "--export",
+            "json",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let value: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("export should be JSON");
+    assert_eq!(value["schema_version"], "2.1");
+    assert_eq!(value["report"]["target"], "8.8.8.8");
+    assert_eq!(value["report"]["backend"], "recording");
+    assert_eq!(value["report"]["rounds"], 3);
+    assert_eq!(value["report"]["hops"][1]["loss_pct"], 50.0);
+    assert_eq!(value["report"]["hops"][1]["worst_ms"], 31.0);
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "replay",
+            "tests/fixtures/session_recording.jsonl",
+            "--export",
+            "csv",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = String::from_utf8(output.stdout).expect("Invalid UTF-8");
+    assert!(output.status.success(), "{stdout}");
+    assert!(
+        stdout.starts_with("hop,ip,hostname,avg_ms,best_ms,worst_ms,loss_pct\n"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("2,8.8.8.8,dns.google,23.0,19.0,31.0,50.0"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn test_schema_prints_the_report_schema() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "schema", "--print"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let schema: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("schema should be JSON");
+    let shipped: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string("docs/schemas/report-v2.schema.json")
+            .expect("shipped schema should be readable"),
+    )
+    .expect("shipped schema should be JSON");
+    assert_eq!(schema, shipped);
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "schema"])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = String::from_utf8(output.stdout).expect("Invalid UTF-8");
+    assert!(output.status.success(), "{stdout}");
+    assert!(
+        stdout.contains("JSON report schema_version 2.1"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("mtr schema --print"), "{stdout}");
+}
+
+#[test]
+fn test_several_targets_require_the_dashboard() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "-r", "127.0.0.1", "127.0.0.2"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).expect("Invalid UTF-8");
+    assert!(
+        stderr.contains("several targets are only supported by the dashboard"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn test_alerts_require_interactive_dashboard() {
+    let dir = tempfile::tempdir().expect("tempdir should exist");
+    let log = dir.path().join("alerts.jsonl");
+    let output = Command::new("cargo")
+        .args(["run", "--", "--json", "--alert-log"])
+        .arg(&log)
+        .arg("127.0.0.1")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).expect("Invalid UTF-8");
+    assert!(
+        stderr.contains("--alert-log need the interactive dashboard"),
+        "{stderr}"
+    );
+    assert!(!log.exists());
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--alert-rounds", "0", "127.0.0.1"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).expect("Invalid UTF-8");
+    assert!(stderr.contains("--alert-rounds"), "{stderr}");
+}
+
+#[test]
+fn test_alerts_imply_the_dashboard_for_its_tuning_flags() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--alert",
+            "--loss-warn-pct",
+            "10",
+            "--loss-bad-pct",
+            "5",
+            "127.0.0.1",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).expect("Invalid UTF-8");
+    assert!(
+        !stderr.contains("enhanced UI tuning flags require --ui dashboard"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("--loss-warn-pct (10) must not be above --loss-bad-pct (5)"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn test_record_requires_interactive_dashboard() {
+    let dir = tempfile::tempdir().expect("tempdir should exist");
+    let recording = dir.path().join("session.jsonl");
+    let output = Command::new("cargo")
+        .args(["run", "--", "-r", "--record"])
+        .arg(&recording)
+        .arg("127.0.0.1")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).expect("Invalid UTF-8");
+    assert!(
+        stderr.contains("--record needs the interactive dashboard"),
+        "{stderr}"
+    );
+    assert!(!recording.exists());
+}
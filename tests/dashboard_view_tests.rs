@@ -0,0 +1,206 @@
+#![cfg(feature = "tui")]
+
+use std::sync::mpsc;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::widgets::Paragraph;
+use windows_mtr::dashboard_ui::{
+    DashboardAction, DashboardOptions, DashboardView, HopSnapshot, Keymap, Theme,
+};
+use windows_mtr::report_render::{SavedHop, SavedHost};
+use windows_mtr::report_schema::ReportBackend;
+
+fn hop(ttl: u32, ip: &str, avg_ms: f64) -> SavedHop {
+    SavedHop {
+        ttl,
+        hosts: vec![SavedHost {
+            ip: ip.to_string(),
+            hostname: None,
+        }],
+        loss_pct: Some(0.0),
+        sent: Some(10),
+        recv: Some(10),
+        last_ms: Some(avg_ms),
+        avg_ms: Some(avg_ms),
+        best_ms: Some(avg_ms),
+        worst_ms: Some(avg_ms),
+        stddev_ms: Some(0.0),
+    }
+}
+
+fn key(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+
+/// The host tool's own header on the first line, the dashboard below it.
+fn draw(view: &DashboardView) -> Buffer {
+    let mut terminal = Terminal::new(TestBackend::new(100, 30)).expect("test terminal");
+    terminal
+        .draw(|frame| {
+            let area = frame.area();
+            frame.render_widget(Paragraph::new("ops console"), Rect { height: 1, ..area });
+            view.render(
+                frame,
+                Rect {
+                    y: 1,
+                    height: area.height - 1,
+                    ..area
+                },
+            );
+        })
+        .expect("dashboard should draw");
+    terminal.backend().buffer().clone()
+}
+
+fn screen(buffer: &Buffer) -> String {
+    let width = buffer.area.width as usize;
+    buffer
+        .content()
+        .chunks(width)
+        .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Foreground colour of the first cell of `text` on the screen.
+fn colour_of(buffer: &Buffer, text: &str) -> Option<Color> {
+    let cells = buffer.content();
+    let len = text.chars().count();
+    (0..cells.len().saturating_sub(len))
+        .find(|&start| {
+            cells[start..start + len]
+                .iter()
+                .map(|cell| cell.symbol())
+                .eq(text.chars().map(String::from))
+        })
+        .map(|start| cells[start].fg)
+}
+
+#[test]
+fn channel_snapshots_are_drawn_inside_the_host_layout() {
+    let (snapshot_tx, snapshot_rx) = mpsc::channel();
+    let mut view = DashboardView::new(
+        DashboardOptions::new(vec!["edge-router".to_string()], ReportBackend::Trippy),
+        snapshot_rx,
+    )
+    .expect("one target");
+
+    for avg_ms in [12.0, 14.0] {
+        snapshot_tx
+            .send(HopSnapshot {
+                target: 0,
+                hops: Ok(vec![hop(1, "10.0.0.1", 1.5), hop(2, "192.0.2.7", avg_ms)]),
+            })
+            .expect("view should be listening");
+    }
+    snapshot_tx
+        .send(HopSnapshot {
+            target: 7,
+            hops: Ok(vec![hop(1, "198.51.100.9", 1.0)]),
+        })
+        .expect("view should be listening");
+    drop(snapshot_tx);
+    view.update();
+    assert!(
+        !view.handle_key(key(KeyCode::Tab)),
+        "Tab opens the Hops view"
+    );
+
+    let buffer = draw(&view);
+    let screen = screen(&buffer);
+    assert!(screen.starts_with("ops console"), "{screen}");
+    assert!(screen.contains("edge-router"), "{screen}");
+    assert!(
+        screen.contains("10.0.0.1") && screen.contains("192.0.2.7"),
+        "{screen}"
+    );
+    assert!(
+        !screen.contains("198.51.100.9"),
+        "snapshots for unknown targets are ignored:\n{screen}"
+    );
+    assert!(
+        screen.contains("14.0"),
+        "the latest snapshot wins:\n{screen}"
+    );
+    assert!(view.take_terminal_output().is_none());
+    assert!(view.handle_key(key(KeyCode::Char('q'))));
+}
+
+#[test]
+fn failed_rounds_are_reported_like_failed_polls() {
+    let (snapshot_tx, snapshot_rx) = mpsc::channel();
+    let mut view = DashboardView::new(
+        DashboardOptions::new(vec!["edge-router".to_string()], ReportBackend::Trippy),
+        snapshot_rx,
+    )
+    .expect("one target");
+    for _ in 0..3 {
+        snapshot_tx
+            .send(HopSnapshot {
+                target: 0,
+                hops: Err("probe agent unreachable".to_string()),
+            })
+            .expect("view should be listening");
+    }
+    view.update();
+
+    let screen = screen(&draw(&view));
+    assert!(screen.contains("probe agent unreachable"), "{screen}");
+    assert!(
+        !screen.contains("Administrator"),
+        "mtr's own troubleshooting hints do not apply:\n{screen}"
+    );
+}
+
+#[test]
+fn theme_and_keymap_are_applied() {
+    let mut keymap = Keymap::default();
+    assert_eq!(
+        keymap.unbind(KeyCode::Char('q')),
+        Some(DashboardAction::Quit)
+    );
+    assert_eq!(keymap.bind(KeyCode::Char('Q'), DashboardAction::Quit), None);
+    keymap.bind(KeyCode::F(2), DashboardAction::NextTab);
+    let mut options = DashboardOptions::new(vec!["edge-router".to_string()], ReportBackend::Trippy);
+    options.theme = Theme {
+        accent: Color::Magenta,
+        ..Theme::default()
+    };
+    options.keymap = keymap;
+    let (_snapshot_tx, snapshot_rx) = mpsc::channel();
+    let mut view = DashboardView::new(options, snapshot_rx).expect("one target");
+
+    assert_eq!(colour_of(&draw(&view), "Overview"), Some(Color::Magenta));
+    assert!(!view.handle_key(key(KeyCode::F(2))));
+    let buffer = draw(&view);
+    assert_eq!(colour_of(&buffer, "Hops"), Some(Color::Magenta));
+    assert_eq!(colour_of(&buffer, "Overview"), Some(Color::White));
+
+    assert!(!view.handle_key(key(KeyCode::Char('h'))));
+    let screen = screen(&draw(&view));
+    assert!(
+        screen.contains("Tab Right d F2"),
+        "the help overlay lists the bound keys:\n{screen}"
+    );
+    assert!(screen.contains("h/? closes it; Q quits"), "{screen}");
+
+    assert!(!view.handle_key(key(KeyCode::Char('q'))), "q is unbound");
+    assert!(view.handle_key(key(KeyCode::Char('Q'))));
+}
+
+#[test]
+fn a_dashboard_without_targets_is_rejected() {
+    let (_snapshot_tx, snapshot_rx) = mpsc::channel();
+    assert!(
+        DashboardView::new(
+            DashboardOptions::new(Vec::new(), ReportBackend::Trippy),
+            snapshot_rx
+        )
+        .is_err()
+    );
+}
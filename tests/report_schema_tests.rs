@@ -0,0 +1,134 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::{Value, json};
+use windows_mtr::native_icmp::{Hop, json_report};
+use windows_mtr::recording::Recording;
+use windows_mtr::report_render::SavedReport;
+use windows_mtr::report_schema::{REPORT_SCHEMA, ReportDocument, ReportProtocol};
+use windows_mtr::service::CLI_JSON_SCHEMA_VERSION;
+
+fn validator() -> jsonschema::Validator {
+    let schema: Value = serde_json::from_str(REPORT_SCHEMA).expect("schema should be valid JSON");
+    jsonschema::draft202012::new(&schema).expect("schema should compile")
+}
+
+fn assert_valid(document: &Value) {
+    let errors = validator()
+        .iter_errors(document)
+        .map(|error| format!("{} at {}", error, error.instance_path))
+        .collect::<Vec<_>>();
+    assert!(
+        errors.is_empty(),
+        "report does not match the schema: {errors:#?}\n{document:#}"
+    );
+}
+
+#[test]
+fn shipped_schema_matches_the_embedded_copy() {
+    let shipped = fs::read_to_string("docs/schemas/report-v2.schema.json")
+        .expect("shipped schema should be readable");
+    assert_eq!(shipped, REPORT_SCHEMA);
+
+    let schema: Value = serde_json::from_str(REPORT_SCHEMA).expect("schema should parse");
+    let major = CLI_JSON_SCHEMA_VERSION
+        .split_once('.')
+        .map(|(major, _)| major)
+        .expect("version is major.minor");
+    assert!(
+        schema["$id"]
+            .as_str()
+            .is_some_and(|id| id.ends_with(&format!("report-v{major}.schema.json"))),
+        "schema file name should carry the schema_version major"
+    );
+}
+
+#[test]
+fn trippy_output_validates_after_normalization() {
+    let raw: Value = serde_json::from_str(
+        &fs::read_to_string(Path::new("tests/fixtures/trippy_0_13_json_output.json"))
+            .expect("fixture should be readable"),
+    )
+    .expect("fixture should be valid JSON");
+
+    for protocol in [
+        ReportProtocol::Icmp,
+        ReportProtocol::Tcp,
+        ReportProtocol::Udp,
+    ] {
+        let document = ReportDocument::from_trippy(&raw, "one.one.one.one", protocol)
+            .expect("trippy output should normalize");
+        assert_valid(&serde_json::to_value(document).expect("report should serialize"));
+    }
+}
+
+#[test]
+fn native_icmp_report_validates() {
+    let hops = [
+        Hop {
+            ttl: 1,
+            address: Some("192.168.1.1".parse().expect("valid address")),
+            sent: 3,
+            received: vec![1.0, 1.4, 1.2],
+        },
+        Hop {
+            ttl: 2,
+            address: None,
+            sent: 3,
+            received: vec![],
+        },
+    ];
+
+    let report = serde_json::to_value(json_report("8.8.8.8", &hops)).expect("report serializes");
+    assert_valid(&report);
+    assert_valid(&serde_json::to_value(json_report("dns.google", &[])).expect("report serializes"));
+}
+
+#[test]
+fn recording_export_validates() {
+    let recording = Recording::load(Path::new("tests/fixtures/session_recording.jsonl"))
+        .expect("fixture recording should load");
+    assert_valid(&serde_json::to_value(recording.aggregate_report()).expect("report serializes"));
+}
+
+#[test]
+fn both_backends_render_alike_from_the_normalized_shape() {
+    let raw: Value = serde_json::from_str(
+        &fs::read_to_string("tests/fixtures/trippy_0_13_json_output.json")
+            .expect("fixture should be readable"),
+    )
+    .expect("fixture should be valid JSON");
+    let trippy = ReportDocument::from_trippy(&raw, "1.1.1.1", ReportProtocol::Icmp)
+        .expect("trippy output should normalize");
+    let native = json_report(
+        "1.1.1.1",
+        &[Hop {
+            ttl: 1,
+            address: Some("192.168.1.1".parse().expect("valid address")),
+            sent: 5,
+            received: vec![1.24; 5],
+        }],
+    );
+
+    for document in [trippy, native] {
+        let value = serde_json::to_value(document).expect("report serializes");
+        let report = SavedReport::from_json(&value).expect("normalized report should render");
+        assert_eq!(report.hops[0].host_label(), "192.168.1.1");
+        assert_eq!(report.hops[0].avg_ms, Some(1.24));
+    }
+}
+
+#[test]
+fn schema_rejects_the_version_1_native_shape() {
+    let legacy = json!({
+        "schema_version": "1.0",
+        "report": {
+            "target": "8.8.8.8",
+            "protocol": "icmp",
+            "backend": "windows-icmp-helper",
+            "hops": [{"ttl": 1, "host": null, "loss_pct": 100.0, "sent": 1, "recv": 0,
+                      "last": null, "avg": null, "best": null, "worst": null}]
+        }
+    });
+    assert!(!validator().is_valid(&legacy));
+}
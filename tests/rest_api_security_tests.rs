@@ -1,5 +1,6 @@
 use std::net::SocketAddr;
 use std::time::{Duration, Instant};
+#[allow(deprecated)]
 use windows_mtr::service::rest_api::{
     AuthStrategy, CreateProbeApiRequest, FixedWindowRateLimiter, MAX_API_PROBE_COUNT,
     MAX_API_PROBE_EXECUTION_TIMEOUT, MAX_API_PROBE_INTERVAL_SECONDS, MAX_API_PROBE_TIMEOUT_SECONDS,
@@ -18,6 +19,13 @@ fn valid_request() -> CreateProbeApiRequest {
         include_asn: None,
         interval_seconds: None,
         timeout_seconds: None,
+        source_port: None,
+        packet_size: None,
+        src: None,
+        interface: None,
+        ecmp: None,
+        dns_cache_ttl_seconds: None,
+        callback_url: None,
     }
 }
 
@@ -92,6 +100,13 @@ fn request_validation_normalizes_and_deduplicates_targets() {
         include_asn: None,
         interval_seconds: Some(0.5),
         timeout_seconds: Some(1.0),
+        source_port: None,
+        packet_size: None,
+        src: None,
+        interface: None,
+        ecmp: None,
+        dns_cache_ttl_seconds: None,
+        callback_url: None,
     };
 
     let normalized = request
@@ -113,6 +128,13 @@ fn rejects_invalid_untrusted_inputs() {
         include_asn: None,
         interval_seconds: Some(-1.0),
         timeout_seconds: Some(0.5),
+        source_port: None,
+        packet_size: None,
+        src: None,
+        interface: None,
+        ecmp: None,
+        dns_cache_ttl_seconds: None,
+        callback_url: None,
     };
 
     assert!(matches!(
@@ -132,6 +154,7 @@ fn rejects_oversized_payloads() {
 }
 
 #[test]
+#[allow(deprecated)]
 fn enforces_max_concurrent_probes_limit() {
     let gate = ProbeConcurrencyGate::new(1).expect("valid gate");
     let first = gate.try_acquire().expect("first should pass");
@@ -181,6 +204,13 @@ fn rejects_too_many_targets_per_request() {
         include_asn: None,
         interval_seconds: None,
         timeout_seconds: None,
+        source_port: None,
+        packet_size: None,
+        src: None,
+        interface: None,
+        ecmp: None,
+        dns_cache_ttl_seconds: None,
+        callback_url: None,
     };
 
     assert!(matches!(
@@ -201,6 +231,13 @@ fn normalizes_defaults_for_documented_optional_fields() {
         include_asn: None,
         interval_seconds: None,
         timeout_seconds: None,
+        source_port: None,
+        packet_size: None,
+        src: None,
+        interface: None,
+        ecmp: None,
+        dns_cache_ttl_seconds: None,
+        callback_url: None,
     };
 
     let normalized = request
@@ -225,6 +262,13 @@ fn accepts_documented_optional_fields_within_bounds() {
         include_asn: Some(true),
         interval_seconds: Some(0.25),
         timeout_seconds: Some(1.0),
+        source_port: None,
+        packet_size: None,
+        src: None,
+        interface: None,
+        ecmp: None,
+        dns_cache_ttl_seconds: None,
+        callback_url: None,
     };
 
     let normalized = request
@@ -251,6 +295,13 @@ fn rejects_out_of_bounds_count_and_max_hops() {
         include_asn: None,
         interval_seconds: None,
         timeout_seconds: None,
+        source_port: None,
+        packet_size: None,
+        src: None,
+        interface: None,
+        ecmp: None,
+        dns_cache_ttl_seconds: None,
+        callback_url: None,
     };
     assert!(matches!(
         zero_count.normalize_and_validate(&config),
@@ -267,6 +318,13 @@ fn rejects_out_of_bounds_count_and_max_hops() {
         include_asn: None,
         interval_seconds: None,
         timeout_seconds: None,
+        source_port: None,
+        packet_size: None,
+        src: None,
+        interface: None,
+        ecmp: None,
+        dns_cache_ttl_seconds: None,
+        callback_url: None,
     };
     assert!(matches!(
         invalid_max_hops.normalize_and_validate(&config),
@@ -9,8 +9,11 @@ use tokio::net::TcpListener;
 use tokio::sync::oneshot;
 use tokio::time::{Instant, sleep};
 use tower::util::ServiceExt;
-use windows_mtr::service::rest_api::{AuthStrategy, RestApiConfig};
+use windows_mtr::service::config_reload::ConfigSource;
+use windows_mtr::service::rest_api::{AuthStrategy, NamedApiKey, RestApiConfig};
 use windows_mtr::service::rest_server::{RestServerState, build_router};
+use windows_mtr::service::target_policy::TargetPolicy;
+use windows_mtr::service::webhooks;
 
 fn probe_runner_path() -> PathBuf {
     PathBuf::from(env!("CARGO_BIN_EXE_mtr"))
@@ -269,6 +272,78 @@ async fn health_endpoint_returns_ok() {
     let _ = shutdown.send(());
 }
 
+#[tokio::test]
+async fn openapi_endpoints_serve_the_embedded_contract_and_explorer() {
+    let (addr, shutdown) = spawn_server().await;
+    let client = build_http_client();
+
+    let json_res = client
+        .get(format!("http://{addr}/api/v1/openapi.json"))
+        .send()
+        .await
+        .expect("openapi.json request should succeed");
+    assert_eq!(json_res.status(), reqwest::StatusCode::OK);
+    let served: serde_json::Value = json_res.json().await.expect("json body expected");
+    let on_disk: serde_json::Value = serde_yaml::from_str(
+        &std::fs::read_to_string("docs/api/openapi.yaml").expect("spec should be readable"),
+    )
+    .expect("spec should parse");
+    assert_eq!(served, on_disk);
+
+    let yaml_res = client
+        .get(format!("http://{addr}/api/v1/openapi.yaml"))
+        .send()
+        .await
+        .expect("openapi.yaml request should succeed");
+    assert_eq!(yaml_res.status(), reqwest::StatusCode::OK);
+    assert_eq!(yaml_res.headers()["content-type"], "application/yaml");
+
+    let docs_res = client
+        .get(format!("http://{addr}/api/v1/docs"))
+        .send()
+        .await
+        .expect("docs request should succeed");
+    assert_eq!(docs_res.status(), reqwest::StatusCode::OK);
+    assert!(
+        docs_res.headers()["content-security-policy"]
+            .to_str()
+            .unwrap()
+            .contains("default-src 'none'")
+    );
+    let html = docs_res.text().await.expect("html body expected");
+    assert!(html.contains("openapi.json"));
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test]
+async fn openapi_json_requires_configured_auth() {
+    let config = RestApiConfig {
+        auth_strategy: AuthStrategy::ApiKey,
+        api_key: Some("secret".to_string()),
+        ..RestApiConfig::default()
+    };
+    let (addr, shutdown) = spawn_server_with_config(config).await;
+    let client = build_http_client();
+
+    let denied = client
+        .get(format!("http://{addr}/api/v1/openapi.json"))
+        .send()
+        .await
+        .expect("openapi.json request should succeed");
+    assert_eq!(denied.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    let allowed = client
+        .get(format!("http://{addr}/api/v1/openapi.json"))
+        .header("X-API-Key", "secret")
+        .send()
+        .await
+        .expect("openapi.json request should succeed");
+    assert_eq!(allowed.status(), reqwest::StatusCode::OK);
+
+    let _ = shutdown.send(());
+}
+
 #[tokio::test]
 async fn create_probe_transitions_through_queued_running_and_completed() {
     let (addr, shutdown) = spawn_server().await;
@@ -523,6 +598,205 @@ async fn create_probe_rejects_missing_tcp_port_as_unprocessable_entity() {
     let _ = shutdown.send(());
 }
 
+#[tokio::test]
+async fn create_probe_rejects_policy_forbidden_targets_with_403() {
+    let mut config = RestApiConfig::default();
+    config.target_policy.default.block_private_ranges = true;
+    config.target_policy.default.deny = vec!["*.blocked.invalid".parse().unwrap()];
+    let (addr, shutdown) = spawn_server_with_config(config).await;
+    let client = build_http_client();
+
+    for target in [
+        "127.0.0.1",
+        "169.254.169.254",
+        "localhost",
+        "api.blocked.invalid",
+    ] {
+        let create_res = client
+            .post(format!("http://{addr}/api/v1/probes"))
+            .json(&serde_json::json!({
+                "targets": [target],
+                "protocol": "icmp"
+            }))
+            .send()
+            .await
+            .expect("create probe request should succeed");
+
+        assert_eq!(
+            create_res.status(),
+            reqwest::StatusCode::FORBIDDEN,
+            "{target} should be rejected by policy"
+        );
+        let body: serde_json::Value = create_res.json().await.expect("json body expected");
+        assert_error_shape(&body, 403, "target_forbidden");
+        assert_eq!(
+            body["error"]["type"],
+            "https://windows-mtr.dev/problems/target-forbidden"
+        );
+    }
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test]
+async fn create_probe_applies_per_principal_target_policy() {
+    let mut config = RestApiConfig::default();
+    config.target_policy.default.block_private_ranges = true;
+    config.target_policy.principals.insert(
+        "local".to_string(),
+        TargetPolicy {
+            allow: vec!["127.0.0.1/32".parse().unwrap()],
+            ..TargetPolicy::default()
+        },
+    );
+    let (addr, shutdown) = spawn_server_with_config(config).await;
+    let client = build_http_client();
+
+    create_probe_payload(
+        &client,
+        addr,
+        serde_json::json!({
+            "targets": ["127.0.0.1"],
+            "protocol": "icmp",
+            "count": 1
+        }),
+    )
+    .await;
+
+    let denied = client
+        .post(format!("http://{addr}/api/v1/probes"))
+        .json(&serde_json::json!({
+            "targets": ["10.0.0.1"],
+            "protocol": "icmp"
+        }))
+        .send()
+        .await
+        .expect("create probe request should succeed");
+    assert_eq!(denied.status(), reqwest::StatusCode::FORBIDDEN);
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test]
+async fn named_api_keys_get_their_own_target_policy() {
+    let mut config = RestApiConfig {
+        auth_strategy: AuthStrategy::ApiKey,
+        named_api_keys: vec![
+            NamedApiKey {
+                name: "lab-runner".to_string(),
+                key: "lab-key".to_string(),
+            },
+            NamedApiKey {
+                name: "ci".to_string(),
+                key: "ci-key".to_string(),
+            },
+        ],
+        ..RestApiConfig::default()
+    };
+    config.target_policy.default.block_private_ranges = true;
+    config.target_policy.principals.insert(
+        "lab-runner".to_string(),
+        TargetPolicy {
+            allow: vec!["127.0.0.1/32".parse().unwrap()],
+            ..TargetPolicy::default()
+        },
+    );
+    let (addr, shutdown) = spawn_server_with_config(config).await;
+    let client = build_http_client();
+
+    let create = |key: &'static str| {
+        client
+            .post(format!("http://{addr}/api/v1/probes"))
+            .header("X-API-Key", key)
+            .json(&serde_json::json!({
+                "targets": ["127.0.0.1"],
+                "protocol": "icmp",
+                "count": 1
+            }))
+            .send()
+    };
+
+    let lab = create("lab-key").await.expect("request should succeed");
+    assert_eq!(lab.status(), reqwest::StatusCode::ACCEPTED);
+
+    let ci = create("ci-key").await.expect("request should succeed");
+    assert_eq!(ci.status(), reqwest::StatusCode::FORBIDDEN);
+    let body: serde_json::Value = ci.json().await.expect("json body expected");
+    assert_error_shape(&body, 403, "target_forbidden");
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test]
+async fn create_probe_enforces_source_and_interface_allow_lists() {
+    let config = RestApiConfig {
+        allowed_source_addresses: vec!["127.0.0.0/8".parse().unwrap()],
+        allowed_interfaces: vec!["lo".to_string()],
+        ..RestApiConfig::default()
+    };
+    let (addr, shutdown) = spawn_server_with_config(config).await;
+    let client = build_http_client();
+
+    for (payload, expected_status, expected_code) in [
+        (
+            serde_json::json!({"targets": ["127.0.0.1"], "protocol": "icmp", "src": "192.0.2.10"}),
+            403,
+            "option_forbidden",
+        ),
+        (
+            serde_json::json!({"targets": ["127.0.0.1"], "protocol": "icmp", "interface": "eth9"}),
+            403,
+            "option_forbidden",
+        ),
+        (
+            serde_json::json!({"targets": ["127.0.0.1"], "protocol": "icmp", "packet_size": 4096}),
+            400,
+            "invalid_request",
+        ),
+        (
+            serde_json::json!({"targets": ["127.0.0.1"], "protocol": "udp", "port": 33434, "source_port": 40000}),
+            400,
+            "invalid_request",
+        ),
+        (
+            serde_json::json!({"targets": ["127.0.0.1"], "protocol": "tcp", "port": 443, "ecmp": "paris"}),
+            400,
+            "invalid_request",
+        ),
+    ] {
+        let create_res = client
+            .post(format!("http://{addr}/api/v1/probes"))
+            .json(&payload)
+            .send()
+            .await
+            .expect("create probe request should succeed");
+
+        assert_eq!(create_res.status().as_u16(), expected_status, "{payload}");
+        let body: serde_json::Value = create_res.json().await.expect("json body expected");
+        assert_error_shape(&body, expected_status, expected_code);
+    }
+
+    let accepted = client
+        .post(format!("http://{addr}/api/v1/probes"))
+        .json(&serde_json::json!({
+            "targets": ["127.0.0.1"],
+            "protocol": "udp",
+            "port": 33434,
+            "src": "127.0.0.1",
+            "interface": "lo",
+            "ecmp": "dublin",
+            "source_port": 40000,
+            "packet_size": 64,
+            "dns_cache_ttl_seconds": 60
+        }))
+        .send()
+        .await
+        .expect("create probe request should succeed");
+    assert_eq!(accepted.status(), reqwest::StatusCode::ACCEPTED);
+
+    let _ = shutdown.send(());
+}
+
 #[tokio::test]
 async fn create_probe_rejects_oversized_payload_with_413() {
     let config = RestApiConfig {
@@ -613,6 +887,58 @@ async fn create_probe_rejects_burst_traffic_with_429() {
     let _ = shutdown.send(());
 }
 
+#[tokio::test]
+async fn rate_limited_clients_get_429_before_their_body_is_read() {
+    let config = RestApiConfig {
+        max_requests_per_window: 1,
+        rate_limit_window: Duration::from_secs(60),
+        max_payload_bytes: 128,
+        ..RestApiConfig::default()
+    };
+    let (addr, shutdown) = spawn_server_with_config(config).await;
+    let client = build_http_client();
+    let url = format!("http://{addr}/api/v1/probes");
+
+    let first = client
+        .post(&url)
+        .header("Idempotency-Key", "first-job")
+        .json(&serde_json::json!({"targets": ["127.0.0.1"], "protocol": "icmp"}))
+        .send()
+        .await
+        .expect("first create probe request should succeed");
+    assert_eq!(first.status(), reqwest::StatusCode::ACCEPTED);
+
+    for key in [None, Some("unknown-key")] {
+        let mut request = client.post(&url).json(&serde_json::json!({
+            "targets": ["a".repeat(256)],
+            "protocol": "icmp"
+        }));
+        if let Some(key) = key {
+            request = request.header("Idempotency-Key", key);
+        }
+        let oversized = request
+            .send()
+            .await
+            .expect("oversized request should receive a response");
+        assert_eq!(oversized.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(oversized.headers()["X-RateLimit-Remaining"], "0");
+        let body: serde_json::Value = oversized.json().await.expect("json body expected");
+        assert_error_shape(&body, 429, "rate_limited");
+    }
+
+    let replay = client
+        .post(&url)
+        .header("Idempotency-Key", "first-job")
+        .json(&serde_json::json!({"targets": ["127.0.0.1"], "protocol": "icmp"}))
+        .send()
+        .await
+        .expect("replayed request should succeed");
+    assert_eq!(replay.status(), reqwest::StatusCode::ACCEPTED);
+    assert_eq!(replay.headers()["Idempotent-Replayed"], "true");
+
+    let _ = shutdown.send(());
+}
+
 #[tokio::test]
 async fn create_probe_allows_requests_after_rate_limit_window_resets() {
     let config = RestApiConfig {
@@ -1121,3 +1447,1093 @@ async fn probe_execution_timeout_releases_concurrency_permit() {
 
     let _ = shutdown.send(());
 }
+
+#[derive(Debug, Clone)]
+struct ReceivedWebhook {
+    path: String,
+    timestamp: u64,
+    signature: String,
+    event: String,
+    body: axum::body::Bytes,
+}
+
+/// Local webhook receiver that fails the first `failures` deliveries with HTTP 500.
+async fn spawn_webhook_receiver(
+    failures: usize,
+) -> (
+    SocketAddr,
+    std::sync::Arc<std::sync::Mutex<Vec<ReceivedWebhook>>>,
+) {
+    use axum::http::{HeaderMap, StatusCode, Uri};
+
+    let received = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink = received.clone();
+    let app = axum::Router::new().fallback(
+        move |uri: Uri, headers: HeaderMap, body: axum::body::Bytes| {
+            let sink = sink.clone();
+            async move {
+                let header = |name: &str| {
+                    headers
+                        .get(name)
+                        .and_then(|value| value.to_str().ok())
+                        .unwrap_or_default()
+                        .to_string()
+                };
+                let mut received = sink.lock().unwrap();
+                received.push(ReceivedWebhook {
+                    path: uri.path().to_string(),
+                    timestamp: header(webhooks::TIMESTAMP_HEADER)
+                        .parse()
+                        .unwrap_or_default(),
+                    signature: header(webhooks::SIGNATURE_HEADER),
+                    event: header(webhooks::EVENT_HEADER),
+                    body,
+                });
+                if received.len() <= failures {
+                    StatusCode::INTERNAL_SERVER_ERROR
+                } else {
+                    StatusCode::NO_CONTENT
+                }
+            }
+        },
+    );
+
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("receiver should bind");
+    let addr = listener.local_addr().expect("receiver addr should resolve");
+    tokio::spawn(async move {
+        axum::serve(listener, app)
+            .await
+            .expect("receiver should run");
+    });
+
+    (addr, received)
+}
+
+fn webhook_test_config(sinks: Vec<reqwest::Url>) -> RestApiConfig {
+    RestApiConfig {
+        webhooks: webhooks::WebhookConfig {
+            sinks,
+            secret: Some("test-secret".to_string()),
+            initial_backoff: Duration::from_millis(20),
+            max_backoff: Duration::from_millis(50),
+            ..webhooks::WebhookConfig::default()
+        },
+        ..RestApiConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn failed_probe_delivers_signed_webhooks_to_every_sink_with_retry() {
+    let (receiver, received) = spawn_webhook_receiver(1).await;
+    let config = webhook_test_config(vec![
+        format!("http://{receiver}/sink").parse().unwrap(),
+        format!("http://{receiver}/backup").parse().unwrap(),
+    ]);
+    let (addr, shutdown) = spawn_server_with_config(config).await;
+    let client = build_http_client();
+
+    let created = create_probe_payload(
+        &client,
+        addr,
+        serde_json::json!({
+            "targets": ["definitely-not-a-real-host.invalid"],
+            "protocol": "tcp",
+            "port": 443,
+            "count": 1
+        }),
+    )
+    .await;
+    let id = created["data"]["id"]
+        .as_str()
+        .expect("id should be a string")
+        .to_string();
+    wait_for_probe_status(&client, addr, &id, "failed").await;
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let deliveries = loop {
+        let deliveries = received.lock().unwrap().clone();
+        if deliveries.len() >= 3 || Instant::now() > deadline {
+            break deliveries;
+        }
+        sleep(Duration::from_millis(25)).await;
+    };
+
+    assert_eq!(
+        deliveries.len(),
+        3,
+        "one delivery is retried after the initial 500: {deliveries:?}"
+    );
+    for path in ["/sink", "/backup"] {
+        assert!(
+            deliveries.iter().any(|delivery| delivery.path == path),
+            "{path} should receive the event"
+        );
+    }
+    for delivery in &deliveries {
+        assert_eq!(delivery.event, "probe.failed");
+        assert!(webhooks::verify_signature(
+            "test-secret",
+            delivery.timestamp,
+            &delivery.body,
+            &delivery.signature
+        ));
+        let payload: serde_json::Value =
+            serde_json::from_slice(&delivery.body).expect("payload should be json");
+        assert_eq!(payload["event"], "probe.failed");
+        assert_eq!(payload["job_id"], id.as_str());
+        assert_eq!(payload["status"], "failed");
+        assert!(payload["error"].is_string());
+    }
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test]
+async fn undeliverable_webhooks_are_dead_lettered_after_max_attempts() {
+    let (receiver, received) = spawn_webhook_receiver(usize::MAX).await;
+    let dead_letters = tempfile::tempdir().expect("tempdir should exist");
+    let dead_letter_path = dead_letters.path().join("webhooks.jsonl");
+    let mut config = webhook_test_config(vec![format!("http://{receiver}/sink").parse().unwrap()]);
+    config.webhooks.max_attempts = 2;
+    config.webhooks.dead_letter_path = Some(dead_letter_path.clone());
+    let (addr, shutdown) = spawn_server_with_config(config).await;
+    let client = build_http_client();
+
+    let created = create_probe(&client, addr, "definitely-not-a-real-host.invalid").await;
+    let id = created["data"]["id"]
+        .as_str()
+        .expect("id should be a string")
+        .to_string();
+    wait_for_probe_status(&client, addr, &id, "failed").await;
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let record = loop {
+        if let Ok(contents) = std::fs::read_to_string(&dead_letter_path)
+            && let Some(line) = contents.lines().next()
+        {
+            break serde_json::from_str::<serde_json::Value>(line)
+                .expect("dead letter should be json");
+        }
+        assert!(Instant::now() < deadline, "dead letter was never written");
+        sleep(Duration::from_millis(25)).await;
+    };
+
+    assert_eq!(received.lock().unwrap().len(), 2);
+    assert_eq!(record["event"], "probe.failed");
+    assert_eq!(record["attempts"], 2);
+    assert_eq!(record["error"], "HTTP 500 Internal Server Error");
+    assert_eq!(record["payload"]["job_id"], id.as_str());
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test]
+async fn create_probe_rejects_callback_urls_unless_allowed_and_policy_permits() {
+    let (addr, shutdown) = spawn_server().await;
+    let client = build_http_client();
+    let payload = serde_json::json!({
+        "targets": ["127.0.0.1"],
+        "protocol": "icmp",
+        "callback_url": "https://hooks.example.com/mtr"
+    });
+
+    let response = client
+        .post(format!("http://{addr}/api/v1/probes"))
+        .json(&payload)
+        .send()
+        .await
+        .expect("create probe request should succeed");
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+    let body: serde_json::Value = response.json().await.expect("json body expected");
+    assert_error_shape(&body, 403, "option_forbidden");
+    let _ = shutdown.send(());
+
+    let mut config = webhook_test_config(Vec::new());
+    config.webhooks.allow_callback_urls = true;
+    config.target_policy.default.deny = vec!["*.example.com".parse().unwrap()];
+    let (addr, shutdown) = spawn_server_with_config(config).await;
+
+    let response = client
+        .post(format!("http://{addr}/api/v1/probes"))
+        .json(&payload)
+        .send()
+        .await
+        .expect("create probe request should succeed");
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+    let body: serde_json::Value = response.json().await.expect("json body expected");
+    assert_error_shape(&body, 403, "target_forbidden");
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test]
+async fn create_probe_rejects_private_callback_hosts_under_the_default_policy() {
+    let mut config = webhook_test_config(Vec::new());
+    config.webhooks.allow_callback_urls = true;
+    assert!(config.target_policy.default.is_unrestricted());
+    let (addr, shutdown) = spawn_server_with_config(config).await;
+    let client = build_http_client();
+
+    for callback_url in [
+        "http://127.0.0.1/mtr",
+        "http://169.254.169.254/latest/meta-data/",
+        "http://10.0.0.5:8080/mtr",
+        "http://[::1]/mtr",
+        "http://localhost/mtr",
+    ] {
+        let response = client
+            .post(format!("http://{addr}/api/v1/probes"))
+            .json(&serde_json::json!({
+                "targets": ["127.0.0.1"],
+                "protocol": "icmp",
+                "callback_url": callback_url
+            }))
+            .send()
+            .await
+            .expect("create probe request should succeed");
+        assert_eq!(
+            response.status(),
+            reqwest::StatusCode::FORBIDDEN,
+            "{callback_url}"
+        );
+        let body: serde_json::Value = response.json().await.expect("json body expected");
+        assert_error_shape(&body, 403, "target_forbidden");
+    }
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test]
+async fn idempotency_key_replays_original_job_without_spending_rate_limit() {
+    let config = RestApiConfig {
+        max_requests_per_window: 2,
+        rate_limit_window: Duration::from_secs(60),
+        ..RestApiConfig::default()
+    };
+    let (addr, shutdown) = spawn_server_with_config(config).await;
+    let client = build_http_client();
+    let url = format!("http://{addr}/api/v1/probes");
+    let payload = serde_json::json!({
+        "targets": ["127.0.0.1"],
+        "protocol": "icmp",
+        "count": 1
+    });
+
+    let first = client
+        .post(&url)
+        .header("Idempotency-Key", "retry-7f3a")
+        .json(&payload)
+        .send()
+        .await
+        .expect("first request should succeed");
+    assert_eq!(first.status(), reqwest::StatusCode::ACCEPTED);
+    assert!(first.headers().get("Idempotent-Replayed").is_none());
+    assert_eq!(first.headers()["X-RateLimit-Remaining"], "1");
+    let first: serde_json::Value = first.json().await.expect("json body expected");
+    let id = first["data"]["id"].as_str().expect("id should be a string");
+
+    for _ in 0..3 {
+        let replay = client
+            .post(&url)
+            .header("Idempotency-Key", "retry-7f3a")
+            .json(&serde_json::json!({
+                "count": 1,
+                "protocol": "icmp",
+                "targets": ["127.0.0.1"]
+            }))
+            .send()
+            .await
+            .expect("replayed request should succeed");
+        assert_eq!(replay.status(), reqwest::StatusCode::ACCEPTED);
+        assert_eq!(replay.headers()["Idempotent-Replayed"], "true");
+        assert_eq!(replay.headers()["X-RateLimit-Remaining"], "1");
+        let replay: serde_json::Value = replay.json().await.expect("json body expected");
+        assert_meta(&replay);
+        assert_eq!(replay["data"]["id"], id);
+    }
+
+    let mismatched = client
+        .post(&url)
+        .header("Idempotency-Key", "retry-7f3a")
+        .json(&serde_json::json!({
+            "targets": ["127.0.0.2"],
+            "protocol": "icmp",
+            "count": 1
+        }))
+        .send()
+        .await
+        .expect("mismatched request should receive a response");
+    assert_eq!(
+        mismatched.status(),
+        reqwest::StatusCode::UNPROCESSABLE_ENTITY
+    );
+    let body: serde_json::Value = mismatched.json().await.expect("json body expected");
+    assert_error_shape(&body, 422, "idempotency_key_mismatch");
+
+    let invalid = client
+        .post(&url)
+        .header("Idempotency-Key", "has space")
+        .json(&payload)
+        .send()
+        .await
+        .expect("invalid key request should receive a response");
+    assert_eq!(invalid.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    let unkeyed = client
+        .post(&url)
+        .json(&payload)
+        .send()
+        .await
+        .expect("unkeyed request should succeed");
+    assert_eq!(unkeyed.status(), reqwest::StatusCode::ACCEPTED);
+    let unkeyed: serde_json::Value = unkeyed.json().await.expect("json body expected");
+    assert_ne!(unkeyed["data"]["id"], id);
+
+    let _ = shutdown.send(());
+}
+
+/// A probe runner that holds its slot for a while and then fails, so queueing is observable.
+#[cfg(unix)]
+fn slow_probe_runner(directory: &std::path::Path) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = directory.join("slow-runner.sh");
+    std::fs::write(&path, "#!/bin/sh\nsleep 1\nexit 1\n").expect("runner script should write");
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+        .expect("runner script should be executable");
+    path
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn busy_probe_slots_queue_jobs_and_reject_with_503_only_when_queue_is_full() {
+    let runner_dir = tempfile::tempdir().expect("tempdir should exist");
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("listener should bind");
+    let addr = listener.local_addr().expect("local addr should resolve");
+    let config = RestApiConfig {
+        bind_addr: addr,
+        max_concurrent_probes: 1,
+        max_queued_probes: 1,
+        ..RestApiConfig::default()
+    };
+    let state =
+        RestServerState::new_with_probe_runner(config, slow_probe_runner(runner_dir.path()))
+            .expect("state should initialize");
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            build_router(state).into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .expect("server should run");
+    });
+    let client = build_http_client();
+
+    let running = create_probe(&client, addr, "127.0.0.1").await;
+    assert_eq!(running["data"]["queue_position"], serde_json::Value::Null);
+    let queued = create_probe(&client, addr, "127.0.0.2").await;
+    assert_eq!(queued["data"]["status"], "queued");
+    assert_eq!(queued["data"]["queue_position"], 1);
+    let queued_id = queued["data"]["id"]
+        .as_str()
+        .expect("id should be a string");
+
+    let rejected = client
+        .post(format!("http://{addr}/api/v1/probes"))
+        .json(&serde_json::json!({
+            "targets": ["127.0.0.3"],
+            "protocol": "tcp",
+            "port": 443
+        }))
+        .send()
+        .await
+        .expect("queue-full request should receive a response");
+    assert_eq!(rejected.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(rejected.headers()["Retry-After"], "5");
+    let body: serde_json::Value = rejected.json().await.expect("json body expected");
+    assert_error_shape(&body, 503, "queue_full");
+
+    let waiting = fetch_probe(&client, addr, queued_id).await;
+    assert_eq!(waiting["data"]["status"], "queued");
+    assert_eq!(waiting["data"]["queue_position"], 1);
+
+    let finished = wait_for_probe_status(&client, addr, queued_id, "failed").await;
+    assert_eq!(finished["data"]["queue_position"], serde_json::Value::Null);
+    let error = finished["data"]["error"]
+        .as_str()
+        .expect("error text should exist");
+    assert!(
+        !error.contains("concurrency"),
+        "queued job should run instead of failing on the concurrency limit: {error}"
+    );
+}
+
+/// Serve `config` with a reload source that returns whatever `next` holds at reload time.
+async fn spawn_reloadable_server(
+    mut config: RestApiConfig,
+    probe_runner: PathBuf,
+    next: std::sync::Arc<std::sync::Mutex<anyhow::Result<RestApiConfig>>>,
+) -> (SocketAddr, oneshot::Sender<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("listener should bind");
+    let addr = listener.local_addr().expect("local addr should resolve");
+    config.bind_addr = addr;
+
+    let source = ConfigSource::new(move || match &*next.lock().expect("lock next config") {
+        Ok(config) => Ok(config.clone()),
+        Err(error) => Err(anyhow::anyhow!("{error}")),
+    });
+    let state = RestServerState::new_with_probe_runner(config, probe_runner)
+        .expect("state should initialize")
+        .with_config_source(source);
+
+    let (tx, rx) = oneshot::channel();
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            build_router(state).into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(async {
+            let _ = rx.await;
+        })
+        .await
+        .expect("server should run");
+    });
+
+    (addr, tx)
+}
+
+async fn post_reload(
+    client: &reqwest::Client,
+    addr: SocketAddr,
+    api_key: Option<&str>,
+) -> (reqwest::StatusCode, serde_json::Value) {
+    let mut request = client.post(format!("http://{addr}/api/v1/admin/reload"));
+    if let Some(key) = api_key {
+        request = request.header("X-API-Key", key);
+    }
+    let response = request.send().await.expect("reload request should succeed");
+    let status = response.status();
+    (status, response.json().await.expect("json body expected"))
+}
+
+#[tokio::test]
+async fn admin_reload_requires_admin_scope_and_keeps_config_when_invalid() {
+    let next = std::sync::Arc::new(std::sync::Mutex::new(Ok(RestApiConfig::default())));
+    let (addr, shutdown) =
+        spawn_reloadable_server(RestApiConfig::default(), probe_runner_path(), next.clone()).await;
+    let client = build_http_client();
+
+    let (status, body) = post_reload(&client, addr, None).await;
+    assert_eq!(status, reqwest::StatusCode::FORBIDDEN);
+    assert_error_shape(&body, 403, "admin_scope_required");
+    let _ = shutdown.send(());
+
+    let admin = RestApiConfig {
+        admin_principals: vec!["local".to_string()],
+        ..RestApiConfig::default()
+    };
+    let (addr, shutdown) =
+        spawn_reloadable_server(admin.clone(), probe_runner_path(), next.clone()).await;
+
+    *next.lock().unwrap() = Ok(RestApiConfig {
+        max_requests_per_window: 0,
+        ..admin.clone()
+    });
+    let (status, body) = post_reload(&client, addr, None).await;
+    assert_eq!(status, reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+    assert_error_shape(&body, 422, "invalid_config");
+
+    *next.lock().unwrap() = Err(anyhow::anyhow!("config.toml line 3: unknown field"));
+    let (status, body) = post_reload(&client, addr, None).await;
+    assert_eq!(status, reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(
+        body["error"]["detail"]
+            .as_str()
+            .is_some_and(|detail| detail.contains("line 3"))
+    );
+
+    let created = client
+        .post(format!("http://{addr}/api/v1/probes"))
+        .json(&serde_json::json!({"targets": ["mtr-reload.invalid"], "protocol": "icmp"}))
+        .send()
+        .await
+        .expect("create request should succeed");
+    assert_eq!(created.status(), reqwest::StatusCode::ACCEPTED);
+    assert_eq!(created.headers()["X-RateLimit-Limit"], "8");
+
+    *next.lock().unwrap() = Ok(RestApiConfig {
+        bind_addr: SocketAddr::from(([127, 0, 0, 1], 1)),
+        max_requests_per_window: 20,
+        ..admin
+    });
+    let (status, body) = post_reload(&client, addr, None).await;
+    assert_eq!(status, reqwest::StatusCode::OK);
+    assert_meta(&body);
+    assert_eq!(
+        body["data"]["changed"],
+        serde_json::json!(["max_requests_per_window"])
+    );
+    assert_eq!(
+        body["data"]["restart_required"],
+        serde_json::json!(["bind_addr"])
+    );
+
+    let health = client
+        .get(format!("http://{addr}/api/v1/health"))
+        .send()
+        .await
+        .expect("server should still listen on the original address");
+    assert_eq!(health.status(), reqwest::StatusCode::OK);
+
+    let _ = shutdown.send(());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn admin_reload_rotates_keys_and_limits_without_dropping_probes() {
+    let runner_dir = tempfile::tempdir().expect("tempdir should exist");
+    let initial = RestApiConfig {
+        auth_strategy: AuthStrategy::ApiKey,
+        api_key: Some("old-key".to_string()),
+        admin_principals: vec!["api-key".to_string()],
+        max_concurrent_probes: 1,
+        max_requests_per_window: 2,
+        ..RestApiConfig::default()
+    };
+    let next = std::sync::Arc::new(std::sync::Mutex::new(Ok(initial.clone())));
+    let (addr, shutdown) = spawn_reloadable_server(
+        initial.clone(),
+        slow_probe_runner(runner_dir.path()),
+        next.clone(),
+    )
+    .await;
+    let client = build_http_client();
+
+    let create = |key: &'static str, target: &'static str| {
+        client
+            .post(format!("http://{addr}/api/v1/probes"))
+            .header("X-API-Key", key)
+            .json(&serde_json::json!({"targets": [target], "protocol": "tcp", "port": 443}))
+            .send()
+    };
+    let running: serde_json::Value = create("old-key", "127.0.0.1")
+        .await
+        .expect("create request should succeed")
+        .json()
+        .await
+        .expect("json body expected");
+    let queued: serde_json::Value = create("old-key", "127.0.0.2")
+        .await
+        .expect("create request should succeed")
+        .json()
+        .await
+        .expect("json body expected");
+    assert_eq!(queued["data"]["queue_position"], 1);
+    let limited = create("old-key", "127.0.0.3")
+        .await
+        .expect("create request should succeed");
+    assert_eq!(limited.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+
+    *next.lock().unwrap() = Ok(RestApiConfig {
+        api_key: Some("new-key".to_string()),
+        max_concurrent_probes: 2,
+        max_requests_per_window: 50,
+        ..initial
+    });
+    let (status, body) = post_reload(&client, addr, Some("old-key")).await;
+    assert_eq!(status, reqwest::StatusCode::OK, "{body}");
+    assert_eq!(
+        body["data"]["changed"],
+        serde_json::json!([
+            "api_key",
+            "max_concurrent_probes",
+            "max_requests_per_window"
+        ])
+    );
+    assert!(!body.to_string().contains("new-key"));
+
+    let health = |key: &'static str| {
+        client
+            .get(format!("http://{addr}/api/v1/health"))
+            .header("X-API-Key", key)
+            .send()
+    };
+    assert_eq!(
+        health("old-key").await.unwrap().status(),
+        reqwest::StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        health("new-key").await.unwrap().status(),
+        reqwest::StatusCode::OK
+    );
+    let accepted = create("new-key", "127.0.0.3")
+        .await
+        .expect("create request should succeed");
+    assert_eq!(accepted.status(), reqwest::StatusCode::ACCEPTED);
+    assert_eq!(accepted.headers()["X-RateLimit-Limit"], "50");
+    assert_eq!(
+        accepted.headers()["X-RateLimit-Remaining"],
+        "47",
+        "the two requests from before the reload still count"
+    );
+
+    for job in [&running, &queued] {
+        let id = job["data"]["id"].as_str().expect("id should be a string");
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            let probe: serde_json::Value = client
+                .get(format!("http://{addr}/api/v1/probes/{id}"))
+                .header("X-API-Key", "new-key")
+                .send()
+                .await
+                .expect("get probe request should succeed")
+                .json()
+                .await
+                .expect("json body expected");
+            if probe["data"]["status"] == "failed" {
+                let error = probe["data"]["error"].as_str().unwrap_or_default();
+                assert!(!error.contains("timed out"), "{error}");
+                break;
+            }
+            assert!(
+                Instant::now() < deadline,
+                "job {id} never finished: {probe}"
+            );
+            sleep(Duration::from_millis(15)).await;
+        }
+    }
+
+    let _ = shutdown.send(());
+}
+
+async fn spawn_server_with_runner(
+    mut config: RestApiConfig,
+    probe_runner: PathBuf,
+) -> (SocketAddr, oneshot::Sender<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("listener should bind");
+    let addr = listener.local_addr().expect("local addr should resolve");
+    config.bind_addr = addr;
+    let state = RestServerState::new_with_probe_runner(config, probe_runner)
+        .expect("state should initialize");
+
+    let (tx, rx) = oneshot::channel();
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            build_router(state).into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(async {
+            let _ = rx.await;
+        })
+        .await
+        .expect("server should run");
+    });
+
+    (addr, tx)
+}
+
+async fn get_json(
+    client: &reqwest::Client,
+    url: String,
+) -> (reqwest::StatusCode, serde_json::Value) {
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .expect("request should succeed");
+    let status = response.status();
+    (status, response.json().await.expect("json body expected"))
+}
+
+#[cfg(unix)]
+fn script_runner(directory: &std::path::Path, name: &str, body: &str) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = directory.join(name);
+    std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).expect("runner script should write");
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+        .expect("runner script should be executable");
+    path
+}
+
+#[tokio::test]
+async fn readiness_fails_when_probe_runner_is_missing() {
+    let runner_dir = tempfile::tempdir().expect("tempdir should exist");
+    let (addr, shutdown) = spawn_server_with_runner(
+        RestApiConfig::default(),
+        runner_dir.path().join("missing-runner"),
+    )
+    .await;
+    let client = build_http_client();
+
+    let (status, body) = get_json(&client, format!("http://{addr}/api/v1/ready")).await;
+    assert_eq!(status, reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    assert_meta(&body);
+    assert_eq!(body["data"]["status"], "not_ready");
+    assert_eq!(body["data"]["checks"][0]["name"], "probe_runner");
+    assert_eq!(body["data"]["checks"][0]["ok"], false);
+    assert_eq!(body["data"]["checks"][1]["name"], "probe_privileges");
+    assert_eq!(body["data"]["checks"][1]["ok"], false);
+
+    let health = client
+        .get(format!("http://{addr}/api/v1/health"))
+        .send()
+        .await
+        .expect("health request should succeed");
+    assert_eq!(health.status(), reqwest::StatusCode::OK);
+
+    let _ = shutdown.send(());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn readiness_reports_runner_and_privilege_checks() {
+    let runner_dir = tempfile::tempdir().expect("tempdir should exist");
+    let client = build_http_client();
+
+    let working = script_runner(runner_dir.path(), "working.sh", "exit 0");
+    let (addr, shutdown) = spawn_server_with_runner(RestApiConfig::default(), working).await;
+    let (status, body) = get_json(&client, format!("http://{addr}/api/v1/ready")).await;
+    assert_eq!(status, reqwest::StatusCode::OK, "{body}");
+    assert_eq!(body["data"]["status"], "ready");
+    assert!(
+        body["data"]["checks"]
+            .as_array()
+            .expect("checks should be an array")
+            .iter()
+            .all(|check| check["ok"] == true)
+    );
+    let _ = shutdown.send(());
+
+    let unprivileged = script_runner(
+        runner_dir.path(),
+        "unprivileged.sh",
+        "[ -n \"$WINDOWS_MTR_EMBEDDED_TRIPPY\" ] && exit 1\nexit 0",
+    );
+    let (addr, shutdown) = spawn_server_with_runner(RestApiConfig::default(), unprivileged).await;
+    let (status, body) = get_json(&client, format!("http://{addr}/api/v1/ready")).await;
+    assert_eq!(status, reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["data"]["checks"][0]["ok"], true);
+    assert_eq!(body["data"]["checks"][1]["ok"], false);
+    assert!(
+        body["data"]["checks"][1]["detail"]
+            .as_str()
+            .is_some_and(|detail| detail.contains("privileges"))
+    );
+    let _ = shutdown.send(());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn diagnostics_report_probe_load_store_size_and_uptime() {
+    let runner_dir = tempfile::tempdir().expect("tempdir should exist");
+    let runner = script_runner(
+        runner_dir.path(),
+        "slow.sh",
+        "[ \"$1\" = \"--version\" ] && exit 0\nsleep 1\nexit 1",
+    );
+    let config = RestApiConfig {
+        max_concurrent_probes: 1,
+        max_queued_probes: 4,
+        ..RestApiConfig::default()
+    };
+    let (addr, shutdown) = spawn_server_with_runner(config, runner).await;
+    let client = build_http_client();
+
+    // Warm the cached checks so the snapshot below is taken while both jobs are in flight.
+    let (status, _) = get_json(&client, format!("http://{addr}/api/v1/ready")).await;
+    assert_eq!(status, reqwest::StatusCode::SERVICE_UNAVAILABLE);
+
+    let running = create_probe(&client, addr, "127.0.0.1").await["data"]["id"]
+        .as_str()
+        .expect("id should be a string")
+        .to_string();
+    let queued = create_probe(&client, addr, "127.0.0.2").await["data"]["id"]
+        .as_str()
+        .expect("id should be a string")
+        .to_string();
+
+    let (status, body) = get_json(&client, format!("http://{addr}/api/v1/diagnostics")).await;
+    assert_eq!(status, reqwest::StatusCode::OK);
+    assert_meta(&body);
+    let data = &body["data"];
+    assert_eq!(data["status"], "not_ready");
+    assert_eq!(data["service"], "windows-mtr");
+    assert_eq!(data["version"], env!("CARGO_PKG_VERSION"));
+    assert!(data["uptime_seconds"].as_u64().is_some());
+    assert_eq!(data["running_probes"], 1);
+    assert_eq!(data["queued_probes"], 1);
+    assert_eq!(data["max_concurrent_probes"], 1);
+    assert_eq!(data["max_queued_probes"], 4);
+    assert_eq!(data["stored_jobs"], 2);
+    assert_eq!(data["checks"][0]["ok"], true);
+    assert_eq!(data["checks"][1]["ok"], false);
+
+    wait_for_probe_status(&client, addr, &running, "failed").await;
+    wait_for_probe_status(&client, addr, &queued, "failed").await;
+    let (_, body) = get_json(&client, format!("http://{addr}/api/v1/diagnostics")).await;
+    assert_eq!(body["data"]["running_probes"], 0);
+    assert_eq!(body["data"]["queued_probes"], 0);
+    assert_eq!(body["data"]["stored_jobs"], 2);
+
+    let _ = shutdown.send(());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn probe_diff_compares_hops_of_two_completed_jobs() {
+    let runner_dir = tempfile::tempdir().expect("tempdir should exist");
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let marker = runner_dir.path().join("first-run-done");
+    // The first probe prints the baseline report and every later one the degraded report.
+    let runner = script_runner(
+        runner_dir.path(),
+        "report.sh",
+        &format!(
+            "if [ -e '{marker}' ]; then cat '{after}'; else touch '{marker}'; cat '{before}'; fi",
+            marker = marker.display(),
+            before = fixtures.join("trippy_0_13_report.json").display(),
+            after = fixtures.join("probe_diff_after.json").display(),
+        ),
+    );
+    let config = RestApiConfig {
+        max_concurrent_probes: 1,
+        ..RestApiConfig::default()
+    };
+    let (addr, shutdown) = spawn_server_with_runner(config, runner).await;
+    let client = build_http_client();
+
+    let mut ids = Vec::new();
+    for _ in 0..2 {
+        let id = create_probe(&client, addr, "127.0.0.1").await["data"]["id"]
+            .as_str()
+            .expect("id should be a string")
+            .to_string();
+        wait_for_probe_status(&client, addr, &id, "completed").await;
+        ids.push(id);
+    }
+    let diff_url = format!("http://{addr}/api/v1/probes/{}/diff/{}", ids[0], ids[1]);
+
+    let (status, body) = get_json(&client, diff_url.clone()).await;
+    assert_eq!(status, reqwest::StatusCode::OK);
+    assert_meta(&body);
+    assert_eq!(body["data"]["from"], ids[0].as_str());
+    assert_eq!(body["data"]["to"], ids[1].as_str());
+    assert_eq!(body["data"]["verdict"], "degraded");
+    let target = &body["data"]["targets"][0];
+    assert_eq!(target["target"], "127.0.0.1");
+    assert_eq!(target["path_changed"], true);
+    assert_eq!(target["hops"][1]["change"], "host_changed");
+    assert_eq!(target["hops"][2]["change"], "added");
+    assert_eq!(target["hops"][2]["new_asn"], 15169);
+
+    let markdown = client
+        .get(format!("{diff_url}?format=markdown"))
+        .send()
+        .await
+        .expect("diff request should succeed");
+    assert_eq!(markdown.status(), reqwest::StatusCode::OK);
+    assert_eq!(
+        markdown.headers()[reqwest::header::CONTENT_TYPE],
+        "text/markdown; charset=utf-8"
+    );
+    let markdown = markdown.text().await.expect("body should be text");
+    assert!(
+        markdown.contains("**Overall verdict:** degraded"),
+        "{markdown}"
+    );
+    assert!(markdown.contains("| Hop | Change |"), "{markdown}");
+
+    let (status, body) = get_json(&client, format!("{diff_url}?format=yaml")).await;
+    assert_error_shape(&body, 400, "invalid_request");
+    assert_eq!(status, reqwest::StatusCode::BAD_REQUEST);
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test]
+async fn probe_diff_rejects_jobs_that_did_not_complete() {
+    let (addr, shutdown) = spawn_server().await;
+    let client = build_http_client();
+
+    let failed =
+        create_probe(&client, addr, "definitely-not-a-real-host.invalid").await["data"]["id"]
+            .as_str()
+            .expect("id should be a string")
+            .to_string();
+    wait_for_probe_status(&client, addr, &failed, "failed").await;
+
+    let (status, body) = get_json(
+        &client,
+        format!("http://{addr}/api/v1/probes/{failed}/diff/{failed}"),
+    )
+    .await;
+    assert_eq!(status, reqwest::StatusCode::CONFLICT);
+    assert_error_shape(&body, 409, "probe_not_comparable");
+
+    let (status, body) = get_json(
+        &client,
+        format!("http://{addr}/api/v1/probes/{failed}/diff/missing-probe"),
+    )
+    .await;
+    assert_eq!(status, reqwest::StatusCode::NOT_FOUND);
+    assert_error_shape(&body, 404, "probe_not_found");
+
+    let _ = shutdown.send(());
+}
+
+/// Serve `state` without a shutdown trigger so tests can call [`RestServerState::drain`]
+/// directly and keep querying the API afterwards.
+#[cfg(unix)]
+async fn spawn_server_with_state(
+    mut config: RestApiConfig,
+    probe_runner: PathBuf,
+) -> (SocketAddr, RestServerState) {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("listener should bind");
+    let addr = listener.local_addr().expect("local addr should resolve");
+    config.bind_addr = addr;
+    let state = RestServerState::new_with_probe_runner(config, probe_runner)
+        .expect("state should initialize");
+
+    let router = build_router(state.clone());
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .expect("server should run");
+    });
+
+    (addr, state)
+}
+
+#[cfg(target_os = "linux")]
+fn process_exited(pid: &str) -> bool {
+    // A killed child may linger as a zombie until the runtime reaps it.
+    match std::fs::read_to_string(format!("/proc/{pid}/stat")) {
+        Ok(stat) => stat
+            .rsplit_once(')')
+            .is_some_and(|(_, rest)| rest.trim_start().starts_with('Z')),
+        Err(_) => true,
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn drain_refuses_new_probes_and_interrupts_jobs_after_grace_period() {
+    let runner_dir = tempfile::tempdir().expect("tempdir should exist");
+    let pid_path = runner_dir.path().join("runner.pid");
+    let runner = script_runner(
+        runner_dir.path(),
+        "hanging-runner.sh",
+        &format!("echo $$ > '{}'\nexec sleep 30", pid_path.display()),
+    );
+    let config = RestApiConfig {
+        max_concurrent_probes: 1,
+        max_queued_probes: 4,
+        shutdown_grace_period: Duration::from_millis(300),
+        ..RestApiConfig::default()
+    };
+    let (addr, state) = spawn_server_with_state(config, runner).await;
+    let client = build_http_client();
+
+    let running = create_probe(&client, addr, "127.0.0.1").await["data"]["id"]
+        .as_str()
+        .expect("id should be a string")
+        .to_string();
+    let queued = create_probe(&client, addr, "127.0.0.2").await["data"]["id"]
+        .as_str()
+        .expect("id should be a string")
+        .to_string();
+    wait_for_probe_status(&client, addr, &running, "running").await;
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let runner_pid = loop {
+        if let Ok(pid) = std::fs::read_to_string(&pid_path)
+            && pid.ends_with('\n')
+        {
+            break pid.trim().to_string();
+        }
+        assert!(Instant::now() < deadline, "runner never started");
+        sleep(Duration::from_millis(15)).await;
+    };
+
+    let started = Instant::now();
+    let drain = tokio::spawn({
+        let state = state.clone();
+        async move { state.drain().await }
+    });
+
+    let interrupted = wait_for_probe_status(&client, addr, &queued, "interrupted").await;
+    assert_eq!(interrupted["data"]["error"], "server shutting down");
+
+    let rejected = client
+        .post(format!("http://{addr}/api/v1/probes"))
+        .json(&serde_json::json!({"targets": ["127.0.0.3"], "protocol": "tcp", "port": 443}))
+        .send()
+        .await
+        .expect("create request should succeed");
+    assert_eq!(rejected.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    let body: serde_json::Value = rejected.json().await.expect("json body expected");
+    assert_error_shape(&body, 503, "shutting_down");
+
+    drain.await.expect("drain should finish");
+    assert!(started.elapsed() >= Duration::from_millis(300));
+    let interrupted = fetch_probe(&client, addr, &running).await;
+    assert_eq!(interrupted["data"]["status"], "interrupted");
+    assert_eq!(interrupted["data"]["error"], "server shutting down");
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !process_exited(&runner_pid) {
+        assert!(
+            Instant::now() < deadline,
+            "runner {runner_pid} was not killed"
+        );
+        sleep(Duration::from_millis(15)).await;
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn drain_lets_running_probes_finish_within_grace_period() {
+    let runner_dir = tempfile::tempdir().expect("tempdir should exist");
+    let config = RestApiConfig {
+        shutdown_grace_period: Duration::from_secs(30),
+        ..RestApiConfig::default()
+    };
+    let (addr, state) = spawn_server_with_state(config, slow_probe_runner(runner_dir.path())).await;
+    let client = build_http_client();
+
+    let running = create_probe(&client, addr, "127.0.0.1").await["data"]["id"]
+        .as_str()
+        .expect("id should be a string")
+        .to_string();
+    wait_for_probe_status(&client, addr, &running, "running").await;
+
+    let drain = tokio::spawn({
+        let state = state.clone();
+        async move { state.drain().await }
+    });
+    let (status, body) = get_json(&client, format!("http://{addr}/api/v1/ready")).await;
+    assert_eq!(status, reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["data"]["status"], "draining");
+
+    tokio::time::timeout(Duration::from_secs(10), drain)
+        .await
+        .expect("drain should end once the running probe finishes")
+        .expect("drain should finish");
+
+    let finished = fetch_probe(&client, addr, &running).await;
+    assert_eq!(finished["data"]["status"], "failed");
+    assert_ne!(finished["data"]["error"], "server shutting down");
+}
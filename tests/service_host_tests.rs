@@ -0,0 +1,107 @@
+#![cfg(target_os = "linux")]
+
+use std::os::unix::net::UnixDatagram;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Read datagrams from the fake notify socket until one contains `needle`.
+fn wait_for_notification(socket: &UnixDatagram, needle: &str) -> String {
+    let deadline = Instant::now() + Duration::from_secs(30);
+    let mut buffer = [0u8; 1024];
+    let mut seen = Vec::new();
+    while Instant::now() < deadline {
+        match socket.recv(&mut buffer) {
+            Ok(len) => {
+                let message = String::from_utf8_lossy(&buffer[..len]).into_owned();
+                if message.contains(needle) {
+                    return message;
+                }
+                seen.push(message);
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => {}
+            Err(error) => panic!("notify socket failed: {error}"),
+        }
+    }
+    panic!("no `{needle}` notification within 30s; saw {seen:?}");
+}
+
+fn wait_for_file(path: &Path) -> String {
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while Instant::now() < deadline {
+        if let Ok(contents) = std::fs::read_to_string(path)
+            && contents.ends_with('\n')
+        {
+            return contents;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    panic!("{} was not written within 30s", path.display());
+}
+
+#[test]
+fn service_run_reports_readiness_watchdog_and_stop_to_systemd() {
+    let dir = tempfile::tempdir().expect("tempdir should exist");
+    let notify_path = dir.path().join("notify.sock");
+    let notify = UnixDatagram::bind(&notify_path).expect("fake notify socket should bind");
+    notify
+        .set_read_timeout(Some(Duration::from_millis(200)))
+        .expect("read timeout should apply");
+    let config_path = dir.path().join("config.toml");
+    std::fs::write(&config_path, "").expect("empty config should be written");
+    let pid_path = dir.path().join("run").join("mtr.pid");
+    let log_path = dir.path().join("log").join("api.log");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mtr"))
+        .arg("--config")
+        .arg(&config_path)
+        .args(["--api-bind", "127.0.0.1:0", "service", "run", "--pid-file"])
+        .arg(&pid_path)
+        .arg("--log-file")
+        .arg(&log_path)
+        .env("NOTIFY_SOCKET", &notify_path)
+        .env("WATCHDOG_USEC", "200000")
+        .stdin(Stdio::null())
+        .spawn()
+        .expect("service host should start");
+
+    let ready = wait_for_notification(&notify, "READY=1");
+    assert!(
+        ready.contains("STATUS=Serving REST API on 127.0.0.1:"),
+        "{ready}"
+    );
+    assert!(
+        ready.contains(&format!("MAINPID={}", child.id())),
+        "{ready}"
+    );
+    wait_for_notification(&notify, "WATCHDOG=1");
+
+    assert_eq!(wait_for_file(&pid_path).trim(), child.id().to_string());
+
+    // SAFETY: `child.id()` is the pid of a process this test spawned and has not reaped.
+    let signalled = unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGTERM) };
+    assert_eq!(signalled, 0, "SIGTERM should be delivered");
+    wait_for_notification(&notify, "STOPPING=1");
+
+    let status = child.wait().expect("service host should exit");
+    assert!(status.success(), "service host exited with {status}");
+    assert!(!pid_path.exists(), "pid file should be removed on exit");
+
+    let log = std::fs::read_to_string(&log_path).expect("log file should exist");
+    assert!(log.contains("REST API listening on 127.0.0.1:"), "{log}");
+}
+
+#[test]
+fn service_install_points_linux_users_at_systemd() {
+    let output = Command::new(env!("CARGO_BIN_EXE_mtr"))
+        .args(["service", "install"])
+        .output()
+        .expect("mtr should run");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("docs/systemd/windows-mtr.service"),
+        "{stderr}"
+    );
+}
@@ -93,6 +93,70 @@ fn plan_rejects_report_wide_without_report_or_json() {
     ));
 }
 
+#[test]
+fn plan_accepts_enhanced_tuning_flags_only_with_the_dashboard() {
+    let mut request = base_request();
+    request.has_enhanced_overrides = true;
+    request.enhanced_ui.latency_warn_ms = 40.0;
+    assert!(matches!(
+        build_probe_plan(&request),
+        Err(ProbeError::InvalidOption(message))
+            if message == "enhanced UI tuning flags require --ui dashboard"
+    ));
+
+    request.ui_mode = UiMode::Dashboard;
+    assert!(build_probe_plan(&request).is_ok());
+}
+
+#[test]
+fn plan_rejects_out_of_range_or_inverted_thresholds() {
+    let defaults = EnhancedUiConfig::default();
+    let cases = [
+        (
+            EnhancedUiConfig {
+                latency_bad_ms: f32::NAN,
+                ..defaults
+            },
+            "--latency-bad-ms must be a non-negative number of milliseconds",
+        ),
+        (
+            EnhancedUiConfig {
+                loss_warn_pct: 120.0,
+                ..defaults
+            },
+            "--loss-warn-pct must be a percentage between 0 and 100",
+        ),
+        (
+            EnhancedUiConfig {
+                latency_warn_ms: 300.0,
+                ..defaults
+            },
+            "--latency-warn-ms (300) must not be above --latency-bad-ms (250)",
+        ),
+        (
+            EnhancedUiConfig {
+                loss_bad_pct: 1.5,
+                ..defaults
+            },
+            "--loss-warn-pct (2) must not be above --loss-bad-pct (1.5)",
+        ),
+    ];
+    for (enhanced_ui, expected) in cases {
+        let mut request = base_request();
+        request.ui_mode = UiMode::Dashboard;
+        request.has_enhanced_overrides = true;
+        request.enhanced_ui = enhanced_ui;
+
+        assert!(
+            matches!(
+                build_probe_plan(&request),
+                Err(ProbeError::InvalidOption(ref message)) if message == expected
+            ),
+            "{expected}"
+        );
+    }
+}
+
 #[test]
 fn plan_rejects_invalid_host() {
     let mut request = base_request();